@@ -0,0 +1,637 @@
+//! Maintenance tool that checks for new releases of Python and the packaging tools used by the
+//! buildpack, and updates the pinned versions in the buildpack's source to match.
+//!
+//! Run from the root of the repository using: `cargo run --bin update_inventory`
+//!
+//! The packaging tool requirements files are regenerated (with the hashes of every package) using
+//! `uv pip compile`, so [uv](https://docs.astral.sh/uv/) must be installed.
+//!
+//! The changes made should then be reviewed, the CHANGELOG updated, and the integration tests run
+//! (since new Python versions can't be used until they have been built for all supported targets).
+
+// Required due to: https://github.com/rust-lang/rust/issues/95513
+#![allow(unused_crate_dependencies)]
+
+use ring::digest;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{Read, Write as _};
+use std::process::{Command, ExitCode, Stdio};
+
+// The buildpack's packaging tool requirements files (see `packaging_tool_versions.rs`), along
+// with the name of the package on PyPI that they pin.
+const REQUIREMENTS_FILES: [(&str, &str); 5] = [
+    ("keyring", "requirements/keyring.txt"),
+    ("pdm", "requirements/pdm.txt"),
+    ("pip", "requirements/pip.txt"),
+    ("pipenv", "requirements/pipenv.txt"),
+    ("poetry", "requirements/poetry.txt"),
+];
+
+// The `poetry-dynamic-versioning` plugin is installed into Poetry's environment, so its
+// requirements file only contains the packages that aren't already in Poetry's.
+const POETRY_DYNAMIC_VERSIONING_REQUIREMENTS_FILE: &str =
+    "requirements/poetry-dynamic-versioning.txt";
+const POETRY_REQUIREMENTS_FILE: &str = "requirements/poetry.txt";
+
+const REQUIREMENTS_FILE_HEADER: &str =
+    "# Generated by `cargo run --bin update_inventory`, do not edit manually.\n";
+
+// The inventory of the Python releases that can be installed by the buildpack.
+const PYTHON_INVENTORY_FILE: &str = "python-inventory.toml";
+
+const PYTHON_RELEASES_URL: &str =
+    "https://www.python.org/api/v2/downloads/release/?is_published=true";
+
+// The subset of the Python inventory used when checking for new Python releases.
+#[derive(Deserialize)]
+struct PythonInventory {
+    base_url: String,
+    releases: Vec<PythonInventoryRelease>,
+    #[serde(default)]
+    checksums: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct PythonInventoryRelease {
+    version: String,
+    targets: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PypiProject {
+    info: PypiProjectInfo,
+}
+
+#[derive(Deserialize)]
+struct PypiProjectInfo {
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct PythonRelease {
+    name: String,
+    pre_release: bool,
+}
+
+fn main() -> ExitCode {
+    match update_inventory() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn update_inventory() -> Result<(), String> {
+    let contents = read_file(PYTHON_INVENTORY_FILE)?;
+    let oldest_python_version = oldest_python_version(&contents)?;
+
+    for (package_name, path) in REQUIREMENTS_FILES {
+        let latest_version = fetch_latest_pypi_version(package_name)?;
+        let requirements = compile_requirements(
+            &format!("{package_name}=={latest_version}"),
+            None,
+            &oldest_python_version,
+        )?;
+        update_requirements_file(path, package_name, &latest_version, &requirements)?;
+    }
+
+    let poetry_requirements = read_file(POETRY_REQUIREMENTS_FILE)?;
+    let poetry_version = pinned_version(&poetry_requirements, "poetry").ok_or_else(|| {
+        format!("Unable to find the Poetry version in {POETRY_REQUIREMENTS_FILE}")
+    })?;
+    let latest_version = fetch_latest_pypi_version("poetry-dynamic-versioning")?;
+    let requirements = compile_requirements(
+        &format!("poetry=={poetry_version}\npoetry-dynamic-versioning[plugin]=={latest_version}"),
+        Some(POETRY_REQUIREMENTS_FILE),
+        &oldest_python_version,
+    )?;
+    update_requirements_file(
+        POETRY_DYNAMIC_VERSIONING_REQUIREMENTS_FILE,
+        "poetry-dynamic-versioning",
+        &latest_version,
+        &exclude_requirements(&requirements, &poetry_requirements),
+    )?;
+
+    let latest_python_versions = fetch_latest_python_versions()?;
+    let (updated_contents, updates) = update_python_versions(
+        &contents,
+        &latest_python_versions,
+        is_python_archive_available,
+        fetch_python_archive_checksum,
+    )?;
+    if updates.is_empty() {
+        println!("Python versions are already up to date");
+    }
+    for version in updates {
+        println!("Updated Python to {version}");
+    }
+    if updated_contents != contents {
+        write_file(PYTHON_INVENTORY_FILE, &updated_contents)?;
+    }
+
+    Ok(())
+}
+
+// Writes the regenerated requirements file, which always happens (even if the version pinned for
+// the package hasn't changed), so that the pins and hashes of its dependencies are kept current.
+fn update_requirements_file(
+    path: &str,
+    package_name: &str,
+    latest_version: &str,
+    requirements: &str,
+) -> Result<(), String> {
+    let current_version = pinned_version(&read_file(path)?, package_name);
+    write_file(path, &format!("{REQUIREMENTS_FILE_HEADER}{requirements}"))?;
+    if current_version.as_deref() == Some(latest_version) {
+        println!("{package_name} is already up to date ({latest_version})");
+    } else {
+        println!("Updated {package_name} to {latest_version}");
+    }
+    Ok(())
+}
+
+// Resolves the requirements (and all of their dependencies) to exact versions, along with the
+// hashes of their distributions, for all of the Python versions supported by the buildpack.
+fn compile_requirements(
+    requirements: &str,
+    constraints_file: Option<&str>,
+    oldest_python_version: &str,
+) -> Result<String, String> {
+    let mut command = Command::new("uv");
+    command
+        .args([
+            "pip",
+            "compile",
+            "-",
+            "--generate-hashes",
+            "--no-annotate",
+            "--no-header",
+            "--quiet",
+            "--universal",
+            "--python-version",
+            oldest_python_version,
+        ])
+        .args(constraints_file.map(|path| format!("--constraint={path}")))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+    let mut child = command
+        .spawn()
+        .map_err(|error| format!("Unable to run 'uv pip compile' (is uv installed?): {error}"))?;
+    child
+        .stdin
+        .take()
+        .ok_or("Unable to write to 'uv pip compile'")?
+        .write_all(requirements.as_bytes())
+        .map_err(|error| format!("Unable to write to 'uv pip compile': {error}"))?;
+    let output = child
+        .wait_with_output()
+        .map_err(|error| format!("Unable to run 'uv pip compile': {error}"))?;
+    if !output.status.success() {
+        return Err(format!("'uv pip compile' failed ({})", output.status));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|error| format!("Unable to read the output of 'uv pip compile': {error}"))
+}
+
+// The `X.Y` version of the oldest Python release in the inventory (which is listed last).
+fn oldest_python_version(inventory_contents: &str) -> Result<String, String> {
+    let inventory: PythonInventory = toml::from_str(inventory_contents)
+        .map_err(|error| format!("Unable to parse {PYTHON_INVENTORY_FILE}: {error}"))?;
+    inventory
+        .releases
+        .last()
+        .and_then(|release| release.version.rsplit_once('.'))
+        .map(|(major_minor, _)| major_minor.to_string())
+        .ok_or_else(|| format!("No Python releases found in {PYTHON_INVENTORY_FILE}"))
+}
+
+// Splits a compiled requirements file into the entries for each package, where each entry is the
+// `name==version` line followed by its indented `--hash` continuation lines.
+fn requirement_entries(requirements: &str) -> Vec<String> {
+    let mut entries: Vec<String> = Vec::new();
+    for line in requirements.lines() {
+        match entries.last_mut() {
+            Some(entry) if line.starts_with(char::is_whitespace) => {
+                entry.push_str(line);
+                entry.push('\n');
+            }
+            _ if line.is_empty() || line.starts_with('#') => {}
+            _ => entries.push(format!("{line}\n")),
+        }
+    }
+    entries
+}
+
+// The version of the package that's pinned using `name==version` in the requirements file.
+fn pinned_version(requirements: &str, package_name: &str) -> Option<String> {
+    requirement_entries(requirements).iter().find_map(|entry| {
+        let (name, rest) = entry.split_once("==")?;
+        (name == package_name).then(|| {
+            rest.split(|c: char| c.is_whitespace() || c == '\\' || c == ';')
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        })
+    })
+}
+
+// Removes the entries for any packages that are already pinned in the other requirements file.
+fn exclude_requirements(requirements: &str, other_requirements: &str) -> String {
+    let other_entries = requirement_entries(other_requirements);
+    requirement_entries(requirements)
+        .into_iter()
+        .filter(|entry| !other_entries.contains(entry))
+        .collect()
+}
+
+fn fetch_latest_pypi_version(package_name: &str) -> Result<String, String> {
+    let project: PypiProject = fetch_json(&format!("https://pypi.org/pypi/{package_name}/json"))?;
+    Ok(project.info.version)
+}
+
+// Returns the latest patch version for each `(major, minor)` Python version.
+fn fetch_latest_python_versions() -> Result<BTreeMap<(u16, u16), u16>, String> {
+    let releases: Vec<PythonRelease> = fetch_json(PYTHON_RELEASES_URL)?;
+    Ok(latest_python_patch_versions(&releases))
+}
+
+fn latest_python_patch_versions(releases: &[PythonRelease]) -> BTreeMap<(u16, u16), u16> {
+    let mut latest_versions = BTreeMap::new();
+    for release in releases.iter().filter(|release| !release.pre_release) {
+        let Some(version) = release.name.strip_prefix("Python ") else {
+            continue;
+        };
+        if let [Ok(major), Ok(minor), Ok(patch)] = version
+            .split('.')
+            .map(str::parse::<u16>)
+            .collect::<Vec<_>>()[..]
+        {
+            let latest_patch = latest_versions.entry((major, minor)).or_insert(patch);
+            *latest_patch = patch.max(*latest_patch);
+        }
+    }
+    latest_versions
+}
+
+// A new Python release can only be used once it has been built and uploaded by the
+// heroku-buildpack-python build workflows, so we check an archive exists before using it.
+fn is_python_archive_available(base_url: &str, version: &str, target: &str) -> bool {
+    ureq::head(&python_archive_url(base_url, version, target))
+        .call()
+        .is_ok()
+}
+
+// The SHA-256 checksum of the archive, which the buildpack verifies when installing Python.
+fn fetch_python_archive_checksum(
+    base_url: &str,
+    version: &str,
+    target: &str,
+) -> Result<String, String> {
+    let url = python_archive_url(base_url, version, target);
+    let mut reader = ureq::get(&url)
+        .call()
+        .map_err(|error| format!("Unable to download {url}: {error}"))?
+        .into_reader();
+    let mut context = digest::Context::new(&digest::SHA256);
+    let mut buffer = [0; 8 * 1024];
+    loop {
+        let bytes_read = reader
+            .read(&mut buffer)
+            .map_err(|error| format!("Unable to download {url}: {error}"))?;
+        if bytes_read == 0 {
+            break;
+        }
+        context.update(&buffer[..bytes_read]);
+    }
+    Ok(context
+        .finish()
+        .as_ref()
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        }))
+}
+
+fn python_archive_url(base_url: &str, version: &str, target: &str) -> String {
+    format!("{base_url}/{}", python_archive_name(version, target))
+}
+
+// This must match the archive name format in `PythonVersion::archive_name`.
+fn python_archive_name(version: &str, target: &str) -> String {
+    format!("python-{version}-{target}.tar.zst")
+}
+
+fn fetch_json<T: for<'de> Deserialize<'de>>(url: &str) -> Result<T, String> {
+    ureq::get(url)
+        .call()
+        .map_err(|error| format!("Unable to fetch {url}: {error}"))?
+        .into_string()
+        .map_err(|error| format!("Unable to read response from {url}: {error}"))
+        .and_then(|body| {
+            serde_json::from_str(&body)
+                .map_err(|error| format!("Unable to parse response from {url}: {error}"))
+        })
+}
+
+fn read_file(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|error| format!("Unable to read {path}: {error}"))
+}
+
+fn write_file(path: &str, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|error| format!("Unable to write {path}: {error}"))
+}
+
+/// Updates the version of any releases in the Python inventory for which a newer patch release
+/// is available for all of the release's targets, returning the updated contents and the list
+/// of new versions. The checksums of the archives of every patch release (up to the latest) of
+/// each `X.Y` version are then recorded, for any archives that exist but are missing checksums.
+///
+/// The file is updated in place (rather than re-serialised) to preserve comments.
+fn update_python_versions(
+    contents: &str,
+    latest_versions: &BTreeMap<(u16, u16), u16>,
+    is_available: impl Fn(&str, &str, &str) -> bool,
+    archive_checksum: impl Fn(&str, &str, &str) -> Result<String, String>,
+) -> Result<(String, Vec<String>), String> {
+    let inventory: PythonInventory = toml::from_str(contents)
+        .map_err(|error| format!("Unable to parse {PYTHON_INVENTORY_FILE}: {error}"))?;
+
+    let mut updated_contents = contents.to_string();
+    let mut updates = Vec::new();
+    let mut checksums = inventory.checksums;
+    for release in inventory.releases {
+        let [Ok(major), Ok(minor), Ok(patch)] = release
+            .version
+            .split('.')
+            .map(str::parse::<u16>)
+            .collect::<Vec<_>>()[..]
+        else {
+            return Err(format!("Invalid Python version: {}", release.version));
+        };
+        let new_patch = latest_versions
+            .get(&(major, minor))
+            .copied()
+            .filter(|&latest_patch| {
+                latest_patch > patch
+                    && release.targets.iter().all(|target| {
+                        is_available(
+                            &inventory.base_url,
+                            &format!("{major}.{minor}.{latest_patch}"),
+                            target,
+                        )
+                    })
+            });
+        if let Some(new_patch) = new_patch {
+            let new_version = format!("{major}.{minor}.{new_patch}");
+            updated_contents = updated_contents.replace(
+                &format!("version = \"{}\"\n", release.version),
+                &format!("version = \"{new_version}\"\n"),
+            );
+            updates.push(new_version);
+        }
+
+        for version in
+            (0..=new_patch.unwrap_or(patch)).map(|patch| format!("{major}.{minor}.{patch}"))
+        {
+            for target in &release.targets {
+                let archive_name = python_archive_name(&version, target);
+                if !checksums.contains_key(&archive_name)
+                    && is_available(&inventory.base_url, &version, target)
+                {
+                    let checksum = archive_checksum(&inventory.base_url, &version, target)?;
+                    checksums.insert(archive_name, checksum);
+                }
+            }
+        }
+    }
+    Ok((update_checksums(&updated_contents, &checksums), updates))
+}
+
+/// Replaces the `[checksums]` table at the end of the Python inventory (adding it if it doesn't
+/// exist yet) with the specified checksums.
+fn update_checksums(contents: &str, checksums: &BTreeMap<String, String>) -> String {
+    let releases = contents
+        .split_once("\n[checksums]\n")
+        .map_or(contents, |(releases, _)| releases);
+    checksums.iter().fold(
+        format!("{}\n\n[checksums]\n", releases.trim_end()),
+        |mut updated_contents, (archive_name, checksum)| {
+            let _ = writeln!(updated_contents, "\"{archive_name}\" = \"{checksum}\"");
+            updated_contents
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn pinned_version_compiled_requirements() {
+        let requirements = indoc! {r"
+            # Comment
+            cachecontrol==0.14.1 \
+                --hash=sha256:aaa \
+                --hash=sha256:bbb
+            poetry==1.8.5 \
+                --hash=sha256:ccc
+            tomli==2.2.1 ; python_full_version < '3.11' \
+                --hash=sha256:ddd
+        "};
+        assert_eq!(
+            pinned_version(requirements, "poetry").as_deref(),
+            Some("1.8.5")
+        );
+        assert_eq!(
+            pinned_version(requirements, "tomli").as_deref(),
+            Some("2.2.1")
+        );
+        assert_eq!(pinned_version(requirements, "cachecontrol-extra"), None);
+        assert_eq!(pinned_version(requirements, "pip"), None);
+    }
+
+    #[test]
+    fn exclude_requirements_removes_shared_entries() {
+        let poetry_requirements = indoc! {r"
+            # Comment
+            poetry==1.8.5 \
+                --hash=sha256:aaa
+            tomlkit==0.13.2 \
+                --hash=sha256:bbb
+        "};
+        let requirements = indoc! {r"
+            dunamai==1.23.0 \
+                --hash=sha256:ccc
+            poetry==1.8.5 \
+                --hash=sha256:aaa
+            poetry-dynamic-versioning==1.4.1 \
+                --hash=sha256:ddd
+            tomlkit==0.13.2 \
+                --hash=sha256:bbb
+        "};
+        assert_eq!(
+            exclude_requirements(requirements, poetry_requirements),
+            indoc! {r"
+                dunamai==1.23.0 \
+                    --hash=sha256:ccc
+                poetry-dynamic-versioning==1.4.1 \
+                    --hash=sha256:ddd
+            "}
+        );
+    }
+
+    #[test]
+    fn oldest_python_version_from_inventory() {
+        let contents = indoc! {r#"
+            base_url = "https://example.com"
+
+            [[releases]]
+            version = "3.13.1"
+            targets = ["ubuntu-24.04-amd64"]
+
+            [[releases]]
+            version = "3.8.20"
+            targets = ["ubuntu-20.04-amd64"]
+        "#};
+        assert_eq!(oldest_python_version(contents).as_deref(), Ok("3.8"));
+        assert!(oldest_python_version("base_url = \"\"\nreleases = []\n").is_err());
+    }
+
+    #[test]
+    fn latest_python_patch_versions_filters_releases() {
+        let releases = [
+            ("Python 3.13.0", false),
+            ("Python 3.13.1", false),
+            ("Python 3.14.0a3", true),
+            ("Python 3.12.8", false),
+            ("Python 3.12.7", false),
+            ("Python Install Manager 25.0", false),
+        ]
+        .map(|(name, pre_release)| PythonRelease {
+            name: name.to_string(),
+            pre_release,
+        });
+        assert_eq!(
+            latest_python_patch_versions(&releases),
+            BTreeMap::from([((3, 12), 8), ((3, 13), 1)])
+        );
+    }
+
+    #[test]
+    fn update_python_versions_updates_available_versions() {
+        let contents = indoc! {r#"
+            # Comment
+            base_url = "https://example.com"
+
+            [[releases]]
+            version = "3.13.1"
+            targets = ["ubuntu-24.04-amd64", "ubuntu-24.04-arm64"]
+
+            [[releases]]
+            version = "3.12.1"
+            targets = ["ubuntu-24.04-amd64", "ubuntu-24.04-arm64"]
+
+            [checksums]
+            "python-3.13.0-ubuntu-24.04-amd64.tar.zst" = "old"
+            "python-3.13.0-ubuntu-24.04-arm64.tar.zst" = "old"
+            "python-3.13.1-ubuntu-24.04-amd64.tar.zst" = "old"
+            "python-3.13.1-ubuntu-24.04-arm64.tar.zst" = "old"
+        "#};
+        let latest_versions = BTreeMap::from([((3, 12), 2), ((3, 13), 2)]);
+
+        let (updated_contents, updates) = update_python_versions(
+            contents,
+            &latest_versions,
+            |base_url, version, target| {
+                assert_eq!(base_url, "https://example.com");
+                ![
+                    ("3.12.0", "ubuntu-24.04-arm64"),
+                    ("3.12.2", "ubuntu-24.04-arm64"),
+                ]
+                .contains(&(version, target))
+            },
+            |base_url, version, target| {
+                assert_eq!(base_url, "https://example.com");
+                Ok(format!("new-{version}-{target}"))
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            updated_contents,
+            indoc! {r#"
+                # Comment
+                base_url = "https://example.com"
+
+                [[releases]]
+                version = "3.13.2"
+                targets = ["ubuntu-24.04-amd64", "ubuntu-24.04-arm64"]
+
+                [[releases]]
+                version = "3.12.1"
+                targets = ["ubuntu-24.04-amd64", "ubuntu-24.04-arm64"]
+
+                [checksums]
+                "python-3.12.0-ubuntu-24.04-amd64.tar.zst" = "new-3.12.0-ubuntu-24.04-amd64"
+                "python-3.12.1-ubuntu-24.04-amd64.tar.zst" = "new-3.12.1-ubuntu-24.04-amd64"
+                "python-3.12.1-ubuntu-24.04-arm64.tar.zst" = "new-3.12.1-ubuntu-24.04-arm64"
+                "python-3.13.0-ubuntu-24.04-amd64.tar.zst" = "old"
+                "python-3.13.0-ubuntu-24.04-arm64.tar.zst" = "old"
+                "python-3.13.1-ubuntu-24.04-amd64.tar.zst" = "old"
+                "python-3.13.1-ubuntu-24.04-arm64.tar.zst" = "old"
+                "python-3.13.2-ubuntu-24.04-amd64.tar.zst" = "new-3.13.2-ubuntu-24.04-amd64"
+                "python-3.13.2-ubuntu-24.04-arm64.tar.zst" = "new-3.13.2-ubuntu-24.04-arm64"
+            "#}
+        );
+        assert_eq!(updates, ["3.13.2"]);
+    }
+
+    #[test]
+    fn update_checksums_adds_table() {
+        assert_eq!(
+            update_checksums(
+                "base_url = \"https://example.com\"\n",
+                &BTreeMap::from([("python-3.13.1.tar.zst".to_string(), "abc".to_string())])
+            ),
+            "base_url = \"https://example.com\"\n\n[checksums]\n\"python-3.13.1.tar.zst\" = \"abc\"\n"
+        );
+    }
+
+    #[test]
+    fn update_python_versions_checksum_error() {
+        let contents = indoc! {r#"
+            base_url = "https://example.com"
+
+            [[releases]]
+            version = "3.13.1"
+            targets = ["ubuntu-24.04-amd64"]
+        "#};
+        assert_eq!(
+            update_python_versions(
+                contents,
+                &BTreeMap::new(),
+                |_, _, _| true,
+                |_, _, _| Err("Unable to download".to_string()),
+            ),
+            Err("Unable to download".to_string())
+        );
+    }
+
+    #[test]
+    fn update_python_versions_invalid_inventory() {
+        assert!(update_python_versions(
+            "releases = 1",
+            &BTreeMap::new(),
+            |_, _, _| true,
+            |_, _, _| Ok(String::new()),
+        )
+        .is_err());
+    }
+}
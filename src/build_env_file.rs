@@ -0,0 +1,162 @@
+use crate::build_log::log_info;
+use crate::utils;
+use libcnb::Env;
+use std::io;
+use std::path::Path;
+
+/// The env file that's loaded by default, if it exists in the app's root directory.
+const DEFAULT_BUILD_ENV_FILE: &str = ".env.build";
+
+/// Loads the env vars from the app's build env file into the build environment, so that
+/// requirements file env var interpolation and the app's build commands can use non-secret
+/// settings that are committed to the app's repository.
+///
+/// The file is `.env.build` unless a different file is configured in `project.toml`. A missing
+/// file is only an error if it was configured explicitly. Env vars that are already set (such as
+/// via the platform's config vars) take precedence over those in the file.
+pub(crate) fn load_build_env_file(
+    app_dir: &Path,
+    env: &mut Env,
+    configured_file: Option<&str>,
+) -> Result<(), BuildEnvFileError> {
+    let file_name = configured_file.unwrap_or(DEFAULT_BUILD_ENV_FILE);
+    let Some(contents) =
+        utils::read_optional_file(&app_dir.join(file_name)).map_err(BuildEnvFileError::ReadFile)?
+    else {
+        return match configured_file {
+            Some(file_name) => Err(BuildEnvFileError::FileNotFound(file_name.to_string())),
+            None => Ok(()),
+        };
+    };
+
+    let mut loaded_names = Vec::new();
+    for (name, value) in parse(&contents).map_err(|line_number| BuildEnvFileError::InvalidLine {
+        file_name: file_name.to_string(),
+        line_number,
+    })? {
+        if !env.contains_key(&name) {
+            env.insert(&name, value);
+            loaded_names.push(name);
+        }
+    }
+
+    // Only the names are logged, since whilst the file shouldn't contain secrets, it may do.
+    if loaded_names.is_empty() {
+        log_info(format!(
+            "No environment variables were loaded from {file_name}"
+        ));
+    } else {
+        log_info(format!(
+            "Loaded environment variables from {file_name}: {}",
+            loaded_names.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Parses the contents of an env file, returning the line number of the first invalid line
+/// if there is one.
+///
+/// Each line must be of form `NAME=value`, optionally prefixed with `export `. Values can be
+/// wrapped in single or double quotes, however, variable interpolation and multi-line values
+/// aren't supported. Empty lines and lines that are comments (that begin with `#`) are ignored.
+fn parse(contents: &str) -> Result<Vec<(String, String)>, usize> {
+    let mut vars = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = line
+            .split_once('=')
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .filter(|(name, _)| is_env_var_name(name))
+            .ok_or(index + 1)?;
+        let value = ['"', '\'']
+            .into_iter()
+            .find_map(|quote| {
+                value
+                    .strip_prefix(quote)
+                    .and_then(|value| value.strip_suffix(quote))
+            })
+            .unwrap_or(value);
+        vars.push((name.to_string(), value.to_string()));
+    }
+    Ok(vars)
+}
+
+fn is_env_var_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Errors that can occur when loading the build env file.
+#[derive(Debug)]
+pub(crate) enum BuildEnvFileError {
+    FileNotFound(String),
+    InvalidLine {
+        file_name: String,
+        line_number: usize,
+    },
+    ReadFile(io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn parse_valid() {
+        assert_eq!(
+            parse(indoc! {r#"
+                # A comment
+                DJANGO_SETTINGS_MODULE=myapp.settings.production
+
+                export PIP_INDEX_URL = "https://example.com/simple"
+                  EMPTY=
+                QUOTED='a "value" with = signs'
+                MISMATCHED="value'
+            "#})
+            .unwrap(),
+            [
+                (
+                    "DJANGO_SETTINGS_MODULE".to_string(),
+                    "myapp.settings.production".to_string()
+                ),
+                (
+                    "PIP_INDEX_URL".to_string(),
+                    "https://example.com/simple".to_string()
+                ),
+                ("EMPTY".to_string(), String::new()),
+                (
+                    "QUOTED".to_string(),
+                    r#"a "value" with = signs"#.to_string()
+                ),
+                ("MISMATCHED".to_string(), r#""value'"#.to_string()),
+            ]
+        );
+        assert_eq!(parse("").unwrap(), []);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(parse("A=1\nnot an assignment\n"), Err(2));
+        assert_eq!(parse("\n\n1INVALID=value"), Err(3));
+        assert_eq!(parse("=value"), Err(1));
+    }
+
+    #[test]
+    fn load_build_env_file_missing() {
+        let app_dir = Path::new("tests/fixtures/empty");
+        let mut env = Env::new();
+        load_build_env_file(app_dir, &mut env, None).unwrap();
+        assert_eq!(env.iter().count(), 0);
+        assert!(matches!(
+            load_build_env_file(app_dir, &mut env, Some("config/build.env")),
+            Err(BuildEnvFileError::FileNotFound(file_name)) if file_name == "config/build.env"
+        ));
+    }
+}
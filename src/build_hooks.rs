@@ -0,0 +1,69 @@
+use crate::build_log;
+use crate::utils::{self, StreamedCommandError};
+use libcnb::Env;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// The scripts that apps can use to run their own build steps, named the same as in the classic
+/// Heroku Python buildpack for compatibility.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum BuildHook {
+    /// Run before the app's dependencies are installed (and before the package manager is
+    /// determined), so that it can generate files such as `requirements.txt`.
+    PreCompile,
+    /// Run after the app's dependencies are installed, so that it can use the installed packages.
+    PostCompile,
+}
+
+impl BuildHook {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::PreCompile => "pre_compile",
+            Self::PostCompile => "post_compile",
+        }
+    }
+
+    /// The path of the hook's script, relative to the app root.
+    pub(crate) fn path(self) -> String {
+        format!("bin/{}", self.name())
+    }
+}
+
+/// Runs the app's script for the build hook if it exists, with the current build environment,
+/// in the app directory.
+///
+/// The script is run using Bash, so that it doesn't need to be marked as executable.
+pub(crate) fn run_build_hook(
+    app_dir: &Path,
+    env: &Env,
+    hook: BuildHook,
+) -> Result<(), BuildHookError> {
+    let path = hook.path();
+    if !app_dir
+        .join(&path)
+        .try_exists()
+        .map_err(|io_error| BuildHookError::CheckHookExists(hook, io_error))?
+    {
+        return Ok(());
+    }
+
+    build_log::timed_section(format!("Running the {} hook", hook.name()), || {
+        utils::log_running_command(&path);
+        utils::run_command_and_stream_output(
+            Command::new("bash")
+                .arg(&path)
+                .current_dir(app_dir)
+                .env_clear()
+                .envs(env),
+        )
+    })
+    .map_err(|error| BuildHookError::HookCommand(hook, error))
+}
+
+/// Errors that can occur when running the app's build hooks.
+#[derive(Debug)]
+pub(crate) enum BuildHookError {
+    CheckHookExists(BuildHook, io::Error),
+    HookCommand(BuildHook, StreamedCommandError),
+}
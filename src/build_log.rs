@@ -0,0 +1,354 @@
+use crate::telemetry;
+use libcnb::Env;
+use libherokubuildpack::log;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// The env var used to select the format of the build log, for CI systems and platforms that
+/// parse the build output. Supported values are `text` (the default) and `json`.
+pub(crate) const BUILD_LOG_FORMAT_ENV_VAR: &str = "BUILD_LOG_FORMAT";
+
+// The log functions are called from throughout the buildpack (including from `on_error`, which
+// isn't passed the build context), so the state has to be tracked globally.
+static BUILD_LOG: Mutex<BuildLog> = Mutex::new(BuildLog {
+    format: BuildLogFormat::Text,
+    start_time: None,
+    section: None,
+});
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BuildLogFormat {
+    Text,
+    Json,
+}
+
+struct BuildLog {
+    format: BuildLogFormat,
+    start_time: Option<Instant>,
+    section: Option<Section>,
+}
+
+struct Section {
+    name: String,
+    start_time: Instant,
+}
+
+/// The events written (one JSON object per line, to stdout) when using the JSON format.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum BuildLogEvent<'a> {
+    SectionStart {
+        section: &'a str,
+    },
+    SectionEnd {
+        section: &'a str,
+        duration_ms: u64,
+    },
+    Info {
+        section: Option<&'a str>,
+        message: &'a str,
+    },
+    Warning {
+        section: Option<&'a str>,
+        title: &'a str,
+        message: &'a str,
+    },
+    Error {
+        section: Option<&'a str>,
+        title: &'a str,
+        message: &'a str,
+    },
+    /// A line of output from a command run by the buildpack (such as `pip install`).
+    Output {
+        section: Option<&'a str>,
+        stream: OutputStream,
+        line: &'a str,
+    },
+    BuildEnd {
+        success: bool,
+        duration_ms: u64,
+    },
+}
+
+/// The output stream of a command run by the buildpack, which is included in the JSON events,
+/// and determines where the output is written when using the text format.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Configure the build log format using `BUILD_LOG_FORMAT`, and start timing the build. This must
+/// be called before anything is logged, since otherwise the earlier output uses the text format.
+pub(crate) fn configure(env: &Env) -> Result<(), BuildLogError> {
+    let format = match env.get_string_lossy(BUILD_LOG_FORMAT_ENV_VAR) {
+        None => BuildLogFormat::Text,
+        Some(value) => parse_format(&value).ok_or(BuildLogError::UnknownFormat(value))?,
+    };
+    let mut build_log = lock_build_log();
+    build_log.format = format;
+    build_log.start_time = Some(Instant::now());
+    Ok(())
+}
+
+fn parse_format(value: &str) -> Option<BuildLogFormat> {
+    match value.trim().to_lowercase().as_str() {
+        "" | "text" => Some(BuildLogFormat::Text),
+        "json" => Some(BuildLogFormat::Json),
+        _ => None,
+    }
+}
+
+/// Log the start of a new section of the build output, ending the previous section (if any).
+/// When using the JSON format, the duration of each section is recorded when it ends.
+pub(crate) fn log_header(title: impl AsRef<str>) {
+    start_section(title.as_ref());
+}
+
+/// Log the start of a new section for one of the slower build steps (such as installing Python
+/// or the app's dependencies), and then run the step. If it succeeds, the section is ended, and
+/// when using the text format, how long it took is logged (in the form `- Done (1.2s)`).
+pub(crate) fn timed_section<T, E>(
+    title: impl AsRef<str>,
+    f: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    start_section(title.as_ref());
+    let result = f();
+    if result.is_ok() {
+        end_section(&mut lock_build_log(), true);
+    }
+    result
+}
+
+pub(crate) fn log_info(message: impl AsRef<str>) {
+    let message = message.as_ref();
+    let build_log = lock_build_log();
+    match build_log.format {
+        BuildLogFormat::Text => log::log_info(message),
+        BuildLogFormat::Json => write_event(&BuildLogEvent::Info {
+            section: current_section(&build_log),
+            message,
+        }),
+    }
+}
+
+pub(crate) fn log_warning(title: impl AsRef<str>, message: impl AsRef<str>) {
+    let (title, message) = (title.as_ref(), message.as_ref());
+    let build_log = lock_build_log();
+    match build_log.format {
+        BuildLogFormat::Text => log::log_warning(title, message),
+        BuildLogFormat::Json => write_event(&BuildLogEvent::Warning {
+            section: current_section(&build_log),
+            title,
+            message: message.trim_end(),
+        }),
+    }
+}
+
+pub(crate) fn log_error(title: impl AsRef<str>, message: impl AsRef<str>) {
+    let (title, message) = (title.as_ref(), message.as_ref());
+    let build_log = lock_build_log();
+    match build_log.format {
+        BuildLogFormat::Text => log::log_error(title, message),
+        BuildLogFormat::Json => write_event(&BuildLogEvent::Error {
+            section: current_section(&build_log),
+            title,
+            message: message.trim_end(),
+        }),
+    }
+}
+
+/// Write a line of output from a command run by the buildpack. The line is expected to include
+/// its trailing newline (if any), which is omitted from the JSON events.
+pub(crate) fn write_command_output(stream: OutputStream, line: &str) -> io::Result<()> {
+    // The lock is held whilst writing, so that the output from the threads streaming a command's
+    // stdout and stderr isn't interleaved.
+    let build_log = lock_build_log();
+    match (build_log.format, stream) {
+        (BuildLogFormat::Text, OutputStream::Stdout) => write_and_flush(io::stdout(), line),
+        (BuildLogFormat::Text, OutputStream::Stderr) => write_and_flush(io::stderr(), line),
+        (BuildLogFormat::Json, _) => {
+            write_event(&BuildLogEvent::Output {
+                section: current_section(&build_log),
+                stream,
+                line: line.trim_end_matches('\n').trim_end_matches('\r'),
+            });
+            Ok(())
+        }
+    }
+}
+
+/// End the build log, which ends the current section, and when using the JSON format, records
+/// the outcome and duration of the build.
+pub(crate) fn finish(success: bool) {
+    let mut build_log = lock_build_log();
+    end_section(&mut build_log, false);
+    if build_log.format == BuildLogFormat::Json {
+        write_event(&BuildLogEvent::BuildEnd {
+            success,
+            duration_ms: build_log
+                .start_time
+                .map_or(0, |start_time| telemetry::duration_ms(start_time.elapsed())),
+        });
+    }
+}
+
+// A poisoned lock can only be caused by a panic whilst logging, in which case the build has
+// already failed, so the state is used as-is rather than losing the remaining output.
+fn lock_build_log() -> MutexGuard<'static, BuildLog> {
+    BUILD_LOG.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn start_section(title: &str) {
+    let mut build_log = lock_build_log();
+    end_section(&mut build_log, false);
+    match build_log.format {
+        BuildLogFormat::Text => log::log_header(title),
+        BuildLogFormat::Json => write_event(&BuildLogEvent::SectionStart { section: title }),
+    }
+    build_log.section = Some(Section {
+        name: title.to_string(),
+        start_time: Instant::now(),
+    });
+}
+
+fn current_section(build_log: &BuildLog) -> Option<&str> {
+    build_log
+        .section
+        .as_ref()
+        .map(|section| section.name.as_str())
+}
+
+// The duration is always included in the JSON events, but is only shown in the text format for
+// the timed sections, since for the rest it would just be noise.
+fn end_section(build_log: &mut BuildLog, show_duration: bool) {
+    let Some(section) = build_log.section.take() else {
+        return;
+    };
+    let duration = section.start_time.elapsed();
+    match build_log.format {
+        BuildLogFormat::Text if show_duration => {
+            log::log_info(format!("- Done ({})", format_duration(duration)));
+        }
+        BuildLogFormat::Text => {}
+        BuildLogFormat::Json => write_event(&BuildLogEvent::SectionEnd {
+            section: &section.name,
+            duration_ms: telemetry::duration_ms(duration),
+        }),
+    }
+}
+
+// For example: "< 0.1s", "12.3s" or "2m 5s".
+fn format_duration(duration: Duration) -> String {
+    let tenths = duration.as_millis() / 100;
+    if tenths == 0 {
+        "< 0.1s".to_string()
+    } else if tenths < 600 {
+        format!("{}.{}s", tenths / 10, tenths % 10)
+    } else {
+        let secs = duration.as_secs();
+        format!("{}m {}s", secs / 60, secs % 60)
+    }
+}
+
+// Errors writing the events are ignored, since they can only be caused by stdout having been
+// closed, in which case there's nowhere left to report them.
+fn write_event(event: &BuildLogEvent) {
+    if let Ok(json) = serde_json::to_string(event) {
+        let _ = write_and_flush(io::stdout(), &format!("{json}\n"));
+    }
+}
+
+fn write_and_flush(mut writer: impl Write, contents: &str) -> io::Result<()> {
+    writer.write_all(contents.as_bytes())?;
+    writer.flush()
+}
+
+/// Errors that can occur when configuring the build log format.
+#[derive(Debug)]
+pub(crate) enum BuildLogError {
+    UnknownFormat(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_format_valid() {
+        assert_eq!(parse_format("text"), Some(BuildLogFormat::Text));
+        assert_eq!(parse_format(""), Some(BuildLogFormat::Text));
+        assert_eq!(parse_format(" JSON "), Some(BuildLogFormat::Json));
+    }
+
+    #[test]
+    fn configure_unknown_format() {
+        let mut env = Env::new();
+        env.insert(BUILD_LOG_FORMAT_ENV_VAR, "yaml");
+        assert!(matches!(
+            configure(&env),
+            Err(BuildLogError::UnknownFormat(format)) if format == "yaml"
+        ));
+    }
+
+    #[test]
+    fn format_duration_formatting() {
+        assert_eq!(format_duration(Duration::from_millis(99)), "< 0.1s");
+        assert_eq!(format_duration(Duration::from_millis(100)), "0.1s");
+        assert_eq!(format_duration(Duration::from_millis(12_345)), "12.3s");
+        assert_eq!(format_duration(Duration::from_millis(59_999)), "59.9s");
+        assert_eq!(format_duration(Duration::from_secs(60)), "1m 0s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 5s");
+    }
+
+    #[test]
+    fn build_log_event_serialization() {
+        let to_json = |event| serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            to_json(BuildLogEvent::SectionStart {
+                section: "Installing Python"
+            }),
+            r#"{"event":"section_start","section":"Installing Python"}"#
+        );
+        assert_eq!(
+            to_json(BuildLogEvent::SectionEnd {
+                section: "Installing Python",
+                duration_ms: 1234
+            }),
+            r#"{"event":"section_end","section":"Installing Python","duration_ms":1234}"#
+        );
+        assert_eq!(
+            to_json(BuildLogEvent::Info {
+                section: None,
+                message: "Using cached pip"
+            }),
+            r#"{"event":"info","section":null,"message":"Using cached pip"}"#
+        );
+        assert_eq!(
+            to_json(BuildLogEvent::Warning {
+                section: Some("Installing dependencies using pip"),
+                title: "Unpinned dependencies",
+                message: "Line one\nLine two"
+            }),
+            r#"{"event":"warning","section":"Installing dependencies using pip","title":"Unpinned dependencies","message":"Line one\nLine two"}"#
+        );
+        assert_eq!(
+            to_json(BuildLogEvent::Output {
+                section: Some("Installing dependencies using pip"),
+                stream: OutputStream::Stderr,
+                line: "ERROR: No matching distribution found"
+            }),
+            r#"{"event":"output","section":"Installing dependencies using pip","stream":"stderr","line":"ERROR: No matching distribution found"}"#
+        );
+        assert_eq!(
+            to_json(BuildLogEvent::BuildEnd {
+                success: false,
+                duration_ms: 5678
+            }),
+            r#"{"event":"build_end","success":false,"duration_ms":5678}"#
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use libcnb::Env;
+
+/// The env var used to enable dependency plan mode, in which the app's dependencies are only
+/// resolved (and the packages that would be installed shown), after which the build stops.
+pub(crate) const DEPENDENCY_PLAN_ENV_VAR: &str = "PYTHON_DEPENDENCY_PLAN";
+
+/// Whether dependency plan mode has been enabled, which lets users quickly check the effect of
+/// changes to their dependencies, without waiting for the packages to be installed and the rest
+/// of the build to run.
+pub(crate) fn is_plan_only(env: &Env) -> bool {
+    env.get_string_lossy(DEPENDENCY_PLAN_ENV_VAR)
+        .is_some_and(|value| matches!(value.trim(), "1" | "true"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_plan_only_values() {
+        assert!(!is_plan_only(&Env::new()));
+
+        for (value, expected) in [
+            ("", false),
+            ("0", false),
+            ("false", false),
+            ("1", true),
+            ("true", true),
+            (" 1\n", true),
+        ] {
+            let mut env = Env::new();
+            env.insert(DEPENDENCY_PLAN_ENV_VAR, value);
+            assert_eq!(is_plan_only(&env), expected, "value: {value:?}");
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::{project_root, project_toml};
+use libcnb::Env;
 use std::io;
 use std::path::Path;
 
@@ -24,9 +26,27 @@ const KNOWN_PYTHON_PROJECT_FILES: [&str; 14] = [
     "uv.lock",
 ];
 
+/// Returns whether the app is a Python project, and so should pass buildpack detection.
+///
+/// If a project root has been configured (for apps where the Python project is in a subdirectory),
+/// then the project files are looked for in that directory instead. Apps with an invalid project
+/// root (or `project.toml`) still pass detection, so that the error can be shown during the build.
+pub(crate) fn is_python_project(app_dir: &Path, env: &Env) -> io::Result<bool> {
+    let Ok(project_toml) = project_toml::read_project_toml(app_dir) else {
+        return Ok(true);
+    };
+    let configured_project_root = project_toml
+        .as_ref()
+        .and_then(|project_toml| project_toml.python_config().project_root.as_deref());
+    match project_root::determine_project_dir(app_dir, env, configured_project_root) {
+        Ok(project_dir) => is_python_project_directory(&project_dir),
+        Err(_) => Ok(true),
+    }
+}
+
 /// Returns whether the specified project directory is that of a Python project, and so
 /// should pass buildpack detection.
-pub(crate) fn is_python_project_directory(app_dir: &Path) -> io::Result<bool> {
+fn is_python_project_directory(app_dir: &Path) -> io::Result<bool> {
     // Until `Iterator::try_find` is stabilised, this is cleaner as a for loop.
     for filename in KNOWN_PYTHON_PROJECT_FILES {
         let path = app_dir.join(filename);
@@ -60,6 +80,21 @@ mod tests {
         assert!(is_python_project_directory(Path::new("tests/fixtures/empty/.gitkeep")).is_err());
     }
 
+    #[test]
+    fn is_python_project_project_root() {
+        let app_dir = Path::new("tests/fixtures");
+        let detect_with_project_root = |project_root: &str| {
+            let mut env = Env::new();
+            env.insert(project_root::PROJECT_ROOT_ENV_VAR, project_root);
+            is_python_project(app_dir, &env).unwrap()
+        };
+        assert!(detect_with_project_root("pyproject_toml_only"));
+        assert!(!detect_with_project_root("empty"));
+        // Invalid project roots are reported during the build.
+        assert!(detect_with_project_root("non_existent"));
+        assert!(!is_python_project(Path::new("tests/fixtures/empty"), &Env::new()).unwrap());
+    }
+
     #[test]
     fn known_python_project_files_contains_all_package_manager_files() {
         assert!(SUPPORTED_PACKAGE_MANAGERS.iter().all(|package_manager| {
@@ -0,0 +1,635 @@
+use crate::build_log::log_warning;
+use crate::package_manager::PackageManager;
+use crate::project_files::{self, ProjectFileProblem};
+use crate::project_toml::PythonBuildpackConfig;
+use crate::strict_mode::{StrictMode, StrictModeError, WarningClass};
+use crate::{procfile, pyproject_toml, python_version_file, runtime_txt};
+use crate::{system_libraries, utils};
+use indoc::formatdoc;
+use std::io;
+use std::path::Path;
+
+// Files used by Python package managers that this buildpack doesn't support (or by Pipenv, but which
+// aren't used to determine the package manager). If these are present alongside a supported package
+// manager file, the user may be surprised that they are ignored.
+const UNSUPPORTED_PACKAGE_MANAGER_FILES: [&str; 2] = ["Pipfile", "uv.lock"];
+
+// Modules that commonly contain the WSGI/ASGI app, when it's not a Django app.
+const WEB_ENTRYPOINT_MODULES: [&str; 6] = ["app", "main", "wsgi", "asgi", "server", "application"];
+
+// Files larger than this are unlikely to be intentionally committed source code, and
+// increase the size of the app image (slowing down deploys and app boot).
+const LARGE_FILE_THRESHOLD_BYTES: u64 = 100 * 1024 * 1024;
+
+// Directories that are skipped when checking for large files, since they are either
+// not included in the app image, or their contents are not under the user's control.
+const LARGE_FILE_IGNORED_DIRS: [&str; 2] = [".git", "node_modules"];
+
+/// The category of a diagnostic, which is used to group related warnings in the build output.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum DiagnosticCategory {
+    Configuration,
+    Dependencies,
+    Processes,
+    SourceFiles,
+}
+
+impl DiagnosticCategory {
+    fn heading(self) -> &'static str {
+        match self {
+            Self::Configuration => "Possible problems with your app's configuration",
+            Self::Dependencies => "Possible problems with your app's dependencies",
+            Self::Processes => "Possible problems with your app's processes",
+            Self::SourceFiles => "Possible problems with your app's source files",
+        }
+    }
+}
+
+/// A potential problem found in the app source, along with details of how to resolve it.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Diagnostic {
+    pub(crate) category: DiagnosticCategory,
+    pub(crate) message: String,
+    /// The class of the warning, used to suppress it or promote it to an error using strict mode.
+    pub(crate) warning_class: WarningClass,
+}
+
+/// Run fast, read-only checks on the app source for common problems that don't prevent the build
+/// from succeeding, but which are likely to cause problems later (such as when the app boots).
+///
+/// Problems that prevent the build from succeeding are instead handled by the relevant build step,
+/// so that the build can fail with an error specific to that step.
+pub(crate) fn run_diagnostics(
+    app_dir: &Path,
+    package_manager: PackageManager,
+    python_config: &PythonBuildpackConfig,
+    strict_mode: &StrictMode,
+) -> Result<Vec<Diagnostic>, DiagnosticsError> {
+    let mut diagnostics = Vec::new();
+    diagnostics.extend(check_web_process(app_dir, python_config)?);
+    if package_manager == PackageManager::Pip {
+        diagnostics.extend(check_requirements_pinned(
+            app_dir,
+            python_config.requirements_file(),
+            strict_mode.is_enabled(WarningClass::UnpinnedDependencies),
+        )?);
+    }
+    diagnostics.extend(system_libraries::check_system_libraries(
+        app_dir,
+        package_manager,
+        python_config,
+    ));
+    diagnostics.extend(check_runtime_txt(app_dir));
+    diagnostics.extend(check_misnamed_project_files(app_dir)?);
+    diagnostics.extend(check_unsupported_package_manager_files(
+        app_dir,
+        package_manager,
+    )?);
+    diagnostics.extend(check_large_files(app_dir)?);
+    Ok(diagnostics)
+}
+
+fn check_web_process(
+    app_dir: &Path,
+    python_config: &PythonBuildpackConfig,
+) -> Result<Option<Diagnostic>, DiagnosticsError> {
+    let procfile_processes = procfile::read_procfile(app_dir)
+        .map_err(DiagnosticsError::ReadProcfile)?
+        .unwrap_or_default();
+    if !procfile_processes.is_empty() || !python_config.processes.is_empty() {
+        return Ok(None);
+    }
+
+    // Apps without a web app entrypoint are only warned about if they look like a web app,
+    // since otherwise they may be workers or scripts that don't need a 'web' process.
+    let Some(web_entrypoint) =
+        find_web_entrypoint(app_dir).map_err(DiagnosticsError::FindWebEntrypoint)?
+    else {
+        return Ok(None);
+    };
+    let (hint, example_command) = match web_entrypoint {
+        WebEntrypoint::DjangoProject(project_dir) => (
+            format!("A Django project was found in the '{project_dir}' directory."),
+            format!("gunicorn {project_dir}.wsgi"),
+        ),
+        WebEntrypoint::PythonModule(module) => (
+            format!("A possible web app entrypoint was found in '{module}.py'."),
+            format!("gunicorn {module}:app"),
+        ),
+        WebEntrypoint::ProjectScript(script) => (
+            format!("The script '{script}' is declared in your 'pyproject.toml' file."),
+            script,
+        ),
+    };
+
+    Ok(Some(Diagnostic {
+        category: DiagnosticCategory::Processes,
+        warning_class: WarningClass::NoWebProcess,
+        message: formatdoc! {"
+            No processes are defined for your app, so it won't have a 'web' process
+            and so won't be able to receive HTTP requests once deployed.
+
+            {hint}
+
+            To define a web process, create a 'Procfile' in the root of your app's
+            source code that specifies the command used to start your app, such as:
+            web: {example_command}
+
+            Alternatively, configure processes in a 'project.toml' file using a
+            '[com.heroku.buildpacks.python.processes]' table.
+        "},
+    }))
+}
+
+fn check_requirements_pinned(
+    app_dir: &Path,
+    requirements_file: &str,
+    require_all_pinned: bool,
+) -> Result<Option<Diagnostic>, DiagnosticsError> {
+    let Some(contents) = utils::read_optional_file(&app_dir.join(requirements_file))
+        .map_err(DiagnosticsError::ReadRequirementsTxt)?
+    else {
+        return Ok(None);
+    };
+
+    let unpinned_requirements = unpinned_requirements(&contents, require_all_pinned);
+    Ok((!unpinned_requirements.is_empty()).then(|| Diagnostic {
+        category: DiagnosticCategory::Dependencies,
+        warning_class: WarningClass::UnpinnedDependencies,
+        message: formatdoc! {"
+            The following packages in your '{requirements_file}' file are not pinned
+            to an exact version:
+
+            {package_list}
+
+            This means a different version may be installed each time your app is
+            built, which can unexpectedly break your app. Pin each package to an
+            exact version using '==' (for example: 'Django==5.1.4').
+            ",
+            package_list = unpinned_requirements.join("\n"),
+        },
+    }))
+}
+
+fn check_runtime_txt(app_dir: &Path) -> Option<Diagnostic> {
+    // Any errors reading or parsing the files are ignored, since they will be reported when
+    // determining the Python version. Only leftover `runtime.txt` files that request the same
+    // version as `.python-version` are warned about, since otherwise the app may intentionally
+    // be using `runtime.txt` (which is still supported, and whose use is shown in the output
+    // when determining the Python version).
+    let read_file = |filename: &str| {
+        utils::read_optional_file(&app_dir.join(filename))
+            .ok()
+            .flatten()
+    };
+    let (Some(runtime_txt_version), Some(python_version_file_version)) = (
+        read_file("runtime.txt").and_then(|contents| runtime_txt::parse(&contents).ok()),
+        read_file(".python-version")
+            .and_then(|contents| python_version_file::parse(&contents).ok()),
+    ) else {
+        return None;
+    };
+    let same_version = (
+        runtime_txt_version.major,
+        runtime_txt_version.minor,
+        runtime_txt_version.patch,
+    ) == (
+        python_version_file_version.major,
+        python_version_file_version.minor,
+        python_version_file_version.patch,
+    );
+
+    same_version.then(|| Diagnostic {
+        category: DiagnosticCategory::Configuration,
+        warning_class: WarningClass::RuntimeTxtIgnored,
+        message: formatdoc! {"
+            Both a 'runtime.txt' and a '.python-version' file were found, which
+            request the same Python version ({runtime_txt_version}).
+
+            The 'runtime.txt' file takes precedence, so if you later update the
+            version in '.python-version', the change will be ignored. Delete the
+            'runtime.txt' file and use '.python-version' instead.
+        "},
+    })
+}
+
+fn check_misnamed_project_files(app_dir: &Path) -> Result<Option<Diagnostic>, DiagnosticsError> {
+    // A `Pipfile` without a lockfile is instead reported as an unsupported package manager file,
+    // since a package manager has been found.
+    let problems = project_files::check_project_files(app_dir)
+        .map_err(DiagnosticsError::CheckFileExists)?
+        .into_iter()
+        .filter(|problem| *problem != ProjectFileProblem::PipfileWithoutLockfile)
+        .map(|problem| problem.to_string())
+        .collect::<Vec<_>>();
+
+    Ok((!problems.is_empty()).then(|| Diagnostic {
+        category: DiagnosticCategory::Configuration,
+        warning_class: WarningClass::MisnamedProjectFiles,
+        message: formatdoc! {"
+            The following files look like misnamed project files, so will be ignored:
+
+            {problem_list}
+
+            Rename the files to the expected names, so that they are used by the build.
+            Note that filenames are case-sensitive.
+            ",
+            problem_list = problems.join("\n"),
+        },
+    }))
+}
+
+fn check_unsupported_package_manager_files(
+    app_dir: &Path,
+    package_manager: PackageManager,
+) -> Result<Vec<Diagnostic>, DiagnosticsError> {
+    let mut diagnostics = Vec::new();
+    for filename in UNSUPPORTED_PACKAGE_MANAGER_FILES {
+        if package_manager == PackageManager::Pipenv && filename == "Pipfile" {
+            continue;
+        }
+        if app_dir
+            .join(filename)
+            .try_exists()
+            .map_err(DiagnosticsError::CheckFileExists)?
+        {
+            diagnostics.push(Diagnostic {
+                category: DiagnosticCategory::Configuration,
+                warning_class: WarningClass::UnsupportedPackageManagerFile,
+                message: formatdoc! {"
+                    A '{filename}' file was found, however, it will be ignored since this
+                    buildpack installs dependencies using {package_manager} (from '{packages_file}').
+
+                    Delete the '{filename}' file if it is no longer used, to avoid confusion
+                    about which file is used to install your app's dependencies.
+                    ",
+                    package_manager = package_manager.name(),
+                    packages_file = package_manager.packages_file(),
+                },
+            });
+        }
+    }
+    Ok(diagnostics)
+}
+
+fn check_large_files(app_dir: &Path) -> Result<Option<Diagnostic>, DiagnosticsError> {
+    let mut large_files = Vec::new();
+    find_large_files(app_dir, app_dir, &mut large_files)
+        .map_err(DiagnosticsError::FindLargeFiles)?;
+    large_files.sort();
+
+    Ok((!large_files.is_empty()).then(|| Diagnostic {
+        category: DiagnosticCategory::SourceFiles,
+        warning_class: WarningClass::LargeFiles,
+        message: formatdoc! {"
+            The following files in your app's source code are larger than {threshold} MB:
+
+            {file_list}
+
+            Large files increase the size of your app image, which slows down deploys
+            and app boot. If these files aren't needed at runtime, exclude them from
+            your app's source code (for example, using '.gitignore' or 'project.toml').
+            ",
+            threshold = LARGE_FILE_THRESHOLD_BYTES / 1024 / 1024,
+            file_list = large_files.join("\n"),
+        },
+    }))
+}
+
+/// Log the diagnostics as warnings, with one warning per category.
+///
+/// If strict mode is enabled for any of the diagnostics, the build fails instead.
+pub(crate) fn log_diagnostics(
+    diagnostics: &[Diagnostic],
+    strict_mode: &StrictMode,
+) -> Result<(), StrictModeError> {
+    if let Some(diagnostic) = diagnostics
+        .iter()
+        .find(|diagnostic| strict_mode.is_enabled(diagnostic.warning_class))
+    {
+        return strict_mode.warn(
+            diagnostic.warning_class,
+            diagnostic.category.heading(),
+            diagnostic.message.trim_end(),
+        );
+    }
+
+    let diagnostics: Vec<&Diagnostic> = diagnostics
+        .iter()
+        .filter(|diagnostic| !strict_mode.is_suppressed(diagnostic.warning_class))
+        .collect();
+    let mut categories: Vec<DiagnosticCategory> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.category)
+        .collect();
+    categories.sort_unstable();
+    categories.dedup();
+
+    for category in categories {
+        log_warning(
+            category.heading(),
+            diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.category == category)
+                .map(|diagnostic| diagnostic.message.trim_end())
+                .collect::<Vec<&str>>()
+                .join("\n\n"),
+        );
+    }
+    Ok(())
+}
+
+/// Returns the names of any requirements that aren't pinned to an exact version.
+///
+/// Unless `require_all_pinned` is set (by strict mode), requirements are only returned if some of
+/// the other requirements are pinned, since a file that doesn't pin any packages has most likely
+/// been left unpinned intentionally, whereas otherwise the unpinned packages were likely missed.
+///
+/// Lines containing pip options (such as `-r` or `--index-url`) and direct references (such as
+/// VCS or URL requirements) are skipped, since they can't be pinned using a version specifier.
+fn unpinned_requirements(contents: &str, require_all_pinned: bool) -> Vec<String> {
+    // pip supports line continuations, which we join before parsing each requirement.
+    let contents = contents.replace("\\\n", " ");
+    let requirements: Vec<&str> = contents
+        .lines()
+        .map(|line| {
+            // Comments must be preceded by whitespace, otherwise the `#` is part of a URL.
+            let line = line.split(" #").next().unwrap_or_default();
+            // Strip any environment markers, since they can contain version comparisons.
+            line.split(';').next().unwrap_or_default().trim()
+        })
+        .filter(|line| {
+            !line.is_empty()
+                && !line.starts_with(['#', '-'])
+                && !line.contains(" @ ")
+                && !line.contains("://")
+        })
+        .collect();
+    if !require_all_pinned && !requirements.iter().any(|line| line.contains("==")) {
+        return Vec::new();
+    }
+
+    requirements
+        .into_iter()
+        .filter(|line| !line.contains("=="))
+        .map(|line| {
+            line.split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn find_large_files(dir: &Path, app_dir: &Path, large_files: &mut Vec<String>) -> io::Result<()> {
+    for entry in dir.read_dir()? {
+        let entry = entry?;
+        // Unlike `Path::metadata`, the `DirEntry` methods don't follow symlinks, which
+        // avoids counting files twice or traversing directories outside of the app.
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if !LARGE_FILE_IGNORED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()) {
+                find_large_files(&entry.path(), app_dir, large_files)?;
+            }
+        } else if file_type.is_file() && entry.metadata()?.len() > LARGE_FILE_THRESHOLD_BYTES {
+            let path = entry.path();
+            large_files.push(
+                path.strip_prefix(app_dir)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .to_string(),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// A likely entrypoint for the app's web process, used to suggest a command for the process.
+#[derive(Debug, PartialEq)]
+enum WebEntrypoint {
+    /// A Django project directory (one containing a `wsgi.py` module).
+    DjangoProject(String),
+    /// A Python module in the root of the app that commonly contains a WSGI/ASGI app.
+    PythonModule(String),
+    /// A script (console entry point) declared in `pyproject.toml`.
+    ProjectScript(String),
+}
+
+fn find_web_entrypoint(app_dir: &Path) -> io::Result<Option<WebEntrypoint>> {
+    // Django's `startproject` creates the `wsgi.py` module in a directory next to `manage.py`.
+    if app_dir.join("manage.py").try_exists()? {
+        let mut project_dirs = Vec::new();
+        for entry in app_dir.read_dir()? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() && entry.path().join("wsgi.py").try_exists()? {
+                project_dirs.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        project_dirs.sort();
+        if let Some(project_dir) = project_dirs.into_iter().next() {
+            return Ok(Some(WebEntrypoint::DjangoProject(project_dir)));
+        }
+    }
+
+    for module in WEB_ENTRYPOINT_MODULES {
+        if app_dir.join(format!("{module}.py")).try_exists()? {
+            return Ok(Some(WebEntrypoint::PythonModule(module.to_string())));
+        }
+    }
+
+    // Any errors reading pyproject.toml are ignored, since they will be reported by the
+    // relevant build step if the file is used, and this check is only informational.
+    Ok(pyproject_toml::read_pyproject_toml(app_dir)
+        .ok()
+        .flatten()
+        .and_then(|pyproject_toml| {
+            pyproject_toml
+                .script_names()
+                .first()
+                .map(|script| WebEntrypoint::ProjectScript((*script).to_string()))
+        }))
+}
+
+/// Errors that can occur when running the app diagnostics.
+#[derive(Debug)]
+pub(crate) enum DiagnosticsError {
+    CheckFileExists(io::Error),
+    FindLargeFiles(io::Error),
+    FindWebEntrypoint(io::Error),
+    ReadProcfile(io::Error),
+    ReadRequirementsTxt(io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strict_mode::STRICT_MODE_ENV_VAR;
+    use indoc::indoc;
+    use libcnb::Env;
+
+    #[test]
+    fn unpinned_requirements_detected() {
+        let contents = indoc! {"
+            # A comment
+            Django
+            gunicorn>=22.0  # A trailing comment
+            typing-extensions==4.12.2
+            requests[socks]
+            urllib3==2.2.3 ; python_version < '3.10'
+            numpy ; python_version >= '3.10'
+            six===1.16.0
+            pyyaml \\
+                ==6.0.2
+            -r other-requirements.txt
+            --index-url https://example.com/simple
+            -e .
+            mypackage @ https://example.com/mypackage.zip
+            git+https://github.com/example/project.git#egg=project
+        "};
+        assert_eq!(
+            unpinned_requirements(contents, false),
+            ["Django", "gunicorn", "requests", "numpy"]
+        );
+        assert_eq!(
+            unpinned_requirements(contents, true),
+            ["Django", "gunicorn", "requests", "numpy"]
+        );
+    }
+
+    #[test]
+    fn unpinned_requirements_none_pinned() {
+        let contents = indoc! {"
+            Django
+            gunicorn>=22.0
+        "};
+        assert!(unpinned_requirements(contents, false).is_empty());
+        assert_eq!(
+            unpinned_requirements(contents, true),
+            ["Django", "gunicorn"]
+        );
+    }
+
+    #[test]
+    fn find_web_entrypoint_found() {
+        assert_eq!(
+            find_web_entrypoint(Path::new("tests/fixtures/no_web_process")).unwrap(),
+            Some(WebEntrypoint::DjangoProject("testproject".to_string()))
+        );
+        assert_eq!(
+            find_web_entrypoint(Path::new("tests/fixtures/gunicorn_misconfigured")).unwrap(),
+            Some(WebEntrypoint::PythonModule("app".to_string()))
+        );
+    }
+
+    #[test]
+    fn find_web_entrypoint_not_found() {
+        assert_eq!(
+            find_web_entrypoint(Path::new("tests/fixtures/pip_invalid_requirement")).unwrap(),
+            None
+        );
+    }
+
+    fn warning_classes(app_dir: &str, package_manager: PackageManager) -> Vec<WarningClass> {
+        run_diagnostics(
+            Path::new(app_dir),
+            package_manager,
+            &PythonBuildpackConfig::default(),
+            &StrictMode::default(),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|diagnostic| diagnostic.warning_class)
+        .collect()
+    }
+
+    #[test]
+    fn run_diagnostics_no_problems() {
+        assert_eq!(
+            warning_classes("tests/fixtures/pip_basic", PackageManager::Pip),
+            []
+        );
+        assert_eq!(
+            warning_classes("tests/fixtures/pipenv_basic", PackageManager::Pipenv),
+            []
+        );
+        // These fixtures don't define any processes, don't pin any of their requirements, or
+        // have a `runtime.txt` that requests a different version to `.python-version`.
+        assert_eq!(
+            warning_classes("tests/fixtures/django_no_manage_py", PackageManager::Pip),
+            []
+        );
+        assert_eq!(
+            warning_classes(
+                "tests/fixtures/pip_invalid_requirement",
+                PackageManager::Pip
+            ),
+            []
+        );
+        assert_eq!(
+            warning_classes(
+                "tests/fixtures/runtime_txt_and_python_version_file",
+                PackageManager::Pip
+            ),
+            []
+        );
+    }
+
+    #[test]
+    fn run_diagnostics_no_web_process() {
+        assert_eq!(
+            warning_classes("tests/fixtures/no_web_process", PackageManager::Pip),
+            [WarningClass::NoWebProcess]
+        );
+    }
+
+    #[test]
+    fn run_diagnostics_unpinned_requirements() {
+        assert_eq!(
+            warning_classes(
+                "tests/fixtures/pip_unpinned_requirements",
+                PackageManager::Pip
+            ),
+            [WarningClass::UnpinnedDependencies]
+        );
+    }
+
+    #[test]
+    fn run_diagnostics_unpinned_requirements_strict_mode() {
+        let mut env = Env::new();
+        env.insert(STRICT_MODE_ENV_VAR, "unpinned-dependencies");
+        let diagnostics = run_diagnostics(
+            Path::new("tests/fixtures/django_no_manage_py"),
+            PackageManager::Pip,
+            &PythonBuildpackConfig::default(),
+            &StrictMode::from_env(&env).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            diagnostics
+                .into_iter()
+                .map(|diagnostic| diagnostic.warning_class)
+                .collect::<Vec<_>>(),
+            [WarningClass::UnpinnedDependencies]
+        );
+    }
+
+    #[test]
+    fn run_diagnostics_runtime_txt_leftover() {
+        assert_eq!(
+            warning_classes("tests/fixtures/runtime_txt_leftover", PackageManager::Pip),
+            [WarningClass::RuntimeTxtIgnored]
+        );
+    }
+
+    #[test]
+    fn run_diagnostics_misnamed_project_files() {
+        assert_eq!(
+            warning_classes("tests/fixtures/misnamed_project_files", PackageManager::Pip),
+            [
+                WarningClass::MisnamedProjectFiles,
+                WarningClass::UnsupportedPackageManagerFile
+            ]
+        );
+    }
+}
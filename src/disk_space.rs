@@ -0,0 +1,132 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+// The estimated disk space needed to install packages, as a multiple of their download size.
+// Wheels are compressed zip archives that are typically around half the size of the installed
+// package, and the downloaded files are also stored in pip's cache (which is in a layer on the
+// same volume). This intentionally errs on the side of underestimating, since the check fails
+// the build, and a false positive would be worse than not catching a borderline case.
+const REQUIRED_SPACE_MULTIPLIER: u64 = 3;
+
+// If this much space is available, the per-package size lookups are skipped, since even the
+// largest dependency sets (such as PyTorch with its CUDA dependencies) will fit.
+const ALWAYS_SUFFICIENT_BYTES: u64 = 50 * 1024 * 1024 * 1024;
+
+const HEAD_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The estimated disk space required to install a set of packages, and that available.
+#[derive(Debug, PartialEq)]
+pub(crate) struct DiskSpaceEstimate {
+    pub(crate) required_bytes: u64,
+    pub(crate) available_bytes: u64,
+}
+
+impl DiskSpaceEstimate {
+    pub(crate) fn is_sufficient(&self) -> bool {
+        self.required_bytes <= self.available_bytes
+    }
+}
+
+/// Estimate the disk space needed to download and install the packages at the specified URLs,
+/// and compare it to the space available on the volume containing `dir`.
+///
+/// Returns `None` if the available space couldn't be determined, or if there is so much space
+/// available that an estimate isn't necessary. The size of each package is
+/// determined using a `HEAD` request, with any packages whose size is unknown (for example, due
+/// to the index requiring authentication) being excluded from the estimate.
+pub(crate) fn estimate_disk_space(urls: &[&str], dir: &Path) -> Option<DiskSpaceEstimate> {
+    let available_bytes = available_space(dir).filter(|bytes| *bytes < ALWAYS_SUFFICIENT_BYTES)?;
+    let agent = ureq::AgentBuilder::new()
+        .timeout(HEAD_REQUEST_TIMEOUT)
+        .build();
+    let download_bytes: u64 = urls
+        .iter()
+        .filter_map(|url| {
+            agent
+                .head(url)
+                .call()
+                .ok()?
+                .header("Content-Length")?
+                .parse::<u64>()
+                .ok()
+        })
+        .sum();
+    Some(DiskSpaceEstimate {
+        required_bytes: download_bytes * REQUIRED_SPACE_MULTIPLIER,
+        available_bytes,
+    })
+}
+
+/// Formats a number of bytes in gigabytes, for use in log output.
+pub(crate) fn format_gigabytes(bytes: u64) -> String {
+    const BYTES_PER_GIGABYTE: u64 = 1024 * 1024 * 1024;
+    let tenths = (bytes * 10 + BYTES_PER_GIGABYTE / 2) / BYTES_PER_GIGABYTE;
+    format!("{}.{} GB", tenths / 10, tenths % 10)
+}
+
+// Rust's standard library doesn't expose `statvfs`, so we use the POSIX output format of `df`,
+// whose fourth column is the available space in 1024-byte blocks.
+fn available_space(dir: &Path) -> Option<u64> {
+    let output = Command::new("df").arg("-Pk").arg(dir).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_df_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_df_output(output: &str) -> Option<u64> {
+    let available_blocks = output
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse::<u64>()
+        .ok()?;
+    Some(available_blocks * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_df_output_valid() {
+        assert_eq!(
+            parse_df_output(
+                "Filesystem     1024-blocks      Used Available Capacity Mounted on\n/dev/vda1         61202244  13407396  44653620      24% /\n"
+            ),
+            Some(44_653_620 * 1024)
+        );
+        assert_eq!(parse_df_output(""), None);
+        assert_eq!(parse_df_output("Filesystem\n/dev/vda1 unknown"), None);
+    }
+
+    #[test]
+    fn available_space_current_dir() {
+        assert!(available_space(Path::new(".")).is_some());
+    }
+
+    #[test]
+    fn disk_space_estimate_is_sufficient() {
+        let estimate = DiskSpaceEstimate {
+            required_bytes: 10,
+            available_bytes: 10,
+        };
+        assert!(estimate.is_sufficient());
+        let estimate = DiskSpaceEstimate {
+            required_bytes: 11,
+            available_bytes: 10,
+        };
+        assert!(!estimate.is_sufficient());
+    }
+
+    #[test]
+    fn format_gigabytes_rounds() {
+        assert_eq!(format_gigabytes(0), "0.0 GB");
+        assert_eq!(
+            format_gigabytes(5 * 1024 * 1024 * 1024 + 300_000_000),
+            "5.3 GB"
+        );
+    }
+}
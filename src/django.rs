@@ -1,17 +1,64 @@
+use crate::build_log::log_info;
+use crate::strict_mode::{StrictMode, WarningClass};
 use crate::utils::{self, CapturedCommandError, StreamedCommandError};
-use indoc::indoc;
+use indoc::{formatdoc, indoc};
 use libcnb::Env;
-use libherokubuildpack::log::log_info;
-use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::{fs, io};
 
 const MANAGEMENT_SCRIPT_NAME: &str = "manage.py";
 
+const SETTINGS_MODULE_ENV_VAR: &str = "DJANGO_SETTINGS_MODULE";
+
+/// The env var used to opt in to running the app's database migrations during the build.
+pub(crate) const RUN_MIGRATIONS_ENV_VAR: &str = "DJANGO_RUN_MIGRATIONS_DURING_BUILD";
+
+// The names of the modules in a split settings package (one that has a module per environment)
+// that are used for production, in order of preference.
+const PRODUCTION_SETTINGS_MODULE_NAMES: [&str; 3] = ["production", "prod", "heroku"];
+
+// How many directories deep to search for settings modules, relative to the `manage.py` script.
+const SETTINGS_SEARCH_MAX_DEPTH: usize = 3;
+
+// Directories that never contain the app's own settings, and which can be large to search.
+const SETTINGS_SEARCH_IGNORED_DIRS: [&str; 4] =
+    ["node_modules", "site-packages", "staticfiles", "venv"];
+
 pub(crate) fn is_django_installed(dependencies_layer_dir: &Path) -> io::Result<bool> {
     dependencies_layer_dir.join("bin/django-admin").try_exists()
 }
 
+/// Returns the directories containing the app's Django projects, which are either those configured
+/// using the `django-project-dirs` setting in `project.toml` (for monorepos containing multiple
+/// Django services), or otherwise the app's root directory.
+///
+/// Configured directories must contain a `manage.py` script, since otherwise the Django build
+/// steps would be silently skipped for a project the user expects them to run for.
+pub(crate) fn project_dirs(
+    app_dir: &Path,
+    configured_dirs: &[String],
+) -> Result<Vec<PathBuf>, DjangoCollectstaticError> {
+    if configured_dirs.is_empty() {
+        return Ok(vec![app_dir.to_path_buf()]);
+    }
+    configured_dirs
+        .iter()
+        .map(|dir| {
+            let project_dir = app_dir.join(dir);
+            if has_management_script(&project_dir)
+                .map_err(DjangoCollectstaticError::CheckManagementScriptExists)?
+            {
+                Ok(project_dir)
+            } else {
+                Err(DjangoCollectstaticError::ConfiguredProjectNotFound(
+                    dir.clone(),
+                ))
+            }
+        })
+        .collect()
+}
+
 pub(crate) fn run_django_collectstatic(
     app_dir: &Path,
     env: &Env,
@@ -37,7 +84,7 @@ pub(crate) fn run_django_collectstatic(
         return Ok(());
     }
 
-    log_info("Running 'manage.py collectstatic'");
+    utils::log_running_command("manage.py collectstatic");
     utils::run_command_and_stream_output(
         Command::new("python")
             .args([
@@ -54,10 +101,226 @@ pub(crate) fn run_django_collectstatic(
     .map_err(DjangoCollectstaticError::CollectstaticCommand)
 }
 
-fn has_management_script(app_dir: &Path) -> io::Result<bool> {
+/// Sets `DJANGO_SETTINGS_MODULE` for the Django build steps, if it isn't already set and the
+/// default settings module in the app's `manage.py` script doesn't exist.
+///
+/// This handles apps whose settings have since been split into a package with a module per
+/// environment (such as `settings/production.py`), and apps whose `manage.py` doesn't set a
+/// default. A settings module is only used if exactly one is found, since otherwise we can't
+/// know which is correct. A warning is shown, so that the choice isn't made silently.
+pub(crate) fn configure_settings_module(
+    app_dir: &Path,
+    env: &mut Env,
+    strict_mode: &StrictMode,
+) -> io::Result<()> {
+    if env.contains_key(SETTINGS_MODULE_ENV_VAR) || !has_management_script(app_dir)? {
+        return Ok(());
+    }
+
+    // Python adds the directory containing the script to `sys.path`, after resolving symlinks.
+    let script_path = fs::canonicalize(app_dir.join(MANAGEMENT_SCRIPT_NAME))?;
+    let project_dir = script_path.parent().unwrap_or(app_dir);
+    let default_module = default_settings_module(&fs::read_to_string(&script_path)?);
+    if let Some(default_module) = &default_module {
+        if settings_module_exists(project_dir, default_module)? {
+            return Ok(());
+        }
+    }
+
+    let mut found_modules = Vec::new();
+    find_settings_modules(project_dir, &mut Vec::new(), &mut found_modules)?;
+    let [settings_module] = found_modules.as_slice() else {
+        return Ok(());
+    };
+
+    let default_description = default_module.map_or_else(
+        || "doesn't set a default settings module".to_string(),
+        |module| format!("sets a default settings module of '{module}', which doesn't exist"),
+    );
+    strict_mode.log_warning(
+        WarningClass::DjangoSettingsModule,
+        "Django settings module not configured",
+        formatdoc! {"
+            The {SETTINGS_MODULE_ENV_VAR} environment variable isn't set, and your app's
+            'manage.py' script {default_description}.
+
+            Using the settings module '{settings_module}' instead, which was found
+            in your app's source code.
+
+            To use a different settings module, set the {SETTINGS_MODULE_ENV_VAR}
+            environment variable, or update the default in your 'manage.py' script.
+        "},
+    );
+    env.insert(SETTINGS_MODULE_ENV_VAR, settings_module);
+    Ok(())
+}
+
+/// Runs Django's deployment checks, which report common security and configuration problems
+/// with settings that are only appropriate for local development (such as `DEBUG = True`).
+///
+/// Django's `--fail-level` option is used so that warnings (and not just errors) fail the
+/// command, since the check is only run when it has been enabled via strict mode.
+pub(crate) fn run_django_deploy_check(
+    app_dir: &Path,
+    env: &Env,
+) -> Result<(), DjangoDeployCheckError> {
+    if !has_management_script(app_dir)
+        .map_err(DjangoDeployCheckError::CheckManagementScriptExists)?
+    {
+        log_info(indoc! {"
+            Skipping the Django deployment checks since no Django 'manage.py'
+            script (or symlink to one) was found in the root directory of your
+            application."
+        });
+        return Ok(());
+    }
+
+    utils::log_running_command("manage.py check --deploy");
+    utils::run_command_and_stream_output(
+        Command::new("python")
+            .args([
+                MANAGEMENT_SCRIPT_NAME,
+                "check",
+                "--deploy",
+                "--fail-level",
+                "WARNING",
+            ])
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+    )
+    .map_err(DjangoDeployCheckError::DeployCheckCommand)
+}
+
+/// Whether running the Django database migrations during the build has been enabled.
+///
+/// Migrations aren't run by default, since the database often isn't reachable from the build
+/// environment, and the migrations would be applied even if the app is never deployed. Running
+/// them using a `release` process is recommended instead, where that's supported.
+pub(crate) fn is_migrate_enabled(env: &Env) -> bool {
+    env.get_string_lossy(RUN_MIGRATIONS_ENV_VAR)
+        .is_some_and(|value| matches!(value.trim(), "1" | "true"))
+}
+
+/// Runs the app's Django database migrations using `manage.py migrate`.
+pub(crate) fn run_django_migrate(app_dir: &Path, env: &Env) -> Result<(), DjangoMigrateError> {
+    if !has_management_script(app_dir).map_err(DjangoMigrateError::CheckManagementScriptExists)? {
+        log_info(indoc! {"
+            Skipping the Django database migrations since no Django 'manage.py'
+            script (or symlink to one) was found in the root directory of your
+            application."
+        });
+        return Ok(());
+    }
+
+    utils::log_running_command("manage.py migrate --noinput");
+    utils::run_command_and_stream_output(
+        Command::new("python")
+            .args([MANAGEMENT_SCRIPT_NAME, "migrate", "--noinput"])
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+    )
+    .map_err(DjangoMigrateError::MigrateCommand)
+}
+
+pub(crate) fn has_management_script(app_dir: &Path) -> io::Result<bool> {
     app_dir.join(MANAGEMENT_SCRIPT_NAME).try_exists()
 }
 
+// The default settings module set by `manage.py` scripts generated by Django's `startproject`:
+// `os.environ.setdefault("DJANGO_SETTINGS_MODULE", "mysite.settings")`
+fn default_settings_module(management_script: &str) -> Option<String> {
+    management_script.lines().find_map(|line| {
+        let (_, arguments) = line.split_once(SETTINGS_MODULE_ENV_VAR)?;
+        let module = arguments
+            .split(['"', '\''])
+            .map(str::trim)
+            .find(|part| !part.is_empty() && !part.starts_with(','))?;
+        module
+            .split('.')
+            .all(is_identifier)
+            .then(|| module.to_string())
+    })
+}
+
+// Whether the module exists, either as a `.py` file or a package. Packages whose `__init__.py`
+// is empty (other than comments) are treated as not existing, since they are most likely a split
+// settings package, whose settings have to be imported from one of its modules.
+fn settings_module_exists(project_dir: &Path, module: &str) -> io::Result<bool> {
+    let module_path = module
+        .split('.')
+        .fold(project_dir.to_path_buf(), |path, name| path.join(name));
+    if module_path.with_extension("py").try_exists()? {
+        return Ok(true);
+    }
+    Ok(utils::read_optional_file(&module_path.join("__init__.py"))?
+        .is_some_and(|contents| !is_empty_module(&contents)))
+}
+
+fn find_settings_modules(
+    dir: &Path,
+    packages: &mut Vec<String>,
+    found_modules: &mut Vec<String>,
+) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<PathBuf>>>()?;
+    entries.sort();
+
+    for path in entries {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name == "settings.py" && path.is_file() {
+            found_modules.push(module_name(packages, "settings"));
+        } else if name == "settings" && path.is_dir() {
+            if let Some(init_contents) = utils::read_optional_file(&path.join("__init__.py"))? {
+                if !is_empty_module(&init_contents) {
+                    found_modules.push(module_name(packages, "settings"));
+                } else if let Some(production_module) = PRODUCTION_SETTINGS_MODULE_NAMES
+                    .into_iter()
+                    .find(|module| path.join(format!("{module}.py")).is_file())
+                {
+                    found_modules.push(format!(
+                        "{}.{production_module}",
+                        module_name(packages, "settings")
+                    ));
+                }
+            }
+        } else if path.is_dir()
+            && !path.is_symlink()
+            && packages.len() + 1 < SETTINGS_SEARCH_MAX_DEPTH
+            && is_identifier(&name)
+            && !SETTINGS_SEARCH_IGNORED_DIRS.contains(&name.as_ref())
+        {
+            packages.push(name.to_string());
+            find_settings_modules(&path, packages, found_modules)?;
+            packages.pop();
+        }
+    }
+    Ok(())
+}
+
+fn module_name(packages: &[String], name: &str) -> String {
+    packages
+        .iter()
+        .map(String::as_str)
+        .chain([name])
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn is_empty_module(contents: &str) -> bool {
+    contents
+        .lines()
+        .map(str::trim)
+        .all(|line| line.is_empty() || line.starts_with('#'))
+}
+
 fn has_collectstatic_command(app_dir: &Path, env: &Env) -> Result<bool, CapturedCommandError> {
     utils::run_command_and_capture_output(
         Command::new("python")
@@ -89,12 +352,38 @@ pub(crate) enum DjangoCollectstaticError {
     CheckCollectstaticCommandExists(CapturedCommandError),
     CheckManagementScriptExists(io::Error),
     CollectstaticCommand(StreamedCommandError),
+    ConfiguredProjectNotFound(String),
+}
+
+/// Errors that can occur when running the Django deployment checks.
+#[derive(Debug)]
+pub(crate) enum DjangoDeployCheckError {
+    CheckManagementScriptExists(io::Error),
+    DeployCheckCommand(StreamedCommandError),
+}
+
+/// Errors that can occur when running the Django database migrations.
+#[derive(Debug)]
+pub(crate) enum DjangoMigrateError {
+    CheckManagementScriptExists(io::Error),
+    MigrateCommand(StreamedCommandError),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn is_migrate_enabled_values() {
+        assert!(!is_migrate_enabled(&Env::new()));
+
+        for (value, expected) in [("", false), ("0", false), ("1", true), ("true", true)] {
+            let mut env = Env::new();
+            env.insert(RUN_MIGRATIONS_ENV_VAR, value);
+            assert_eq!(is_migrate_enabled(&env), expected, "value: {value:?}");
+        }
+    }
+
     #[test]
     fn has_management_script_django_project() {
         assert!(has_management_script(Path::new(
@@ -108,6 +397,110 @@ mod tests {
         assert!(!has_management_script(Path::new("tests/fixtures/empty")).unwrap());
     }
 
+    #[test]
+    fn project_dirs_default() {
+        let app_dir = Path::new("tests/fixtures/empty");
+        assert_eq!(project_dirs(app_dir, &[]).unwrap(), [app_dir]);
+    }
+
+    #[test]
+    fn project_dirs_configured() {
+        let app_dir = Path::new("tests/fixtures");
+        assert_eq!(
+            project_dirs(app_dir, &["django_staticfiles_latest_django".to_string()]).unwrap(),
+            [app_dir.join("django_staticfiles_latest_django")]
+        );
+        assert!(matches!(
+            project_dirs(app_dir, &["empty".to_string()]),
+            Err(DjangoCollectstaticError::ConfiguredProjectNotFound(dir)) if dir == "empty"
+        ));
+    }
+
+    #[test]
+    fn default_settings_module_valid() {
+        assert_eq!(
+            default_settings_module(
+                "    os.environ.setdefault(\"DJANGO_SETTINGS_MODULE\", \"testproject.settings\")"
+            )
+            .as_deref(),
+            Some("testproject.settings")
+        );
+        assert_eq!(
+            default_settings_module(
+                "os.environ.setdefault('DJANGO_SETTINGS_MODULE','app.settings.dev')"
+            )
+            .as_deref(),
+            Some("app.settings.dev")
+        );
+        assert_eq!(
+            default_settings_module("os.environ.setdefault(\"DJANGO_SETTINGS_MODULE\", \"nonexistent-module.settings\")"),
+            None
+        );
+        assert_eq!(default_settings_module("import os"), None);
+    }
+
+    #[test]
+    fn configure_settings_module_split_settings() {
+        let mut env = Env::new();
+        configure_settings_module(
+            Path::new("tests/fixtures/django_split_settings"),
+            &mut env,
+            &StrictMode::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            env.get_string_lossy("DJANGO_SETTINGS_MODULE").as_deref(),
+            Some("testproject.settings.production")
+        );
+    }
+
+    #[test]
+    fn configure_settings_module_already_set() {
+        let mut env = Env::new();
+        env.insert("DJANGO_SETTINGS_MODULE", "testproject.settings.development");
+        configure_settings_module(
+            Path::new("tests/fixtures/django_split_settings"),
+            &mut env,
+            &StrictMode::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            env.get_string_lossy("DJANGO_SETTINGS_MODULE").as_deref(),
+            Some("testproject.settings.development")
+        );
+    }
+
+    #[test]
+    fn configure_settings_module_default_exists() {
+        let mut env = Env::new();
+        configure_settings_module(
+            Path::new("tests/fixtures/django_staticfiles_latest_django"),
+            &mut env,
+            &StrictMode::default(),
+        )
+        .unwrap();
+        assert!(!env.contains_key("DJANGO_SETTINGS_MODULE"));
+    }
+
+    #[test]
+    fn configure_settings_module_none_found() {
+        let mut env = Env::new();
+        configure_settings_module(
+            Path::new("tests/fixtures/django_invalid_settings_module"),
+            &mut env,
+            &StrictMode::default(),
+        )
+        .unwrap();
+        assert!(!env.contains_key("DJANGO_SETTINGS_MODULE"));
+        configure_settings_module(
+            Path::new("tests/fixtures/empty"),
+            &mut env,
+            &StrictMode::default(),
+        )
+        .unwrap();
+        assert!(!env.contains_key("DJANGO_SETTINGS_MODULE"));
+    }
+
     #[test]
     fn has_management_script_io_error() {
         assert!(has_management_script(Path::new("tests/fixtures/empty/.gitkeep")).is_err());
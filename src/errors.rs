@@ -1,37 +1,84 @@
+use crate::build_env_file::BuildEnvFileError;
+use crate::build_hooks::{BuildHook, BuildHookError};
+use crate::build_log::{self, BuildLogError, BUILD_LOG_FORMAT_ENV_VAR};
 use crate::checks::ChecksError;
-use crate::django::DjangoCollectstaticError;
+use crate::dependency_plan::DEPENDENCY_PLAN_ENV_VAR;
+use crate::diagnostics::DiagnosticsError;
+use crate::disk_space::{self, DiskSpaceEstimate};
+use crate::django::{
+    DjangoCollectstaticError, DjangoDeployCheckError, DjangoMigrateError, RUN_MIGRATIONS_ENV_VAR,
+};
+use crate::gunicorn::GunicornChecksError;
+use crate::import_check::{ImportCheckError, IMPORT_CHECK_ENV_VAR};
+use crate::install_failures::{self, InstallFailure};
+use crate::layers::additional_python::AdditionalPythonError;
+use crate::layers::assets::AssetsLayerError;
+use crate::layers::cached_packages::CachedPackagesLayerError;
+use crate::layers::dependency_report::DependencyReportLayerError;
+use crate::layers::nltk_data::NltkDataLayerError;
+use crate::layers::pdm::PdmLayerError;
+use crate::layers::pdm_dependencies::PdmDependenciesLayerError;
 use crate::layers::pip::PipLayerError;
-use crate::layers::pip_dependencies::PipDependenciesLayerError;
+use crate::layers::pip_cache::{PipCacheLayerError, PIP_CACHE_MAX_SIZE_ENV_VAR};
+use crate::layers::pip_dependencies::{
+    HashCheckingFailure, PipDependenciesLayerError, DEPENDENCY_CHECK_ENV_VAR,
+};
+use crate::layers::pipenv::PipenvLayerError;
+use crate::layers::pipenv_dependencies::PipenvDependenciesLayerError;
 use crate::layers::poetry::PoetryLayerError;
 use crate::layers::poetry_dependencies::PoetryDependenciesLayerError;
-use crate::layers::python::PythonLayerError;
+use crate::layers::python::{
+    PythonLayerError, RUNTIME_MIRROR_AUTHORIZATION_ENV_VAR, RUNTIME_MIRROR_URL_ENV_VAR,
+};
+use crate::package_index::PackageIndexError;
+use crate::package_inventory::{ListInstalledPackagesError, PACKAGES_FILE_NAME};
 use crate::package_manager::DeterminePackageManagerError;
+use crate::packaging_tool_install::PackagingToolInstallError;
+use crate::pdm_lock::ReadPdmLockError;
+use crate::pip_extra_args::{PipExtraArgsError, PIP_EXTRA_ARGS_ENV_VAR};
+use crate::pipfile::ReadPipfileError;
+use crate::poetry_lock::ReadPoetryLockError;
+use crate::processes::ConfiguredProcessesError;
+use crate::project_root::{ProjectRootError, PROJECT_ROOT_ENV_VAR};
+use crate::project_toml::ReadProjectTomlError;
+use crate::pyproject_toml::ReadPyprojectTomlError;
+use crate::python_path::PythonPathError;
 use crate::python_version::{
-    RequestedPythonVersion, RequestedPythonVersionError, ResolvePythonVersionError,
-    DEFAULT_PYTHON_FULL_VERSION, DEFAULT_PYTHON_VERSION,
+    self, PythonVersion, PythonVersionOrigin, RequestedPythonVersion, RequestedPythonVersionError,
+    ResolvePythonVersionError, ResolveRequiresPythonError, DEFAULT_PYTHON_VERSION,
+    PYTHON_VERSION_ENV_VAR,
 };
 use crate::python_version_file::ParsePythonVersionFileError;
+use crate::redact::redact_url_credentials;
 use crate::runtime_txt::ParseRuntimeTxtError;
-use crate::utils::{CapturedCommandError, DownloadUnpackArchiveError, StreamedCommandError};
+use crate::strict_mode::{StrictModeError, STRICT_MODE_ENV_VAR};
+use crate::telemetry;
+use crate::utils::{
+    CapturedCommandError, DownloadUnpackArchiveError, RetriedCommandError, StreamedCommandError,
+    DOWNLOAD_CONNECT_TIMEOUT, DOWNLOAD_READ_TIMEOUT, DOWNLOAD_TOTAL_TIMEOUT,
+};
 use crate::BuildpackError;
 use indoc::{formatdoc, indoc};
-use libherokubuildpack::log::log_error;
 use std::io;
+use std::process::ExitStatus;
 
 /// Handle any non-recoverable buildpack or libcnb errors that occur.
 ///
 /// The buildpack will exit non-zero after this handler has run, so all that needs to be
-/// performed here is the logging of an error message - and in the future, emitting metrics.
+/// performed here is the logging of an error message, and sending the build telemetry.
+/// When using the JSON build log format, the log is also ended, so that it records the failure.
 ///
 /// We're intentionally not using `libherokubuildpack::error::on_error` since:
 /// - It doesn't currently do anything other than logging an internal error for the libcnb
 ///   error case, and by inlining that here it's easier to keep the output consistent with
 ///   the messages emitted for buildpack-specific errors.
 /// - Using it causes trait mismatch errors when Dependabot PRs incrementally update crates.
-/// - When we want to add metrics to our buildpacks, it's going to need a rewrite of
-///   `Buildpack::on_error` anyway (we'll need to write out metrics not log them, so will need
-///   access to the `BuildContext`), at which point we can re-evaluate.
+/// - Telemetry needs state from the build, which `Buildpack::on_error` doesn't have access to,
+///   so the telemetry module has to track that state itself (see `telemetry.rs`).
 pub(crate) fn on_error(error: libcnb::Error<BuildpackError>) {
+    // The error code is determined up front, since the error is consumed when logging it.
+    // Telemetry is sent afterwards, so that the error message is shown without delay.
+    let error_code = telemetry::error_code(&error);
     match error {
         libcnb::Error::BuildpackError(buildpack_error) => on_buildpack_error(buildpack_error),
         libcnb_error => log_error(
@@ -44,24 +91,264 @@ pub(crate) fn on_error(error: libcnb::Error<BuildpackError>) {
                 Details: {libcnb_error}
             "},
         ),
-    };
+    }
+    build_log::finish(false);
+    telemetry::send_build_failure(error_code);
 }
 
 fn on_buildpack_error(error: BuildpackError) {
     match error {
+        BuildpackError::AdditionalPython(error) => on_additional_python_error(error),
+        BuildpackError::AssetsLayer(error) => on_assets_layer_error(error),
+        BuildpackError::BuildEnvFile(error) => on_build_env_file_error(error),
+        BuildpackError::BuildHook(error) => on_build_hook_error(error),
+        BuildpackError::BuildLog(error) => on_build_log_error(error),
         BuildpackError::BuildpackDetection(error) => on_buildpack_detection_error(&error),
+        BuildpackError::CachedPackagesLayer(error) => on_cached_packages_layer_error(error),
         BuildpackError::Checks(error) => on_buildpack_checks_error(error),
+        BuildpackError::ConfiguredProcesses(error) => on_configured_processes_error(error),
+        BuildpackError::DependencyPlanOnly => on_dependency_plan_only_error(),
+        BuildpackError::DependencyReportLayer(error) => on_dependency_report_layer_error(error),
         BuildpackError::DeterminePackageManager(error) => on_determine_package_manager_error(error),
+        BuildpackError::Diagnostics(error) => on_diagnostics_error(error),
         BuildpackError::DjangoCollectstatic(error) => on_django_collectstatic_error(error),
+        BuildpackError::DjangoDeployCheck(error) => on_django_deploy_check_error(error),
         BuildpackError::DjangoDetection(error) => on_django_detection_error(&error),
+        BuildpackError::DjangoMigrate(error) => on_django_migrate_error(error),
+        BuildpackError::DjangoSettingsModule(error) => on_django_settings_module_error(&error),
+        BuildpackError::GunicornChecks(error) => on_gunicorn_checks_error(error),
+        BuildpackError::ImportCheck(error) => on_import_check_error(error),
+        BuildpackError::NltkDataLayer(error) => on_nltk_data_layer_error(error),
+        BuildpackError::PackageIndex(error) => on_package_index_error(error),
+        BuildpackError::PackagesFile(io_error) => log_io_error(
+            "Unable to write the installed packages file",
+            &format!("writing the '{PACKAGES_FILE_NAME}' file to the dependencies layer"),
+            &io_error,
+        ),
+        BuildpackError::PdmDependenciesLayer(error) => on_pdm_dependencies_layer_error(error),
+        BuildpackError::PdmLayer(error) => on_pdm_layer_error(error),
+        BuildpackError::PipCacheLayer(error) => on_pip_cache_layer_error(error),
         BuildpackError::PipDependenciesLayer(error) => on_pip_dependencies_layer_error(error),
         BuildpackError::PipLayer(error) => on_pip_layer_error(error),
+        BuildpackError::PipenvDependenciesLayer(error) => on_pipenv_dependencies_layer_error(error),
+        BuildpackError::PipenvLayer(error) => on_pipenv_layer_error(error),
         BuildpackError::PoetryDependenciesLayer(error) => on_poetry_dependencies_layer_error(error),
         BuildpackError::PoetryLayer(error) => on_poetry_layer_error(error),
+        BuildpackError::ProcfileChecks(error) => on_procfile_checks_error(&error),
+        BuildpackError::ProjectRoot(error) => on_project_root_error(error),
+        BuildpackError::PythonEnvRunLayer(error) => on_python_env_run_layer_error(&error),
         BuildpackError::PythonLayer(error) => on_python_layer_error(error),
+        BuildpackError::PythonPath(error) => on_python_path_error(error),
+        BuildpackError::ReadProjectToml(error) => on_read_project_toml_error(error),
+        BuildpackError::ReadPyprojectToml(error) => on_read_pyproject_toml_error(error),
         BuildpackError::RequestedPythonVersion(error) => on_requested_python_version_error(error),
         BuildpackError::ResolvePythonVersion(error) => on_resolve_python_version_error(error),
-    };
+        BuildpackError::SlimSitePackages(error) => on_slim_site_packages_error(&error),
+        BuildpackError::StrictMode(error) => on_strict_mode_error(error),
+        BuildpackError::VerifyCommand(error) => on_verify_command_error(error),
+    }
+}
+
+fn on_build_hook_error(error: BuildHookError) {
+    match error {
+        BuildHookError::CheckHookExists(hook, io_error) => log_io_error(
+            &format!("Unable to check for a {} hook", hook.name()),
+            &format!("checking if the '{}' script exists", hook.path()),
+            &io_error,
+        ),
+        BuildHookError::HookCommand(hook, error) => match error {
+            StreamedCommandError::Io(io_error) => log_io_error(
+                &format!("Unable to run the {} hook", hook.name()),
+                &format!("running the '{}' script", hook.path()),
+                &io_error,
+            ),
+            StreamedCommandError::NonZeroExitStatus(exit_status) => {
+                let when = match hook {
+                    BuildHook::PreCompile => "before",
+                    BuildHook::PostCompile => "after",
+                };
+                log_error(
+                    format!("The {} hook failed", hook.name()),
+                    formatdoc! {"
+                        The '{path}' script in your app failed ({exit_status}).
+
+                        This script is run {when} your app's dependencies are installed, and
+                        must exit successfully for the build to continue.
+
+                        See the log output above for more information.
+                        ",
+                        path = hook.path(),
+                    },
+                );
+            }
+        },
+    }
+}
+
+fn on_build_log_error(error: BuildLogError) {
+    match error {
+        BuildLogError::UnknownFormat(format) => log_error(
+            "Invalid build log format",
+            formatdoc! {"
+                The '{BUILD_LOG_FORMAT_ENV_VAR}' environment variable is set to an
+                unknown build log format: '{format}'
+                
+                Set it to either 'text' (the default) or 'json'.
+            "},
+        ),
+    }
+}
+
+fn on_assets_layer_error(error: AssetsLayerError) {
+    match error {
+        AssetsLayerError::PrefetchCommand(error) => match error {
+            StreamedCommandError::Io(io_error) => log_io_error(
+                "Unable to prefetch assets",
+                "running the assets prefetch command configured in project.toml",
+                &io_error,
+            ),
+            StreamedCommandError::NonZeroExitStatus(exit_status) => log_error(
+                "Unable to prefetch assets",
+                formatdoc! {"
+                    The assets prefetch command configured in your app's 'project.toml'
+                    file failed ({exit_status}).
+                    
+                    See the log output above for more information.
+                "},
+            ),
+        },
+    }
+}
+
+fn on_nltk_data_layer_error(error: NltkDataLayerError) {
+    match error {
+        NltkDataLayerError::DownloadCommand(error) => match error {
+            StreamedCommandError::Io(io_error) => log_io_error(
+                "Unable to download NLTK data",
+                "running 'python -m nltk.downloader' to download the NLTK data",
+                &io_error,
+            ),
+            StreamedCommandError::NonZeroExitStatus(exit_status) => log_error(
+                "Unable to download NLTK data",
+                formatdoc! {"
+                    The 'python -m nltk.downloader' command to download the NLTK data
+                    listed in your app's 'nltk.txt' file failed ({exit_status}).
+
+                    Check that the names in 'nltk.txt' are valid NLTK data package
+                    names (such as 'punkt_tab' or 'stopwords'), as listed here:
+                    https://www.nltk.org/nltk_data/
+
+                    See the log output above for more information.
+                "},
+            ),
+        },
+        NltkDataLayerError::InvalidCorpusName(name) => log_error(
+            "Invalid nltk.txt file",
+            formatdoc! {"
+                The NLTK data package name '{name}' in your app's 'nltk.txt' file
+                isn't valid.
+
+                The file must contain the names of NLTK data packages (such as
+                'punkt_tab' or 'stopwords'), separated by spaces or newlines. Names
+                can only contain letters, numbers, '_', '-' and '.', and can't start
+                with '-'. See: https://www.nltk.org/nltk_data/
+            "},
+        ),
+        NltkDataLayerError::ReadNltkTxt(io_error) => log_io_error(
+            "Unable to read nltk.txt",
+            "reading the nltk.txt file",
+            &io_error,
+        ),
+    }
+}
+
+fn on_cached_packages_layer_error(error: CachedPackagesLayerError) {
+    match error {
+        CachedPackagesLayerError::InstallCommand(error, python_version) => {
+            on_install_command_error(
+                error,
+                &python_version,
+                "Unable to install cached packages",
+                "running 'pip install' to install the cached packages",
+                |exit_status| {
+                    formatdoc! {"
+                        The 'pip install' command to install the packages listed in the
+                        'cached-packages' setting in your app's 'project.toml' file
+                        failed ({exit_status}).
+
+                        See the log output above for more information.
+                    "}
+                },
+            );
+        }
+        CachedPackagesLayerError::WritePthFile(io_error) => log_io_error(
+            "Unable to configure cached packages",
+            "writing the .pth file that adds the cached packages to the virtual environment",
+            &io_error,
+        ),
+        CachedPackagesLayerError::WriteRequirementsFile(io_error) => log_io_error(
+            "Unable to install cached packages",
+            "writing the requirements file for the cached packages",
+            &io_error,
+        ),
+    }
+}
+
+fn on_dependency_plan_only_error() {
+    log_error(
+        "Build stopped after showing the dependency plan",
+        formatdoc! {"
+            The app's dependencies were resolved, but not installed, since the
+            {DEPENDENCY_PLAN_ENV_VAR} environment variable is set. See the log output
+            above for the packages that would be installed.
+            
+            To build the app, unset the {DEPENDENCY_PLAN_ENV_VAR} environment variable.
+        "},
+    );
+}
+
+fn on_dependency_report_layer_error(error: DependencyReportLayerError) {
+    match error {
+        DependencyReportLayerError::WriteReport(io_error) => log_io_error(
+            "Unable to write the dependency report",
+            "writing the report of the installed packages",
+            &io_error,
+        ),
+    }
+}
+
+fn on_build_env_file_error(error: BuildEnvFileError) {
+    match error {
+        BuildEnvFileError::FileNotFound(file_name) => log_error(
+            "Build env file not found",
+            formatdoc! {"
+                The env file '{file_name}' configured using the 'build-env-file' setting
+                in your app's 'project.toml' file could not be found.
+
+                Check that the file exists and has been committed to your app's
+                repository, and that the path is relative to your app's root directory.
+            "},
+        ),
+        BuildEnvFileError::InvalidLine {
+            file_name,
+            line_number,
+        } => log_error(
+            "Invalid build env file",
+            formatdoc! {"
+                Line {line_number} of the env file '{file_name}' is not in the
+                correct format.
+
+                Each line must be of the form 'NAME=value', where the name only
+                contains letters, numbers and underscores (and doesn't start with
+                a number). Empty lines and lines starting with '#' are ignored.
+            "},
+        ),
+        BuildEnvFileError::ReadFile(io_error) => log_io_error(
+            "Unable to read the build env file",
+            "reading the env file to load into the build environment",
+            &io_error,
+        ),
+    }
 }
 
 fn on_buildpack_detection_error(error: &io::Error) {
@@ -84,7 +371,7 @@ fn on_buildpack_checks_error(error: ChecksError) {
                 yourself, check that it wasn't set by an earlier buildpack.
             "},
         ),
-    };
+    }
 }
 
 fn on_determine_package_manager_error(error: DeterminePackageManagerError) {
@@ -119,14 +406,15 @@ fn on_determine_package_manager_error(error: DeterminePackageManagerError) {
                 "},
             );
         }
-        DeterminePackageManagerError::NoneFound => log_error(
+        DeterminePackageManagerError::NoneFound(problems) => log_error(
             "Couldn't find any supported Python package manager files",
-            indoc! {"
-                Your app must have either a pip requirements file ('requirements.txt')
-                or Poetry lockfile ('poetry.lock') in the root directory of its source
-                code, so your app's dependencies can be installed.
+            formatdoc! {"
+                Your app must have either a pip requirements file ('requirements.txt'),
+                Pipenv lockfile ('Pipfile.lock'), Poetry lockfile ('poetry.lock') or PDM
+                lockfile ('pdm.lock') in the root directory of its source code, so your
+                app's dependencies can be installed.
                 
-                If your app already has one of those files, check that it:
+                {problems_hint}If your app already has one of those files, check that it:
                 
                 1. Is in the top level directory (not a subdirectory).
                 2. Has the correct spelling (the filenames are case-sensitive).
@@ -134,13 +422,115 @@ fn on_determine_package_manager_error(error: DeterminePackageManagerError) {
                 
                 Otherwise, add a package manager file to your app. If your app has
                 no dependencies, then create an empty 'requirements.txt' file.
+                
+                If your app's pip requirements file is in a subdirectory, set the
+                'requirements-file' setting in 'project.toml' to its path.
+                
+                Alternatively, if your app declares its dependencies in the '[project]'
+                table of its 'pyproject.toml' file, then check that the table exists
+                and has a 'name' field, so that pip can install the project.
+                ",
+                problems_hint = if problems.is_empty() {
+                    String::new()
+                } else {
+                    format!(
+                        "The following files look like misnamed (or incomplete) project files:\n\n{}\n\n",
+                        problems.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n")
+                    )
+                },
+            },
+        ),
+        DeterminePackageManagerError::ReadPyprojectToml(error) => {
+            on_read_pyproject_toml_error(error);
+        }
+    }
+}
+
+fn on_diagnostics_error(error: DiagnosticsError) {
+    match error {
+        DiagnosticsError::CheckFileExists(io_error) => log_io_error(
+            "Unable to check app source",
+            "checking which files exist in the app source",
+            &io_error,
+        ),
+        DiagnosticsError::FindLargeFiles(io_error) => log_io_error(
+            "Unable to check app source",
+            "checking for large files in the app source",
+            &io_error,
+        ),
+        DiagnosticsError::FindWebEntrypoint(io_error) => log_io_error(
+            "Unable to check app source",
+            "checking for a web app entrypoint in the app source",
+            &io_error,
+        ),
+        DiagnosticsError::ReadProcfile(io_error) => {
+            log_io_error("Unable to read Procfile", "reading the Procfile", &io_error);
+        }
+        DiagnosticsError::ReadRequirementsTxt(io_error) => log_io_error(
+            "Unable to read requirements file",
+            "reading the app's pip requirements file",
+            &io_error,
+        ),
+    }
+}
+
+fn on_read_project_toml_error(error: ReadProjectTomlError) {
+    match error {
+        ReadProjectTomlError::Io(io_error) => log_io_error(
+            "Unable to read project.toml",
+            "reading the project.toml file",
+            &io_error,
+        ),
+        ReadProjectTomlError::Parse(toml_error) => log_error(
+            "Unable to parse project.toml",
+            formatdoc! {"
+                The 'project.toml' file could not be parsed since it is either not valid
+                TOML, or the '[com.heroku.buildpacks.python]' table contains unknown or
+                invalid configuration.
+
+                Details: {toml_error}
+
+                Check the file for syntax errors, and that the buildpack configuration
+                matches the format described in this buildpack's README.
             "},
         ),
-    };
+    }
+}
+
+fn on_read_pyproject_toml_error(error: ReadPyprojectTomlError) {
+    match error {
+        ReadPyprojectTomlError::Io(io_error) => log_io_error(
+            "Unable to read pyproject.toml",
+            "reading the pyproject.toml file",
+            &io_error,
+        ),
+        ReadPyprojectTomlError::Parse(toml_error) => log_error(
+            "Unable to parse pyproject.toml",
+            formatdoc! {"
+                The 'pyproject.toml' file could not be parsed since it is not valid TOML.
+
+                Details: {toml_error}
+
+                Check the file for syntax errors, such as missing quotes or brackets.
+            "},
+        ),
+    }
 }
 
 fn on_requested_python_version_error(error: RequestedPythonVersionError) {
     match error {
+        RequestedPythonVersionError::InvalidEnvVarVersion(version) => {
+            on_invalid_configured_python_version_error(&PythonVersionOrigin::EnvVar, &version);
+        }
+        RequestedPythonVersionError::InvalidProjectTomlVersion(version) => {
+            on_invalid_configured_python_version_error(&PythonVersionOrigin::ProjectToml, &version);
+        }
+        RequestedPythonVersionError::ReadPyprojectToml(error) => {
+            on_read_pyproject_toml_error(error);
+        }
+        RequestedPythonVersionError::ResolveRequiresPython(error) => {
+            on_resolve_requires_python_error(error);
+        }
         RequestedPythonVersionError::ReadPythonVersionFile(io_error) => log_io_error(
             "Unable to read .python-version",
             "reading the .python-version file",
@@ -161,7 +551,8 @@ fn on_requested_python_version_error(error: RequestedPythonVersionError) {
                     {version}
                     
                     However, the version must be specified as either:
-                    1. '<major>.<minor>' (recommended, for automatic security updates)
+                    1. '<major>.<minor>' or '<major>.<minor>.*' (recommended, for automatic
+                       security updates)
                     2. '<major>.<minor>.<patch>' (to pin to an exact Python version)
                     
                     Do not include quotes or a 'python-' prefix. To include comments, add them
@@ -201,6 +592,7 @@ fn on_requested_python_version_error(error: RequestedPythonVersionError) {
             ),
         },
         RequestedPythonVersionError::ParseRuntimeTxt(ParseRuntimeTxtError { cleaned_contents }) => {
+            let default_python_full_version = python_version::default_python_full_version();
             log_error(
                 "Invalid Python version in runtime.txt",
                 formatdoc! {"
@@ -212,13 +604,108 @@ fn on_requested_python_version_error(error: RequestedPythonVersionError) {
                     However, the file contents must begin with a 'python-' prefix, followed by the
                     version specified as '<major>.<minor>.<patch>'. Comments are not supported.
                     
-                    For example, to request Python {DEFAULT_PYTHON_FULL_VERSION}, update the 'runtime.txt' file so it
+                    For example, to request Python {default_python_full_version}, update the 'runtime.txt' file so it
                     contains exactly:
-                    python-{DEFAULT_PYTHON_FULL_VERSION}
+                    python-{default_python_full_version}
                 "},
             );
         }
+    }
+}
+
+fn on_invalid_configured_python_version_error(origin: &PythonVersionOrigin, version: &str) {
+    let (setting, example) = match origin {
+        PythonVersionOrigin::EnvVar => (
+            format!("the '{PYTHON_VERSION_ENV_VAR}' env var"),
+            format!("set '{PYTHON_VERSION_ENV_VAR}' to:\n{DEFAULT_PYTHON_VERSION}"),
+        ),
+        _ => (
+            "the 'version' setting in 'project.toml'".to_string(),
+            format!("update the setting to:\nversion = \"{DEFAULT_PYTHON_VERSION}\""),
+        ),
     };
+    log_error(
+        format!("Invalid Python version in {origin}"),
+        formatdoc! {"
+            The Python version specified by {setting} is not in the correct format.
+
+            The following version was found:
+            {version}
+
+            However, the version must be specified as either:
+            1. '<major>.<minor>' or '<major>.<minor>.*' (recommended, for automatic
+               security updates)
+            2. '<major>.<minor>.<patch>' (to pin to an exact Python version)
+
+            Do not include quotes or a 'python-' prefix.
+
+            For example, to request the latest version of Python {DEFAULT_PYTHON_VERSION},
+            {example}
+        "},
+    );
+}
+
+fn on_resolve_requires_python_error(error: ResolveRequiresPythonError) {
+    match error {
+        ResolveRequiresPythonError::InvalidSpecifier(requires_python) => log_error(
+            "Invalid requires-python in pyproject.toml",
+            formatdoc! {"
+                The 'requires-python' field in your app's 'pyproject.toml' file
+                couldn't be parsed:
+                {requires_python}
+
+                It must be a list of comma-separated version specifiers, such as:
+                >=3.11,<3.13
+
+                Alternatively, request an exact Python version by creating a
+                '.python-version' file in the root of your app, containing a
+                Python version like '{DEFAULT_PYTHON_VERSION}'.
+            "},
+        ),
+        ResolveRequiresPythonError::NoCompatibleVersion(requires_python) => log_error(
+            "No compatible Python version",
+            formatdoc! {"
+                The 'requires-python' field in your app's 'pyproject.toml' file
+                doesn't match any of the Python versions supported by this buildpack:
+                {requires_python}
+
+                Update 'requires-python' so it includes a supported Python version,
+                such as Python {DEFAULT_PYTHON_VERSION}. For a list of the supported Python versions, see:
+                https://devcenter.heroku.com/articles/python-support#supported-python-versions
+            "},
+        ),
+    }
+}
+
+fn on_additional_python_error(error: AdditionalPythonError) {
+    match error {
+        AdditionalPythonError::DuplicateVersion(version) => log_error(
+            "Invalid additional Python versions",
+            formatdoc! {"
+                The 'additional-python-versions' setting in your app's 'project.toml'
+                file contains more than one version of Python {version}.
+
+                Only one version of each Python X.Y release can be installed, so
+                remove the duplicate version.
+            "},
+        ),
+        AdditionalPythonError::InvalidVersion(version) => log_error(
+            "Invalid additional Python version",
+            formatdoc! {"
+                The version '{version}' in the 'additional-python-versions' setting
+                in your app's 'project.toml' file isn't in the correct format.
+
+                Versions must be of the form 'X.Y' or 'X.Y.Z', for example:
+                additional-python-versions = [\"3.12\", \"3.11\"]
+            "},
+        ),
+        AdditionalPythonError::LinkPythonCommands(io_error) => log_io_error(
+            "Unable to install additional Python versions",
+            "adding the additional Python versions' commands to the PATH",
+            &io_error,
+        ),
+        AdditionalPythonError::ResolveVersion(error) => on_resolve_python_version_error(error),
+    }
 }
 
 fn on_resolve_python_version_error(error: ResolvePythonVersionError) {
@@ -230,6 +717,7 @@ fn on_resolve_python_version_error(error: ResolvePythonVersionError) {
                 origin,
                 ..
             } = requested_python_version;
+            let config_location = origin.config_location();
             log_error(
                 "Requested Python version has reached end-of-life",
                 formatdoc! {"
@@ -240,7 +728,7 @@ fn on_resolve_python_version_error(error: ResolvePythonVersionError) {
                     As such, it is no longer supported by this buildpack.
                     
                     Please upgrade to a newer Python version by updating the version
-                    configured via the {origin} file.
+                    configured via {config_location}.
                     
                     If possible, we recommend upgrading all the way to Python {DEFAULT_PYTHON_VERSION},
                     since it contains many performance and usability improvements.
@@ -248,16 +736,11 @@ fn on_resolve_python_version_error(error: ResolvePythonVersionError) {
             );
         }
         ResolvePythonVersionError::UnknownVersion(requested_python_version) => {
-            let RequestedPythonVersion {
-                major,
-                minor,
-                origin,
-                ..
-            } = requested_python_version;
+            let config_location = requested_python_version.origin.config_location();
             log_error(
                 "Requested Python version is not recognised",
                 formatdoc! {"
-                    The requested Python version {major}.{minor} is not recognised.
+                    The requested Python version {requested_python_version} is not recognised.
                     
                     Check that this Python version has been officially released:
                     https://devguide.python.org/versions/#supported-versions
@@ -265,16 +748,93 @@ fn on_resolve_python_version_error(error: ResolvePythonVersionError) {
                     If it has, make sure that you are using the latest version of this buildpack.
                     
                     If it has not, please switch to a supported version (such as Python {DEFAULT_PYTHON_VERSION})
-                    by updating the version configured via the {origin} file.
+                    by updating the version configured via {config_location}.
                 "},
             );
         }
     }
 }
 
+fn on_python_env_run_layer_error(error: &io::Error) {
+    log_io_error(
+        "Unable to create the python-env-run script",
+        "writing the 'python-env-run' wrapper script to its layer",
+        error,
+    );
+}
+
+fn on_slim_site_packages_error(error: &io::Error) {
+    log_io_error(
+        "Unable to remove the installed packages' test suites",
+        "removing the 'tests' directories of the installed packages (for 'slim-site-packages')",
+        error,
+    );
+}
+
+fn on_python_path_error(error: PythonPathError) {
+    match error {
+        PythonPathError::CheckDirectoryExists(io_error) => log_io_error(
+            "Unable to configure the Python path",
+            "checking if the directories in the 'python-path' setting exist",
+            &io_error,
+        ),
+        PythonPathError::DirectoryNotFound(directory) => log_error(
+            "Python path directory not found",
+            formatdoc! {"
+                The '{directory}' directory listed in the 'python-path' setting in your
+                app's 'project.toml' file doesn't exist.
+
+                Check that the directory name is spelt correctly, and that it's relative
+                to the root directory of your app (or its project root, if configured).
+            "},
+        ),
+        PythonPathError::InvalidDirectory(directory) => log_error(
+            "Invalid Python path directory",
+            formatdoc! {"
+                The '{directory}' directory listed in the 'python-path' setting in your
+                app's 'project.toml' file isn't inside your app's directory.
+
+                Directories must be relative to the root directory of your app, and can't
+                be absolute paths or refer to parent directories using '..'.
+            "},
+        ),
+        PythonPathError::WritePthFile(io_error) => log_io_error(
+            "Unable to configure the Python path",
+            "writing the '.pth' file to the virtual environment",
+            &io_error,
+        ),
+    }
+}
+
 fn on_python_layer_error(error: PythonLayerError) {
     match error {
         PythonLayerError::DownloadUnpackPythonArchive(error) => match error {
+            DownloadUnpackArchiveError::ChecksumMismatch { expected, actual } => log_error(
+                "Python archive checksum mismatch",
+                formatdoc! {"
+                    The downloaded Python runtime archive doesn't match the checksum expected
+                    by the buildpack, so it may have been corrupted or tampered with.
+                    
+                    Expected SHA-256: {expected}
+                    Actual SHA-256: {actual}
+                    
+                    If a Python runtime mirror has been configured using the
+                    {RUNTIME_MIRROR_URL_ENV_VAR} environment variable, check that it
+                    serves unmodified copies of the archives. Otherwise, please try
+                    again to see if the error resolves itself.
+                "},
+            ),
+            DownloadUnpackArchiveError::Download(io_error) => log_error(
+                "Unable to download Python",
+                formatdoc! {"
+                    An error occurred whilst downloading the Python runtime archive.
+                    
+                    In some cases, this happens due to an unstable network connection.
+                    Please try again and to see if the error resolves itself.
+                    
+                    Details: {io_error}
+                "},
+            ),
             DownloadUnpackArchiveError::Request(ureq_error) => log_error(
                 "Unable to download Python",
                 formatdoc! {"
@@ -286,13 +846,35 @@ fn on_python_layer_error(error: PythonLayerError) {
                     Details: {ureq_error}
                 "},
             ),
+            DownloadUnpackArchiveError::Timeout => log_error(
+                "Timed out downloading Python",
+                formatdoc! {"
+                    The download of the Python runtime archive timed out, since either
+                    the connection couldn't be made within {connect_timeout} seconds, no data
+                    was received for {read_timeout} seconds, or the download didn't complete
+                    within {total_timeout} minutes.
+                    
+                    This is caused by a slow or unstable network connection between the
+                    build environment and the server hosting the archive, rather than by
+                    a problem with your app. Please try again to see if the error resolves
+                    itself.
+                    ",
+                    connect_timeout = DOWNLOAD_CONNECT_TIMEOUT.as_secs(),
+                    read_timeout = DOWNLOAD_READ_TIMEOUT.as_secs(),
+                    total_timeout = DOWNLOAD_TOTAL_TIMEOUT.as_secs() / 60,
+                },
+            ),
             DownloadUnpackArchiveError::Unpack(io_error) => log_io_error(
                 "Unable to unpack the Python archive",
                 "unpacking the downloaded Python runtime archive and writing it to disk",
                 &io_error,
             ),
         },
-        // This error will change once the Python version is validated against a manifest.
+        PythonLayerError::MoveBuildOnlyFiles(io_error) => log_io_error(
+            "Unable to separate Python's build-only files",
+            "moving the Python headers and other build-only files to their own layer",
+            &io_error,
+        ),
         // TODO: (W-12613425) Write the supported Python versions inline, instead of linking out to Dev Center.
         // TODO: Decide how to explain to users how stacks, base images and builder images versions relate to each other.
         PythonLayerError::PythonArchiveNotFound { python_version } => log_error(
@@ -307,21 +889,67 @@ fn on_python_layer_error(error: PythonLayerError) {
                 https://devcenter.heroku.com/articles/python-support#supported-runtimes
             "},
         ),
-    };
-}
+        PythonLayerError::RuntimeMirrorAccessDenied(status) => log_error(
+            "Unable to download Python from the mirror",
+            formatdoc! {"
+                The Python runtime mirror configured using {RUNTIME_MIRROR_URL_ENV_VAR}
+                denied access to the Python runtime archive (HTTP status {status}).
 
-fn on_pip_layer_error(error: PipLayerError) {
+                Check that the {RUNTIME_MIRROR_AUTHORIZATION_ENV_VAR} environment
+                variable is set to a valid 'Authorization' header value (such as
+                'Bearer <token>'), which has access to the mirror.
+            "},
+        ),
+    }
+}
+
+fn on_pdm_layer_error(error: PdmLayerError) {
     match error {
-        PipLayerError::InstallPipCommand(error) => match error {
-            StreamedCommandError::Io(io_error) => log_io_error(
-                "Unable to install pip",
-                "running 'python' to install pip",
+        PdmLayerError::InstallPdm(error) => on_packaging_tool_install_error("PDM", error),
+        PdmLayerError::LocateBundledPip(io_error) => log_io_error(
+            "Unable to locate the bundled copy of pip",
+            "locating the pip wheel file bundled inside the Python 'ensurepip' module",
+            &io_error,
+        ),
+    }
+}
+
+fn on_packaging_tool_install_error(tool_name: &str, error: PackagingToolInstallError) {
+    match error {
+        PackagingToolInstallError::HashMismatch(packages) => log_error(
+            format!("Unable to verify {tool_name}'s packages"),
+            formatdoc! {"
+                The hashes of the following packages that were downloaded to install {tool_name}
+                don't match the hashes pinned by the buildpack:
+                
+                {packages}
+                
+                This means the packages served by PyPI (or the package index configured
+                for the app) are different to those the buildpack was released with.
+                They may have been tampered with, so they haven't been installed.
+                
+                If you have configured a custom package index or proxy, check that it
+                serves unmodified copies of the packages from PyPI. Otherwise, this may
+                be a supply chain attack, so please open a support ticket and include
+                the full log output of this build.
+            ",
+                packages = packages
+                    .iter()
+                    .map(|package| format!("- {package}"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            },
+        ),
+        PackagingToolInstallError::InstallCommand(error) => match error {
+            RetriedCommandError::Io(io_error) => log_io_error(
+                &format!("Unable to install {tool_name}"),
+                &format!("running 'python' to install {tool_name}"),
                 &io_error,
             ),
-            StreamedCommandError::NonZeroExitStatus(exit_status) => log_error(
-                "Unable to install pip",
+            RetriedCommandError::NonZeroExitStatus(exit_status, _) => log_error(
+                format!("Unable to install {tool_name}"),
                 formatdoc! {"
-                    The command to install pip did not exit successfully ({exit_status}).
+                    The command to install {tool_name} did not exit successfully ({exit_status}).
                     
                     See the log output above for more information.
                     
@@ -334,16 +962,215 @@ fn on_pip_layer_error(error: PipLayerError) {
                 "},
             ),
         },
+        PackagingToolInstallError::WriteRequirementsFile(io_error) => log_io_error(
+            &format!("Unable to install {tool_name}"),
+            &format!("writing the requirements file used to install {tool_name}"),
+            &io_error,
+        ),
+    }
+}
+
+fn on_package_index_error(error: PackageIndexError) {
+    match error {
+        PackageIndexError::CredentialsEnvVarNotSet(index, env_var) => log_error(
+            "Package index credentials not set",
+            formatdoc! {"
+                The 'package-indexes' setting in your app's 'project.toml' file says that
+                the credentials for the package index '{index}' are in the '{env_var}'
+                environment variable, however, it isn't set (or is empty).
+
+                Check that the environment variable name is correct, and that the
+                environment variable has been set for the app.
+            "},
+        ),
+        PackageIndexError::IncompleteCredentials(index) => log_error(
+            "Incomplete package index credentials",
+            formatdoc! {"
+                The entry for the package index '{index}' in the 'package-indexes' setting
+                in your app's 'project.toml' file only sets one of 'username-env' and
+                'password-env'.
+
+                Set both to the names of the environment variables containing the index's
+                username and password, or remove both if the index doesn't need credentials.
+            "},
+        ),
+        PackageIndexError::InvalidUrl(index, url) => log_error(
+            "Invalid package index URL",
+            formatdoc! {"
+                The URL '{url}' for the package index '{index}' in the 'package-indexes'
+                setting in your app's 'project.toml' file isn't valid.
+
+                Use the full URL of the index, including the scheme, for example:
+                https://example.com/simple
+            "},
+        ),
+        PackageIndexError::MissingUrl(index) => log_error(
+            "Package index URL not set",
+            formatdoc! {"
+                The entry for the package index '{index}' in the 'package-indexes' setting
+                in your app's 'project.toml' file doesn't have a 'url'.
+
+                When using pip, the URL of each package index must be set.
+            "},
+        ),
+        PackageIndexError::MultipleDefaultIndexes => log_error(
+            "Multiple default package indexes",
+            formatdoc! {"
+                More than one entry in the 'package-indexes' setting in your app's
+                'project.toml' file has 'default = true'.
+
+                Only one index can replace PyPI as the default index. Any other indexes
+                are used as extra indexes, and so shouldn't set 'default'.
+            "},
+        ),
+    }
+}
+
+fn on_pdm_dependencies_layer_error(error: PdmDependenciesLayerError) {
+    match error {
+        PdmDependenciesLayerError::CreateVenvCommand(error) => match error {
+            StreamedCommandError::Io(io_error) => log_io_error(
+                "Unable to create virtual environment",
+                "running 'python -m venv' to create a virtual environment",
+                &io_error,
+            ),
+            StreamedCommandError::NonZeroExitStatus(exit_status) => log_error(
+                "Unable to create virtual environment",
+                formatdoc! {"
+                    The 'python -m venv' command to create a virtual environment did
+                    not exit successfully ({exit_status}).
+                    
+                    See the log output above for more information.
+                "},
+            ),
+        },
+        PdmDependenciesLayerError::ListInstalledPackages(error) => {
+            on_list_installed_packages_error(error);
+        }
+        PdmDependenciesLayerError::PdmDryRunCommand(error, python_version) => {
+            on_dry_run_command_error(
+                "PDM",
+                "pdm sync --prod --clean --dry-run",
+                error,
+                &python_version,
+            );
+        }
+        PdmDependenciesLayerError::PdmSyncCommand(error, python_version) => {
+            on_install_command_error(
+                error,
+                &python_version,
+                "Unable to install dependencies using PDM",
+                "running 'pdm sync' to install the app's dependencies",
+                |exit_status| {
+                    formatdoc! {"
+                    The 'pdm sync --prod --clean' command to install the app's
+                    dependencies failed ({exit_status}).
+                    
+                    See the log output above for more information.
+                "}
+                },
+            );
+        }
+        PdmDependenciesLayerError::ReadPdmLock(error) => match error {
+            ReadPdmLockError::Io(io_error) => log_io_error(
+                "Unable to read pdm.lock",
+                "reading the pdm.lock file",
+                &io_error,
+            ),
+            ReadPdmLockError::Parse(toml_error) => log_error(
+                "Unable to parse pdm.lock",
+                formatdoc! {"
+                    The 'pdm.lock' file could not be parsed since it is not valid TOML.
+
+                    Details: {toml_error}
+
+                    Regenerate the lockfile by running 'pdm lock', rather than
+                    editing it by hand.
+                "},
+            ),
+        },
+    }
+}
+
+fn on_pip_cache_layer_error(error: PipCacheLayerError) {
+    match error {
+        PipCacheLayerError::InvalidMaxSize(value) => log_error(
+            "Invalid pip cache size limit",
+            formatdoc! {"
+                The {PIP_CACHE_MAX_SIZE_ENV_VAR} env var is set to '{value}', which
+                isn't a valid size.
+
+                It must be a whole number of megabytes, for example:
+                {PIP_CACHE_MAX_SIZE_ENV_VAR}=2048
+            "},
+        ),
+        PipCacheLayerError::PruneCache(io_error) => log_io_error(
+            "Unable to prune the pip cache",
+            "removing old files from the cached pip download/wheel cache",
+            &io_error,
+        ),
+    }
+}
+
+fn on_dependency_conflicts_error(problems: &str) {
+    log_error(
+        "Incompatible dependencies found",
+        formatdoc! {"
+            The 'pip check' command found the following problems with the
+            dependencies installed from your app's 'requirements.txt' file:
+
+            {problems}
+
+            Update the package versions in 'requirements.txt' so they are compatible.
+
+            This check is failing the build since the '{DEPENDENCY_CHECK_ENV_VAR}'
+            environment variable is set to 'error'.
+        "},
+    );
+}
+
+fn on_invalid_dependency_check_mode_error(value: &str) {
+    log_error(
+        "Invalid dependency check mode",
+        formatdoc! {"
+            The {DEPENDENCY_CHECK_ENV_VAR} env var is set to '{value}', which
+            isn't a valid dependency check mode.
+
+            It must be one of 'warn' (the default), 'error' or 'off', for example:
+            {DEPENDENCY_CHECK_ENV_VAR}=error
+        "},
+    );
+}
+
+fn on_pip_layer_error(error: PipLayerError) {
+    match error {
+        PipLayerError::InstallPip(error) => on_packaging_tool_install_error("pip", error),
         PipLayerError::LocateBundledPip(io_error) => log_io_error(
             "Unable to locate the bundled copy of pip",
             "locating the pip wheel file bundled inside the Python 'ensurepip' module",
             &io_error,
         ),
-    };
+    }
 }
 
 fn on_pip_dependencies_layer_error(error: PipDependenciesLayerError) {
     match error {
+        PipDependenciesLayerError::BuildRequirementsInstallCommand(error, python_version) => {
+            on_install_command_error(
+                error,
+                &python_version,
+                "Unable to install build requirements",
+                "running 'pip install' to install the build requirements",
+                |exit_status| {
+                    formatdoc! {"
+                        The 'pip install' command to install the build requirements listed
+                        in your app's 'project.toml' file failed ({exit_status}).
+
+                        See the log output above for more information.
+                    "}
+                },
+            );
+        }
         PipDependenciesLayerError::CreateVenvCommand(error) => match error {
             StreamedCommandError::Io(io_error) => log_io_error(
                 "Unable to create virtual environment",
@@ -360,61 +1187,561 @@ fn on_pip_dependencies_layer_error(error: PipDependenciesLayerError) {
                 "},
             ),
         },
-        PipDependenciesLayerError::PipInstallCommand(error) => match error {
+        PipDependenciesLayerError::DependencyConflicts(problems) => {
+            on_dependency_conflicts_error(&problems);
+        }
+        PipDependenciesLayerError::HashChecking(failure) => on_hash_checking_failure(failure),
+        PipDependenciesLayerError::InsufficientDiskSpace(estimate) => {
+            on_insufficient_disk_space_error(&estimate);
+        }
+        PipDependenciesLayerError::InvalidDependencyCheckMode(value) => {
+            on_invalid_dependency_check_mode_error(&value);
+        }
+        PipDependenciesLayerError::ListInstalledPackages(error) => {
+            on_list_installed_packages_error(error);
+        }
+        PipDependenciesLayerError::NoBuildIsolationInstallCommand(error, python_version) => {
+            on_install_command_error(
+                error,
+                &python_version,
+                "Unable to install dependencies using pip",
+                "running 'pip install --no-build-isolation' to install dependencies",
+                |exit_status| {
+                    formatdoc! {"
+                        The 'pip install --no-build-isolation' command to install the packages
+                        listed in the 'no-build-isolation' setting in your app's 'project.toml'
+                        file failed ({exit_status}).
+
+                        When build isolation is disabled, a package's build dependencies must
+                        already be installed. Check that they are listed in the 'build-requirements'
+                        setting in 'project.toml'.
+
+                        See the log output above for more information.
+                    "}
+                },
+            );
+        }
+        PipDependenciesLayerError::NoBuildIsolationPackageNotFound(package) => {
+            on_no_build_isolation_package_not_found_error(&package);
+        }
+        PipDependenciesLayerError::PipDryRunCommand(error, python_version) => {
+            on_dry_run_command_error("pip", "pip install --dry-run", error, &python_version);
+        }
+        PipDependenciesLayerError::PipCheckCommand(io_error) => log_io_error(
+            "Unable to check installed dependencies",
+            "running 'pip check' to check the installed dependencies",
+            &io_error,
+        ),
+        // TODO: Add more suggestions here as to causes (eg network, invalid requirements.txt,
+        // package broken or not compatible with version of Python, missing system dependencies etc)
+        PipDependenciesLayerError::PipDownloadCommand(error, python_version) => {
+            on_pip_command_error("download", error, &python_version);
+        }
+        PipDependenciesLayerError::PipExtraArgs(error) => on_pip_extra_args_error(error),
+        PipDependenciesLayerError::PipInstallCommand(error, python_version) => {
+            on_pip_command_error("install", error, &python_version);
+        }
+        PipDependenciesLayerError::PrepareDownloadsDir(io_error) => log_io_error(
+            "Unable to prepare the pip downloads directory",
+            "preparing the directory that pip downloads the app's dependencies into",
+            &io_error,
+        ),
+        PipDependenciesLayerError::ReadPyprojectToml(error) => on_read_pyproject_toml_error(error),
+        PipDependenciesLayerError::ReadRequirementsFile(file, io_error) => log_io_error(
+            &format!("Unable to read {file}"),
+            &format!("reading the {file} file"),
+            &io_error,
+        ),
+    }
+}
+
+fn on_hash_checking_failure(failure: HashCheckingFailure) {
+    let (header, problem, requirements) = match failure {
+        HashCheckingFailure::HashMismatch(requirements) => (
+            "Package hashes don't match the requirements file",
+            indoc! {"
+                The hashes of the following packages that pip downloaded don't match
+                any of the hashes listed for them in your app's requirements file:
+            "},
+            requirements,
+        ),
+        HashCheckingFailure::MissingHashes(requirements) => (
+            "Package hashes missing from the requirements file",
+            indoc! {"
+                In hash-checking mode, every package that's installed must have hashes
+                in the requirements file, including the dependencies of the packages
+                your app uses directly. However, the following packages don't:
+            "},
+            requirements,
+        ),
+        HashCheckingFailure::UnpinnedRequirements(requirements) => (
+            "Unpinned requirements in hash-checking mode",
+            indoc! {"
+                In hash-checking mode, every package that's installed must be pinned
+                to an exact version using '=='. However, the following packages aren't:
+            "},
+            requirements,
+        ),
+    };
+    let requirements = requirements
+        .iter()
+        .map(|requirement| format!("- {requirement}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    log_error(
+        header,
+        formatdoc! {"
+            Your app's requirements file uses pip's hash-checking mode, since it
+            contains '--hash' options (or '--require-hashes' is enabled), which means
+            pip checks every package it downloads against the hashes in the file.
+
+            {problem}
+            {requirements}
+
+            This usually means that the requirements file was edited by hand, or that
+            its package versions were updated without regenerating the hashes.
+
+            To fix this, regenerate the requirements file (including the hashes of
+            all dependencies) using the tool that created it, for example:
+            - pip-tools: 'pip-compile --generate-hashes requirements.in'
+            - uv: 'uv pip compile --generate-hashes requirements.in -o requirements.txt'
+
+            If the hashes are already up to date, check that the package index being
+            used is the same one the hashes were generated from, since otherwise the
+            package files may differ (or may have been tampered with).
+        "},
+    );
+}
+
+fn on_pip_extra_args_error(error: PipExtraArgsError) {
+    let problem = match error {
+        PipExtraArgsError::MissingValue(option) => {
+            format!("The '{option}' option requires a value, however, none was given.")
+        }
+        PipExtraArgsError::UnsupportedArgument(argument) => {
+            format!("The argument '{argument}' isn't supported.")
+        }
+        PipExtraArgsError::UnterminatedQuote => {
+            "A quoted value is missing its closing quote.".to_string()
+        }
+    };
+    log_error(
+        format!("Invalid {PIP_EXTRA_ARGS_ENV_VAR} environment variable"),
+        formatdoc! {"
+            The '{PIP_EXTRA_ARGS_ENV_VAR}' environment variable contains additional options
+            to pass to pip, however, it isn't valid:
+
+            {problem}
+
+            Only the following pip options are supported, since other options
+            would conflict with how the buildpack installs your app's dependencies:
+            --config-settings (-C), --extra-index-url, --find-links (-f),
+            --index-url (-i), --no-binary, --only-binary, --pre, --prefer-binary,
+            --retries, --timeout, --trusted-host, --use-pep517
+
+            Values containing spaces can be quoted, for example:
+            {PIP_EXTRA_ARGS_ENV_VAR}=\"--config-settings 'key=a value'\"
+        "},
+    );
+}
+
+// Handles errors from the `pip download` and `pip install` commands run for the app's requirements.
+fn on_pip_command_error(
+    subcommand: &str,
+    error: RetriedCommandError,
+    python_version: &PythonVersion,
+) {
+    on_install_command_error(
+        error,
+        python_version,
+        &format!("Unable to {subcommand} dependencies using pip"),
+        &format!("running 'pip {subcommand}' to {subcommand} the app's dependencies"),
+        |exit_status| {
+            formatdoc! {"
+                The 'pip {subcommand}' command to {subcommand} the app's dependencies
+                failed ({exit_status}).
+                
+                See the log output above for more information.
+            "}
+        },
+    );
+}
+
+// Handles errors from the commands used to resolve the app's dependencies in dependency plan mode.
+fn on_dry_run_command_error(
+    package_manager: &str,
+    command: &str,
+    error: RetriedCommandError,
+    python_version: &PythonVersion,
+) {
+    on_install_command_error(
+        error,
+        python_version,
+        &format!("Unable to resolve dependencies using {package_manager}"),
+        &format!("running '{command}' to resolve the app's dependencies"),
+        |exit_status| {
+            formatdoc! {"
+                The '{command}' command to resolve the app's
+                dependencies failed ({exit_status}).
+                
+                See the log output above for more information.
+            "}
+        },
+    );
+}
+
+// Handles errors from the package install commands. If the cause of a failed install is known,
+// a more specific error message is shown instead of the generic message for the failed command.
+fn on_install_command_error(
+    error: RetriedCommandError,
+    python_version: &PythonVersion,
+    header: &str,
+    occurred_whilst: &str,
+    failed_command_message: impl FnOnce(ExitStatus) -> String,
+) {
+    match error {
+        RetriedCommandError::Io(io_error) => log_io_error(header, occurred_whilst, &io_error),
+        RetriedCommandError::NonZeroExitStatus(exit_status, output) => {
+            match install_failures::diagnose_install_failure(&output, python_version) {
+                Some(failure) => on_install_failure(failure),
+                None => log_error(header, failed_command_message(exit_status)),
+            }
+        }
+    }
+}
+
+fn on_install_failure(failure: InstallFailure) {
+    match failure {
+        InstallFailure::DependencyConflict { conflicts } => {
+            on_dependency_conflict_failure(&conflicts);
+        }
+        InstallFailure::RequiresPythonMismatch {
+            package,
+            version,
+            requires_python,
+            python_version,
+        } => {
+            let requested_package = version.map_or_else(
+                || format!("'{package}'"),
+                |version| format!("'{package}' (version {version})"),
+            );
+            log_error(
+                format!("Package '{package}' doesn't support Python {python_version}"),
+                formatdoc! {"
+                    The package {requested_package} requires Python '{requires_python}',
+                    however, your app is using Python {python_version}, so the package
+                    can't be installed.
+
+                    To fix this, either:
+                    - Change the Python version requested in your app's '.python-version'
+                      file (or 'runtime.txt' file, if used instead) to a version that
+                      is supported by '{package}'.
+                    - Or, change the version of '{package}' in your app's dependencies
+                      to one that supports Python {major}.{minor}.
+
+                    See the log output above for more information.
+                    ",
+                    major = python_version.major,
+                    minor = python_version.minor,
+                },
+            );
+        }
+        InstallFailure::RustCompilerRequired { package } => {
+            let package = package.map_or_else(
+                || "One of your app's dependencies".to_string(),
+                |package| format!("The package '{package}'"),
+            );
+            log_error(
+                "Unable to build a package that requires Rust",
+                formatdoc! {"
+                    {package} had to be built from source (since no compatible
+                    pre-built wheel was available), however, its build requires a Rust
+                    compiler, which isn't installed.
+
+                    This usually means that the version of the package being installed
+                    doesn't provide wheels for this Python version or CPU architecture.
+
+                    To fix this, either:
+                    - Update the package to a newer version that provides wheels for this
+                      Python version (check the package's page on PyPI for available wheels).
+                    - Or, if building from source is required, install a Rust toolchain by
+                      adding a Rust buildpack before the Python buildpack.
+
+                    See the log output above for more information.
+                "},
+            );
+        }
+        InstallFailure::UnsupportedPythonVersion {
+            package,
+            version,
+            python_version,
+            minimum_version,
+        } => log_error(
+            format!("Unable to build {package} from source"),
+            formatdoc! {"
+                Your app requires {package} {version}, which doesn't support Python {python_version},
+                so no pre-built wheels are available for it. The package therefore had to
+                be built from source, which failed.
+
+                To fix this, update {package} to version {minimum_version} or newer (the first
+                release that supports Python {major}.{minor}), or use an older Python version.
+
+                See the log output above for more information.
+                ",
+                major = python_version.major,
+                minor = python_version.minor,
+            },
+        ),
+    }
+}
+
+fn on_dependency_conflict_failure(conflicts: &[String]) {
+    let conflicts = if conflicts.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "The conflict is caused by:\n{}\n\n",
+            conflicts
+                .iter()
+                .map(|conflict| format!("- {conflict}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    };
+    log_error(
+        "Unable to resolve your app's dependencies",
+        formatdoc! {"
+            The versions of the packages required by your app conflict with each
+            other, so pip was unable to find a set of package versions that
+            satisfies all of the requirements.
+
+            {conflicts}To fix this, either:
+            - Loosen the version pin of one of the conflicting packages in your
+              'requirements.txt' file (for example, by changing '==' to '>='),
+              so that a compatible version can be chosen.
+            - Or, update the package whose dependencies conflict to a newer
+              version that is compatible with your other requirements.
+
+            If your requirements were generated by a lockfile tool (such as
+            pip-compile), check the lockfile is up to date by regenerating it.
+        "},
+    );
+}
+
+fn on_no_build_isolation_package_not_found_error(package: &str) {
+    log_error(
+        "Package not found in requirements.txt",
+        formatdoc! {"
+            The package '{package}' is listed in the 'no-build-isolation' setting
+            in your app's 'project.toml' file, however, it isn't listed in your
+            app's 'requirements.txt' file.
+
+            Add the package to 'requirements.txt', or remove it from the
+            'no-build-isolation' setting.
+        "},
+    );
+}
+
+fn on_insufficient_disk_space_error(estimate: &DiskSpaceEstimate) {
+    log_error(
+        "Not enough disk space to install dependencies",
+        formatdoc! {"
+            Installing your app's dependencies is estimated to need {required}
+            of disk space, however, only {available} is available.
+
+            Reduce the size of your app's dependencies (for example, by using the
+            CPU-only version of PyTorch if your app doesn't use a GPU), or increase
+            the disk space available to the build.
+            ",
+            required = disk_space::format_gigabytes(estimate.required_bytes),
+            available = disk_space::format_gigabytes(estimate.available_bytes),
+        },
+    );
+}
+
+fn on_list_installed_packages_error(error: ListInstalledPackagesError) {
+    match error {
+        ListInstalledPackagesError::Command(error) => match error {
+            CapturedCommandError::Io(io_error) => log_io_error(
+                "Unable to list installed packages",
+                "running 'python' to list the installed packages",
+                &io_error,
+            ),
+            CapturedCommandError::NonZeroExitStatus(output) => log_error(
+                "Unable to list installed packages",
+                formatdoc! {"
+                    The command to list the packages installed in the virtual
+                    environment failed ({exit_status}).
+
+                    Details:
+
+                    {stderr}
+                    ",
+                    exit_status = &output.status,
+                    stderr = String::from_utf8_lossy(&output.stderr)
+                },
+            ),
+        },
+        ListInstalledPackagesError::Parse(json_error) => log_error(
+            "Unable to list installed packages",
+            formatdoc! {"
+                The output of the command to list the packages installed in the
+                virtual environment could not be parsed.
+
+                Details: {json_error}
+            "},
+        ),
+    }
+}
+
+fn on_pipenv_layer_error(error: PipenvLayerError) {
+    match error {
+        PipenvLayerError::InstallPipenv(error) => on_packaging_tool_install_error("Pipenv", error),
+        PipenvLayerError::LocateBundledPip(io_error) => log_io_error(
+            "Unable to locate the bundled copy of pip",
+            "locating the pip wheel file bundled inside the Python 'ensurepip' module",
+            &io_error,
+        ),
+    }
+}
+
+fn on_pipenv_dependencies_layer_error(error: PipenvDependenciesLayerError) {
+    match error {
+        PipenvDependenciesLayerError::CreateVenvCommand(error) => match error {
+            StreamedCommandError::Io(io_error) => log_io_error(
+                "Unable to create virtual environment",
+                "running 'python -m venv' to create a virtual environment",
+                &io_error,
+            ),
+            StreamedCommandError::NonZeroExitStatus(exit_status) => log_error(
+                "Unable to create virtual environment",
+                formatdoc! {"
+                    The 'python -m venv' command to create a virtual environment did
+                    not exit successfully ({exit_status}).
+                    
+                    See the log output above for more information.
+                "},
+            ),
+        },
+        PipenvDependenciesLayerError::ListInstalledPackages(error) => {
+            on_list_installed_packages_error(error);
+        }
+        PipenvDependenciesLayerError::PipenvCleanCommand(error) => match error {
             StreamedCommandError::Io(io_error) => log_io_error(
-                "Unable to install dependencies using pip",
-                "running 'pip install' to install the app's dependencies",
+                "Unable to remove unused packages",
+                "running 'pipenv clean' to remove packages that are no longer in the lockfile",
                 &io_error,
             ),
-            // TODO: Add more suggestions here as to causes (eg network, invalid requirements.txt,
-            // package broken or not compatible with version of Python, missing system dependencies etc)
             StreamedCommandError::NonZeroExitStatus(exit_status) => log_error(
-                "Unable to install dependencies using pip",
+                "Unable to remove unused packages",
                 formatdoc! {"
-                    The 'pip install -r requirements.txt' command to install the app's
-                    dependencies failed ({exit_status}).
+                    The 'pipenv clean' command to remove packages that are no longer in
+                    'Pipfile.lock' from the cached virtual environment failed ({exit_status}).
                     
                     See the log output above for more information.
                 "},
             ),
         },
-    };
-}
-
-fn on_poetry_layer_error(error: PoetryLayerError) {
-    match error {
-        PoetryLayerError::InstallPoetryCommand(error) => match error {
+        PipenvDependenciesLayerError::PipenvInstallCommand(error, python_version) => {
+            on_install_command_error(
+                error,
+                &python_version,
+                "Unable to install dependencies using Pipenv",
+                "running 'pipenv install' to install the app's dependencies",
+                |exit_status| {
+                    formatdoc! {"
+                    The 'pipenv install --deploy' command to install the app's
+                    dependencies failed ({exit_status}).
+                    
+                    See the log output above for more information.
+                    
+                    If the log output says that your 'Pipfile.lock' is out of date, then
+                    update it by running 'pipenv lock', and commit the changes.
+                "}
+                },
+            );
+        }
+        PipenvDependenciesLayerError::PipenvRequirementsCommand(error) => match error {
             StreamedCommandError::Io(io_error) => log_io_error(
-                "Unable to install Poetry",
-                "running 'python' to install Poetry",
+                "Unable to show the dependency plan",
+                "running 'pipenv requirements' to list the locked packages",
                 &io_error,
             ),
             StreamedCommandError::NonZeroExitStatus(exit_status) => log_error(
-                "Unable to install Poetry",
+                "Unable to show the dependency plan",
                 formatdoc! {"
-                    The command to install Poetry did not exit successfully ({exit_status}).
+                    The 'pipenv requirements' command to list the packages in your
+                    'Pipfile.lock' failed ({exit_status}).
                     
                     See the log output above for more information.
-                    
-                    In some cases, this happens due to an unstable network connection.
-                    Please try again to see if the error resolves itself.
-                    
-                    If that does not help, check the status of PyPI (the upstream Python
-                    package repository service), here:
-                    https://status.python.org
                 "},
             ),
         },
+        PipenvDependenciesLayerError::PipfileNotFound => log_error(
+            "No Pipfile found",
+            indoc! {"
+                A 'Pipfile.lock' file was found, however, there is no 'Pipfile' in the
+                root directory of your app's source code.
+                
+                Pipenv requires both files, so check that the 'Pipfile' has been
+                committed to your Git repository, and that it isn't excluded by
+                '.gitignore' or 'project.toml'.
+            "},
+        ),
+        PipenvDependenciesLayerError::ReadPipfile(error) => on_read_pipfile_error(error),
+    }
+}
+
+fn on_read_pipfile_error(error: ReadPipfileError) {
+    match error {
+        ReadPipfileError::ParsePipfile(toml_error) => log_error(
+            "Unable to parse Pipfile",
+            formatdoc! {"
+                The 'Pipfile' could not be parsed since it is not valid TOML.
+
+                Details: {toml_error}
+            "},
+        ),
+        ReadPipfileError::ParsePipfileLock(json_error) => log_error(
+            "Unable to parse Pipfile.lock",
+            formatdoc! {"
+                The 'Pipfile.lock' file could not be parsed since it is not valid.
+
+                Details: {json_error}
+
+                Regenerate the lockfile by running 'pipenv lock', rather than
+                editing it by hand.
+            "},
+        ),
+        ReadPipfileError::ReadPipfile(io_error) => {
+            log_io_error("Unable to read Pipfile", "reading the Pipfile", &io_error);
+        }
+        ReadPipfileError::ReadPipfileLock(io_error) => log_io_error(
+            "Unable to read Pipfile.lock",
+            "reading the Pipfile.lock file",
+            &io_error,
+        ),
+    }
+}
+
+fn on_poetry_layer_error(error: PoetryLayerError) {
+    match error {
+        PoetryLayerError::InstallPoetry(error) => on_packaging_tool_install_error("Poetry", error),
         PoetryLayerError::LocateBundledPip(io_error) => log_io_error(
             "Unable to locate the bundled copy of pip",
             "locating the pip wheel file bundled inside the Python 'ensurepip' module",
             &io_error,
         ),
-    };
+    }
 }
 
 fn on_poetry_dependencies_layer_error(error: PoetryDependenciesLayerError) {
     match error {
+        PoetryDependenciesLayerError::CheckGitDirectoryExists(io_error) => log_io_error(
+            "Unable to check for Git metadata",
+            "checking if the '.git' directory exists",
+            &io_error,
+        ),
         PoetryDependenciesLayerError::CreateVenvCommand(error) => match error {
             StreamedCommandError::Io(io_error) => log_io_error(
                 "Unable to create virtual environment",
@@ -431,24 +1758,313 @@ fn on_poetry_dependencies_layer_error(error: PoetryDependenciesLayerError) {
                 "},
             ),
         },
-        PoetryDependenciesLayerError::PoetryInstallCommand(error) => match error {
-            StreamedCommandError::Io(io_error) => log_io_error(
+        PoetryDependenciesLayerError::DynamicVersioningGitDirectoryMissing => log_error(
+            "Git metadata is required for dynamic versioning",
+            indoc! {"
+                Your app's 'pyproject.toml' enables the 'poetry-dynamic-versioning' Poetry
+                plugin, which determines your project's version from its Git metadata.
+
+                However, no '.git' directory was found in your app's source code, so the
+                version cannot be determined. This usually happens because the platform
+                or tool used to build your app excludes the '.git' directory.
+
+                To fix this, either:
+
+                1. Ensure the '.git' directory is included in the app source used for
+                   the build (check it isn't excluded by 'project.toml').
+                2. Set a fixed version using the 'POETRY_DYNAMIC_VERSIONING_BYPASS'
+                   environment variable (for example, set it to '1.2.3').
+                3. Disable the plugin by setting 'enable = false' in the
+                   '[tool.poetry-dynamic-versioning]' table of 'pyproject.toml'.
+            "},
+        ),
+        PoetryDependenciesLayerError::ListInstalledPackages(error) => {
+            on_list_installed_packages_error(error);
+        }
+        PoetryDependenciesLayerError::PathDependenciesOutsideAppDir(path_dependencies) => {
+            on_path_dependencies_outside_app_dir_error(path_dependencies);
+        }
+        // TODO: Add more suggestions here as to possible causes (similar to pip)
+        PoetryDependenciesLayerError::PoetryDryRunCommand(error, python_version) => {
+            on_dry_run_command_error(
+                "Poetry",
+                "poetry install --dry-run --sync --only main",
+                error,
+                &python_version,
+            );
+        }
+        PoetryDependenciesLayerError::PoetryInstallCommand(error, python_version) => {
+            on_install_command_error(
+                error,
+                &python_version,
                 "Unable to install dependencies using Poetry",
                 "running 'poetry install' to install the app's dependencies",
-                &io_error,
-            ),
-            // TODO: Add more suggestions here as to possible causes (similar to pip)
-            StreamedCommandError::NonZeroExitStatus(exit_status) => log_error(
-                "Unable to install dependencies using Poetry",
-                formatdoc! {"
+                |exit_status| {
+                    formatdoc! {"
                     The 'poetry install --sync --only main' command to install the app's
                     dependencies failed ({exit_status}).
                     
                     See the log output above for more information.
+                "}
+                },
+            );
+        }
+        PoetryDependenciesLayerError::ReadPoetryLock(error) => match error {
+            ReadPoetryLockError::Io(io_error) => log_io_error(
+                "Unable to read poetry.lock",
+                "reading the poetry.lock file",
+                &io_error,
+            ),
+            ReadPoetryLockError::Parse(toml_error) => log_error(
+                "Unable to parse poetry.lock",
+                formatdoc! {"
+                    The 'poetry.lock' file could not be parsed since it is not valid TOML.
+
+                    Details: {toml_error}
+
+                    Regenerate the lockfile by running 'poetry lock', rather than
+                    editing it by hand.
                 "},
             ),
         },
+        PoetryDependenciesLayerError::ReadPathDependencies(io_error) => log_io_error(
+            "Unable to read path dependencies",
+            "reading the source files of the app's local path dependencies",
+            &io_error,
+        ),
+        PoetryDependenciesLayerError::ReadProjectMetadataFiles(io_error) => log_io_error(
+            "Unable to read project metadata",
+            "reading the project's metadata files (such as pyproject.toml)",
+            &io_error,
+        ),
+        PoetryDependenciesLayerError::UninstallPackage(io_error) => log_io_error(
+            "Unable to reinstall package",
+            "uninstalling a changed package from the cached virtual environment",
+            &io_error,
+        ),
+    }
+}
+
+fn on_path_dependencies_outside_app_dir_error(path_dependencies: Vec<(String, String)>) {
+    let dependency_list = path_dependencies
+        .into_iter()
+        .map(|(name, path)| format!("{name} ({path})"))
+        .collect::<Vec<String>>()
+        .join("\n");
+    log_error(
+        "Poetry path dependency is outside the app directory",
+        formatdoc! {"
+            The following path dependencies in your 'poetry.lock' file point to
+            locations outside of your app's source directory:
+
+            {dependency_list}
+
+            Only the app directory is available during the build, so these
+            dependencies cannot be installed.
+
+            If your app is part of a monorepo and shares code with other projects,
+            you can either:
+
+            1. Move the shared package(s) into a subdirectory of your app, and
+               update the 'path' in 'pyproject.toml' to match.
+            2. Publish the shared package(s) to a package index, or depend on them
+               using a Git URL instead of a local path.
+            3. Build from the root of the repository instead, so that the
+               shared package(s) are inside the app directory.
+
+            After updating 'pyproject.toml', run 'poetry lock' to update the lockfile.
+        "},
+    );
+}
+
+fn on_procfile_checks_error(error: &io::Error) {
+    log_io_error(
+        "Unable to check Procfile commands",
+        "reading the Procfile",
+        error,
+    );
+}
+
+fn on_project_root_error(error: ProjectRootError) {
+    match error {
+        ProjectRootError::CheckDirectoryExists(io_error) => log_io_error(
+            "Unable to determine the project root",
+            "checking if the configured project root directory exists",
+            &io_error,
+        ),
+        ProjectRootError::DirectoryNotFound(project_root) => log_error(
+            "Project root directory not found",
+            formatdoc! {"
+                The project root '{project_root}' doesn't exist, or isn't a directory.
+
+                The project root is set using the '{PROJECT_ROOT_ENV_VAR}' env var or the
+                'project-root' setting in your app's 'project.toml' file.
+
+                Check that the directory name is spelt correctly, and that it's relative
+                to the root directory of your app.
+            "},
+        ),
+        ProjectRootError::InvalidPath(project_root) => log_error(
+            "Invalid project root",
+            formatdoc! {"
+                The project root '{project_root}' isn't a directory inside your app.
+
+                The project root is set using the '{PROJECT_ROOT_ENV_VAR}' env var or the
+                'project-root' setting in your app's 'project.toml' file.
+
+                It must be relative to the root directory of your app, and can't be empty,
+                an absolute path or refer to parent directories using '..'.
+            "},
+        ),
+    }
+}
+
+fn on_gunicorn_checks_error(error: GunicornChecksError) {
+    let (occurred_whilst, io_error) = match error {
+        GunicornChecksError::CheckAsgiFrameworkInstalled(io_error) => {
+            ("checking which web framework is installed", io_error)
+        }
+        GunicornChecksError::CheckGunicornInstalled(io_error) => {
+            ("checking if the 'gunicorn' command exists", io_error)
+        }
+        GunicornChecksError::ReadConfigFile(io_error) => {
+            ("reading the Gunicorn config file", io_error)
+        }
+        GunicornChecksError::ReadProcfile(io_error) => ("reading the Procfile", io_error),
     };
+    log_io_error(
+        "Unable to check Gunicorn configuration",
+        occurred_whilst,
+        &io_error,
+    );
+}
+
+fn on_import_check_error(error: ImportCheckError) {
+    match error {
+        ImportCheckError::ImportCommand(io_error) => log_io_error(
+            "Unable to check installed packages can be imported",
+            "running 'python' to import the installed packages",
+            &io_error,
+        ),
+        ImportCheckError::ImportFailed(failures) => {
+            let failure_details = failures
+                .into_iter()
+                .map(|(module, traceback)| format!("Module '{module}':\n{traceback}"))
+                .collect::<Vec<String>>()
+                .join("\n\n");
+            log_error(
+                "Unable to import installed packages",
+                formatdoc! {"
+                    The following modules could not be imported after installing
+                    your app's dependencies:
+
+                    {failure_details}
+
+                    This usually means that a package is broken or incompatible with
+                    this Python version, or that it requires system libraries that
+                    are not available in the base image.
+
+                    This check was run since the '{IMPORT_CHECK_ENV_VAR}' environment
+                    variable is set.
+                "},
+            );
+        }
+        ImportCheckError::ListModulesCommand(error) => match error {
+            CapturedCommandError::Io(io_error) => log_io_error(
+                "Unable to list installed packages",
+                "running 'python' to list the installed packages",
+                &io_error,
+            ),
+            CapturedCommandError::NonZeroExitStatus(output) => log_error(
+                "Unable to list installed packages",
+                formatdoc! {"
+                    The command to list the modules provided by the installed
+                    packages failed ({exit_status}).
+
+                    Details:
+
+                    {stderr}
+                    ",
+                    exit_status = &output.status,
+                    stderr = String::from_utf8_lossy(&output.stderr)
+                },
+            ),
+        },
+    }
+}
+
+fn on_configured_processes_error(error: ConfiguredProcessesError) {
+    match error {
+        ConfiguredProcessesError::ConflictingReleaseProcess => log_error(
+            "Invalid process configuration",
+            formatdoc! {"
+                A release command is configured using both the 'release' setting and
+                a 'release' process in the '[com.heroku.buildpacks.python.processes]'
+                table of your app's 'project.toml' file.
+
+                Remove one of them, so that it's clear which command should be run.
+            "},
+        ),
+        ConfiguredProcessesError::EmptyCommand(process_type) => log_error(
+            "Invalid process configuration",
+            formatdoc! {"
+                The command for the '{process_type}' process configured in the
+                '[com.heroku.buildpacks.python.processes]' table of your app's
+                'project.toml' file is empty.
+
+                Update the process to specify the command used to start it.
+            "},
+        ),
+        ConfiguredProcessesError::EmptyReleaseCommand => log_error(
+            "Invalid release command",
+            formatdoc! {"
+                The 'release' setting in the '[com.heroku.buildpacks.python]' table
+                of your app's 'project.toml' file is empty.
+
+                Update the setting to specify the command to run on each deploy, or
+                remove it if a release command isn't needed.
+            "},
+        ),
+        ConfiguredProcessesError::ExecutableNotFound {
+            process_type,
+            executable,
+        } => log_error(
+            "Process command not found",
+            formatdoc! {"
+                The '{executable}' command used by the '{process_type}' process configured
+                in the '[com.heroku.buildpacks.python.processes]' table of your app's
+                'project.toml' file could not be found.
+
+                Check that the package providing the command is listed in your app's
+                dependencies, and that the command name is spelt correctly. Commands in
+                your app's source code must be specified using a relative path (such as
+                './bin/start.sh' or 'bin/start.sh').
+            "},
+        ),
+        ConfiguredProcessesError::InvalidProcessType(process_type) => log_error(
+            "Invalid process configuration",
+            formatdoc! {"
+                The process name '{process_type}' configured in the
+                '[com.heroku.buildpacks.python.processes]' table of your app's
+                'project.toml' file is not valid.
+
+                Process names can only contain letters, numbers, '.', '_' and '-'.
+            "},
+        ),
+        ConfiguredProcessesError::ReleaseExecutableNotFound(executable) => log_error(
+            "Release command not found",
+            formatdoc! {"
+                The '{executable}' command used by the 'release' setting in the
+                '[com.heroku.buildpacks.python]' table of your app's 'project.toml'
+                file could not be found.
+
+                Check that the package providing the command is listed in your app's
+                dependencies, and that the command name is spelt correctly. Commands in
+                your app's source code must be specified using a relative path (such as
+                './bin/release.sh' or 'bin/release.sh').
+            "},
+        ),
+    }
 }
 
 fn on_django_detection_error(error: &io::Error) {
@@ -459,6 +2075,14 @@ fn on_django_detection_error(error: &io::Error) {
     );
 }
 
+fn on_django_settings_module_error(error: &io::Error) {
+    log_io_error(
+        "Unable to find the Django settings module",
+        "searching for the Django settings module",
+        error,
+    );
+}
+
 fn on_django_collectstatic_error(error: DjangoCollectstaticError) {
     match error {
         DjangoCollectstaticError::CheckCollectstaticCommandExists(error) => match error {
@@ -518,7 +2142,147 @@ fn on_django_collectstatic_error(error: DjangoCollectstaticError) {
                 "},
             ),
         },
-    };
+        DjangoCollectstaticError::ConfiguredProjectNotFound(dir) => log_error(
+            "Django project not found",
+            formatdoc! {"
+                The 'django-project-dirs' setting in 'project.toml' includes the directory
+                '{dir}', however, no 'manage.py' script was found in that directory.
+                
+                Check that the directory is correct (it must be relative to the root of
+                your app's source code), and that the Django project's 'manage.py' script
+                has been committed to your Git repository.
+            "},
+        ),
+    }
+}
+
+fn on_django_deploy_check_error(error: DjangoDeployCheckError) {
+    match error {
+        DjangoDeployCheckError::CheckManagementScriptExists(io_error) => log_io_error(
+            "Unable to inspect Django configuration",
+            "checking if the 'manage.py' script exists",
+            &io_error,
+        ),
+        DjangoDeployCheckError::DeployCheckCommand(error) => match error {
+            StreamedCommandError::Io(io_error) => log_io_error(
+                "Unable to run the Django deployment checks",
+                "running 'python manage.py check --deploy' to check the Django configuration",
+                &io_error,
+            ),
+            StreamedCommandError::NonZeroExitStatus(exit_status) => log_error(
+                "Django deployment checks failed",
+                formatdoc! {"
+                    The 'python manage.py check --deploy --fail-level WARNING' Django
+                    management command found problems with your app's Django configuration
+                    ({exit_status}).
+                    
+                    See the log output above for the list of problems, and Django's
+                    deployment checklist for how to resolve them:
+                    https://docs.djangoproject.com/en/stable/howto/deployment/checklist/
+                    
+                    This check was run since strict mode is enabled for 'django-deploy-check'
+                    via the '{STRICT_MODE_ENV_VAR}' environment variable.
+                "},
+            ),
+        },
+    }
+}
+
+fn on_django_migrate_error(error: DjangoMigrateError) {
+    match error {
+        DjangoMigrateError::CheckManagementScriptExists(io_error) => log_io_error(
+            "Unable to inspect Django configuration",
+            "checking if the 'manage.py' script exists",
+            &io_error,
+        ),
+        DjangoMigrateError::MigrateCommand(error) => match error {
+            StreamedCommandError::Io(io_error) => log_io_error(
+                "Unable to run Django database migrations",
+                "running 'python manage.py migrate' to migrate the database",
+                &io_error,
+            ),
+            StreamedCommandError::NonZeroExitStatus(exit_status) => log_error(
+                "Unable to run Django database migrations",
+                formatdoc! {"
+                    The 'python manage.py migrate --noinput' Django management command
+                    to apply your app's database migrations failed ({exit_status}).
+                    
+                    See the log output above for more information.
+                    
+                    Check that the database is reachable from the build environment, and
+                    that the settings used to connect to it (such as 'DATABASE_URL') are
+                    available at build time.
+                    
+                    Migrations are being run during the build since the
+                    '{RUN_MIGRATIONS_ENV_VAR}' environment variable is set.
+                    To run them at release time instead, unset it and configure a
+                    'release' process (such as 'python manage.py migrate --noinput').
+                "},
+            ),
+        },
+    }
+}
+
+fn on_strict_mode_error(error: StrictModeError) {
+    match error {
+        StrictModeError::PromotedWarning {
+            class,
+            header,
+            body,
+        } => log_error(
+            header,
+            formatdoc! {"
+                {body}
+                
+                This warning has been treated as an error, since strict mode is enabled
+                for '{class}' via the '{STRICT_MODE_ENV_VAR}' environment variable.
+                ",
+                body = body.trim_end(),
+            },
+        ),
+        StrictModeError::UnknownWarningClass(name) => log_error(
+            "Invalid strict mode configuration",
+            formatdoc! {"
+                The '{STRICT_MODE_ENV_VAR}' environment variable contains an unknown
+                warning class: '{name}'
+                
+                Set it to '1' to enable strict mode for all warning classes, or to a
+                comma separated list of one or more of the following warning classes:
+                django-deploy-check, eol-python, unpinned-dependencies
+            "},
+        ),
+    }
+}
+
+fn on_verify_command_error(error: StreamedCommandError) {
+    match error {
+        StreamedCommandError::Io(io_error) => log_io_error(
+            "Unable to run the verification command",
+            "running the verification command configured in project.toml",
+            &io_error,
+        ),
+        StreamedCommandError::NonZeroExitStatus(exit_status) => log_error(
+            "Verification command failed",
+            formatdoc! {"
+                The verification command configured using the 'verify-command'
+                setting in your app's 'project.toml' file failed ({exit_status}).
+
+                This usually means that the app's environment is broken, for example
+                due to a missing or incompatible dependency.
+
+                See the log output above for more information.
+            "},
+        ),
+    }
+}
+
+// All error messages are logged via this wrapper, since they can include user-provided values
+// (such as requirement specifiers, index URLs or command output) that contain credentials.
+fn log_error(header: impl AsRef<str>, body: impl AsRef<str>) {
+    build_log::log_error(
+        redact_url_credentials(header.as_ref()),
+        redact_url_credentials(body.as_ref()),
+    );
 }
 
 fn log_io_error(header: &str, occurred_whilst: &str, io_error: &io::Error) {
@@ -0,0 +1,317 @@
+use crate::diagnostics::{Diagnostic, DiagnosticCategory};
+use crate::procfile;
+use crate::python_version::PythonVersion;
+use crate::strict_mode::WarningClass;
+use crate::utils;
+use indoc::formatdoc;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+// The config file Gunicorn reads by default from the current working directory.
+const DEFAULT_CONFIG_FILE: &str = "gunicorn.conf.py";
+
+// Frameworks that only support ASGI, and so can't be served using Gunicorn's default
+// (synchronous WSGI) worker class. Django is not included since it supports both.
+const ASGI_FRAMEWORKS: [&str; 4] = ["fastapi", "litestar", "quart", "starlette"];
+
+/// The Gunicorn settings used by a process, from either its command line or the config file.
+#[derive(Debug, Default, PartialEq)]
+struct GunicornSettings {
+    bind: Option<String>,
+    worker_class: Option<String>,
+}
+
+/// Check the Gunicorn configuration of the app's processes for common mistakes that cause
+/// the app to fail to boot (or to behave incorrectly) once deployed.
+///
+/// The checks are only run if Gunicorn is installed, and use the processes declared in the
+/// `Procfile` or `project.toml`, along with any Gunicorn config file.
+pub(crate) fn check_gunicorn_config(
+    app_dir: &Path,
+    dependencies_layer_dir: &Path,
+    python_version: &PythonVersion,
+    configured_processes: &BTreeMap<String, String>,
+) -> Result<Vec<Diagnostic>, GunicornChecksError> {
+    if !dependencies_layer_dir
+        .join("bin/gunicorn")
+        .try_exists()
+        .map_err(GunicornChecksError::CheckGunicornInstalled)?
+    {
+        return Ok(Vec::new());
+    }
+
+    let mut processes = procfile::read_procfile(app_dir)
+        .map_err(GunicornChecksError::ReadProcfile)?
+        .unwrap_or_default();
+    processes.extend(
+        configured_processes
+            .iter()
+            .map(|(name, command)| (name.clone(), command.clone())),
+    );
+
+    let asgi_framework = installed_asgi_framework(dependencies_layer_dir, python_version)
+        .map_err(GunicornChecksError::CheckAsgiFrameworkInstalled)?;
+
+    let mut diagnostics = Vec::new();
+    for (process_type, command) in processes {
+        let Some((command_settings, config_file)) = parse_gunicorn_command(&command) else {
+            continue;
+        };
+        let config_file_settings = match config_file {
+            Some(config_file) => utils::read_optional_file(&app_dir.join(config_file))
+                .map_err(GunicornChecksError::ReadConfigFile)?
+                .map(|contents| parse_config_file(&contents))
+                .unwrap_or_default(),
+            None => GunicornSettings::default(),
+        };
+        let bind = command_settings.bind.or(config_file_settings.bind);
+        let worker_class = command_settings
+            .worker_class
+            .or(config_file_settings.worker_class);
+
+        diagnostics.extend(check_bind(&process_type, bind.as_deref()));
+        if let Some(framework) = asgi_framework {
+            diagnostics.extend(check_worker_class(
+                &process_type,
+                worker_class.as_deref(),
+                framework,
+            ));
+        }
+    }
+    Ok(diagnostics)
+}
+
+fn check_bind(process_type: &str, bind: Option<&str>) -> Option<Diagnostic> {
+    // If no bind address is set, Gunicorn uses `0.0.0.0:$PORT` when `PORT` is set.
+    let bind = bind?;
+    let message = if bind.contains("127.0.0.1") || bind.contains("localhost") {
+        formatdoc! {"
+            The Gunicorn server for the '{process_type}' process is configured to listen
+            on a local-only address ({bind}), so it won't be able to receive requests
+            once deployed.
+        "}
+    } else if !bind.contains("PORT") && has_port_number(bind) {
+        formatdoc! {"
+            The Gunicorn server for the '{process_type}' process is configured to listen
+            on a hardcoded port ({bind}), however, the port your app must listen on is
+            assigned by the platform using the 'PORT' environment variable.
+        "}
+    } else {
+        return None;
+    };
+
+    Some(Diagnostic {
+        category: DiagnosticCategory::Processes,
+        warning_class: WarningClass::GunicornBind,
+        message: formatdoc! {"
+            {message}
+            Either remove the bind setting (since Gunicorn uses the 'PORT' environment
+            variable by default), or change it to: '--bind 0.0.0.0:$PORT'
+        "},
+    })
+}
+
+fn check_worker_class(
+    process_type: &str,
+    worker_class: Option<&str>,
+    framework: &str,
+) -> Option<Diagnostic> {
+    if worker_class.is_some_and(|worker_class| worker_class.to_lowercase().contains("uvicorn")) {
+        return None;
+    }
+
+    Some(Diagnostic {
+        category: DiagnosticCategory::Processes,
+        warning_class: WarningClass::GunicornWorkerClass,
+        message: formatdoc! {"
+            The Gunicorn server for the '{process_type}' process is using the '{worker_class}'
+            worker class, however, your app uses {framework} (an ASGI framework), which
+            isn't supported by that worker class.
+
+            Install the 'uvicorn-worker' package, and then configure Gunicorn to use it
+            using: '--worker-class uvicorn_worker.UvicornWorker'
+            ",
+            worker_class = worker_class.unwrap_or("sync"),
+        },
+    })
+}
+
+/// Parse a process command, returning `None` if it doesn't run Gunicorn, or else the settings
+/// passed on the command line and the path to the config file that will be used (if any).
+fn parse_gunicorn_command(command: &str) -> Option<(GunicornSettings, Option<&str>)> {
+    let mut words = command.split_whitespace().skip_while(|word| {
+        word.split_once('=')
+            .is_some_and(|(name, _)| !name.is_empty() && !name.starts_with('-'))
+    });
+    match words.next()? {
+        "gunicorn" => {}
+        "python" | "python3" if words.next() == Some("-m") && words.next() == Some("gunicorn") => {}
+        _ => return None,
+    }
+
+    let mut settings = GunicornSettings::default();
+    let mut config_file = Some(DEFAULT_CONFIG_FILE);
+    while let Some(word) = words.next() {
+        let (option, inline_value) = match word.split_once('=') {
+            Some((option, value)) if option.starts_with("--") => (option, Some(value)),
+            _ => (word, None),
+        };
+        let mut value = || inline_value.or_else(|| words.next());
+        match option {
+            "-b" | "--bind" => settings.bind = value().map(ToString::to_string),
+            "-k" | "--worker-class" => settings.worker_class = value().map(ToString::to_string),
+            "-c" | "--config" => {
+                // Config modules (`python:my.module`) can't be read, so are skipped.
+                config_file = value().filter(|path| !path.starts_with("python:"));
+            }
+            _ => {}
+        }
+    }
+    Some((settings, config_file))
+}
+
+// Gunicorn config files are Python modules, so this only handles the common case of simple
+// assignments. Values are kept as the Python source (for example with quotes), since they're
+// only used to check for common mistakes and in the warning messages.
+fn parse_config_file(contents: &str) -> GunicornSettings {
+    let mut settings = GunicornSettings::default();
+    for line in contents.lines() {
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.split(" #").next().unwrap_or_default().trim();
+        match name.trim_end() {
+            "bind" => settings.bind = Some(value.to_string()),
+            "worker_class" => settings.worker_class = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    settings
+}
+
+fn has_port_number(bind: &str) -> bool {
+    bind.rsplit(':').next().is_some_and(|port| {
+        port.trim_matches(['"', '\'', ']', ',', ' '])
+            .parse::<u16>()
+            .is_ok()
+    })
+}
+
+fn installed_asgi_framework(
+    dependencies_layer_dir: &Path,
+    python_version: &PythonVersion,
+) -> io::Result<Option<&'static str>> {
+    let site_packages_dir = dependencies_layer_dir.join(format!(
+        "lib/python{}.{}/site-packages",
+        python_version.major, python_version.minor
+    ));
+    for framework in ASGI_FRAMEWORKS {
+        if site_packages_dir.join(framework).try_exists()? {
+            return Ok(Some(framework));
+        }
+    }
+    Ok(None)
+}
+
+/// Errors that can occur when checking the app's Gunicorn configuration.
+#[derive(Debug)]
+pub(crate) enum GunicornChecksError {
+    CheckAsgiFrameworkInstalled(io::Error),
+    CheckGunicornInstalled(io::Error),
+    ReadConfigFile(io::Error),
+    ReadProcfile(io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn parse_gunicorn_command_valid() {
+        assert_eq!(
+            parse_gunicorn_command("gunicorn --bind 0.0.0.0:8000 -k gevent myapp.wsgi"),
+            Some((
+                GunicornSettings {
+                    bind: Some("0.0.0.0:8000".to_string()),
+                    worker_class: Some("gevent".to_string()),
+                },
+                Some(DEFAULT_CONFIG_FILE)
+            ))
+        );
+        assert_eq!(
+            parse_gunicorn_command(
+                "DEBUG=1 python -m gunicorn --worker-class=uvicorn_worker.UvicornWorker -c config/gunicorn.py app:app"
+            ),
+            Some((
+                GunicornSettings {
+                    bind: None,
+                    worker_class: Some("uvicorn_worker.UvicornWorker".to_string()),
+                },
+                Some("config/gunicorn.py")
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_gunicorn_command_other_command() {
+        assert_eq!(parse_gunicorn_command("celery --app myapp worker"), None);
+        assert_eq!(parse_gunicorn_command("python manage.py migrate"), None);
+        assert_eq!(parse_gunicorn_command(""), None);
+    }
+
+    #[test]
+    fn parse_config_file_assignments() {
+        assert_eq!(
+            parse_config_file(indoc! {r#"
+                import os
+
+                bind = "127.0.0.1:8000"  # A comment
+                workers = 4
+                worker_class = "uvicorn_worker.UvicornWorker"
+            "#}),
+            GunicornSettings {
+                bind: Some(r#""127.0.0.1:8000""#.to_string()),
+                worker_class: Some(r#""uvicorn_worker.UvicornWorker""#.to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn check_bind_problems() {
+        assert!(check_bind("web", None).is_none());
+        assert!(check_bind("web", Some("0.0.0.0:$PORT")).is_none());
+        assert!(check_bind("web", Some(r#"f"0.0.0.0:{os.environ['PORT']}""#)).is_none());
+        assert!(check_bind("web", Some("0.0.0.0:8000")).is_some());
+        assert!(check_bind("web", Some(r#"["0.0.0.0:5000"]"#)).is_some());
+        assert!(check_bind("web", Some("127.0.0.1:$PORT")).is_some());
+    }
+
+    #[test]
+    fn check_worker_class_problems() {
+        assert!(
+            check_worker_class("web", Some("uvicorn_worker.UvicornWorker"), "fastapi").is_none()
+        );
+        assert!(
+            check_worker_class("web", Some(r#""uvicorn.workers.UvicornWorker""#), "fastapi")
+                .is_none()
+        );
+        assert!(check_worker_class("web", None, "fastapi").is_some());
+        assert!(check_worker_class("web", Some("gevent"), "starlette").is_some());
+    }
+
+    #[test]
+    fn check_gunicorn_config_not_installed() {
+        assert_eq!(
+            check_gunicorn_config(
+                Path::new("tests/fixtures/pip_basic"),
+                Path::new("tests/fixtures/empty"),
+                &PythonVersion::new(3, 13, 1),
+                &BTreeMap::new(),
+            )
+            .unwrap(),
+            []
+        );
+    }
+}
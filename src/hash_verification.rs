@@ -0,0 +1,275 @@
+use crate::build_log::log_info;
+use crate::package_inventory::InstalledPackage;
+use crate::poetry_lock::PoetryLock;
+use crate::strict_mode::{StrictMode, WarningClass};
+use crate::utils;
+use indoc::formatdoc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A package that is pinned to an exact version in a lockfile, along with the hashes of its
+/// distribution files.
+#[derive(Debug, PartialEq)]
+pub(crate) struct LockedPackage {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    pub(crate) hashes: Vec<String>,
+}
+
+/// The result of verifying the installed packages against the hashes in the app's lockfile,
+/// which is recorded in the dependencies layer's metadata as an attestation of what was installed.
+///
+/// The package manager checks the hash of each distribution file it downloads against the
+/// lockfile (which pip does automatically when any requirement has a `--hash` option). Since the
+/// archives themselves aren't kept, the installed packages are then verified by checking that the
+/// installed version of each package is the exact version whose hashes were locked.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct HashVerification {
+    pub(crate) lockfile: String,
+    /// The number of installed packages that match a hashed entry in the lockfile.
+    pub(crate) verified_packages: usize,
+    /// Installed packages that don't have hashes in the lockfile (such as Git dependencies).
+    pub(crate) unverified_packages: Vec<String>,
+    /// Installed packages whose version doesn't match the version in the lockfile.
+    pub(crate) mismatched_packages: Vec<String>,
+}
+
+/// Returns the packages in a `poetry.lock` file that have hashes.
+pub(crate) fn poetry_lock_packages(poetry_lock: &PoetryLock) -> Vec<LockedPackage> {
+    poetry_lock
+        .packages
+        .iter()
+        .filter(|package| !package.files.is_empty())
+        .map(|package| LockedPackage {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            hashes: package.files.iter().map(|file| file.hash.clone()).collect(),
+        })
+        .collect()
+}
+
+/// Returns the requirements pinned to an exact version using `==` that have at least one
+/// `--hash` option, such as those generated by `pip-compile --generate-hashes`.
+pub(crate) fn requirements_packages(requirements: &str) -> Vec<LockedPackage> {
+    requirements
+        .replace("\\\n", " ")
+        .lines()
+        .filter_map(|line| {
+            let line = line.split(" #").next().unwrap_or_default().trim();
+            let mut words = line.split_whitespace();
+            let (name, version) = words.next()?.split_once("==")?;
+            let name = name.split('[').next().unwrap_or_default();
+            let version = version.split(';').next().unwrap_or_default();
+            let words = words.collect::<Vec<_>>();
+            let hashes = words
+                .iter()
+                .enumerate()
+                .filter_map(|(index, word)| match word.strip_prefix("--hash") {
+                    Some("") => words.get(index + 1).copied(),
+                    Some(value) => value.strip_prefix('='),
+                    None => None,
+                })
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            (!name.is_empty() && !hashes.is_empty()).then(|| LockedPackage {
+                name: name.to_string(),
+                version: version.to_string(),
+                hashes,
+            })
+        })
+        .collect()
+}
+
+/// Verifies the installed packages against the packages in the lockfile. Packages in the ignored
+/// list (such as the project itself, which Poetry installs but doesn't lock) aren't verified.
+pub(crate) fn verify_installed_packages(
+    lockfile: &str,
+    locked_packages: &[LockedPackage],
+    installed_packages: &[InstalledPackage],
+    ignored_packages: &[&str],
+) -> HashVerification {
+    let locked_versions = locked_packages
+        .iter()
+        .map(|package| {
+            (
+                utils::normalize_package_name(&package.name),
+                package.version.as_str(),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    let ignored_packages = ignored_packages
+        .iter()
+        .map(|name| utils::normalize_package_name(name))
+        .collect::<Vec<_>>();
+
+    let mut verification = HashVerification {
+        lockfile: lockfile.to_string(),
+        verified_packages: 0,
+        unverified_packages: Vec::new(),
+        mismatched_packages: Vec::new(),
+    };
+    for package in installed_packages {
+        let name = utils::normalize_package_name(&package.name);
+        if ignored_packages.contains(&name) {
+            continue;
+        }
+        match locked_versions.get(&name) {
+            Some(&version) if version == package.version => verification.verified_packages += 1,
+            Some(&version) => verification.mismatched_packages.push(format!(
+                "{} (installed {}, locked {version})",
+                package.name, package.version
+            )),
+            None => verification.unverified_packages.push(package.name.clone()),
+        }
+    }
+    verification
+}
+
+/// Log the result of the verification, warning if any installed packages don't match the lockfile.
+pub(crate) fn log_hash_verification(verification: &HashVerification, strict_mode: &StrictMode) {
+    let HashVerification {
+        lockfile,
+        verified_packages,
+        unverified_packages,
+        mismatched_packages,
+    } = verification;
+
+    log_info(format!(
+        "Installed packages verified against the hashes in {lockfile}: {verified_packages}"
+    ));
+    if !unverified_packages.is_empty() {
+        log_info(format!(
+            "Packages without hashes in {lockfile}, which couldn't be verified: {}",
+            unverified_packages.join(", ")
+        ));
+    }
+    if !mismatched_packages.is_empty() {
+        strict_mode.log_warning(
+            WarningClass::LockfileMismatch,
+            "Installed packages don't match the lockfile",
+            formatdoc! {"
+                The installed version of the following packages doesn't match the
+                version whose hashes are recorded in your app's '{lockfile}' file:
+
+                {}
+
+                Check that the lockfile is up to date, and doesn't contain more than
+                one entry for the same package.
+                ",
+                mismatched_packages.join("\n")
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn installed_package(name: &str, version: &str) -> InstalledPackage {
+        InstalledPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            hash: None,
+        }
+    }
+
+    #[test]
+    fn requirements_packages_hashed() {
+        assert_eq!(
+            requirements_packages(indoc! {"
+                # This file is autogenerated by pip-compile
+                django==5.1.4 \\
+                    --hash=sha256:236e023f \\
+                    --hash=sha256:de450c09
+                    # via -r requirements.in
+                Typing_Extensions[extra]==4.12.2 ; python_version < '3.13' --hash sha256:04e5ca03
+                gunicorn==23.0.0
+                requests>=2.0 --hash=sha256:70761cfe
+            "}),
+            [
+                LockedPackage {
+                    name: "django".to_string(),
+                    version: "5.1.4".to_string(),
+                    hashes: vec!["sha256:236e023f".to_string(), "sha256:de450c09".to_string()],
+                },
+                LockedPackage {
+                    name: "Typing_Extensions".to_string(),
+                    version: "4.12.2".to_string(),
+                    hashes: vec!["sha256:04e5ca03".to_string()],
+                },
+            ]
+        );
+        assert_eq!(requirements_packages("django==5.1.4\n"), []);
+    }
+
+    #[test]
+    fn poetry_lock_packages_hashed() {
+        let poetry_lock: PoetryLock = toml::from_str(indoc! {r#"
+            [[package]]
+            name = "typing-extensions"
+            version = "4.12.2"
+            files = [
+                {file = "typing_extensions-4.12.2-py3-none-any.whl", hash = "sha256:04e5ca03"},
+                {file = "typing_extensions-4.12.2.tar.gz", hash = "sha256:1a7ead55"},
+            ]
+
+            [[package]]
+            name = "shared-lib"
+            version = "0.1.0"
+            files = []
+
+            [package.source]
+            type = "directory"
+            url = "../shared-lib"
+        "#})
+        .unwrap();
+        assert_eq!(
+            poetry_lock_packages(&poetry_lock),
+            [LockedPackage {
+                name: "typing-extensions".to_string(),
+                version: "4.12.2".to_string(),
+                hashes: vec!["sha256:04e5ca03".to_string(), "sha256:1a7ead55".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn verify_installed_packages_result() {
+        let locked_packages = [
+            LockedPackage {
+                name: "Django".to_string(),
+                version: "5.1.4".to_string(),
+                hashes: vec!["sha256:236e023f".to_string()],
+            },
+            LockedPackage {
+                name: "typing_extensions".to_string(),
+                version: "4.12.2".to_string(),
+                hashes: vec!["sha256:04e5ca03".to_string()],
+            },
+        ];
+        assert_eq!(
+            verify_installed_packages(
+                "poetry.lock",
+                &locked_packages,
+                &[
+                    installed_package("django", "5.1.4"),
+                    installed_package("my-app", "0.1.0"),
+                    installed_package("shared-lib", "0.1.0"),
+                    installed_package("typing-extensions", "4.12.1"),
+                ],
+                &["My_App"],
+            ),
+            HashVerification {
+                lockfile: "poetry.lock".to_string(),
+                verified_packages: 1,
+                unverified_packages: vec!["shared-lib".to_string()],
+                mismatched_packages: vec![
+                    "typing-extensions (installed 4.12.1, locked 4.12.2)".to_string()
+                ],
+            }
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::build_log::log_info;
+use crate::utils::{self, CapturedCommandError};
+use libcnb::Env;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// The env var used to enable the import check, and optionally configure which modules are checked.
+pub(crate) const IMPORT_CHECK_ENV_VAR: &str = "PYTHON_IMPORT_CHECK";
+
+// Lists the top-level importable names of all installed distributions. `packages_distributions()`
+// is only available in Python 3.10+, so for older versions we fall back to `top_level.txt`, which
+// is written by setuptools-based builds (the majority of packages that support those versions).
+const LIST_MODULES_SCRIPT: &str = r#"
+import importlib.metadata as metadata
+try:
+    names = set(metadata.packages_distributions())
+except AttributeError:
+    names = {
+        name
+        for dist in metadata.distributions()
+        for name in (dist.read_text("top_level.txt") or "").split()
+    }
+print("\n".join(sorted(names)))
+"#;
+
+// Some packages incorrectly include their test suite or docs as a top-level package. Importing
+// these isn't meaningful, and in the case of test suites may fail due to missing test dependencies.
+const IGNORED_MODULES: [&str; 5] = ["benchmarks", "docs", "examples", "test", "tests"];
+
+/// The modules that should be imported as part of the import check.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ImportCheckModules {
+    /// All top-level modules provided by the installed packages.
+    AllInstalled,
+    /// A list of modules configured by the user.
+    Listed(Vec<String>),
+}
+
+/// Determine whether the import check has been enabled, and if so which modules should be checked.
+///
+/// The check is enabled by setting `PYTHON_IMPORT_CHECK` to either `1` (to check all top-level
+/// modules from the installed packages), or to a comma or space separated list of module names.
+pub(crate) fn import_check_modules(env: &Env) -> Option<ImportCheckModules> {
+    let value = env.get_string_lossy(IMPORT_CHECK_ENV_VAR)?;
+    match value.trim() {
+        "" | "0" | "false" => None,
+        "1" | "true" => Some(ImportCheckModules::AllInstalled),
+        modules => Some(ImportCheckModules::Listed(
+            modules
+                .split([',', ' '])
+                .filter(|module| !module.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+        )),
+    }
+}
+
+/// Imports each of the specified modules in a separate Python subprocess, so that problems such as
+/// missing system libraries or broken compiled packages are caught at build time rather than when
+/// the app first boots. A separate subprocess is used for each module so that a crash in one
+/// module's native code doesn't prevent checking the others.
+pub(crate) fn check_imports(
+    app_dir: &Path,
+    env: &Env,
+    modules: &ImportCheckModules,
+) -> Result<(), ImportCheckError> {
+    let modules = match modules {
+        ImportCheckModules::AllInstalled => list_installed_modules(app_dir, env)?,
+        ImportCheckModules::Listed(modules) => modules.clone(),
+    };
+
+    log_info(format!("Importing {} modules", modules.len()));
+
+    let mut failures = Vec::new();
+    for module in &modules {
+        match utils::run_command_and_capture_output(
+            Command::new("python")
+                // Using `import_module()` rather than an `import` statement, so that the module
+                // name doesn't need to be interpolated into the Python code.
+                .args([
+                    "-c",
+                    "import importlib, sys; importlib.import_module(sys.argv[1])",
+                    module,
+                ])
+                .current_dir(app_dir)
+                .env_clear()
+                .envs(env),
+        ) {
+            Ok(_) => {}
+            Err(CapturedCommandError::NonZeroExitStatus(output)) => failures.push((
+                module.clone(),
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )),
+            Err(CapturedCommandError::Io(io_error)) => {
+                return Err(ImportCheckError::ImportCommand(io_error));
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        log_info("All modules imported successfully");
+        Ok(())
+    } else {
+        Err(ImportCheckError::ImportFailed(failures))
+    }
+}
+
+fn list_installed_modules(app_dir: &Path, env: &Env) -> Result<Vec<String>, ImportCheckError> {
+    let output = utils::run_command_and_capture_output(
+        Command::new("python")
+            .args(["-c", LIST_MODULES_SCRIPT])
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+    )
+    .map_err(ImportCheckError::ListModulesCommand)?;
+
+    Ok(parse_module_list(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_module_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|name| {
+            // Private modules (such as `_distutils_hack`) are implementation details that
+            // aren't intended to be imported directly.
+            !name.is_empty() && !name.starts_with('_') && !IGNORED_MODULES.contains(name)
+        })
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Errors that can occur when running the import check.
+#[derive(Debug)]
+pub(crate) enum ImportCheckError {
+    ImportCommand(io::Error),
+    ImportFailed(Vec<(String, String)>),
+    ListModulesCommand(CapturedCommandError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_check_modules_disabled() {
+        assert_eq!(import_check_modules(&Env::new()), None);
+
+        for value in ["", " ", "0", "false"] {
+            let mut env = Env::new();
+            env.insert(IMPORT_CHECK_ENV_VAR, value);
+            assert_eq!(import_check_modules(&env), None);
+        }
+    }
+
+    #[test]
+    fn import_check_modules_all_installed() {
+        for value in ["1", "true", " 1\n"] {
+            let mut env = Env::new();
+            env.insert(IMPORT_CHECK_ENV_VAR, value);
+            assert_eq!(
+                import_check_modules(&env),
+                Some(ImportCheckModules::AllInstalled)
+            );
+        }
+    }
+
+    #[test]
+    fn import_check_modules_listed() {
+        let mut env = Env::new();
+        env.insert(IMPORT_CHECK_ENV_VAR, "myapp, numpy.linalg,,psycopg");
+        assert_eq!(
+            import_check_modules(&env),
+            Some(ImportCheckModules::Listed(vec![
+                "myapp".to_string(),
+                "numpy.linalg".to_string(),
+                "psycopg".to_string(),
+            ]))
+        );
+    }
+
+    #[test]
+    fn parse_module_list_filters_modules() {
+        assert_eq!(
+            parse_module_list("_distutils_hack\ndjango\n\nnumpy\ntests\nyaml\n"),
+            ["django", "numpy", "yaml"]
+        );
+    }
+}
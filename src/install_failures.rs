@@ -0,0 +1,447 @@
+use crate::python_version::PythonVersion;
+
+/// Known causes of dependency installation failures, for which a more specific error message
+/// can be shown than the generic "command failed" error (whose details are otherwise buried
+/// in the package manager's output).
+#[derive(Debug, PartialEq)]
+pub(crate) enum InstallFailure {
+    /// The app's requirements conflict with each other (or with the requirements of the packages
+    /// being installed), so pip's resolver couldn't find a set of versions that satisfies them.
+    DependencyConflict { conflicts: Vec<String> },
+    /// A package (or the requested version of it) requires a different Python version, via the
+    /// `Requires-Python` field of its metadata.
+    RequiresPythonMismatch {
+        package: String,
+        version: Option<String>,
+        requires_python: String,
+        python_version: PythonVersion,
+    },
+    /// A package had to be built from source, however, its build requires a Rust compiler.
+    RustCompilerRequired { package: Option<String> },
+    /// A version of a scientific package (such as numpy) that predates support for the Python
+    /// version had to be built from source, which failed (as is almost always the case).
+    UnsupportedPythonVersion {
+        package: String,
+        version: String,
+        python_version: PythonVersion,
+        minimum_version: &'static str,
+    },
+}
+
+// The oldest release of each package that provides wheels for each Python version. Older
+// releases have to be built from source, which fails since these packages only gain support
+// for new Python versions in the release that adds wheels for it.
+const MINIMUM_PACKAGE_VERSIONS: [(&str, [(u16, &str); 6]); 2] = [
+    (
+        "numpy",
+        [
+            (8, "1.17.3"),
+            (9, "1.19.3"),
+            (10, "1.21.3"),
+            (11, "1.23.2"),
+            (12, "1.26.0"),
+            (13, "2.1.0"),
+        ],
+    ),
+    (
+        "scipy",
+        [
+            (8, "1.3.3"),
+            (9, "1.5.4"),
+            (10, "1.7.2"),
+            (11, "1.9.2"),
+            (12, "1.11.2"),
+            (13, "1.14.1"),
+        ],
+    ),
+];
+
+// The line output by pip's resolver before the requirements that conflict with each other,
+// which are each output on a separate indented line (such as "The user requested foo==1.0" or
+// "bar 2.0 depends on foo>=2.0").
+const RESOLUTION_CONFLICT_PREFIX: &str = "The conflict is caused by:";
+
+// The number of conflicting requirements shown in the error message, since for some conflicts
+// pip outputs every version of a package that it tried (each of which conflicts).
+const MAX_RESOLUTION_CONFLICTS: usize = 10;
+
+// Output from the build backends used by packages with Rust extensions when a Rust compiler
+// can't be found (`setuptools-rust` and `maturin` respectively).
+const RUST_COMPILER_NOT_FOUND_PATTERNS: [&str; 2] = [
+    "can't find Rust compiler",
+    "Cargo, the Rust package manager, is not installed",
+];
+
+/// Determine whether a failed `pip install` or `poetry install` was due to a known cause,
+/// based on the command's combined stdout/stderr output.
+pub(crate) fn diagnose_install_failure(
+    output: &str,
+    python_version: &PythonVersion,
+) -> Option<InstallFailure> {
+    if let Some((package, version, requires_python)) = requires_python_mismatch(output) {
+        return Some(InstallFailure::RequiresPythonMismatch {
+            package,
+            version,
+            requires_python,
+            python_version: python_version.clone(),
+        });
+    }
+
+    if output.contains("ResolutionImpossible") {
+        return Some(InstallFailure::DependencyConflict {
+            conflicts: resolution_conflicts(output),
+        });
+    }
+
+    if let Some(rust_error_index) = RUST_COMPILER_NOT_FOUND_PATTERNS
+        .iter()
+        .filter_map(|pattern| output.find(pattern))
+        .min()
+    {
+        return Some(InstallFailure::RustCompilerRequired {
+            package: failed_package(output, rust_error_index),
+        });
+    }
+
+    let package = failed_package(output, output.len())?;
+    let (package, minimum_versions) = MINIMUM_PACKAGE_VERSIONS
+        .into_iter()
+        .find(|(name, _)| package.eq_ignore_ascii_case(name))?;
+    let minimum_version = minimum_versions
+        .into_iter()
+        .find(|(minor, _)| *minor == python_version.minor && python_version.major == 3)
+        .map(|(_, minimum_version)| minimum_version)?;
+    let version = source_distribution_version(output, package)?;
+    (parse_version(&version) < parse_version(minimum_version)).then(|| {
+        InstallFailure::UnsupportedPythonVersion {
+            package: package.to_string(),
+            version,
+            python_version: python_version.clone(),
+            minimum_version,
+        }
+    })
+}
+
+/// Returns the package (and if known, its version) that couldn't be installed since its
+/// `Requires-Python` excludes the Python version being used, along with that requirement.
+///
+/// pip reports this either directly (when installing from a path or URL), or by ignoring the
+/// incompatible versions on the package index, and then not finding a matching distribution.
+fn requires_python_mismatch(output: &str) -> Option<(String, Option<String>, String)> {
+    // For example: "ERROR: Package 'foo' requires a different Python: 3.8.20 not in '>=3.9'"
+    if let Some((_, rest)) = output.split_once("ERROR: Package '") {
+        let (package, rest) = rest.split_once("' requires a different Python: ")?;
+        let (_, rest) = rest.split_once(" not in '")?;
+        let (requires_python, _) = rest.split_once('\'')?;
+        return Some((package.to_string(), None, requires_python.to_string()));
+    }
+
+    // For example:
+    // "ERROR: Ignored the following versions that require a different python version: 1.25.0 Requires-Python >=3.9; 1.26.4 Requires-Python >=3.9"
+    // "ERROR: No matching distribution found for numpy==1.26.4"
+    let ignored_versions = output.lines().find_map(|line| {
+        line.split_once("require a different python version: ")
+            .map(|(_, versions)| versions)
+    })?;
+    let requirement = output.lines().find_map(|line| {
+        line.split_once("No matching distribution found for ")
+            .map(|(_, requirement)| requirement.trim())
+    })?;
+    let (package, pinned_version) = match requirement.split_once("==") {
+        Some((package, version)) => (package, Some(version)),
+        None => (requirement, None),
+    };
+    let package = package
+        .split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .next()
+        .filter(|package| !package.is_empty())?;
+    // If a specific version wasn't requested, then the newest of the ignored versions is used.
+    let (version, requires_python) = ignored_versions
+        .split(';')
+        .filter_map(|entry| entry.trim().split_once(" Requires-Python "))
+        .rfind(|(version, _)| pinned_version.is_none_or(|pinned| pinned.trim() == *version))?;
+    Some((
+        package.to_string(),
+        Some(version.to_string()),
+        requires_python.trim().to_string(),
+    ))
+}
+
+/// Returns the conflicting requirements reported by pip's resolver, so that they can be shown in
+/// the error message rather than the user having to find them in the resolver's output.
+fn resolution_conflicts(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with(RESOLUTION_CONFLICT_PREFIX))
+        .skip(1)
+        .map(str::trim)
+        .take_while(|line| !line.is_empty())
+        .take(MAX_RESOLUTION_CONFLICTS)
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Returns the name of the package whose build failed, using the summary lines output by pip
+/// and Poetry, or else the last package that pip started collecting before the error occurred.
+fn failed_package(output: &str, error_index: usize) -> Option<String> {
+    let package = output.lines().find_map(|line| {
+        line.split_once("Failed building wheel for ")
+            .or_else(|| line.split_once("based projects ("))
+            .or_else(|| line.split_once("not a problem with poetry but with "))
+            .map(|(_, rest)| rest)
+    });
+    let package = package.or_else(|| {
+        output[..error_index]
+            .lines()
+            .rev()
+            .find_map(|line| line.trim_start().strip_prefix("Collecting "))
+    })?;
+    let name = package
+        .split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .next()
+        .unwrap_or_default();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Returns the version of the package that was being built from source, using either the
+/// filename of the source distribution downloaded by pip, or the install line output by Poetry.
+fn source_distribution_version(output: &str, package: &str) -> Option<String> {
+    let version = output.lines().find_map(|line| {
+        line.split_once(&format!("{package}-"))
+            .and_then(|(_, rest)| rest.split_once(".tar.gz"))
+            .or_else(|| {
+                line.split_once(&format!("Installing {package} ("))
+                    .and_then(|(_, rest)| rest.split_once(')'))
+            })
+            .map(|(version, _)| version)
+    })?;
+    (!version.is_empty() && version.starts_with(|c: char| c.is_ascii_digit()))
+        .then(|| version.to_string())
+}
+
+// Parses the leading numeric components of a version, ignoring any pre-release suffix.
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .split('.')
+        .map_while(|component| {
+            let digits: String = component.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn diagnose_install_failure_rust_maturin() {
+        let output = indoc! {"
+            Collecting typing-extensions==4.12.2 (from -r requirements.txt (line 1))
+            Collecting pydantic-core==2.27.2 (from -r requirements.txt (line 2))
+              Downloading pydantic_core-2.27.2.tar.gz (413 kB)
+              Installing build dependencies: started
+              Preparing metadata (pyproject.toml): finished with status 'error'
+              error: subprocess-exited-with-error
+
+              × Preparing metadata (pyproject.toml) did not run successfully.
+              ╰─> [6 lines of output]
+                  Cargo, the Rust package manager, is not installed or is not on PATH.
+                  This package requires Rust and Cargo to compile extensions.
+        "};
+        assert_eq!(
+            diagnose_install_failure(output, &PythonVersion::new(3, 13, 1)),
+            Some(InstallFailure::RustCompilerRequired {
+                package: Some("pydantic-core".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn diagnose_install_failure_rust_setuptools_rust() {
+        let output = indoc! {"
+            Collecting cryptography==3.4.8
+            Collecting cffi>=1.12
+            Building wheels for collected packages: cryptography
+              Building wheel for cryptography (pyproject.toml): finished with status 'error'
+                  error: can't find Rust compiler
+            ERROR: Failed building wheel for cryptography
+            ERROR: Failed to build installable wheels for some pyproject.toml based projects (cryptography)
+        "};
+        assert_eq!(
+            diagnose_install_failure(output, &PythonVersion::new(3, 13, 1)),
+            Some(InstallFailure::RustCompilerRequired {
+                package: Some("cryptography".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn diagnose_install_failure_rust_poetry() {
+        let output = indoc! {"
+              - Installing pydantic-core (2.27.2): Failed
+              Cargo, the Rust package manager, is not installed or is not on PATH.
+            Note: This error originates from the build backend, and is likely not a problem with poetry but with pydantic-core (2.27.2) not supporting PEP 517 builds.
+        "};
+        assert_eq!(
+            diagnose_install_failure(output, &PythonVersion::new(3, 13, 1)),
+            Some(InstallFailure::RustCompilerRequired {
+                package: Some("pydantic-core".to_string())
+            })
+        );
+        assert_eq!(
+            diagnose_install_failure(
+                "error: can't find Rust compiler",
+                &PythonVersion::new(3, 13, 1)
+            ),
+            Some(InstallFailure::RustCompilerRequired { package: None })
+        );
+    }
+
+    #[test]
+    fn diagnose_install_failure_requires_python_index() {
+        let output = indoc! {"
+            Collecting numpy==1.25.2 (from -r requirements.txt (line 1))
+            ERROR: Ignored the following versions that require a different python version: 1.25.0 Requires-Python >=3.9; 1.25.1 Requires-Python >=3.9; 1.25.2 Requires-Python >=3.9; 1.26.0 Requires-Python <3.13,>=3.9
+            ERROR: Could not find a version that satisfies the requirement numpy==1.25.2 (from versions: 1.3.0, 1.24.4)
+            ERROR: No matching distribution found for numpy==1.25.2
+        "};
+        assert_eq!(
+            diagnose_install_failure(output, &PythonVersion::new(3, 8, 20)),
+            Some(InstallFailure::RequiresPythonMismatch {
+                package: "numpy".to_string(),
+                version: Some("1.25.2".to_string()),
+                requires_python: ">=3.9".to_string(),
+                python_version: PythonVersion::new(3, 8, 20),
+            })
+        );
+
+        let output = output.replace("numpy==1.25.2", "numpy>=1.25");
+        assert_eq!(
+            diagnose_install_failure(&output, &PythonVersion::new(3, 8, 20)),
+            Some(InstallFailure::RequiresPythonMismatch {
+                package: "numpy".to_string(),
+                version: Some("1.26.0".to_string()),
+                requires_python: "<3.13,>=3.9".to_string(),
+                python_version: PythonVersion::new(3, 8, 20),
+            })
+        );
+    }
+
+    #[test]
+    fn diagnose_install_failure_requires_python_local_package() {
+        let output = indoc! {"
+            Processing ./packages/mypackage
+              Installing build dependencies: finished with status 'done'
+            ERROR: Package 'mypackage' requires a different Python: 3.12.8 not in '>=3.13'
+        "};
+        assert_eq!(
+            diagnose_install_failure(output, &PythonVersion::new(3, 12, 8)),
+            Some(InstallFailure::RequiresPythonMismatch {
+                package: "mypackage".to_string(),
+                version: None,
+                requires_python: ">=3.13".to_string(),
+                python_version: PythonVersion::new(3, 12, 8),
+            })
+        );
+    }
+
+    #[test]
+    fn diagnose_install_failure_dependency_conflict() {
+        let output = indoc! {"
+            Collecting requests==2.20.0 (from -r requirements.txt (line 1))
+            Collecting urllib3==2.2.3 (from -r requirements.txt (line 2))
+            INFO: pip is looking at multiple versions of requests to determine which version is compatible with other requirements. This could take a while.
+            ERROR: Cannot install -r requirements.txt (line 1) and urllib3==2.2.3 because these package versions have conflicting dependencies.
+
+            The conflict is caused by:
+                The user requested urllib3==2.2.3
+                requests 2.20.0 depends on urllib3<1.25 and >=1.21.1
+
+            To fix this you could try to:
+            1. loosen the range of package versions you've specified
+            2. remove package versions to allow pip to attempt to solve the dependency conflict
+
+            ERROR: ResolutionImpossible: for help visit https://pip.pypa.io/en/latest/topics/dependency-resolution/#dealing-with-dependency-conflicts
+        "};
+        assert_eq!(
+            diagnose_install_failure(output, &PythonVersion::new(3, 13, 1)),
+            Some(InstallFailure::DependencyConflict {
+                conflicts: vec![
+                    "The user requested urllib3==2.2.3".to_string(),
+                    "requests 2.20.0 depends on urllib3<1.25 and >=1.21.1".to_string(),
+                ]
+            })
+        );
+        assert_eq!(
+            diagnose_install_failure(
+                "ERROR: ResolutionImpossible: for help visit https://pip.pypa.io",
+                &PythonVersion::new(3, 13, 1)
+            ),
+            Some(InstallFailure::DependencyConflict { conflicts: vec![] })
+        );
+    }
+
+    #[test]
+    fn diagnose_install_failure_unknown() {
+        assert_eq!(
+            diagnose_install_failure(
+                "ERROR: Could not find a version that satisfies the requirement an-unknown-package",
+                &PythonVersion::new(3, 13, 1)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn diagnose_install_failure_numpy_too_old() {
+        let output = indoc! {"
+            Collecting numpy==1.26.4 (from -r requirements.txt (line 1))
+              Downloading numpy-1.26.4.tar.gz (15.8 MB)
+              Installing build dependencies: finished with status 'done'
+            Building wheels for collected packages: numpy
+              Building wheel for numpy (pyproject.toml): finished with status 'error'
+              error: subprocess-exited-with-error
+            ERROR: Failed building wheel for numpy
+        "};
+        assert_eq!(
+            diagnose_install_failure(output, &PythonVersion::new(3, 13, 1)),
+            Some(InstallFailure::UnsupportedPythonVersion {
+                package: "numpy".to_string(),
+                version: "1.26.4".to_string(),
+                python_version: PythonVersion::new(3, 13, 1),
+                minimum_version: "2.1.0",
+            })
+        );
+        // numpy 1.26.4 supports Python 3.12, so the failure must have been due to something else.
+        assert_eq!(
+            diagnose_install_failure(output, &PythonVersion::new(3, 12, 8)),
+            None
+        );
+    }
+
+    #[test]
+    fn diagnose_install_failure_scipy_poetry() {
+        let output = indoc! {"
+              - Installing scipy (1.10.1): Failed
+            Note: This error originates from the build backend, and is likely not a problem with poetry but with scipy (1.10.1) not supporting PEP 517 builds.
+        "};
+        assert_eq!(
+            diagnose_install_failure(output, &PythonVersion::new(3, 12, 8)),
+            Some(InstallFailure::UnsupportedPythonVersion {
+                package: "scipy".to_string(),
+                version: "1.10.1".to_string(),
+                python_version: PythonVersion::new(3, 12, 8),
+                minimum_version: "1.11.2",
+            })
+        );
+    }
+
+    #[test]
+    fn parse_version_components() {
+        assert_eq!(parse_version("1.26.4"), [1, 26, 4]);
+        assert_eq!(parse_version("2.1.0rc1"), [2, 1, 0]);
+        assert!(parse_version("1.9.2") < parse_version("1.10.1"));
+    }
+}
@@ -0,0 +1,257 @@
+use crate::build_log::log_info;
+use crate::layers::python;
+use crate::python_version::{
+    self, PythonVersion, PythonVersionOrigin, RequestedPythonVersion, ResolvePythonVersionError,
+};
+use crate::{BuildpackError, PythonBuildpack};
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::{
+    CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerState, RestoredLayerAction,
+};
+use libcnb::layer_env::Scope;
+use libcnb::Env;
+use serde::{Deserialize, Serialize};
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// Resolve the additional Python versions configured in `project.toml` to specific versions.
+///
+/// Versions with the same major and minor version as the app's own Python version are skipped,
+/// since that version's `pythonX.Y` command is already provided by the Python layer.
+pub(crate) fn resolve_additional_python_versions(
+    configured_versions: &[String],
+    python_version: &PythonVersion,
+) -> Result<Vec<PythonVersion>, AdditionalPythonError> {
+    let mut resolved_versions: Vec<PythonVersion> = Vec::new();
+    for configured_version in configured_versions {
+        let requested_version = parse_version(configured_version)
+            .ok_or_else(|| AdditionalPythonError::InvalidVersion(configured_version.clone()))?;
+        let resolved_version = python_version::resolve_python_version(&requested_version)
+            .map_err(AdditionalPythonError::ResolveVersion)?;
+        if (resolved_version.major, resolved_version.minor)
+            == (python_version.major, python_version.minor)
+        {
+            continue;
+        }
+        if resolved_versions.iter().any(|version| {
+            (version.major, version.minor) == (resolved_version.major, resolved_version.minor)
+        }) {
+            return Err(AdditionalPythonError::DuplicateVersion(
+                configured_version.clone(),
+            ));
+        }
+        resolved_versions.push(resolved_version);
+    }
+    Ok(resolved_versions)
+}
+
+fn parse_version(version: &str) -> Option<RequestedPythonVersion> {
+    match version
+        .trim()
+        .split('.')
+        .map(str::parse)
+        .collect::<Result<Vec<u16>, _>>()
+        .ok()?[..]
+    {
+        [major, minor] => Some(RequestedPythonVersion {
+            major,
+            minor,
+            patch: None,
+            origin: PythonVersionOrigin::ProjectToml,
+        }),
+        [major, minor, patch] => Some(RequestedPythonVersion {
+            major,
+            minor,
+            patch: Some(patch),
+            origin: PythonVersionOrigin::ProjectToml,
+        }),
+        _ => None,
+    }
+}
+
+/// Creates a layer containing additional Python versions, for apps that need to test against
+/// multiple Python versions (such as CI images that use tox or nox).
+///
+/// Each version is installed into its own subdirectory of the layer, and only its `pythonX.Y`
+/// command is exposed on `PATH` (via the layer's `bin/` directory), so that the `python` and
+/// `pip` commands still refer to the app's own Python version.
+pub(crate) fn install_additional_pythons(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    python_versions: &[PythonVersion],
+) -> Result<(), libcnb::Error<BuildpackError>> {
+    let new_metadata = AdditionalPythonLayerMetadata {
+        arch: context.target.arch.clone(),
+        distro_name: context.target.distro_name.clone(),
+        distro_version: context.target.distro_version.clone(),
+        python_versions: python_versions.iter().map(ToString::to_string).collect(),
+    };
+
+    let layer = context.cached_layer(
+        layer_name!("python-additional"),
+        CachedLayerDefinition {
+            build: true,
+            launch: true,
+            invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
+            restored_layer_action: &|cached_metadata: &AdditionalPythonLayerMetadata, _| {
+                if cached_metadata == &new_metadata {
+                    RestoredLayerAction::KeepLayer
+                } else {
+                    RestoredLayerAction::DeleteLayer
+                }
+            },
+        },
+    )?;
+    let layer_path = layer.path();
+    let versions_list = new_metadata.python_versions.join(", ");
+
+    match layer.state {
+        LayerState::Restored { .. } => {
+            log_info(format!("Using cached additional Python {versions_list}"));
+        }
+        LayerState::Empty { ref cause } => {
+            match cause {
+                EmptyLayerCause::InvalidMetadataAction { .. } => {
+                    log_info("Discarding cached additional Python versions since its layer metadata can't be parsed");
+                }
+                EmptyLayerCause::RestoredLayerAction { .. } => {
+                    log_info("Discarding cached additional Python versions since the configured versions or the OS/architecture have changed");
+                }
+                EmptyLayerCause::NewlyCreated => {}
+            }
+            for python_version in python_versions {
+                log_info(format!("Installing additional Python {python_version}"));
+                python::download_python(
+                    context,
+                    env,
+                    python_version,
+                    &version_dir(&layer_path, python_version),
+                )?;
+            }
+            link_python_commands(&layer_path, python_versions)
+                .map_err(AdditionalPythonError::LinkPythonCommands)?;
+            layer.write_metadata(new_metadata)?;
+        }
+    }
+
+    // The layer has no env of its own, however, the automatic `PATH` and `LD_LIBRARY_PATH`
+    // entries for its `bin/` and `lib/` directories have to be applied for later build steps.
+    env.clone_from(&layer.read_env()?.apply(Scope::Build, env));
+
+    Ok(())
+}
+
+fn version_dir(layer_path: &Path, python_version: &PythonVersion) -> PathBuf {
+    layer_path.join(format!(
+        "versions/{}.{}",
+        python_version.major, python_version.minor
+    ))
+}
+
+// Symlinks each version's `pythonX.Y` command into the layer's `bin/` directory, and its shared
+// library into the layer's `lib/` directory, since lifecycle adds these directories to `PATH`
+// and `LD_LIBRARY_PATH` respectively. Python determines its installation prefix from the real
+// path of the executable, so the standard library is still found via the symlink.
+fn link_python_commands(layer_path: &Path, python_versions: &[PythonVersion]) -> io::Result<()> {
+    let bin_dir = layer_path.join("bin");
+    let lib_dir = layer_path.join("lib");
+    fs::create_dir_all(&bin_dir)?;
+    fs::create_dir_all(&lib_dir)?;
+
+    for python_version in python_versions {
+        let version_dir = version_dir(layer_path, python_version);
+        let command_name = format!("python{}.{}", python_version.major, python_version.minor);
+        symlink(
+            version_dir.join("bin").join(&command_name),
+            bin_dir.join(&command_name),
+        )?;
+        for entry in fs::read_dir(version_dir.join("lib"))? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            if file_name.to_string_lossy().starts_with("libpython") {
+                symlink(entry.path(), lib_dir.join(file_name))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+struct AdditionalPythonLayerMetadata {
+    arch: String,
+    distro_name: String,
+    distro_version: String,
+    python_versions: Vec<String>,
+}
+
+/// Errors that can occur when resolving or installing the additional Python versions.
+#[derive(Debug)]
+pub(crate) enum AdditionalPythonError {
+    DuplicateVersion(String),
+    InvalidVersion(String),
+    LinkPythonCommands(io::Error),
+    ResolveVersion(ResolvePythonVersionError),
+}
+
+impl From<AdditionalPythonError> for libcnb::Error<BuildpackError> {
+    fn from(error: AdditionalPythonError) -> Self {
+        Self::BuildpackError(BuildpackError::AdditionalPython(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_additional_python_versions_valid() {
+        assert_eq!(
+            resolve_additional_python_versions(
+                &[
+                    "3.11".to_string(),
+                    " 3.12.1".to_string(),
+                    "3.13".to_string()
+                ],
+                &PythonVersion::new(3, 13, 1),
+            )
+            .unwrap(),
+            [
+                python_version::latest_python_version(3, 11).unwrap(),
+                PythonVersion::new(3, 12, 1)
+            ]
+        );
+        assert_eq!(
+            resolve_additional_python_versions(&[], &PythonVersion::new(3, 13, 1)).unwrap(),
+            []
+        );
+    }
+
+    #[test]
+    fn resolve_additional_python_versions_invalid() {
+        let python_version = PythonVersion::new(3, 13, 1);
+        assert!(matches!(
+            resolve_additional_python_versions(&["3".to_string()], &python_version),
+            Err(AdditionalPythonError::InvalidVersion(version)) if version == "3"
+        ));
+        assert!(matches!(
+            resolve_additional_python_versions(&["3.12.x".to_string()], &python_version),
+            Err(AdditionalPythonError::InvalidVersion(version)) if version == "3.12.x"
+        ));
+        assert!(matches!(
+            resolve_additional_python_versions(&["3.7".to_string()], &python_version),
+            Err(AdditionalPythonError::ResolveVersion(
+                ResolvePythonVersionError::EolVersion(_)
+            ))
+        ));
+        assert!(matches!(
+            resolve_additional_python_versions(
+                &["3.12".to_string(), "3.12.1".to_string()],
+                &python_version
+            ),
+            Err(AdditionalPythonError::DuplicateVersion(version)) if version == "3.12.1"
+        ));
+    }
+}
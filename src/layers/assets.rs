@@ -0,0 +1,141 @@
+use crate::build_log::log_info;
+use crate::project_toml::AssetsConfig;
+use crate::utils::{self, StreamedCommandError};
+use crate::{BuildpackError, PythonBuildpack};
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::{
+    CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerState, RestoredLayerAction,
+};
+use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
+use libcnb::Env;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// Creates a cached layer for assets such as ML models and datasets, which would otherwise be
+/// downloaded by the app each time it boots. The cache directories of commonly used libraries
+/// are pointed at the layer, and an optional prefetch command is run to populate it.
+//
+// The layer is cached so that assets don't have to be downloaded again on every build, and is
+// also a launch layer, so that the assets downloaded at build time are available at run-time.
+// The libraries themselves handle checking whether an asset has already been downloaded, so the
+// layer is never invalidated (other than if its metadata can't be parsed).
+pub(crate) fn install_assets(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    assets_config: &AssetsConfig,
+) -> Result<(), libcnb::Error<BuildpackError>> {
+    let layer = context.cached_layer(
+        layer_name!("assets"),
+        CachedLayerDefinition {
+            build: true,
+            launch: true,
+            invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
+            restored_layer_action: &|_: &AssetsLayerMetadata, _| RestoredLayerAction::KeepLayer,
+        },
+    )?;
+
+    match layer.state {
+        LayerState::Restored { .. } => {
+            log_info("Using cached assets");
+        }
+        LayerState::Empty { ref cause } => {
+            if let EmptyLayerCause::InvalidMetadataAction { .. } = cause {
+                log_info("Discarding cached assets since its layer metadata can't be parsed");
+            }
+            layer.write_metadata(AssetsLayerMetadata {})?;
+        }
+    }
+
+    let layer_env = generate_layer_env(&layer.path());
+    layer.write_env(&layer_env)?;
+    env.clone_from(&layer_env.apply(Scope::Build, env));
+
+    if let Some(prefetch_command) = &assets_config.prefetch {
+        utils::log_running_command(prefetch_command);
+        utils::run_command_and_stream_output(
+            Command::new("bash")
+                .args(["-c", prefetch_command])
+                .current_dir(&context.app_dir)
+                .env_clear()
+                .envs(&*env),
+        )
+        .map_err(AssetsLayerError::PrefetchCommand)?;
+    }
+
+    Ok(())
+}
+
+// The env vars are set as defaults, so that apps can still choose a different location.
+fn generate_layer_env(layer_path: &Path) -> LayerEnv {
+    let huggingface_dir = layer_path.join("huggingface");
+    LayerEnv::new()
+        // https://huggingface.co/docs/huggingface_hub/package_reference/environment_variables#hfhome
+        .chainable_insert(
+            Scope::All,
+            ModificationBehavior::Default,
+            "HF_HOME",
+            &huggingface_dir,
+        )
+        // https://pytorch.org/docs/stable/hub.html#where-are-my-downloaded-models-saved
+        .chainable_insert(
+            Scope::All,
+            ModificationBehavior::Default,
+            "TORCH_HOME",
+            layer_path.join("torch"),
+        )
+        // Deprecated in favour of `HF_HOME`, however, still used by older versions of Transformers.
+        .chainable_insert(
+            Scope::All,
+            ModificationBehavior::Default,
+            "TRANSFORMERS_CACHE",
+            huggingface_dir.join("hub"),
+        )
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct AssetsLayerMetadata {}
+
+/// Errors that can occur when preparing the assets layer.
+#[derive(Debug)]
+pub(crate) enum AssetsLayerError {
+    PrefetchCommand(StreamedCommandError),
+}
+
+impl From<AssetsLayerError> for libcnb::Error<BuildpackError> {
+    fn from(error: AssetsLayerError) -> Self {
+        Self::BuildpackError(BuildpackError::AssetsLayer(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assets_layer_env() {
+        let layer_env = generate_layer_env(Path::new("/layer-dir"));
+
+        let mut base_env = Env::new();
+        base_env.insert("TORCH_HOME", "/app/models");
+
+        assert_eq!(
+            utils::environment_as_sorted_vector(&layer_env.apply(Scope::Build, &base_env)),
+            [
+                ("HF_HOME", "/layer-dir/huggingface"),
+                ("TORCH_HOME", "/app/models"),
+                ("TRANSFORMERS_CACHE", "/layer-dir/huggingface/hub"),
+            ]
+        );
+        assert_eq!(
+            utils::environment_as_sorted_vector(&layer_env.apply(Scope::Launch, &Env::new())),
+            [
+                ("HF_HOME", "/layer-dir/huggingface"),
+                ("TORCH_HOME", "/layer-dir/torch"),
+                ("TRANSFORMERS_CACHE", "/layer-dir/huggingface/hub"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use crate::build_log::log_info;
+use crate::hash_verification;
+use crate::python_version::PythonVersion;
+use crate::utils::{self, RetriedCommandError};
+use crate::{BuildpackError, PythonBuildpack};
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::{
+    CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerState, RestoredLayerAction,
+};
+use libcnb::Env;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// The name of the `.pth` file written to the venv's `site-packages` directory, which adds the
+// cached packages layer to `sys.path`.
+const PTH_FILE_NAME: &str = "_heroku_cached_packages.pth";
+
+// The name of the file in the cached packages layer that the packages' requirements are written to.
+const REQUIREMENTS_FILENAME: &str = "requirements.txt";
+
+// The requirements file options that control where packages are downloaded from, which need to
+// apply when installing the cached packages too. The short forms (`-f` and `-i`) can also be
+// written without a separator before the value.
+const INDEX_OPTIONS: [&str; 5] = [
+    "--extra-index-url",
+    "--find-links",
+    "--index-url",
+    "--no-index",
+    "--trusted-host",
+];
+const SHORT_INDEX_OPTIONS: [&str; 2] = ["-f", "-i"];
+
+/// Installs the packages listed in the `cached-packages` setting in `project.toml` (such as `torch`)
+/// into a separate cached layer, returning the path to the layer's `site-packages` directory.
+///
+/// The venv is recreated whenever the app's requirements change, so without this, any change to
+/// the app's requirements means reinstalling multi-gigabyte packages. The layer is only
+/// invalidated when the pinned version of one of the listed packages (or the Python version)
+/// changes. The layer is added to the venv's `sys.path` using a `.pth` file, so pip sees the
+/// packages as already installed when installing the rest of the app's requirements.
+///
+/// Only the listed packages are installed in the layer (not their dependencies), and they must be
+/// pinned to an exact version in the requirements files, since the version is the cache key.
+/// They are installed using the index options and hashes from the app's requirements files, so
+/// that packages from custom indexes (such as the `PyTorch` CPU-only index) can be cached too.
+pub(crate) fn install_cached_packages(
+    context: &BuildContext<PythonBuildpack>,
+    env: &Env,
+    python_version: &PythonVersion,
+    cached_packages: &[String],
+    requirements: &str,
+    venv_dir: &Path,
+) -> Result<Option<PathBuf>, libcnb::Error<BuildpackError>> {
+    if cached_packages.is_empty() {
+        return Ok(None);
+    }
+    let (pinned_packages, unpinned_packages) = pinned_requirements(requirements, cached_packages);
+    if !unpinned_packages.is_empty() {
+        log_info(format!(
+            "Not caching packages that aren't pinned to an exact version: {}",
+            unpinned_packages.join(", ")
+        ));
+    }
+    if pinned_packages.is_empty() {
+        return Ok(None);
+    }
+
+    let new_metadata = CachedPackagesLayerMetadata {
+        arch: context.target.arch.clone(),
+        distro_name: context.target.distro_name.clone(),
+        distro_version: context.target.distro_version.clone(),
+        python_version: python_version.to_string(),
+        packages: pinned_packages,
+    };
+
+    let layer = context.cached_layer(
+        layer_name!("cached-packages"),
+        CachedLayerDefinition {
+            build: true,
+            launch: true,
+            invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
+            restored_layer_action: &|cached_metadata: &CachedPackagesLayerMetadata, _| {
+                if cached_metadata == &new_metadata {
+                    RestoredLayerAction::KeepLayer
+                } else {
+                    RestoredLayerAction::DeleteLayer
+                }
+            },
+        },
+    )?;
+    let site_packages_dir = layer.path().join("site-packages");
+    let package_list = new_metadata.packages.join(", ");
+
+    match layer.state {
+        LayerState::Restored { .. } => {
+            log_info(format!("Using cached packages: {package_list}"));
+        }
+        LayerState::Empty { ref cause } => {
+            match cause {
+                EmptyLayerCause::InvalidMetadataAction { .. }
+                | EmptyLayerCause::RestoredLayerAction { .. } => {
+                    log_info("Discarding cached packages");
+                }
+                EmptyLayerCause::NewlyCreated => {}
+            }
+            log_info(format!("Installing cached packages: {package_list}"));
+            // pip only supports `--hash` options in requirements files (and not on the command line).
+            let requirements_file = layer.path().join(REQUIREMENTS_FILENAME);
+            fs::write(
+                &requirements_file,
+                cached_packages_requirements(requirements, &new_metadata.packages),
+            )
+            .map_err(CachedPackagesLayerError::WriteRequirementsFile)?;
+            utils::run_command_and_stream_output_with_retries(
+                Command::new("pip")
+                    .args([
+                        "install",
+                        "--no-deps",
+                        "--no-input",
+                        "--progress-bar",
+                        "off",
+                        "--target",
+                        &site_packages_dir.to_string_lossy(),
+                        "--requirement",
+                        &requirements_file.to_string_lossy(),
+                    ])
+                    .current_dir(&context.app_dir)
+                    .env_clear()
+                    .envs(env),
+                "pip install",
+            )
+            .map_err(|error| {
+                CachedPackagesLayerError::InstallCommand(error, python_version.clone())
+            })?;
+            layer.write_metadata(new_metadata)?;
+        }
+    }
+
+    fs::write(
+        venv_dir
+            .join(format!(
+                "lib/python{}.{}/site-packages",
+                python_version.major, python_version.minor
+            ))
+            .join(PTH_FILE_NAME),
+        format!("{}\n", site_packages_dir.display()),
+    )
+    .map_err(CachedPackagesLayerError::WritePthFile)?;
+
+    Ok(Some(site_packages_dir))
+}
+
+/// Returns the requirements for the specified packages that are pinned to an exact version using
+/// `==` (as `name==version`), along with the names of any of the packages that aren't pinned.
+/// Packages that aren't in the requirements at all are ignored.
+fn pinned_requirements(requirements: &str, packages: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut pinned = Vec::new();
+    let mut unpinned = Vec::new();
+    for package in packages {
+        let normalized_package = utils::normalize_package_name(package);
+        let versions = requirements
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter_map(|line| {
+                let requirement = line.split([' ', ';']).next().unwrap_or_default();
+                let name_end = requirement
+                    .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+                    .unwrap_or(requirement.len());
+                (!line.starts_with('-')
+                    && utils::normalize_package_name(&requirement[..name_end])
+                        == normalized_package)
+                    .then(|| {
+                        requirement
+                            .split_once("==")
+                            .map(|(_, version)| version.to_string())
+                    })
+            })
+            .collect::<Vec<_>>();
+        match versions.as_slice() {
+            [] => {}
+            [Some(version)] => pinned.push(format!("{package}=={version}")),
+            _ => unpinned.push(package.clone()),
+        }
+    }
+    (pinned, unpinned)
+}
+
+/// Returns the contents of a requirements file for installing the specified pinned packages
+/// (as returned by `pinned_requirements`), which contains the index options from the app's
+/// requirements, along with any hashes of the packages so that pip's hash-checking mode is used.
+fn cached_packages_requirements(requirements: &str, pinned_packages: &[String]) -> String {
+    let requirements = requirements.replace("\\\n", " ");
+    let locked_packages = hash_verification::requirements_packages(&requirements);
+    let index_options = requirements
+        .lines()
+        .map(|line| line.split(" #").next().unwrap_or_default().trim())
+        .filter(|line| {
+            let option = line.split([' ', '=']).next().unwrap_or_default();
+            INDEX_OPTIONS.contains(&option)
+                || SHORT_INDEX_OPTIONS
+                    .iter()
+                    .any(|short_option| line.starts_with(short_option))
+        });
+    let packages = pinned_packages.iter().map(|package| {
+        let (name, version) = package.split_once("==").unwrap_or((package, ""));
+        let normalized_name = utils::normalize_package_name(name);
+        locked_packages
+            .iter()
+            .filter(|locked| {
+                utils::normalize_package_name(&locked.name) == normalized_name
+                    && locked.version == version
+            })
+            .flat_map(|locked| &locked.hashes)
+            .fold(package.clone(), |line, hash| {
+                format!("{line} --hash={hash}")
+            })
+    });
+    index_options
+        .map(ToString::to_string)
+        .chain(packages)
+        .map(|line| line + "\n")
+        .collect()
+}
+
+#[derive(Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CachedPackagesLayerMetadata {
+    arch: String,
+    distro_name: String,
+    distro_version: String,
+    python_version: String,
+    packages: Vec<String>,
+}
+
+/// Errors that can occur when installing the packages in the cached packages layer.
+#[derive(Debug)]
+pub(crate) enum CachedPackagesLayerError {
+    InstallCommand(RetriedCommandError, PythonVersion),
+    WritePthFile(io::Error),
+    WriteRequirementsFile(io::Error),
+}
+
+impl From<CachedPackagesLayerError> for libcnb::Error<BuildpackError> {
+    fn from(error: CachedPackagesLayerError) -> Self {
+        Self::BuildpackError(BuildpackError::CachedPackagesLayer(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn pinned_requirements_packages() {
+        let requirements = indoc! {"
+            # Comment mentioning torch==1.0
+            --extra-index-url https://download.pytorch.org/whl/cpu
+            Torch==2.5.1 ; sys_platform == 'linux' --hash=sha256:abc
+            tensorflow>=2.0
+            nvidia_cublas_cu12==12.4.5.8  # via torch
+            gunicorn==23.0.0
+        "};
+        assert_eq!(
+            pinned_requirements(
+                requirements,
+                &[
+                    "torch".to_string(),
+                    "tensorflow".to_string(),
+                    "nvidia-cublas-cu12".to_string(),
+                    "jax".to_string(),
+                ]
+            ),
+            (
+                vec![
+                    "torch==2.5.1".to_string(),
+                    "nvidia-cublas-cu12==12.4.5.8".to_string()
+                ],
+                vec!["tensorflow".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn cached_packages_requirements_options_and_hashes() {
+        let requirements = indoc! {"
+            -i https://pypi.org/simple
+            --extra-index-url https://download.pytorch.org/whl/cpu  # PyTorch CPU-only wheels
+            --find-links=./wheels
+            -f./other-wheels
+            --trusted-host example.com
+            -e ./local-package
+            --only-binary :all:
+            torch==2.5.1+cpu \\
+                --hash=sha256:abc \\
+                --hash=sha256:def
+            nvidia_cublas_cu12==12.4.5.8
+            numpy==2.1.3 --hash=sha256:123
+        "};
+        assert_eq!(
+            cached_packages_requirements(
+                requirements,
+                &[
+                    "torch==2.5.1+cpu".to_string(),
+                    "nvidia-cublas-cu12==12.4.5.8".to_string()
+                ]
+            ),
+            indoc! {"
+                -i https://pypi.org/simple
+                --extra-index-url https://download.pytorch.org/whl/cpu
+                --find-links=./wheels
+                -f./other-wheels
+                --trusted-host example.com
+                torch==2.5.1+cpu --hash=sha256:abc --hash=sha256:def
+                nvidia-cublas-cu12==12.4.5.8
+            "}
+        );
+    }
+}
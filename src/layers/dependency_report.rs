@@ -0,0 +1,161 @@
+use crate::package_inventory::InstalledPackage;
+use crate::{utils, BuildpackError, PythonBuildpack};
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::UncachedLayerDefinition;
+use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
+use libcnb::Env;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::{fs, io};
+
+/// The env var that's set (for later buildpacks) to the path of the dependency report file.
+pub(crate) const DEPENDENCY_REPORT_ENV_VAR: &str = "PYTHON_DEPENDENCY_REPORT";
+
+const REPORT_FILE_NAME: &str = "dependency-report.json";
+
+/// A machine-readable report of the packages installed for the app, for use by platform tooling
+/// (such as for enforcing dependency policies) without needing to parse the build log.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct DependencyReport {
+    pub(crate) package_manager: &'static str,
+    pub(crate) packages: Vec<ReportedPackage>,
+}
+
+/// An installed package in the dependency report.
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct ReportedPackage {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    /// Whether the package is a direct dependency of the app, rather than a transitive dependency.
+    pub(crate) direct: bool,
+    /// Where the package was installed from (such as the URL of the distribution or package
+    /// index, or the location of a Git repository or local path), if known.
+    pub(crate) source: Option<String>,
+}
+
+impl DependencyReport {
+    /// Creates a report for the installed packages. Packages are direct dependencies if their
+    /// (normalized) name is in `direct_dependencies`, and their sources are looked up by
+    /// normalized name in `sources`.
+    pub(crate) fn new(
+        package_manager: &'static str,
+        installed_packages: &[InstalledPackage],
+        direct_dependencies: &[String],
+        sources: &HashMap<String, String>,
+    ) -> Self {
+        Self {
+            package_manager,
+            packages: installed_packages
+                .iter()
+                .map(|package| {
+                    let name = utils::normalize_package_name(&package.name);
+                    ReportedPackage {
+                        name: package.name.clone(),
+                        version: package.version.clone(),
+                        direct: direct_dependencies.contains(&name),
+                        source: sources.get(&name).cloned(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Writes the dependency report to a build-only layer, and exposes its location to later
+/// buildpacks using the `PYTHON_DEPENDENCY_REPORT` env var.
+//
+// The layer isn't cached or available at launch, since the report is regenerated on every build,
+// and is only intended for use by tooling that runs during the build.
+pub(crate) fn write_dependency_report(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    report: &DependencyReport,
+) -> Result<(), libcnb::Error<BuildpackError>> {
+    let layer = context.uncached_layer(
+        layer_name!("dependency-report"),
+        UncachedLayerDefinition {
+            build: true,
+            launch: false,
+        },
+    )?;
+    let report_path = layer.path().join(REPORT_FILE_NAME);
+
+    serde_json::to_string_pretty(report)
+        .map_err(io::Error::from)
+        .and_then(|contents| fs::write(&report_path, contents))
+        .map_err(DependencyReportLayerError::WriteReport)?;
+
+    let layer_env = LayerEnv::new().chainable_insert(
+        Scope::Build,
+        ModificationBehavior::Override,
+        DEPENDENCY_REPORT_ENV_VAR,
+        &report_path,
+    );
+    layer.write_env(&layer_env)?;
+    env.clone_from(&layer_env.apply(Scope::Build, env));
+
+    Ok(())
+}
+
+/// Errors that can occur when writing the dependency report layer.
+#[derive(Debug)]
+pub(crate) enum DependencyReportLayerError {
+    WriteReport(io::Error),
+}
+
+impl From<DependencyReportLayerError> for libcnb::Error<BuildpackError> {
+    fn from(error: DependencyReportLayerError) -> Self {
+        Self::BuildpackError(BuildpackError::DependencyReportLayer(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dependency_report_new() {
+        let installed_packages = [
+            InstalledPackage {
+                name: "Django".to_string(),
+                version: "5.1.4".to_string(),
+                hash: None,
+            },
+            InstalledPackage {
+                name: "typing_extensions".to_string(),
+                version: "4.12.2".to_string(),
+                hash: None,
+            },
+        ];
+        let sources = HashMap::from([(
+            "typing-extensions".to_string(),
+            "https://pypi.org/simple".to_string(),
+        )]);
+        assert_eq!(
+            DependencyReport::new(
+                "pip",
+                &installed_packages,
+                &["django".to_string()],
+                &sources
+            ),
+            DependencyReport {
+                package_manager: "pip",
+                packages: vec![
+                    ReportedPackage {
+                        name: "Django".to_string(),
+                        version: "5.1.4".to_string(),
+                        direct: true,
+                        source: None,
+                    },
+                    ReportedPackage {
+                        name: "typing_extensions".to_string(),
+                        version: "4.12.2".to_string(),
+                        direct: false,
+                        source: Some("https://pypi.org/simple".to_string()),
+                    },
+                ],
+            }
+        );
+    }
+}
@@ -1,6 +1,16 @@
+pub(crate) mod additional_python;
+pub(crate) mod assets;
+pub(crate) mod cached_packages;
+pub(crate) mod dependency_report;
+pub(crate) mod nltk_data;
+pub(crate) mod pdm;
+pub(crate) mod pdm_dependencies;
 pub(crate) mod pip;
 pub(crate) mod pip_cache;
 pub(crate) mod pip_dependencies;
+pub(crate) mod pipenv;
+pub(crate) mod pipenv_dependencies;
 pub(crate) mod poetry;
 pub(crate) mod poetry_dependencies;
 pub(crate) mod python;
+pub(crate) mod python_env_run;
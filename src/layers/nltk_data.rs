@@ -0,0 +1,198 @@
+use crate::build_log::log_info;
+use crate::package_inventory::InstalledPackage;
+use crate::utils::{self, StreamedCommandError};
+use crate::{telemetry, BuildpackError, PythonBuildpack};
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::{
+    CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerState, RestoredLayerAction,
+};
+use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
+use libcnb::Env;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// Reads the names of the NLTK corpora (or other NLTK data packages) listed in the app's
+/// `nltk.txt` file, if it exists. The names are separated by whitespace (typically one per line),
+/// and lines starting with `#` are ignored.
+pub(crate) fn read_nltk_txt(app_dir: &Path) -> Result<Option<Vec<String>>, NltkDataLayerError> {
+    let Some(contents) = utils::read_optional_file(&app_dir.join("nltk.txt"))
+        .map_err(NltkDataLayerError::ReadNltkTxt)?
+    else {
+        return Ok(None);
+    };
+    parse_nltk_txt(&contents).map(Some)
+}
+
+fn parse_nltk_txt(contents: &str) -> Result<Vec<String>, NltkDataLayerError> {
+    let mut corpora = Vec::new();
+    for name in contents
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(str::split_whitespace)
+    {
+        // The names are passed as arguments to the NLTK downloader, so are restricted to the
+        // characters used by the packages in NLTK's data index.
+        if name.starts_with('-')
+            || !name
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || matches!(char, '_' | '-' | '.'))
+        {
+            return Err(NltkDataLayerError::InvalidCorpusName(name.to_string()));
+        }
+        if !corpora.iter().any(|corpus| corpus == name) {
+            corpora.push(name.to_string());
+        }
+    }
+    Ok(corpora)
+}
+
+/// Creates a layer containing the NLTK data packages listed in `nltk.txt`, downloaded using
+/// NLTK's downloader, with `NLTK_DATA` set so that NLTK finds them at build and run time.
+//
+// The layer is cached, and reused as long as the list of packages hasn't changed, since the
+// downloader otherwise has to fetch NLTK's data index on every build to check for updates.
+pub(crate) fn install_nltk_data(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    corpora: &[String],
+    installed_packages: &[InstalledPackage],
+) -> Result<(), libcnb::Error<BuildpackError>> {
+    if !installed_packages
+        .iter()
+        .any(|package| utils::normalize_package_name(&package.name) == "nltk")
+    {
+        log_info("Skipping the NLTK data in nltk.txt, since the nltk package isn't installed");
+        return Ok(());
+    }
+    if corpora.is_empty() {
+        log_info("No NLTK data packages are listed in nltk.txt");
+        return Ok(());
+    }
+
+    let new_metadata = NltkDataLayerMetadata {
+        corpora: corpora.to_vec(),
+    };
+    let layer = context.cached_layer(
+        layer_name!("nltk-data"),
+        CachedLayerDefinition {
+            build: true,
+            launch: true,
+            invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
+            restored_layer_action: &|cached_metadata: &NltkDataLayerMetadata, _| {
+                if cached_metadata == &new_metadata {
+                    RestoredLayerAction::KeepLayer
+                } else {
+                    RestoredLayerAction::DeleteLayer
+                }
+            },
+        },
+    )?;
+    telemetry::record_layer_cache("nltk-data", &layer.state);
+    let layer_path = layer.path();
+
+    match layer.state {
+        LayerState::Restored { .. } => {
+            log_info("Using cached NLTK data");
+        }
+        LayerState::Empty { ref cause } => {
+            match cause {
+                EmptyLayerCause::InvalidMetadataAction { .. }
+                | EmptyLayerCause::RestoredLayerAction { .. } => {
+                    log_info("Discarding cached NLTK data");
+                }
+                EmptyLayerCause::NewlyCreated => {}
+            }
+
+            utils::log_running_command(&format!(
+                "python -m nltk.downloader -d {} {}",
+                layer_path.display(),
+                corpora.join(" ")
+            ));
+            // Without `--exit-on-error`, the downloader exits successfully even if a package
+            // couldn't be found or downloaded.
+            utils::run_command_and_stream_output(
+                Command::new("python")
+                    .args(["-m", "nltk.downloader", "--exit-on-error", "-d"])
+                    .arg(&layer_path)
+                    .args(corpora)
+                    .current_dir(&context.app_dir)
+                    .env_clear()
+                    .envs(&*env),
+            )
+            .map_err(NltkDataLayerError::DownloadCommand)?;
+            layer.write_metadata(new_metadata)?;
+        }
+    }
+
+    let layer_env = LayerEnv::new().chainable_insert(
+        Scope::All,
+        ModificationBehavior::Override,
+        "NLTK_DATA",
+        &layer_path,
+    );
+    layer.write_env(&layer_env)?;
+    env.clone_from(&layer_env.apply(Scope::Build, env));
+
+    Ok(())
+}
+
+#[derive(Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+struct NltkDataLayerMetadata {
+    corpora: Vec<String>,
+}
+
+/// Errors that can occur when installing the NLTK data listed in `nltk.txt` into a layer.
+#[derive(Debug)]
+pub(crate) enum NltkDataLayerError {
+    DownloadCommand(StreamedCommandError),
+    InvalidCorpusName(String),
+    ReadNltkTxt(io::Error),
+}
+
+impl From<NltkDataLayerError> for libcnb::Error<BuildpackError> {
+    fn from(error: NltkDataLayerError) -> Self {
+        Self::BuildpackError(BuildpackError::NltkDataLayer(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn parse_nltk_txt_valid() {
+        assert_eq!(
+            parse_nltk_txt(indoc! {"
+                # The corpora used by the app.
+                punkt_tab  stopwords
+                wordnet
+                omw-1.4
+                stopwords
+            "})
+            .unwrap(),
+            ["punkt_tab", "stopwords", "wordnet", "omw-1.4"]
+        );
+        assert!(parse_nltk_txt("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_nltk_txt_invalid_name() {
+        assert!(matches!(
+            parse_nltk_txt("punkt\n--dir=/tmp\n"),
+            Err(NltkDataLayerError::InvalidCorpusName(name)) if name == "--dir=/tmp"
+        ));
+        assert!(matches!(
+            parse_nltk_txt("stopwords;rm\n"),
+            Err(NltkDataLayerError::InvalidCorpusName(name)) if name == "stopwords;rm"
+        ));
+        assert!(matches!(
+            parse_nltk_txt("-q\n"),
+            Err(NltkDataLayerError::InvalidCorpusName(name)) if name == "-q"
+        ));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::build_log::log_info;
+use crate::layers::pip;
+use crate::packaging_tool_install::{self, PackagingToolInstallError};
+use crate::packaging_tool_versions::{KEYRING_REQUIREMENTS, PDM_REQUIREMENTS, PDM_VERSION};
+use crate::python_version::PythonVersion;
+use crate::{telemetry, utils, BuildpackError, PythonBuildpack};
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::{
+    CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerState, RestoredLayerAction,
+};
+use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
+use libcnb::Env;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// Creates a build-only layer containing PDM, along with any keyring backend packages.
+pub(crate) fn install_pdm(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    python_version: &PythonVersion,
+    python_layer_path: &Path,
+    keyring_packages: &[String],
+) -> Result<(), libcnb::Error<BuildpackError>> {
+    let new_metadata = PdmLayerMetadata {
+        arch: context.target.arch.clone(),
+        distro_name: context.target.distro_name.clone(),
+        distro_version: context.target.distro_version.clone(),
+        python_version: python_version.to_string(),
+        pdm_version: PDM_VERSION.to_string(),
+        keyring_packages: keyring_packages.to_vec(),
+    };
+
+    let layer = context.cached_layer(
+        layer_name!("pdm"),
+        CachedLayerDefinition {
+            build: true,
+            launch: false,
+            invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
+            restored_layer_action: &|cached_metadata: &PdmLayerMetadata, _| {
+                let cached_pdm_version = cached_metadata.pdm_version.clone();
+                if cached_metadata == &new_metadata {
+                    (RestoredLayerAction::KeepLayer, cached_pdm_version)
+                } else {
+                    (RestoredLayerAction::DeleteLayer, cached_pdm_version)
+                }
+            },
+        },
+    )?;
+    telemetry::record_layer_cache("pdm", &layer.state);
+
+    let mut layer_env = LayerEnv::new()
+        // Move the Python user base directory to this layer instead of under HOME:
+        // https://docs.python.org/3/using/cmdline.html#envvar-PYTHONUSERBASE
+        .chainable_insert(
+            Scope::Build,
+            ModificationBehavior::Override,
+            "PYTHONUSERBASE",
+            layer.path(),
+        )
+        // The PDM version is controlled by the buildpack, so checking for updates is pointless.
+        // https://pdm-project.org/latest/reference/configuration/
+        .chainable_insert(
+            Scope::Build,
+            ModificationBehavior::Override,
+            "PDM_CHECK_UPDATE",
+            "false",
+        )
+        // Ensures PDM uses the venv (via `VIRTUAL_ENV`), rather than an interpreter saved in a
+        // `.pdm-python` file that was accidentally committed from a developer's machine.
+        .chainable_insert(
+            Scope::Build,
+            ModificationBehavior::Override,
+            "PDM_IGNORE_SAVED_PYTHON",
+            "1",
+        );
+
+    match layer.state {
+        LayerState::Restored {
+            cause: ref cached_pdm_version,
+        } => {
+            log_info(format!("Using cached PDM {cached_pdm_version}"));
+        }
+        LayerState::Empty { ref cause } => {
+            match cause {
+                EmptyLayerCause::InvalidMetadataAction { .. } => {
+                    log_info("Discarding cached PDM since its layer metadata can't be parsed");
+                }
+                EmptyLayerCause::RestoredLayerAction {
+                    cause: cached_pdm_version,
+                } => {
+                    log_info(format!("Discarding cached PDM {cached_pdm_version}"));
+                }
+                EmptyLayerCause::NewlyCreated => {}
+            }
+
+            log_info(format!("Installing PDM {PDM_VERSION}"));
+            // PDM looks up index credentials using keyring if it's importable from PDM's own
+            // environment, so keyring and its backends are installed alongside PDM.
+            // https://pdm-project.org/latest/usage/config/#password-management-with-keyring
+            let keyring_requirements = pip::keyring_requirements(keyring_packages);
+            if !keyring_requirements.is_empty() {
+                log_info(format!(
+                    "Installing keyring packages: {}",
+                    keyring_requirements.join(", ")
+                ));
+            }
+
+            // As for Poetry, we use the pip wheel bundled within Python's standard library to
+            // install PDM. PDM installs packages using its own installer, so pip doesn't need
+            // to be installed into the user site-packages or the venv.
+            let bundled_pip_module_path =
+                utils::bundled_pip_module_path(python_layer_path, python_version)
+                    .map_err(PdmLayerError::LocateBundledPip)?;
+
+            let mut requirements = vec![PDM_REQUIREMENTS];
+            if !keyring_packages.is_empty() {
+                requirements.push(KEYRING_REQUIREMENTS);
+            }
+            packaging_tool_install::install_packaging_tool(
+                &bundled_pip_module_path,
+                &layer_env.apply(Scope::Build, env),
+                &requirements,
+                keyring_packages,
+                &layer.path(),
+            )
+            .map_err(PdmLayerError::InstallPdm)?;
+
+            layer.write_metadata(new_metadata)?;
+        }
+    }
+
+    layer.write_env(&layer_env)?;
+    // Required to pick up the automatic PATH env var. See: https://github.com/heroku/libcnb.rs/issues/842
+    layer_env = layer.read_env()?;
+    env.clone_from(&layer_env.apply(Scope::Build, env));
+
+    Ok(())
+}
+
+// Some of PDM's dependencies contain compiled components so are platform-specific (unlike pure
+// Python packages). As such we have to take arch and distro into account for cache invalidation.
+#[derive(Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+struct PdmLayerMetadata {
+    arch: String,
+    distro_name: String,
+    distro_version: String,
+    python_version: String,
+    pdm_version: String,
+    keyring_packages: Vec<String>,
+}
+
+/// Errors that can occur when installing PDM into a layer.
+#[derive(Debug)]
+pub(crate) enum PdmLayerError {
+    InstallPdm(PackagingToolInstallError),
+    LocateBundledPip(io::Error),
+}
+
+impl From<PdmLayerError> for libcnb::Error<BuildpackError> {
+    fn from(error: PdmLayerError) -> Self {
+        Self::BuildpackError(BuildpackError::PdmLayer(error))
+    }
+}
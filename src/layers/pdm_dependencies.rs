@@ -0,0 +1,275 @@
+use crate::build_log::log_info;
+use crate::dependency_plan;
+use crate::layers::dependency_report::{self, DependencyReport};
+use crate::package_inventory::{self, InstalledPackage, ListInstalledPackagesError};
+use crate::package_manager::PackageManager;
+use crate::packaging_tool_versions::PDM_VERSION;
+use crate::pdm_lock::{self, PdmLock, ReadPdmLockError};
+use crate::pyproject_toml::PyprojectToml;
+use crate::python_version::PythonVersion;
+use crate::redact::redact_url_credentials;
+use crate::utils::{RetriedCommandError, StreamedCommandError};
+use crate::{telemetry, utils, BuildpackError, PythonBuildpack};
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::{
+    CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerState, RestoredLayerAction,
+};
+use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
+use libcnb::Env;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Creates a layer containing the application's Python dependencies, installed using PDM.
+//
+// We install into a virtual environment for the same reasons as when using Poetry (see
+// `poetry_dependencies.rs`). PDM uses the venv rather than creating its own, since
+// `VIRTUAL_ENV` is set to the venv's location.
+//
+// We cache the virtual environment, since it results in faster builds than only caching PDM's
+// download/wheel cache. It's safe to do so, since `pdm sync --clean` fully manages the environment
+// (including e.g. uninstalling packages when they are removed from the lockfile).
+pub(crate) fn install_dependencies(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    python_version: &PythonVersion,
+    pyproject_toml: Option<&PyprojectToml>,
+) -> Result<(PathBuf, Vec<InstalledPackage>), libcnb::Error<BuildpackError>> {
+    let pdm_lock = pdm_lock::read_pdm_lock(&context.app_dir)
+        .map_err(PdmDependenciesLayerError::ReadPdmLock)?;
+
+    let new_metadata = PdmDependenciesLayerMetadata {
+        arch: context.target.arch.clone(),
+        distro_name: context.target.distro_name.clone(),
+        distro_version: context.target.distro_version.clone(),
+        python_version: python_version.to_string(),
+        pdm_version: PDM_VERSION.to_string(),
+        packages: Vec::new(),
+    };
+
+    let layer = context.cached_layer(
+        // The name of this layer must be alphabetically after that of the `python` layer so that
+        // this layer's `bin/` directory (and thus `python` symlink) is listed first in `PATH`:
+        // https://github.com/buildpacks/spec/blob/main/buildpack.md#layer-paths
+        layer_name!("venv"),
+        CachedLayerDefinition {
+            build: true,
+            launch: true,
+            invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
+            restored_layer_action: &|cached_metadata: &PdmDependenciesLayerMetadata, _| {
+                if cached_metadata.is_cache_compatible(&new_metadata) {
+                    RestoredLayerAction::KeepLayer
+                } else {
+                    RestoredLayerAction::DeleteLayer
+                }
+            },
+        },
+    )?;
+    telemetry::record_layer_cache("venv", &layer.state);
+    let layer_path = layer.path();
+
+    match layer.state {
+        LayerState::Restored { .. } => {
+            log_info("Using cached virtual environment");
+        }
+        LayerState::Empty { ref cause } => {
+            match cause {
+                EmptyLayerCause::InvalidMetadataAction { .. }
+                | EmptyLayerCause::RestoredLayerAction { .. } => {
+                    log_info("Discarding cached virtual environment");
+                }
+                EmptyLayerCause::NewlyCreated => {}
+            }
+
+            log_info("Creating virtual environment");
+            utils::run_command_and_stream_output(
+                Command::new("python")
+                    .args(["-m", "venv", "--without-pip", &layer_path.to_string_lossy()])
+                    .env_clear()
+                    .envs(&*env),
+            )
+            .map_err(PdmDependenciesLayerError::CreateVenvCommand)?;
+        }
+    }
+
+    let mut layer_env = LayerEnv::new()
+        // For parity with the venv's `bin/activate` script:
+        // https://docs.python.org/3/library/venv.html#how-venvs-work
+        .chainable_insert(
+            Scope::All,
+            ModificationBehavior::Override,
+            "VIRTUAL_ENV",
+            &layer_path,
+        );
+    layer.write_env(&layer_env)?;
+    // Required to pick up the automatic PATH env var. See: https://github.com/heroku/libcnb.rs/issues/842
+    layer_env = layer.read_env()?;
+    env.clone_from(&layer_env.apply(Scope::Build, env));
+
+    if dependency_plan::is_plan_only(env) {
+        pdm_sync(&context.app_dir, env, python_version, true)?;
+        return Err(libcnb::Error::BuildpackError(
+            BuildpackError::DependencyPlanOnly,
+        ));
+    }
+
+    pdm_sync(&context.app_dir, env, python_version, false)?;
+
+    let packages = package_inventory::list_installed_packages(&context.app_dir, env, &[])
+        .map_err(PdmDependenciesLayerError::ListInstalledPackages)?;
+    dependency_report::write_dependency_report(
+        context,
+        env,
+        &generate_dependency_report(&pdm_lock, pyproject_toml, &packages),
+    )?;
+    layer.write_sboms(&package_inventory::packages_sboms(&packages))?;
+    layer.write_metadata(PdmDependenciesLayerMetadata {
+        packages: packages.clone(),
+        ..new_metadata
+    })?;
+
+    Ok((layer_path, packages))
+}
+
+/// Installs the app's dependencies from `pdm.lock`, or if `dry_run` is set, shows the package
+/// operations that would be performed (which if the virtual environment was restored from the
+/// cache, are the changes compared to the previous build).
+fn pdm_sync(
+    app_dir: &Path,
+    env: &Env,
+    python_version: &PythonVersion,
+    dry_run: bool,
+) -> Result<(), PdmDependenciesLayerError> {
+    let mut args = vec!["sync", "--prod", "--clean"];
+    if dry_run {
+        args.push("--dry-run");
+    }
+    utils::log_running_command(&format!("pdm {}", args.join(" ")));
+    utils::run_command_and_stream_output_with_retries(
+        Command::new("pdm")
+            .args(&args)
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+        "pdm sync",
+    )
+    .map_err(|error| {
+        if dry_run {
+            PdmDependenciesLayerError::PdmDryRunCommand(error, python_version.clone())
+        } else {
+            PdmDependenciesLayerError::PdmSyncCommand(error, python_version.clone())
+        }
+    })
+}
+
+// The direct dependencies are those declared in `pyproject.toml`. Packages without a Git URL, path
+// or URL in `pdm.lock` were installed from a package index, which isn't recorded per package.
+fn generate_dependency_report(
+    pdm_lock: &PdmLock,
+    pyproject_toml: Option<&PyprojectToml>,
+    packages: &[InstalledPackage],
+) -> DependencyReport {
+    let sources = pdm_lock
+        .packages
+        .iter()
+        .filter_map(|package| {
+            Some((
+                utils::normalize_package_name(&package.name),
+                redact_url_credentials(package.source()?),
+            ))
+        })
+        .collect();
+    DependencyReport::new(
+        PackageManager::Pdm.name(),
+        packages,
+        &pyproject_toml
+            .map(PyprojectToml::dependency_names)
+            .unwrap_or_default(),
+        &sources,
+    )
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct PdmDependenciesLayerMetadata {
+    arch: String,
+    distro_name: String,
+    distro_version: String,
+    python_version: String,
+    pdm_version: String,
+    packages: Vec<InstalledPackage>,
+}
+
+impl PdmDependenciesLayerMetadata {
+    /// Whether a cached layer with this metadata can be reused for the new metadata.
+    fn is_cache_compatible(&self, new_metadata: &Self) -> bool {
+        // By destructuring here we ensure that any fields added to the metadata in the future
+        // have to be explicitly considered for cache invalidation. The installed packages are
+        // only recorded for reference, and so are intentionally not compared (any changes in
+        // dependencies are handled by PDM when it syncs the cached venv with the lockfile).
+        let Self {
+            arch,
+            distro_name,
+            distro_version,
+            python_version,
+            pdm_version,
+            packages: _,
+        } = self;
+        (
+            arch,
+            distro_name,
+            distro_version,
+            python_version,
+            pdm_version,
+        ) == (
+            &new_metadata.arch,
+            &new_metadata.distro_name,
+            &new_metadata.distro_version,
+            &new_metadata.python_version,
+            &new_metadata.pdm_version,
+        )
+    }
+}
+
+/// Errors that can occur when installing the project's dependencies into a layer using PDM.
+#[derive(Debug)]
+pub(crate) enum PdmDependenciesLayerError {
+    CreateVenvCommand(StreamedCommandError),
+    ListInstalledPackages(ListInstalledPackagesError),
+    PdmDryRunCommand(RetriedCommandError, PythonVersion),
+    PdmSyncCommand(RetriedCommandError, PythonVersion),
+    ReadPdmLock(ReadPdmLockError),
+}
+
+impl From<PdmDependenciesLayerError> for libcnb::Error<BuildpackError> {
+    fn from(error: PdmDependenciesLayerError) -> Self {
+        Self::BuildpackError(BuildpackError::PdmDependenciesLayer(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pyproject_toml;
+
+    #[test]
+    fn generate_dependency_report_pdm_basic() {
+        let app_dir = Path::new("tests/fixtures/pdm_basic");
+        let pdm_lock = pdm_lock::read_pdm_lock(app_dir).unwrap();
+        let pyproject_toml = pyproject_toml::read_pyproject_toml(app_dir).unwrap();
+        let report = generate_dependency_report(
+            &pdm_lock,
+            pyproject_toml.as_ref(),
+            &[InstalledPackage {
+                name: "typing_extensions".to_string(),
+                version: "4.12.2".to_string(),
+                hash: None,
+            }],
+        );
+        assert_eq!(report.package_manager, "PDM");
+        assert_eq!(report.packages.len(), 1);
+        assert!(report.packages[0].direct);
+        assert_eq!(report.packages[0].source, None);
+    }
+}
@@ -1,7 +1,10 @@
-use crate::packaging_tool_versions::PIP_VERSION;
+use crate::build_log::log_info;
+use crate::packaging_tool_install::{self, PackagingToolInstallError};
+use crate::packaging_tool_versions::{
+    KEYRING_REQUIREMENTS, KEYRING_VERSION, PIP_REQUIREMENTS, PIP_VERSION,
+};
 use crate::python_version::PythonVersion;
-use crate::utils::StreamedCommandError;
-use crate::{utils, BuildpackError, PythonBuildpack};
+use crate::{telemetry, utils, BuildpackError, PythonBuildpack};
 use libcnb::build::BuildContext;
 use libcnb::data::layer_name;
 use libcnb::layer::{
@@ -9,22 +12,23 @@ use libcnb::layer::{
 };
 use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
 use libcnb::Env;
-use libherokubuildpack::log::log_info;
 use serde::{Deserialize, Serialize};
 use std::io;
 use std::path::Path;
-use std::process::Command;
 
-/// Creates a layer containing pip.
+/// Creates a layer containing pip, along with `keyring` and any keyring backend packages
+/// configured in the app's `project.toml`.
 pub(crate) fn install_pip(
     context: &BuildContext<PythonBuildpack>,
     env: &mut Env,
     python_version: &PythonVersion,
     python_layer_path: &Path,
+    keyring_packages: &[String],
 ) -> Result<(), libcnb::Error<BuildpackError>> {
     let new_metadata = PipLayerMetadata {
         python_version: python_version.to_string(),
         pip_version: PIP_VERSION.to_string(),
+        keyring_packages: keyring_packages.to_vec(),
     };
 
     let layer = context.cached_layer(
@@ -43,6 +47,7 @@ pub(crate) fn install_pip(
             },
         },
     )?;
+    telemetry::record_layer_cache("pip", &layer.state);
 
     let mut layer_env = LayerEnv::new()
         // We use a curated pip version, so disable the update check to speed up pip invocations,
@@ -62,6 +67,18 @@ pub(crate) fn install_pip(
             "PYTHONUSERBASE",
             layer.path(),
         );
+    if !keyring_packages.is_empty() {
+        // Makes pip look up index credentials using the `keyring` command installed in this layer.
+        // The subprocess provider is used (rather than `import`), since keyring isn't installed
+        // in the same environment as pip. This is set as a default, so users can still override it.
+        // https://pip.pypa.io/en/stable/topics/authentication/#keyring-support
+        layer_env = layer_env.chainable_insert(
+            Scope::Build,
+            ModificationBehavior::Default,
+            "PIP_KEYRING_PROVIDER",
+            "subprocess",
+        );
+    }
 
     match layer.state {
         LayerState::Restored {
@@ -83,6 +100,13 @@ pub(crate) fn install_pip(
             }
 
             log_info(format!("Installing pip {PIP_VERSION}"));
+            let keyring_requirements = keyring_requirements(keyring_packages);
+            if !keyring_requirements.is_empty() {
+                log_info(format!(
+                    "Installing keyring packages: {}",
+                    keyring_requirements.join(", ")
+                ));
+            }
 
             // We use the pip wheel bundled within Python's standard library to install our chosen
             // pip version, since it's faster than `ensurepip` followed by an upgrade in place.
@@ -90,23 +114,18 @@ pub(crate) fn install_pip(
                 utils::bundled_pip_module_path(python_layer_path, python_version)
                     .map_err(PipLayerError::LocateBundledPip)?;
 
-            utils::run_command_and_stream_output(
-                Command::new("python")
-                    .args([
-                        &bundled_pip_module_path.to_string_lossy(),
-                        "install",
-                        // There is no point using pip's cache here, since the layer itself will be cached.
-                        "--no-cache-dir",
-                        "--no-input",
-                        "--no-warn-script-location",
-                        "--quiet",
-                        "--user",
-                        format!("pip=={PIP_VERSION}").as_str(),
-                    ])
-                    .env_clear()
-                    .envs(&layer_env.apply(Scope::Build, env)),
+            let mut requirements = vec![PIP_REQUIREMENTS];
+            if !keyring_packages.is_empty() {
+                requirements.push(KEYRING_REQUIREMENTS);
+            }
+            packaging_tool_install::install_packaging_tool(
+                &bundled_pip_module_path,
+                &layer_env.apply(Scope::Build, env),
+                &requirements,
+                keyring_packages,
+                &layer.path(),
             )
-            .map_err(PipLayerError::InstallPipCommand)?;
+            .map_err(PipLayerError::InstallPip)?;
 
             layer.write_metadata(new_metadata)?;
         }
@@ -127,12 +146,26 @@ pub(crate) fn install_pip(
 struct PipLayerMetadata {
     python_version: String,
     pip_version: String,
+    // Defaulted so that layer metadata from older buildpack versions can still be read.
+    #[serde(default)]
+    keyring_packages: Vec<String>,
+}
+
+/// The requirements to install for the configured keyring backend packages (if any), which
+/// includes `keyring` itself, since its command is what pip and Poetry use to look up credentials.
+pub(crate) fn keyring_requirements(keyring_packages: &[String]) -> Vec<String> {
+    if keyring_packages.is_empty() {
+        return Vec::new();
+    }
+    let mut requirements = vec![format!("keyring=={KEYRING_VERSION}")];
+    requirements.extend(keyring_packages.iter().cloned());
+    requirements
 }
 
 /// Errors that can occur when installing pip into a layer.
 #[derive(Debug)]
 pub(crate) enum PipLayerError {
-    InstallPipCommand(StreamedCommandError),
+    InstallPip(PackagingToolInstallError),
     LocateBundledPip(io::Error),
 }
 
@@ -141,3 +174,20 @@ impl From<PipLayerError> for libcnb::Error<BuildpackError> {
         Self::BuildpackError(BuildpackError::PipLayer(error))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyring_requirements_configured() {
+        assert_eq!(
+            keyring_requirements(&["keyrings.google-artifactregistry-auth".to_string()]),
+            [
+                format!("keyring=={KEYRING_VERSION}"),
+                "keyrings.google-artifactregistry-auth".to_string()
+            ]
+        );
+        assert!(keyring_requirements(&[]).is_empty());
+    }
+}
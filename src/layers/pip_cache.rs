@@ -1,23 +1,64 @@
+use crate::build_log::log_info;
+use crate::disk_space;
 use crate::packaging_tool_versions::PIP_VERSION;
 use crate::python_version::PythonVersion;
-use crate::{BuildpackError, PythonBuildpack};
+use crate::{telemetry, BuildpackError, PythonBuildpack};
 use libcnb::build::BuildContext;
 use libcnb::data::layer_name;
 use libcnb::layer::{
     CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerState, RestoredLayerAction,
+    UncachedLayerDefinition,
 };
 use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
 use libcnb::Env;
-use libherokubuildpack::log::log_info;
 use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-/// Creates a build-only layer for pip's cache of HTTP requests/downloads and built package wheels.
+/// The env var users can set (to any value) to stop pip's cache being cached between builds,
+/// for example to reduce the size of the build cache, or to rule out the cache as a cause of
+/// install problems.
+pub(crate) const DISABLE_PIP_CACHE_ENV_VAR: &str = "PYTHON_DISABLE_PIP_CACHE";
+
+/// The env var that can be used to change the maximum size of the cache (in megabytes).
+pub(crate) const PIP_CACHE_MAX_SIZE_ENV_VAR: &str = "PYTHON_PIP_CACHE_MAX_SIZE";
+
+// The cache is large enough for the dependencies of most apps (including PyTorch with its CUDA
+// dependencies), whilst stopping it from growing indefinitely as dependencies are upgraded.
+const DEFAULT_MAX_SIZE_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+
+const BYTES_PER_MEGABYTE: u64 = 1024 * 1024;
+
+/// Creates a build-only layer for pip's cache of HTTP requests/downloads and built package wheels,
+/// returning the path to the layer.
+///
+/// If the cache restored from the previous build is larger than the maximum size, the least
+/// recently used files are deleted until it fits. The cache isn't kept between builds
+/// if it has been disabled using `PYTHON_DISABLE_PIP_CACHE`.
 // See: https://pip.pypa.io/en/stable/topics/caching/
 pub(crate) fn prepare_pip_cache(
     context: &BuildContext<PythonBuildpack>,
     env: &mut Env,
     python_version: &PythonVersion,
-) -> Result<(), libcnb::Error<BuildpackError>> {
+) -> Result<PathBuf, libcnb::Error<BuildpackError>> {
+    let max_size = max_cache_size(env)?;
+    if env.contains_key(DISABLE_PIP_CACHE_ENV_VAR) {
+        log_info(format!(
+            "Skipping the pip download/wheel cache, since {DISABLE_PIP_CACHE_ENV_VAR} is set"
+        ));
+        // The layer is still needed for the downloaded files, but is discarded after the build.
+        let layer = context.uncached_layer(
+            layer_name!("pip-cache"),
+            UncachedLayerDefinition {
+                build: true,
+                launch: false,
+            },
+        )?;
+        return configure_pip_cache_dir(env, &layer.path(), |layer_env| layer.write_env(layer_env));
+    }
+
     let new_metadata = PipCacheLayerMetadata {
         arch: context.target.arch.clone(),
         distro_name: context.target.distro_name.clone(),
@@ -41,10 +82,20 @@ pub(crate) fn prepare_pip_cache(
             },
         },
     )?;
+    telemetry::record_layer_cache("pip-cache", &layer.state);
 
     match layer.state {
         LayerState::Restored { .. } => {
             log_info("Using cached pip download/wheel cache");
+            let pruned_size =
+                prune_cache(&layer.path(), max_size).map_err(PipCacheLayerError::PruneCache)?;
+            if pruned_size > 0 {
+                log_info(format!(
+                    "Removed {} of the least recently used files from the cache, to keep it under {}",
+                    disk_space::format_gigabytes(pruned_size),
+                    disk_space::format_gigabytes(max_size)
+                ));
+            }
         }
         LayerState::Empty { cause } => {
             match cause {
@@ -60,19 +111,99 @@ pub(crate) fn prepare_pip_cache(
         }
     }
 
-    // https://pip.pypa.io/en/stable/cli/pip/#cmdoption-cache-dir
+    configure_pip_cache_dir(env, &layer.path(), |layer_env| layer.write_env(layer_env))
+}
+
+// https://pip.pypa.io/en/stable/cli/pip/#cmdoption-cache-dir
+fn configure_pip_cache_dir(
+    env: &mut Env,
+    layer_path: &Path,
+    write_env: impl FnOnce(&LayerEnv) -> libcnb::Result<(), BuildpackError>,
+) -> Result<PathBuf, libcnb::Error<BuildpackError>> {
     let layer_env = LayerEnv::new().chainable_insert(
         Scope::Build,
         ModificationBehavior::Override,
         "PIP_CACHE_DIR",
-        layer.path(),
+        layer_path,
     );
-    layer.write_env(&layer_env)?;
+    write_env(&layer_env)?;
     env.clone_from(&layer_env.apply(Scope::Build, env));
+    Ok(layer_path.to_path_buf())
+}
+
+fn max_cache_size(env: &Env) -> Result<u64, PipCacheLayerError> {
+    let Some(value) = env.get_string_lossy(PIP_CACHE_MAX_SIZE_ENV_VAR) else {
+        return Ok(DEFAULT_MAX_SIZE_BYTES);
+    };
+    value
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|megabytes| megabytes.checked_mul(BYTES_PER_MEGABYTE))
+        .ok_or(PipCacheLayerError::InvalidMaxSize(value))
+}
+
+/// A file in pip's cache, with the details needed to decide whether to prune it.
+#[derive(Debug, PartialEq)]
+struct CacheFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+// Deletes the least recently modified files until the cache fits within the maximum size,
+// returning the total size deleted. For pip's HTTP and wheel caches, the modification time is
+// when the file was downloaded or built, whereas the files in the `downloads/` directory (used
+// by the separate download step) are touched by each build that reuses them, so the files that
+// are still in use are kept. Empty directories are left in place, since they're small, and pip
+// may reuse them.
+fn prune_cache(cache_dir: &Path, max_size: u64) -> io::Result<u64> {
+    let mut files = Vec::new();
+    find_cache_files(cache_dir, &mut files)?;
+    let mut pruned_size = 0;
+    for file in files_to_prune(files, max_size) {
+        fs::remove_file(&file.path)?;
+        pruned_size += file.size;
+    }
+    Ok(pruned_size)
+}
 
+fn find_cache_files(dir: &Path, files: &mut Vec<CacheFile>) -> io::Result<()> {
+    for entry in dir.read_dir()? {
+        let entry = entry?;
+        // The `DirEntry` methods don't follow symlinks, so only files in the cache are counted.
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            find_cache_files(&entry.path(), files)?;
+        } else if file_type.is_file() {
+            let metadata = entry.metadata()?;
+            files.push(CacheFile {
+                path: entry.path(),
+                size: metadata.len(),
+                modified: metadata.modified()?,
+            });
+        }
+    }
     Ok(())
 }
 
+fn files_to_prune(mut files: Vec<CacheFile>, max_size: u64) -> Vec<CacheFile> {
+    let mut excess_size = files
+        .iter()
+        .map(|file| file.size)
+        .sum::<u64>()
+        .saturating_sub(max_size);
+    files.sort_by_key(|file| file.modified);
+    files
+        .into_iter()
+        .take_while(|file| {
+            let take = excess_size > 0;
+            excess_size = excess_size.saturating_sub(file.size);
+            take
+        })
+        .collect()
+}
+
 // Timestamp based cache invalidation isn't used here since the Python and pip versions will
 // change often enough that it isn't worth the added complexity. Ideally pip would support
 // cleaning up its own cache: https://github.com/pypa/pip/issues/6956
@@ -85,3 +216,67 @@ struct PipCacheLayerMetadata {
     python_version: String,
     pip_version: String,
 }
+
+/// Errors that can occur when preparing the pip cache layer.
+#[derive(Debug)]
+pub(crate) enum PipCacheLayerError {
+    InvalidMaxSize(String),
+    PruneCache(io::Error),
+}
+
+impl From<PipCacheLayerError> for libcnb::Error<BuildpackError> {
+    fn from(error: PipCacheLayerError) -> Self {
+        Self::BuildpackError(BuildpackError::PipCacheLayer(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn max_cache_size_configuration() {
+        assert_eq!(max_cache_size(&Env::new()).unwrap(), DEFAULT_MAX_SIZE_BYTES);
+
+        let mut env = Env::new();
+        env.insert(PIP_CACHE_MAX_SIZE_ENV_VAR, " 500 ");
+        assert_eq!(max_cache_size(&env).unwrap(), 500 * 1024 * 1024);
+
+        for value in ["", "1.5", "1GB", "-1"] {
+            env.insert(PIP_CACHE_MAX_SIZE_ENV_VAR, value);
+            assert!(matches!(
+                max_cache_size(&env),
+                Err(PipCacheLayerError::InvalidMaxSize(invalid)) if invalid == value
+            ));
+        }
+    }
+
+    #[test]
+    fn files_to_prune_oldest_first() {
+        let cache_file = |name: &str, size: u64, age_secs: u64| CacheFile {
+            path: PathBuf::from(name),
+            size,
+            modified: SystemTime::UNIX_EPOCH + Duration::from_secs(1000 - age_secs),
+        };
+        let files = || {
+            vec![
+                cache_file("newest", 30, 0),
+                cache_file("oldest", 20, 300),
+                cache_file("middle", 40, 100),
+            ]
+        };
+        let pruned_names = |max_size| {
+            files_to_prune(files(), max_size)
+                .iter()
+                .map(|file| file.path.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(pruned_names(90).is_empty());
+        assert_eq!(pruned_names(80), ["oldest"]);
+        assert_eq!(pruned_names(70), ["oldest"]);
+        assert_eq!(pruned_names(69), ["oldest", "middle"]);
+        assert_eq!(pruned_names(0), ["oldest", "middle", "newest"]);
+    }
+}
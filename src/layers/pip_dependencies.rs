@@ -1,13 +1,43 @@
-use crate::utils::{self, StreamedCommandError};
-use crate::{BuildpackError, PythonBuildpack};
+use crate::build_log::log_info;
+use crate::dependency_plan;
+use crate::disk_space::{self, DiskSpaceEstimate};
+use crate::hash_verification::{self, HashVerification};
+use crate::layers::cached_packages;
+use crate::layers::dependency_report::{self, DependencyReport};
+use crate::package_inventory::{self, InstalledPackage, ListInstalledPackagesError};
+use crate::package_manager::{self, PackageManager};
+use crate::packaging_tool_versions::PIP_VERSION;
+use crate::pip_extra_args::{self, PipExtraArgsError};
+use crate::project_toml::{NoBuildIsolation, PythonBuildpackConfig};
+use crate::pyproject_toml::{self, ReadPyprojectTomlError};
+use crate::python_version::PythonVersion;
+use crate::redact::redact_url_credentials;
+use crate::strict_mode::{StrictMode, WarningClass};
+use crate::utils::{
+    self, CapturedCommandError, RetriedCommandError, StableHasher, StreamedCommandError,
+};
+use crate::{telemetry, BuildpackError, PythonBuildpack};
+use indoc::formatdoc;
 use libcnb::build::BuildContext;
 use libcnb::data::layer_name;
-use libcnb::layer::UncachedLayerDefinition;
+use libcnb::layer::{
+    CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerRef, LayerState,
+    RestoredLayerAction,
+};
 use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
-use libcnb::Env;
-use libherokubuildpack::log::log_info;
-use std::path::PathBuf;
+use libcnb::{Env, Target};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::SystemTime;
+use std::{fs, io};
+
+// https://pytorch.org/get-started/locally/
+/// The env var that controls whether `pip check` problems are a warning, an error, or not checked.
+pub(crate) const DEPENDENCY_CHECK_ENV_VAR: &str = "PYTHON_DEPENDENCY_CHECK";
+
+const PYTORCH_CPU_INDEX_URL: &str = "https://download.pytorch.org/whl/cpu";
 
 /// Creates a layer containing the application's Python dependencies, installed using pip.
 //
@@ -19,7 +49,9 @@ use std::process::Command;
 // - PEP-405 style venvs are very lightweight and are also much more frequently
 //   used in the wild compared to `--user`, and therefore the better tested path.
 //
-// This layer is not cached, since:
+// This layer is cached, however, unlike for the other package managers, the cached venv is only
+// reused if the app's requirements are unchanged (in which case the install is skipped entirely),
+// and otherwise is recreated from scratch, since:
 // - pip is a package installer rather than a project/environment manager, and so does not
 //   deterministically manage installed Python packages. For example, if a package entry in
 //   a requirements file is later removed, pip will not uninstall the package. In addition,
@@ -31,29 +63,240 @@ use std::process::Command;
 pub(crate) fn install_dependencies(
     context: &BuildContext<PythonBuildpack>,
     env: &mut Env,
-) -> Result<PathBuf, libcnb::Error<BuildpackError>> {
-    let layer = context.uncached_layer(
+    python_version: &PythonVersion,
+    python_config: &PythonBuildpackConfig,
+    pip_cache_dir: &Path,
+    strict_mode: &StrictMode,
+) -> Result<(PathBuf, Vec<InstalledPackage>), libcnb::Error<BuildpackError>> {
+    // Apps without a requirements file that were detected using their `pyproject.toml`.
+    let install_project = package_manager::is_pip_project_install(
+        &context.app_dir,
+        python_config.requirements_file(),
+    );
+    let requirements_files = requirements_files(
+        &context.app_dir,
+        &context.target,
+        (!install_project).then(|| python_config.requirements_file()),
+    )?;
+    let (requirements, constraints) =
+        read_requirements(&context.app_dir, &requirements_files, install_project)?;
+    let extra_args = pip_extra_args::read_pip_extra_args(env)
+        .map_err(PipDependenciesLayerError::PipExtraArgs)?;
+    let dependency_check_mode = dependency_check_mode(env)?;
+    let requirements_args = requirements_args(&requirements_files, install_project, &extra_args);
+
+    // The app's project (and any other local path requirements) is reinstalled on every build,
+    // since its source may have changed.
+    let new_metadata = PipDependenciesLayerMetadata::new(
+        context,
+        python_version,
+        (!install_project && !has_local_path_requirements(&requirements))
+            .then(|| requirements_hash(&requirements, &constraints, python_config, env)),
+    );
+    let layer = venv_layer(context, &new_metadata)?;
+    let layer_path = layer.path();
+    let cached_metadata = create_venv(&layer.state, &layer_path, env)?;
+    write_venv_layer_env(&layer, env)?;
+
+    configure_install_env(env, python_config, &requirements);
+
+    if dependency_plan::is_plan_only(env) {
+        show_dependency_plan(&context.app_dir, env, python_version, &requirements_args)?;
+        return Err(libcnb::Error::BuildpackError(
+            BuildpackError::DependencyPlanOnly,
+        ));
+    }
+
+    let report = if cached_metadata.is_none() {
+        check_install(
+            &context.app_dir,
+            env,
+            &requirements_args,
+            &layer_path,
+            strict_mode,
+        )?
+    } else {
+        None
+    };
+    // This is also needed when using the cached venv, so that the cached packages layer (which
+    // the venv's `.pth` file refers to) is kept.
+    let cached_packages_dir = cached_packages::install_cached_packages(
+        context,
+        env,
+        python_version,
+        &python_config.cached_packages,
+        &requirements,
+        &layer_path,
+    )?;
+
+    let metadata = if let Some(cached_metadata) = cached_metadata {
+        log_info("Dependencies unchanged, skipping pip install");
+        cached_metadata
+    } else {
+        install_without_build_isolation(
+            &context.app_dir,
+            env,
+            python_version,
+            python_config,
+            &requirements,
+        )?;
+        install_requirements(
+            &context.app_dir,
+            env,
+            python_version,
+            python_config,
+            &requirements_args,
+            install_project,
+            pip_cache_dir,
+        )
+        .map_err(|error| diagnose_hash_checking_failure(error, &requirements, env))?;
+        check_dependencies(&context.app_dir, env, dependency_check_mode, strict_mode)?;
+
+        let packages = package_inventory::list_installed_packages(
+            &context.app_dir,
+            env,
+            &Vec::from_iter(cached_packages_dir),
+        )
+        .map_err(PipDependenciesLayerError::ListInstalledPackages)?;
+        PipDependenciesLayerMetadata {
+            hash_verification: verify_package_hashes(
+                &requirements_files,
+                &requirements,
+                &packages,
+                strict_mode,
+            ),
+            package_sources: package_sources(report.as_ref()),
+            packages,
+            ..new_metadata
+        }
+    };
+    dependency_report::write_dependency_report(
+        context,
+        env,
+        &generate_dependency_report(&requirements, &metadata.package_sources, &metadata.packages),
+    )?;
+    layer.write_sboms(&package_inventory::packages_sboms(&metadata.packages))?;
+    layer.write_metadata(metadata.clone())?;
+
+    Ok((layer_path, metadata.packages))
+}
+
+// Adjusts the build env used for the install, based on the app's configuration.
+fn configure_install_env(env: &mut Env, python_config: &PythonBuildpackConfig, requirements: &str) {
+    if !prefers_binary(env) {
+        log_info("Not preferring binary packages, since PIP_PREFER_BINARY has been overridden");
+    }
+
+    if python_config.torch_cpu {
+        configure_torch_cpu_index(requirements, env);
+    }
+}
+
+// Checks the packages that the dry run install reports would be installed (if the dry run
+// succeeded), returning the report for use by later steps.
+fn check_install(
+    app_dir: &Path,
+    env: &Env,
+    requirements_args: &[&str],
+    layer_path: &Path,
+    strict_mode: &StrictMode,
+) -> Result<Option<InstallationReport>, PipDependenciesLayerError> {
+    let report = dry_run_report(app_dir, env, requirements_args);
+    if let Some(report) = &report {
+        check_wheel_availability(report, strict_mode);
+        check_disk_space(report, layer_path)?;
+    }
+    Ok(report)
+}
+
+// Whether pip will prefer wheels over newer source distributions, which the buildpack enables by
+// default. pip parses boolean env vars case-insensitively, and accepts several truthy values.
+fn prefers_binary(env: &Env) -> bool {
+    env.get_string_lossy("PIP_PREFER_BINARY")
+        .is_some_and(|value| is_truthy(&value))
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(value.to_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+fn venv_layer(
+    context: &BuildContext<PythonBuildpack>,
+    new_metadata: &PipDependenciesLayerMetadata,
+) -> libcnb::Result<LayerRef<PythonBuildpack, (), PipDependenciesLayerMetadata>, BuildpackError> {
+    let layer = context.cached_layer(
         // The name of this layer must be alphabetically after that of the `python` layer so that
         // this layer's `bin/` directory (and thus `python` symlink) is listed first in `PATH`:
         // https://github.com/buildpacks/spec/blob/main/buildpack.md#layer-paths
         layer_name!("venv"),
-        UncachedLayerDefinition {
+        CachedLayerDefinition {
             build: true,
             launch: true,
+            invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
+            restored_layer_action: &|cached_metadata: &PipDependenciesLayerMetadata, _| {
+                if cached_metadata.is_cache_compatible(new_metadata) {
+                    (RestoredLayerAction::KeepLayer, cached_metadata.clone())
+                } else {
+                    (RestoredLayerAction::DeleteLayer, cached_metadata.clone())
+                }
+            },
         },
     )?;
-    let layer_path = layer.path();
+    telemetry::record_layer_cache("venv", &layer.state);
+    Ok(layer)
+}
 
-    log_info("Creating virtual environment");
-    utils::run_command_and_stream_output(
-        Command::new("python")
-            .args(["-m", "venv", "--without-pip", &layer_path.to_string_lossy()])
-            .env_clear()
-            .envs(&*env),
-    )
-    .map_err(PipDependenciesLayerError::CreateVenvCommand)?;
+/// Creates the virtual environment, unless the cached venv is being reused, in which case the
+/// cached layer metadata is returned.
+fn create_venv(
+    layer_state: &LayerState<(), PipDependenciesLayerMetadata>,
+    layer_path: &Path,
+    env: &Env,
+) -> Result<Option<PipDependenciesLayerMetadata>, PipDependenciesLayerError> {
+    match layer_state {
+        LayerState::Restored {
+            cause: cached_metadata,
+        } => {
+            log_info("Using cached virtual environment");
+            Ok(Some(cached_metadata.clone()))
+        }
+        LayerState::Empty { cause } => {
+            match cause {
+                EmptyLayerCause::InvalidMetadataAction { .. }
+                | EmptyLayerCause::RestoredLayerAction { .. } => {
+                    log_info("Discarding cached virtual environment");
+                }
+                EmptyLayerCause::NewlyCreated => {}
+            }
 
-    let mut layer_env = LayerEnv::new()
+            log_info("Creating virtual environment");
+            utils::run_command_and_stream_output(
+                Command::new("python")
+                    .args(["-m", "venv", "--without-pip", &layer_path.to_string_lossy()])
+                    .env_clear()
+                    .envs(env),
+            )
+            .map_err(PipDependenciesLayerError::CreateVenvCommand)?;
+            Ok(None)
+        }
+    }
+}
+
+// Writes the venv layer's env vars, and applies them to the build env.
+fn write_venv_layer_env(
+    layer: &LayerRef<PythonBuildpack, (), PipDependenciesLayerMetadata>,
+    env: &mut Env,
+) -> libcnb::Result<(), BuildpackError> {
+    layer.write_env(venv_layer_env(&layer.path()))?;
+    // Required to pick up the automatic PATH env var. See: https://github.com/heroku/libcnb.rs/issues/842
+    let layer_env = layer.read_env()?;
+    env.clone_from(&layer_env.apply(Scope::Build, env));
+    Ok(())
+}
+
+// The env vars for the venv layer, which configure pip to install into the venv.
+fn venv_layer_env(layer_path: &Path) -> LayerEnv {
+    LayerEnv::new()
         // pip is installed in a separate build-only layer, we have to explicitly tell it to
         // perform operations against this venv instead of the global Python install.
         // https://pip.pypa.io/en/stable/cli/pip/#cmdoption-python
@@ -61,7 +304,18 @@ pub(crate) fn install_dependencies(
             Scope::Build,
             ModificationBehavior::Override,
             "PIP_PYTHON",
-            &layer_path,
+            layer_path,
+        )
+        // Prefer installing wheels over source distributions, even if the wheel is for an older
+        // version of the package, since building from source is slow and frequently fails due to
+        // missing system dependencies. This is set as a default (rather than passing the CLI arg)
+        // so that users can opt out by setting `PIP_PREFER_BINARY=0` themselves.
+        // https://pip.pypa.io/en/stable/cli/pip_install/#cmdoption-prefer-binary
+        .chainable_insert(
+            Scope::Build,
+            ModificationBehavior::Default,
+            "PIP_PREFER_BINARY",
+            "1",
         )
         // For parity with the venv's `bin/activate` script:
         // https://docs.python.org/3/library/venv.html#how-venvs-work
@@ -69,38 +323,948 @@ pub(crate) fn install_dependencies(
             Scope::All,
             ModificationBehavior::Override,
             "VIRTUAL_ENV",
-            &layer_path,
-        );
-    layer.write_env(&layer_env)?;
-    // Required to pick up the automatic PATH env var. See: https://github.com/heroku/libcnb.rs/issues/842
-    layer_env = layer.read_env()?;
-    env.clone_from(&layer_env.apply(Scope::Build, env));
+            layer_path,
+        )
+}
+
+// Packages listed in the requirements files are direct dependencies.
+fn generate_dependency_report(
+    requirements: &str,
+    sources: &HashMap<String, String>,
+    packages: &[InstalledPackage],
+) -> DependencyReport {
+    let direct_dependencies = requirement_lines(requirements)
+        .into_iter()
+        .map(|requirement| utils::normalize_package_name(requirement_name(requirement)))
+        .collect::<Vec<_>>();
+    DependencyReport::new(
+        PackageManager::Pip.name(),
+        packages,
+        &direct_dependencies,
+        sources,
+    )
+}
+
+// The source of each package is the URL of the distribution chosen by the dry run install, if it
+// succeeded. These are saved in the layer metadata, for use when the cached venv is reused.
+fn package_sources(report: Option<&InstallationReport>) -> HashMap<String, String> {
+    report
+        .iter()
+        .flat_map(|report| &report.install)
+        .map(|item| {
+            (
+                utils::normalize_package_name(&item.metadata.name),
+                redact_url_credentials(&item.download_info.url),
+            )
+        })
+        .collect()
+}
+
+// A hash of everything that affects which packages pip installs into the venv: the combined
+// contents of the requirements and constraints files, the install-related settings in
+// `project.toml`, and the `PIP_*` env vars (such as `PIP_INDEX_URL`). The hash is only used for
+// cache invalidation, so env vars containing credentials aren't exposed.
+fn requirements_hash(
+    requirements: &str,
+    constraints: &str,
+    python_config: &PythonBuildpackConfig,
+    env: &Env,
+) -> String {
+    let mut hasher = StableHasher::new();
+    hasher.update(requirements);
+    hasher.update(constraints);
+    hasher.update_all(&python_config.build_requirements);
+    hasher.update_all(&python_config.cached_packages);
+    match &python_config.no_build_isolation {
+        NoBuildIsolation::All(all) => hasher.update_all(&["all", &all.to_string()]),
+        NoBuildIsolation::Packages(packages) => {
+            hasher.update("packages");
+            hasher.update_all(packages);
+        }
+    }
+    hasher.update(python_config.torch_cpu.to_string());
+    for (name, value) in env
+        .iter()
+        .filter(|(name, _)| name.to_string_lossy().starts_with("PIP_"))
+        .collect::<BTreeMap<_, _>>()
+    {
+        hasher.update(name.as_encoded_bytes());
+        hasher.update(value.as_encoded_bytes());
+    }
+    hasher.hex_digest()
+}
+
+// Verifies the installed packages against the hashes in the requirements files. Only requirements
+// files that use hash-checking mode can be verified, and in that mode pip requires every
+// requirement (including transitive dependencies) to be pinned and hashed.
+fn verify_package_hashes(
+    requirements_files: &[String],
+    requirements: &str,
+    packages: &[InstalledPackage],
+    strict_mode: &StrictMode,
+) -> Option<HashVerification> {
+    let locked_packages = hash_verification::requirements_packages(requirements);
+    if locked_packages.is_empty() {
+        return None;
+    }
+    let verification = hash_verification::verify_installed_packages(
+        &requirements_files.join(", "),
+        &locked_packages,
+        packages,
+        &[],
+    );
+    hash_verification::log_hash_verification(&verification, strict_mode);
+    Some(verification)
+}
 
-    log_info("Running 'pip install -r requirements.txt'");
-    utils::run_command_and_stream_output(
+/// Installs the app's requirements files (or project), by first downloading all of the
+/// distributions, and then installing from the downloaded files.
+fn install_requirements(
+    app_dir: &Path,
+    env: &Env,
+    python_version: &PythonVersion,
+    python_config: &PythonBuildpackConfig,
+    requirements_args: &[&str],
+    install_project: bool,
+    pip_cache_dir: &Path,
+) -> Result<(), PipDependenciesLayerError> {
+    let downloads_dir = pip_cache_dir.join("downloads");
+    let only_wheels_downloaded = download_dependencies(
+        app_dir,
+        env,
+        python_version,
+        requirements_args,
+        &downloads_dir,
+    )?;
+
+    utils::log_running_command(&format!("pip install {}", requirements_args.join(" ")));
+    utils::run_command_and_stream_output_with_retries(
+        Command::new("pip")
+            .args(["install", "--no-input", "--progress-bar", "off"])
+            .args(["--find-links", &downloads_dir.to_string_lossy()])
+            // Packages that have to be built from source need their build requirements to be
+            // installed from the package index, so the install can only be offline if there
+            // aren't any source distributions. The same applies to building the app's project.
+            .args((only_wheels_downloaded && !install_project).then_some("--no-index"))
+            .args(requirements_args)
+            .args(
+                (python_config.no_build_isolation == NoBuildIsolation::All(true))
+                    .then_some("--no-build-isolation"),
+            )
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+        "pip install",
+    )
+    .map_err(|error| PipDependenciesLayerError::PipInstallCommand(error, python_version.clone()))
+}
+
+// pip's error messages for the failures that are specific to hash-checking mode, each of which
+// is followed by the affected requirements on separate indented lines.
+const MISSING_HASHES_ERROR: &str = "Hashes are required in --require-hashes mode";
+const HASH_MISMATCH_ERROR: &str =
+    "THESE PACKAGES DO NOT MATCH THE HASHES FROM THE REQUIREMENTS FILE";
+const UNPINNED_REQUIREMENTS_ERROR: &str =
+    "In --require-hashes mode, all requirements must have their versions pinned with ==";
+
+/// Replaces a failed pip download/install with a [`HashCheckingFailure`] error if the failure
+/// was due to the app's requirements not satisfying pip's hash-checking mode, since pip's own
+/// error messages don't explain how to fix the requirements files.
+fn diagnose_hash_checking_failure(
+    error: PipDependenciesLayerError,
+    requirements: &str,
+    env: &Env,
+) -> PipDependenciesLayerError {
+    let (PipDependenciesLayerError::PipDownloadCommand(
+        RetriedCommandError::NonZeroExitStatus(_, output),
+        _,
+    )
+    | PipDependenciesLayerError::PipInstallCommand(
+        RetriedCommandError::NonZeroExitStatus(_, output),
+        _,
+    )) = &error
+    else {
+        return error;
+    };
+    if !uses_hash_checking_mode(requirements, env) {
+        return error;
+    }
+    match hash_checking_failure(output) {
+        Some(failure) => PipDependenciesLayerError::HashChecking(failure),
+        None => error,
+    }
+}
+
+// pip enables hash-checking mode when any requirement has a `--hash` option, or when the
+// `--require-hashes` option is used (either in a requirements file or via the env var).
+fn uses_hash_checking_mode(requirements: &str, env: &Env) -> bool {
+    requirements
+        .lines()
+        .map(|line| line.split(" #").next().unwrap_or_default())
+        .any(|line| line.contains("--hash") || line.contains("--require-hashes"))
+        || env
+            .get_string_lossy("PIP_REQUIRE_HASHES")
+            .is_some_and(|value| is_truthy(&value))
+}
+
+// Finds the first hash-checking mode error in pip's output, along with the requirements it
+// lists. Only the first word of each requirement line is kept (such as `foo==1.0`), since the
+// rest is either the URL of the distribution or the hashes, which pip outputs on deeper
+// indented lines.
+pub(crate) fn hash_checking_failure(output: &str) -> Option<HashCheckingFailure> {
+    let mut lines = output.lines();
+    let error_line = lines.find(|line| {
+        [
+            MISSING_HASHES_ERROR,
+            HASH_MISMATCH_ERROR,
+            UNPINNED_REQUIREMENTS_ERROR,
+        ]
+        .iter()
+        .any(|error| line.contains(error))
+    })?;
+    let requirements = lines
+        .take_while(|line| line.starts_with("    "))
+        .filter(|line| !line.starts_with("     "))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|requirement| requirement.trim_end_matches(':').to_string())
+        .collect();
+    Some(if error_line.contains(MISSING_HASHES_ERROR) {
+        HashCheckingFailure::MissingHashes(requirements)
+    } else if error_line.contains(HASH_MISMATCH_ERROR) {
+        HashCheckingFailure::HashMismatch(requirements)
+    } else {
+        HashCheckingFailure::UnpinnedRequirements(requirements)
+    })
+}
+
+/// Resolves the app's dependencies without installing them, so that pip shows the packages
+/// that would be installed. The installed packages are ignored, so that the full set is shown.
+fn show_dependency_plan(
+    app_dir: &Path,
+    env: &Env,
+    python_version: &PythonVersion,
+    requirements_args: &[&str],
+) -> Result<(), PipDependenciesLayerError> {
+    utils::log_running_command(&format!(
+        "pip install --dry-run {}",
+        requirements_args.join(" ")
+    ));
+    utils::run_command_and_stream_output_with_retries(
         Command::new("pip")
             .args([
                 "install",
+                "--dry-run",
+                "--ignore-installed",
                 "--no-input",
                 "--progress-bar",
                 "off",
-                "--requirement",
-                "requirements.txt",
             ])
-            .current_dir(&context.app_dir)
+            .args(requirements_args)
+            .current_dir(app_dir)
             .env_clear()
-            .envs(&*env),
+            .envs(env),
+        "pip install --dry-run",
     )
-    .map_err(PipDependenciesLayerError::PipInstallCommand)?;
+    .map_err(|error| PipDependenciesLayerError::PipDryRunCommand(error, python_version.clone()))
+}
+
+/// Downloads the distributions for all of the app's dependencies into a directory in the pip
+/// cache layer, ahead of installing them. Returns whether all of the downloaded distributions
+/// are wheels (rather than source distributions that have to be built).
+//
+// Splitting the install into separate download and install steps means that network failures
+// occur (and are retried) before any packages have been installed, and that the install step
+// doesn't depend on the package index (unless source distributions have to be built), so a
+// failed install doesn't lose the completed downloads. The directory is kept between builds, so
+// that pip can reuse the files downloaded by previous builds. Afterwards, the files that were
+// reused are touched, so that pruning the pip cache (which deletes the least recently modified
+// files first) keeps them. Any files that the current requirements don't need are deleted, since
+// the install step uses the directory as a source of packages.
+fn download_dependencies(
+    app_dir: &Path,
+    env: &Env,
+    python_version: &PythonVersion,
+    requirements_args: &[&str],
+    downloads_dir: &Path,
+) -> Result<bool, PipDependenciesLayerError> {
+    utils::log_running_command(&format!("pip download {}", requirements_args.join(" ")));
+    let output = utils::run_command_and_stream_captured_output_with_retries(
+        Command::new("pip")
+            .args(["download", "--no-input", "--progress-bar", "off"])
+            .args(["--dest", &downloads_dir.to_string_lossy()])
+            .args(requirements_args)
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+        "pip download",
+    )
+    .map_err(|error| {
+        PipDependenciesLayerError::PipDownloadCommand(error, python_version.clone())
+    })?;
+
+    let file_names = update_downloads_dir(downloads_dir, &downloaded_file_names(&output))
+        .map_err(PipDependenciesLayerError::PrepareDownloadsDir)?;
+    Ok(only_wheels(&file_names))
+}
+
+// Returns the names of the files that `pip download` either saved, or reused from a previous
+// download, based on its output. pip logs the full path of each file, for example:
+// `Saved /layers/heroku_python/pip-cache/downloads/typing_extensions-4.12.2-py3-none-any.whl`
+fn downloaded_file_names(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix("Saved ")
+                .or_else(|| line.strip_prefix("File was already downloaded "))
+        })
+        .filter_map(|path| Path::new(path.trim()).file_name())
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .collect()
+}
+
+// Touches the files in the downloads directory that were used by the current download, and
+// deletes the rest, returning the names of the files that were kept.
+fn update_downloads_dir(
+    downloads_dir: &Path,
+    downloaded_file_names: &HashSet<String>,
+) -> io::Result<Vec<String>> {
+    // pip doesn't create the directory if there was nothing to download.
+    let Some(entries) = utils::read_optional_dir(downloads_dir)? else {
+        return Ok(Vec::new());
+    };
+    let now = SystemTime::now();
+    let mut file_names = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if downloaded_file_names.contains(&file_name) {
+            fs::File::open(entry.path())?.set_modified(now)?;
+            file_names.push(file_name);
+        } else if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(entry.path())?;
+        } else {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(file_names)
+}
+
+fn only_wheels(file_names: &[String]) -> bool {
+    file_names.iter().all(|file_name| {
+        Path::new(file_name)
+            .extension()
+            .is_some_and(|ext| ext == "whl")
+    })
+}
+
+/// Returns the requirements files to install from, which are the app's `requirements.txt` (or the
+/// file configured using the `requirements-file` setting, or none if the app's project is being
+/// installed instead), followed by any overlays in the app root for the target's architecture or
+/// distro that exist (for example, `requirements-arm64.txt` or `requirements-ubuntu-24.04.txt`).
+//
+// This allows apps that need different packages or versions for each architecture (such as when a
+// package only has wheels for one of them) to build multi-arch images from the same source. Pip
+// merges the files, however, a package can't be listed in more than one of them.
+fn requirements_files(
+    app_dir: &Path,
+    target: &Target,
+    requirements_file: Option<&str>,
+) -> Result<Vec<String>, PipDependenciesLayerError> {
+    let mut files = Vec::from_iter(requirements_file.map(str::to_string));
+    for overlay in [
+        format!("requirements-{}.txt", target.arch),
+        format!(
+            "requirements-{}-{}.txt",
+            target.distro_name, target.distro_version
+        ),
+    ] {
+        if app_dir.join(&overlay).try_exists().map_err(|io_error| {
+            PipDependenciesLayerError::ReadRequirementsFile(overlay.clone(), io_error)
+        })? {
+            log_info(format!(
+                "Including target-specific requirements from {overlay}"
+            ));
+            files.push(overlay);
+        }
+    }
+    Ok(files)
+}
+
+/// Reads the requirements files, along with any requirements files that they include using `-r`
+/// (and the dependencies of the app's project, if it's being installed), returning their combined
+/// contents. This allows the requirements to be checked before the install (such as for `torch`
+/// or the cached packages), when they are split across files.
+///
+/// The combined contents of any constraints files included using `-c` are returned separately,
+/// since they only affect the versions installed, and not which packages are installed.
+//
+// pip resolves included files relative to the file that includes them. Each file is only read
+// once, so that files included multiple times (or recursively) don't cause an infinite loop.
+// Files included by a constraints file are also treated as constraints.
+fn read_requirements(
+    app_dir: &Path,
+    requirements_files: &[String],
+    install_project: bool,
+) -> Result<(String, String), PipDependenciesLayerError> {
+    let mut pending_files: Vec<(PathBuf, bool)> = requirements_files
+        .iter()
+        .rev()
+        .map(|file| (PathBuf::from(file), false))
+        .collect();
+    let mut seen_files = HashSet::new();
+    let mut contents = Vec::new();
+    let mut constraints = Vec::new();
+    while let Some((file, is_constraints)) = pending_files.pop() {
+        let read_error = |io_error| {
+            PipDependenciesLayerError::ReadRequirementsFile(
+                file.to_string_lossy().to_string(),
+                io_error,
+            )
+        };
+        if !seen_files.insert(fs::canonicalize(app_dir.join(&file)).map_err(read_error)?) {
+            continue;
+        }
+        let file_contents = fs::read_to_string(app_dir.join(&file)).map_err(read_error)?;
+        let file_dir = file.parent().unwrap_or(Path::new(""));
+        pending_files.extend(included_files(&file_contents).into_iter().rev().map(
+            |(included_file, is_included_constraints)| {
+                (
+                    file_dir.join(included_file),
+                    is_constraints || is_included_constraints,
+                )
+            },
+        ));
+        if is_constraints {
+            constraints.push(file_contents);
+        } else {
+            contents.push(file_contents);
+        }
+    }
+    if install_project {
+        contents.push(project_requirements(app_dir)?);
+    }
+    Ok((contents.join("\n"), constraints.join("\n")))
+}
+
+// Returns the local files included by a requirements file using `-r` or `--requirement`, or
+// using `-c` or `--constraint` (for which `true` is returned alongside the file). Files included
+// by URL are skipped, since they can't be read ahead of the install.
+fn included_files(requirements_file: &str) -> Vec<(&str, bool)> {
+    requirements_file
+        .lines()
+        .filter_map(|line| {
+            let line = line.split(" #").next().unwrap_or_default().trim();
+            line.strip_prefix("--requirement")
+                .or_else(|| line.strip_prefix("-r"))
+                .map(|file| (file, false))
+                .or_else(|| {
+                    line.strip_prefix("--constraint")
+                        .or_else(|| line.strip_prefix("-c"))
+                        .map(|file| (file, true))
+                })
+                .map(|(file, is_constraints)| (file.trim_start_matches('=').trim(), is_constraints))
+        })
+        .filter(|(file, _)| !file.is_empty() && !file.contains("://"))
+        .collect()
+}
+
+// Whether the requirements include any local paths (such as `.`, `./libs/foo` or `-e .`), whose
+// source may have changed even when the requirements haven't.
+fn has_local_path_requirements(requirements: &str) -> bool {
+    requirements.lines().any(|line| {
+        let line = line.split(" #").next().unwrap_or_default().trim();
+        let requirement = line
+            .strip_prefix("--editable")
+            .or_else(|| line.strip_prefix("-e"))
+            .map_or(line, |requirement| {
+                requirement.trim_start_matches('=').trim()
+            });
+        requirement.starts_with(['.', '/', '~'])
+            || requirement.contains("file:")
+            || (!requirement.starts_with('-')
+                && !requirement.contains("://")
+                && requirement.contains(['/', '\\']))
+    })
+}
+
+// Returns the dependencies declared in the `[project]` table of the app's `pyproject.toml`,
+// in the same format as a requirements file, so that they can be checked like requirements.
+fn project_requirements(app_dir: &Path) -> Result<String, PipDependenciesLayerError> {
+    let dependencies = pyproject_toml::read_pyproject_toml(app_dir)
+        .map_err(PipDependenciesLayerError::ReadPyprojectToml)?
+        .map(|pyproject_toml| pyproject_toml.project.dependencies)
+        .unwrap_or_default();
+    Ok(dependencies.join("\n"))
+}
+
+// The arguments passed to the pip commands that install the app's requirements, which include any
+// additional options from `PIP_EXTRA_ARGS`.
+fn requirements_args<'a>(
+    requirements_files: &'a [String],
+    install_project: bool,
+    extra_args: &'a [String],
+) -> Vec<&'a str> {
+    install_project
+        .then_some(".")
+        .into_iter()
+        .chain(
+            requirements_files
+                .iter()
+                .flat_map(|file| ["-r", file.as_str()]),
+        )
+        .chain(extra_args.iter().map(String::as_str))
+        .collect()
+}
 
-    Ok(layer_path)
+/// Configures pip to install the CPU-only variant of `torch`, if it's listed in the app's
+/// requirements files, by adding the `PyTorch` CPU package index as an extra index.
+//
+// On Linux, the `torch` wheels on PyPI include the CUDA libraries, which add several gigabytes
+// to the app image, even though most apps are deployed without a GPU. The CPU index's wheels have
+// a `+cpu` local version label, which pip prefers over the PyPI release of the same version, and
+// which still satisfies exact version pins such as `torch==2.5.1`:
+// https://pytorch.org/get-started/locally/
+fn configure_torch_cpu_index(requirements: &str, env: &mut Env) {
+    if !requires_torch(requirements) {
+        return;
+    }
+
+    log_info("Using the CPU-only variant of PyTorch");
+    let extra_index_urls = match env.get_string_lossy("PIP_EXTRA_INDEX_URL") {
+        Some(urls) if !urls.trim().is_empty() => format!("{urls} {PYTORCH_CPU_INDEX_URL}"),
+        _ => PYTORCH_CPU_INDEX_URL.to_string(),
+    };
+    env.insert("PIP_EXTRA_INDEX_URL", extra_index_urls);
+}
+
+fn requires_torch(requirements_file: &str) -> bool {
+    requirement_lines(requirements_file)
+        .iter()
+        .any(|requirement| utils::normalize_package_name(requirement_name(requirement)) == "torch")
+}
+
+/// Determines which packages will be installed, using a dry run install, whose JSON report lists
+/// the distribution chosen for each package. This is used to check for potential problems with
+/// the install (such as packages that need to be built from source) before it's started.
+//
+// https://pip.pypa.io/en/stable/reference/installation-report/
+//
+// Any errors are ignored, since these checks are only a best-effort attempt to catch problems
+// early, and any problems with the requirements file will be reported by the real install.
+fn dry_run_report(
+    app_dir: &Path,
+    env: &Env,
+    requirements_args: &[&str],
+) -> Option<InstallationReport> {
+    let output = utils::run_command_and_capture_output(
+        Command::new("pip")
+            .args([
+                "install",
+                "--dry-run",
+                "--ignore-installed",
+                "--no-input",
+                "--quiet",
+                "--report",
+                "-",
+            ])
+            .args(requirements_args)
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+    )
+    .ok()?;
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+/// Warns about any packages that will have to be built from source, since no compatible wheels
+/// are available for them (for example, if the pinned version predates the Python version).
+///
+/// Building packages from source is slow and often fails due to missing system dependencies, so
+/// this lets users see which packages are affected before the (potentially lengthy) build starts.
+fn check_wheel_availability(report: &InstallationReport, strict_mode: &StrictMode) {
+    let packages = source_distributions(report);
+    if packages.is_empty() {
+        return;
+    }
+    let package_list = packages
+        .iter()
+        .map(|(name, version)| format!("- {name} {version}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    strict_mode.log_warning(
+        WarningClass::BuildFromSource,
+        "Packages will be built from source",
+        formatdoc! {"
+            The following packages don't provide pre-built wheels that are compatible
+            with this Python version and CPU architecture, so must be built from source:
+
+            {package_list}
+
+            Building packages from source is slow, and often fails due to missing
+            compilers or system libraries. If the install fails or takes too long,
+            update these packages to versions that provide compatible wheels.
+        "},
+    );
+}
+
+/// Checks that there is likely to be enough disk space to install the packages, so that builds
+/// with very large dependencies (such as `torch` with CUDA support) fail early with a clear error, rather
+/// than part way through the install with a "No space left on device" error.
+fn check_disk_space(
+    report: &InstallationReport,
+    layer_path: &Path,
+) -> Result<(), PipDependenciesLayerError> {
+    let urls: Vec<&str> = report
+        .install
+        .iter()
+        .filter(|item| item.download_info.archive_info.is_some())
+        .map(|item| item.download_info.url.as_str())
+        .collect();
+    match disk_space::estimate_disk_space(&urls, layer_path) {
+        Some(estimate) if !estimate.is_sufficient() => {
+            Err(PipDependenciesLayerError::InsufficientDiskSpace(estimate))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns the names and versions of the packages in a pip installation report that will be
+/// installed from a source distribution. VCS and local directory requirements are excluded,
+/// since those are always built from source.
+fn source_distributions(report: &InstallationReport) -> Vec<(&str, &str)> {
+    report
+        .install
+        .iter()
+        .filter(|item| {
+            item.download_info.archive_info.is_some()
+                && !Path::new(&item.download_info.url)
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("whl"))
+        })
+        .map(|item| (item.metadata.name.as_str(), item.metadata.version.as_str()))
+        .collect()
+}
+
+// The subset of pip's installation report format that's used by `source_distributions`.
+#[derive(Deserialize)]
+struct InstallationReport {
+    install: Vec<InstallationReportItem>,
+}
+
+#[derive(Deserialize)]
+struct InstallationReportItem {
+    download_info: DownloadInfo,
+    metadata: InstallationReportMetadata,
+}
+
+#[derive(Deserialize)]
+struct DownloadInfo {
+    url: String,
+    archive_info: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct InstallationReportMetadata {
+    name: String,
+    version: String,
+}
+
+/// Installs the build requirements and any packages that must be installed without build
+/// isolation, as configured in `project.toml`.
+///
+/// By default pip builds source distributions in an isolated environment, into which it installs
+/// the package's build dependencies. For some heavy packages (such as those that build against
+/// `torch` or CUDA), this means repeatedly downloading and installing the same large toolchain.
+/// Disabling build isolation instead builds these packages using the packages already installed
+/// in the venv, so their build dependencies must be listed in `build-requirements`.
+///
+/// pip doesn't support disabling build isolation for individual packages, so the requirements
+/// for the listed packages are first installed on their own (using their entries from the app's
+/// `requirements.txt`), after which the main install will find them already installed.
+fn install_without_build_isolation(
+    app_dir: &Path,
+    env: &Env,
+    python_version: &PythonVersion,
+    python_config: &PythonBuildpackConfig,
+    requirements: &str,
+) -> Result<(), PipDependenciesLayerError> {
+    if !python_config.build_requirements.is_empty() {
+        log_info(format!(
+            "Installing build requirements: {}",
+            redact_url_credentials(&python_config.build_requirements.join(", "))
+        ));
+        utils::run_command_and_stream_output_with_retries(
+            Command::new("pip")
+                .args(["install", "--no-input", "--progress-bar", "off"])
+                .args(&python_config.build_requirements)
+                .current_dir(app_dir)
+                .env_clear()
+                .envs(env),
+            "pip install",
+        )
+        .map_err(|error| {
+            PipDependenciesLayerError::BuildRequirementsInstallCommand(
+                error,
+                python_version.clone(),
+            )
+        })?;
+    }
+
+    let NoBuildIsolation::Packages(packages) = &python_config.no_build_isolation else {
+        if python_config.no_build_isolation.is_enabled() {
+            log_info("Disabling build isolation for all packages");
+        }
+        return Ok(());
+    };
+    if packages.is_empty() {
+        return Ok(());
+    }
+
+    let requirements = requirements_for_packages(requirements, packages)
+        .map_err(PipDependenciesLayerError::NoBuildIsolationPackageNotFound)?;
+
+    log_info(format!(
+        "Installing without build isolation: {}",
+        redact_url_credentials(&requirements.join(", "))
+    ));
+    utils::run_command_and_stream_output_with_retries(
+        Command::new("pip")
+            .args([
+                "install",
+                "--no-input",
+                "--progress-bar",
+                "off",
+                "--no-build-isolation",
+            ])
+            .args(&requirements)
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+        "pip install",
+    )
+    .map_err(|error| {
+        PipDependenciesLayerError::NoBuildIsolationInstallCommand(error, python_version.clone())
+    })
+}
+
+/// Returns the `requirements.txt` entries for the specified packages, in the order the packages
+/// were listed, or else the name of the first package that doesn't have an entry.
+fn requirements_for_packages(
+    requirements_file: &str,
+    packages: &[String],
+) -> Result<Vec<String>, String> {
+    let requirements = requirement_lines(requirements_file);
+    packages
+        .iter()
+        .map(|package| {
+            requirements
+                .iter()
+                .find(|requirement| {
+                    utils::normalize_package_name(requirement_name(requirement))
+                        == utils::normalize_package_name(package)
+                })
+                .map(ToString::to_string)
+                .ok_or_else(|| package.clone())
+        })
+        .collect()
+}
+
+// Returns the requirement lines from a requirements file, excluding comments and pip options.
+fn requirement_lines(requirements_file: &str) -> Vec<&str> {
+    requirements_file
+        .lines()
+        .map(|line| line.split(" #").next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', '-']))
+        .collect()
+}
+
+fn requirement_name(requirement: &str) -> &str {
+    requirement
+        .split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .next()
+        .unwrap_or_default()
+}
+
+/// Checks that the installed packages have compatible dependencies using `pip check`.
+///
+/// pip's resolver doesn't always prevent conflicts between installed packages (for example, when
+/// using `--no-deps` or when a requirements file pins conflicting transitive dependencies), and
+/// such problems otherwise only show up as `ImportError`s or other failures at app runtime.
+///
+/// By default any problems found are shown as a warning, however, this can be changed to a build
+/// error by setting `PYTHON_DEPENDENCY_CHECK=error`, or the check skipped using `=off`.
+fn check_dependencies(
+    app_dir: &Path,
+    env: &Env,
+    mode: DependencyCheckMode,
+    strict_mode: &StrictMode,
+) -> Result<(), PipDependenciesLayerError> {
+    if mode == DependencyCheckMode::Off {
+        return Ok(());
+    }
+
+    // https://pip.pypa.io/en/stable/cli/pip_check/
+    match utils::run_command_and_capture_output(
+        Command::new("pip")
+            .args(["check", "--no-input"])
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+    ) {
+        Ok(_) => Ok(()),
+        // pip exits non-zero and lists the problems on stdout if any were found.
+        Err(CapturedCommandError::NonZeroExitStatus(output)) => {
+            let problems = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if mode == DependencyCheckMode::Error {
+                Err(PipDependenciesLayerError::DependencyConflicts(problems))
+            } else {
+                strict_mode.log_warning(
+                    WarningClass::DependencyConflicts,
+                    "Incompatible dependencies found",
+                    formatdoc! {"
+                        The 'pip check' command found the following problems with the
+                        dependencies installed from your app's 'requirements.txt' file:
+
+                        {problems}
+
+                        These are likely to cause errors when your app runs. Update the
+                        package versions in 'requirements.txt' so they are compatible.
+
+                        To make this check fail the build, set the environment variable
+                        'PYTHON_DEPENDENCY_CHECK' to 'error'.
+                    "},
+                );
+                Ok(())
+            }
+        }
+        Err(CapturedCommandError::Io(io_error)) => {
+            Err(PipDependenciesLayerError::PipCheckCommand(io_error))
+        }
+    }
+}
+
+/// How problems found by `pip check` are handled, as configured by `PYTHON_DEPENDENCY_CHECK`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DependencyCheckMode {
+    Error,
+    Off,
+    Warn,
+}
+
+fn dependency_check_mode(env: &Env) -> Result<DependencyCheckMode, PipDependenciesLayerError> {
+    let Some(value) = env.get_string_lossy(DEPENDENCY_CHECK_ENV_VAR) else {
+        return Ok(DependencyCheckMode::Warn);
+    };
+    match value.trim() {
+        "error" => Ok(DependencyCheckMode::Error),
+        "off" => Ok(DependencyCheckMode::Off),
+        "warn" => Ok(DependencyCheckMode::Warn),
+        _ => Err(PipDependenciesLayerError::InvalidDependencyCheckMode(value)),
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct PipDependenciesLayerMetadata {
+    arch: String,
+    distro_name: String,
+    distro_version: String,
+    python_version: String,
+    pip_version: String,
+    requirements_hash: Option<String>,
+    packages: Vec<InstalledPackage>,
+    hash_verification: Option<HashVerification>,
+    package_sources: HashMap<String, String>,
+}
+
+impl PipDependenciesLayerMetadata {
+    fn new(
+        context: &BuildContext<PythonBuildpack>,
+        python_version: &PythonVersion,
+        requirements_hash: Option<String>,
+    ) -> Self {
+        Self {
+            arch: context.target.arch.clone(),
+            distro_name: context.target.distro_name.clone(),
+            distro_version: context.target.distro_version.clone(),
+            python_version: python_version.to_string(),
+            pip_version: PIP_VERSION.to_string(),
+            requirements_hash,
+            packages: Vec::new(),
+            hash_verification: None,
+            package_sources: HashMap::new(),
+        }
+    }
+
+    /// Whether a cached layer with this metadata can be reused for the new metadata.
+    fn is_cache_compatible(&self, new_metadata: &Self) -> bool {
+        // By destructuring here we ensure that any fields added to the metadata in the future
+        // have to be explicitly considered for cache invalidation. The installed packages (and
+        // their sources and hash verification result) are only recorded for reuse along with
+        // the cached venv, and so aren't compared. Apps without a requirements hash (those whose
+        // project is installed) never reuse the cached venv.
+        let Self {
+            arch,
+            distro_name,
+            distro_version,
+            python_version,
+            pip_version,
+            requirements_hash,
+            packages: _,
+            hash_verification: _,
+            package_sources: _,
+        } = self;
+        requirements_hash.is_some()
+            && (
+                arch,
+                distro_name,
+                distro_version,
+                python_version,
+                pip_version,
+                requirements_hash,
+            ) == (
+                &new_metadata.arch,
+                &new_metadata.distro_name,
+                &new_metadata.distro_version,
+                &new_metadata.python_version,
+                &new_metadata.pip_version,
+                &new_metadata.requirements_hash,
+            )
+    }
 }
 
 /// Errors that can occur when installing the project's dependencies into a layer using pip.
 #[derive(Debug)]
 pub(crate) enum PipDependenciesLayerError {
+    BuildRequirementsInstallCommand(RetriedCommandError, PythonVersion),
     CreateVenvCommand(StreamedCommandError),
-    PipInstallCommand(StreamedCommandError),
+    DependencyConflicts(String),
+    HashChecking(HashCheckingFailure),
+    InsufficientDiskSpace(DiskSpaceEstimate),
+    InvalidDependencyCheckMode(String),
+    ListInstalledPackages(ListInstalledPackagesError),
+    NoBuildIsolationInstallCommand(RetriedCommandError, PythonVersion),
+    NoBuildIsolationPackageNotFound(String),
+    PipCheckCommand(io::Error),
+    PipDownloadCommand(RetriedCommandError, PythonVersion),
+    PipDryRunCommand(RetriedCommandError, PythonVersion),
+    PipExtraArgs(PipExtraArgsError),
+    PipInstallCommand(RetriedCommandError, PythonVersion),
+    PrepareDownloadsDir(io::Error),
+    ReadPyprojectToml(ReadPyprojectTomlError),
+    ReadRequirementsFile(String, io::Error),
+}
+
+/// The ways in which the app's requirements can fail pip's hash-checking mode, along with the
+/// affected requirements.
+#[derive(Debug, PartialEq)]
+pub(crate) enum HashCheckingFailure {
+    /// The hashes of the downloaded distributions don't match those in the requirements files.
+    HashMismatch(Vec<String>),
+    /// Some requirements (typically transitive dependencies) don't have any hashes.
+    MissingHashes(Vec<String>),
+    /// Some requirements aren't pinned to an exact version using `==`.
+    UnpinnedRequirements(Vec<String>),
 }
 
 impl From<PipDependenciesLayerError> for libcnb::Error<BuildpackError> {
@@ -108,3 +1272,407 @@ impl From<PipDependenciesLayerError> for libcnb::Error<BuildpackError> {
         Self::BuildpackError(BuildpackError::PipDependenciesLayer(error))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn requirements_hash_inputs() {
+        let python_config = PythonBuildpackConfig::default();
+        let mut env = Env::new();
+        env.insert("HOME", "/home/app");
+        let hash = requirements_hash("six==1.17.0\n", "", &python_config, &env);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(
+            requirements_hash("six==1.17.0\n", "", &python_config, &env),
+            hash
+        );
+        assert_ne!(
+            requirements_hash("six==1.16.0\n", "", &python_config, &env),
+            hash
+        );
+        assert_ne!(
+            requirements_hash("six==1.17.0\n", "six<2\n", &python_config, &env),
+            hash
+        );
+
+        env.insert("HOME", "/app");
+        assert_eq!(
+            requirements_hash("six==1.17.0\n", "", &python_config, &env),
+            hash
+        );
+        env.insert("PIP_INDEX_URL", "https://example.com/simple");
+        assert_ne!(
+            requirements_hash("six==1.17.0\n", "", &python_config, &env),
+            hash
+        );
+    }
+
+    #[test]
+    fn only_wheels_file_names() {
+        assert!(only_wheels(&[
+            "typing_extensions-4.12.2-py3-none-any.whl".to_string(),
+            "six-1.17.0-py2.py3-none-any.whl".to_string(),
+        ]));
+        assert!(only_wheels(&[]));
+        assert!(!only_wheels(&[
+            "typing_extensions-4.12.2-py3-none-any.whl".to_string(),
+            "extension.dist-0.1.tar.gz".to_string(),
+        ]));
+        assert!(!only_wheels(&["my-package.zip".to_string()]));
+    }
+
+    #[test]
+    fn requirements_for_packages_found() {
+        let requirements_file = indoc! {"
+            # A comment
+            --extra-index-url https://download.pytorch.org/whl/cu124
+            torch==2.5.1
+            Flash_Attn==2.7.2  # Built against torch
+            numpy>=2; python_version >= '3.10'
+        "};
+        assert_eq!(
+            requirements_for_packages(
+                requirements_file,
+                &["flash-attn".to_string(), "numpy".to_string()]
+            ),
+            Ok(vec![
+                "Flash_Attn==2.7.2".to_string(),
+                "numpy>=2; python_version >= '3.10'".to_string()
+            ])
+        );
+        assert_eq!(
+            requirements_for_packages(requirements_file, &["torchvision".to_string()]),
+            Err("torchvision".to_string())
+        );
+    }
+
+    #[test]
+    fn requirements_files_with_overlays() {
+        let target = Target {
+            os: "linux".to_string(),
+            arch: "arm64".to_string(),
+            arch_variant: None,
+            distro_name: "ubuntu".to_string(),
+            distro_version: "24.04".to_string(),
+        };
+        assert_eq!(
+            requirements_files(
+                Path::new("tests/fixtures/pip_target_requirements"),
+                &target,
+                Some("requirements.txt")
+            )
+            .unwrap(),
+            ["requirements.txt", "requirements-arm64.txt"]
+        );
+        assert_eq!(
+            requirements_files(
+                Path::new("tests/fixtures/pip_basic"),
+                &target,
+                Some("requirements.txt")
+            )
+            .unwrap(),
+            ["requirements.txt"]
+        );
+        assert_eq!(
+            requirements_files(
+                Path::new("tests/fixtures/pip_requirements_file"),
+                &target,
+                Some("requirements/production.txt")
+            )
+            .unwrap(),
+            ["requirements/production.txt"]
+        );
+        assert_eq!(
+            requirements_files(
+                Path::new("tests/fixtures/pip_target_requirements"),
+                &target,
+                None
+            )
+            .unwrap(),
+            ["requirements-arm64.txt"]
+        );
+        assert_eq!(
+            requirements_args(
+                &[
+                    "requirements.txt".to_string(),
+                    "requirements-arm64.txt".to_string()
+                ],
+                false,
+                &[]
+            ),
+            ["-r", "requirements.txt", "-r", "requirements-arm64.txt"]
+        );
+        assert_eq!(
+            requirements_args(
+                &["requirements-arm64.txt".to_string()],
+                true,
+                &["--pre".to_string()]
+            ),
+            [".", "-r", "requirements-arm64.txt", "--pre"]
+        );
+    }
+
+    #[test]
+    fn project_requirements_pyproject() {
+        assert_eq!(
+            requirement_lines(
+                &project_requirements(Path::new("tests/fixtures/pip_pyproject")).unwrap()
+            ),
+            ["typing-extensions==4.12.2"]
+        );
+        assert_eq!(
+            project_requirements(Path::new("tests/fixtures/empty")).unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn read_requirements_follows_includes() {
+        let (requirements, constraints) = read_requirements(
+            Path::new("tests/fixtures/pip_requirements_file"),
+            &["requirements/production.txt".to_string()],
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            requirement_lines(&requirements),
+            ["six==1.17.0", "typing-extensions==4.12.2"]
+        );
+        assert_eq!(requirement_lines(&constraints), ["typing-extensions<5"]);
+        assert!(matches!(
+            read_requirements(
+                Path::new("tests/fixtures/pip_basic"),
+                &["requirements/production.txt".to_string()],
+                false,
+            ),
+            Err(PipDependenciesLayerError::ReadRequirementsFile(file, _)) if file == "requirements/production.txt"
+        ));
+    }
+
+    #[test]
+    fn included_files_parsing() {
+        assert_eq!(
+            included_files(indoc! {"
+                -r base.txt
+                --requirement=common/web.txt  # Shared with the worker
+                -r https://example.com/requirements.txt
+                -c constraints.txt
+                --constraint https://example.com/constraints.txt
+                # -r commented-out.txt
+                django==5.1.4
+            "}),
+            [
+                ("base.txt", false),
+                ("common/web.txt", false),
+                ("constraints.txt", true)
+            ]
+        );
+    }
+
+    #[test]
+    fn has_local_path_requirements_detected() {
+        for requirement in [
+            ".",
+            "./libs/foo",
+            "libs/foo",
+            "-e .",
+            "--editable=./libs/foo",
+            "mypackage @ file:///app/libs/mypackage",
+            "./dist/mypackage-1.0-py3-none-any.whl",
+        ] {
+            assert!(
+                has_local_path_requirements(&format!("django==5.1.4\n{requirement}\n")),
+                "{requirement}"
+            );
+        }
+        assert!(!has_local_path_requirements(indoc! {"
+            -r requirements/base.txt
+            --index-url https://example.com/simple
+            django==5.1.4 ; sys_platform == 'linux'
+            mypackage @ https://example.com/mypackage.zip
+            -e git+https://github.com/example/project.git#egg=project
+        "}));
+    }
+
+    #[test]
+    fn requires_torch_listed() {
+        assert!(requires_torch(
+            "numpy==2.2.1\ntorch==2.5.1  # For inference\n"
+        ));
+        assert!(requires_torch("Torch>=2.0; sys_platform == 'linux'"));
+        assert!(!requires_torch("torchvision==0.20.1\n# torch==2.5.1\n"));
+        assert!(!requires_torch(""));
+    }
+
+    #[test]
+    fn source_distributions_in_report() {
+        let report = r#"{
+            "version": "1",
+            "pip_version": "24.3.1",
+            "install": [
+                {
+                    "download_info": {
+                        "url": "https://files.pythonhosted.org/packages/numpy-1.26.4.tar.gz",
+                        "archive_info": {"hash": "sha256=abc"}
+                    },
+                    "is_direct": false,
+                    "metadata": {"name": "numpy", "version": "1.26.4"}
+                },
+                {
+                    "download_info": {
+                        "url": "https://files.pythonhosted.org/packages/typing_extensions-4.12.2-py3-none-any.whl",
+                        "archive_info": {"hash": "sha256=def"}
+                    },
+                    "is_direct": false,
+                    "metadata": {"name": "typing_extensions", "version": "4.12.2"}
+                },
+                {
+                    "download_info": {
+                        "url": "https://github.com/pypa/wheel.git",
+                        "vcs_info": {"vcs": "git", "commit_id": "abc"}
+                    },
+                    "is_direct": true,
+                    "metadata": {"name": "wheel", "version": "0.44.0"}
+                }
+            ]
+        }"#;
+        let report: InstallationReport = serde_json::from_str(report).unwrap();
+        assert_eq!(source_distributions(&report), [("numpy", "1.26.4")]);
+    }
+
+    #[test]
+    fn prefers_binary_parsing() {
+        let mut env = Env::new();
+        assert!(!prefers_binary(&env));
+        for value in ["1", "true", "YES", "On"] {
+            env.insert("PIP_PREFER_BINARY", value);
+            assert!(prefers_binary(&env));
+        }
+        for value in ["0", "false", "off", ""] {
+            env.insert("PIP_PREFER_BINARY", value);
+            assert!(!prefers_binary(&env));
+        }
+    }
+
+    #[test]
+    fn downloaded_file_names_from_output() {
+        let output = indoc! {"
+            Collecting typing-extensions==4.12.2 (from -r requirements.txt (line 2))
+            File was already downloaded /layers/heroku_python/pip-cache/downloads/typing_extensions-4.12.2-py3-none-any.whl
+            Collecting six==1.17.0 (from -r requirements.txt (line 3))
+              Downloading six-1.17.0.tar.gz (34 kB)
+            Saved /layers/heroku_python/pip-cache/downloads/six-1.17.0.tar.gz
+            Successfully downloaded six typing-extensions
+        "};
+        assert_eq!(
+            downloaded_file_names(output),
+            HashSet::from([
+                "typing_extensions-4.12.2-py3-none-any.whl".to_string(),
+                "six-1.17.0.tar.gz".to_string()
+            ])
+        );
+        assert!(downloaded_file_names("Successfully downloaded\n").is_empty());
+    }
+
+    #[test]
+    fn dependency_check_mode_configuration() {
+        assert_eq!(
+            dependency_check_mode(&Env::new()).unwrap(),
+            DependencyCheckMode::Warn
+        );
+
+        let mut env = Env::new();
+        for (value, expected_mode) in [
+            ("error", DependencyCheckMode::Error),
+            ("off", DependencyCheckMode::Off),
+            (" warn ", DependencyCheckMode::Warn),
+        ] {
+            env.insert(DEPENDENCY_CHECK_ENV_VAR, value);
+            assert_eq!(dependency_check_mode(&env).unwrap(), expected_mode);
+        }
+
+        for value in ["", "Error", "false", "warning"] {
+            env.insert(DEPENDENCY_CHECK_ENV_VAR, value);
+            assert!(matches!(
+                dependency_check_mode(&env),
+                Err(PipDependenciesLayerError::InvalidDependencyCheckMode(invalid)) if invalid == value
+            ));
+        }
+    }
+
+    #[test]
+    fn uses_hash_checking_mode_detection() {
+        let env = Env::new();
+        assert!(uses_hash_checking_mode(
+            "foo==1.0 \\\n    --hash=sha256:abc\n",
+            &env
+        ));
+        assert!(uses_hash_checking_mode(
+            "--require-hashes\nfoo==1.0\n",
+            &env
+        ));
+        assert!(!uses_hash_checking_mode(
+            "foo==1.0  # Regenerate with --hash\n",
+            &env
+        ));
+
+        let mut env = Env::new();
+        env.insert("PIP_REQUIRE_HASHES", "1");
+        assert!(uses_hash_checking_mode("foo==1.0\n", &env));
+        env.insert("PIP_REQUIRE_HASHES", "false");
+        assert!(!uses_hash_checking_mode("foo==1.0\n", &env));
+    }
+
+    #[test]
+    fn hash_checking_failure_parsing() {
+        let output = indoc! {"
+            Collecting typing-extensions==4.12.2 (from -r requirements.txt (line 2))
+              Downloading typing_extensions-4.12.2-py3-none-any.whl.metadata (3.0 kB)
+            ERROR: THESE PACKAGES DO NOT MATCH THE HASHES FROM THE REQUIREMENTS FILE. If you have updated the package versions, please update the hashes. Otherwise, examine the package contents carefully; someone may have tampered with them.
+                typing-extensions==4.12.2 from https://files.pythonhosted.org/packages/typing_extensions-4.12.2-py3-none-any.whl#sha256=04e5 (from -r requirements.txt (line 2)):
+                    Expected sha256 abc
+                         Got        04e5
+                six==1.17.0 from https://files.pythonhosted.org/packages/six-1.17.0-py2.py3-none-any.whl#sha256=4721 (from -r requirements.txt (line 5)):
+                    Expected sha256 def
+                         Got        4721
+
+        "};
+        assert_eq!(
+            hash_checking_failure(output),
+            Some(HashCheckingFailure::HashMismatch(vec![
+                "typing-extensions==4.12.2".to_string(),
+                "six==1.17.0".to_string(),
+            ]))
+        );
+
+        let output = indoc! {"
+            ERROR: Hashes are required in --require-hashes mode, but they are missing from some requirements. Here is a list of those requirements along with the hashes their downloaded archives actually had. You can add lines like these to your requirements files to pin them.
+                six==1.17.0 --hash=sha256:4721
+        "};
+        assert_eq!(
+            hash_checking_failure(output),
+            Some(HashCheckingFailure::MissingHashes(vec![
+                "six==1.17.0".to_string()
+            ]))
+        );
+
+        let output = indoc! {"
+            ERROR: In --require-hashes mode, all requirements must have their versions pinned with ==. These do not:
+                six from https://files.pythonhosted.org/packages/six-1.17.0-py2.py3-none-any.whl (from -r requirements.txt (line 5))
+        "};
+        assert_eq!(
+            hash_checking_failure(output),
+            Some(HashCheckingFailure::UnpinnedRequirements(vec![
+                "six".to_string()
+            ]))
+        );
+
+        assert_eq!(
+            hash_checking_failure("ERROR: No matching distribution found for foo==1.0\n"),
+            None
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use crate::build_log::log_info;
+use crate::packaging_tool_install::{self, PackagingToolInstallError};
+use crate::packaging_tool_versions::{PIPENV_REQUIREMENTS, PIPENV_VERSION};
+use crate::python_version::PythonVersion;
+use crate::{telemetry, utils, BuildpackError, PythonBuildpack};
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::{
+    CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerState, RestoredLayerAction,
+};
+use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
+use libcnb::Env;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// Creates a build-only layer containing Pipenv.
+pub(crate) fn install_pipenv(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    python_version: &PythonVersion,
+    python_layer_path: &Path,
+) -> Result<(), libcnb::Error<BuildpackError>> {
+    let new_metadata = PipenvLayerMetadata {
+        arch: context.target.arch.clone(),
+        distro_name: context.target.distro_name.clone(),
+        distro_version: context.target.distro_version.clone(),
+        python_version: python_version.to_string(),
+        pipenv_version: PIPENV_VERSION.to_string(),
+    };
+
+    let layer = context.cached_layer(
+        layer_name!("pipenv"),
+        CachedLayerDefinition {
+            build: true,
+            launch: false,
+            invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
+            restored_layer_action: &|cached_metadata: &PipenvLayerMetadata, _| {
+                let cached_pipenv_version = cached_metadata.pipenv_version.clone();
+                if cached_metadata == &new_metadata {
+                    (RestoredLayerAction::KeepLayer, cached_pipenv_version)
+                } else {
+                    (RestoredLayerAction::DeleteLayer, cached_pipenv_version)
+                }
+            },
+        },
+    )?;
+    telemetry::record_layer_cache("pipenv", &layer.state);
+
+    let mut layer_env = LayerEnv::new()
+        // Move the Python user base directory to this layer instead of under HOME:
+        // https://docs.python.org/3/using/cmdline.html#envvar-PYTHONUSERBASE
+        .chainable_insert(
+            Scope::Build,
+            ModificationBehavior::Override,
+            "PYTHONUSERBASE",
+            layer.path(),
+        )
+        // Suppresses the notice shown when Pipenv uses an existing virtual environment (which is
+        // always the case, since we create the venv ourselves), along with other verbose output.
+        // https://pipenv.pypa.io/en/latest/configuration.html
+        .chainable_insert(
+            Scope::Build,
+            ModificationBehavior::Override,
+            "PIPENV_VERBOSITY",
+            "-1",
+        );
+
+    match layer.state {
+        LayerState::Restored {
+            cause: ref cached_pipenv_version,
+        } => {
+            log_info(format!("Using cached Pipenv {cached_pipenv_version}"));
+        }
+        LayerState::Empty { ref cause } => {
+            match cause {
+                EmptyLayerCause::InvalidMetadataAction { .. } => {
+                    log_info("Discarding cached Pipenv since its layer metadata can't be parsed");
+                }
+                EmptyLayerCause::RestoredLayerAction {
+                    cause: cached_pipenv_version,
+                } => {
+                    log_info(format!("Discarding cached Pipenv {cached_pipenv_version}"));
+                }
+                EmptyLayerCause::NewlyCreated => {}
+            }
+
+            log_info(format!("Installing Pipenv {PIPENV_VERSION}"));
+
+            // As for Poetry, we use the pip wheel bundled within Python's standard library to
+            // install Pipenv. Pipenv installs packages using its own vendored copy of pip, so
+            // pip doesn't need to be installed into the user site-packages or the venv.
+            let bundled_pip_module_path =
+                utils::bundled_pip_module_path(python_layer_path, python_version)
+                    .map_err(PipenvLayerError::LocateBundledPip)?;
+
+            packaging_tool_install::install_packaging_tool(
+                &bundled_pip_module_path,
+                &layer_env.apply(Scope::Build, env),
+                &[PIPENV_REQUIREMENTS],
+                &[],
+                &layer.path(),
+            )
+            .map_err(PipenvLayerError::InstallPipenv)?;
+
+            layer.write_metadata(new_metadata)?;
+        }
+    }
+
+    layer.write_env(&layer_env)?;
+    // Required to pick up the automatic PATH env var. See: https://github.com/heroku/libcnb.rs/issues/842
+    layer_env = layer.read_env()?;
+    env.clone_from(&layer_env.apply(Scope::Build, env));
+
+    Ok(())
+}
+
+// Some of Pipenv's dependencies may contain compiled components so are platform-specific (unlike
+// pure Python packages). As such we have to take arch and distro into account for cache invalidation.
+#[derive(Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+struct PipenvLayerMetadata {
+    arch: String,
+    distro_name: String,
+    distro_version: String,
+    python_version: String,
+    pipenv_version: String,
+}
+
+/// Errors that can occur when installing Pipenv into a layer.
+#[derive(Debug)]
+pub(crate) enum PipenvLayerError {
+    InstallPipenv(PackagingToolInstallError),
+    LocateBundledPip(io::Error),
+}
+
+impl From<PipenvLayerError> for libcnb::Error<BuildpackError> {
+    fn from(error: PipenvLayerError) -> Self {
+        Self::BuildpackError(BuildpackError::PipenvLayer(error))
+    }
+}
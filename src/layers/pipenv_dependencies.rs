@@ -0,0 +1,317 @@
+use crate::build_log::log_info;
+use crate::dependency_plan;
+use crate::layers::dependency_report::{self, DependencyReport};
+use crate::package_inventory::{self, InstalledPackage, ListInstalledPackagesError};
+use crate::package_manager::PackageManager;
+use crate::packaging_tool_versions::PIPENV_VERSION;
+use crate::pipfile::{self, Pipfile, PipfileLock, ReadPipfileError};
+use crate::python_version::PythonVersion;
+use crate::redact::redact_url_credentials;
+use crate::utils::{RetriedCommandError, StreamedCommandError};
+use crate::{telemetry, utils, BuildpackError, PythonBuildpack};
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::{
+    CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerState, RestoredLayerAction,
+};
+use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
+use libcnb::Env;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Creates a layer containing the application's Python dependencies, installed using Pipenv.
+//
+// We install into a virtual environment for the same reasons as when using Poetry (see
+// `poetry_dependencies.rs`). Pipenv uses the venv rather than creating its own, since
+// `VIRTUAL_ENV` is set to the venv's location.
+//
+// We cache the virtual environment, since it results in faster builds than only caching Pipenv's
+// download/wheel cache. Unlike `poetry install --sync`, `pipenv install` doesn't uninstall packages
+// that have been removed from the lockfile, so we run `pipenv clean` when using the cached venv.
+pub(crate) fn install_dependencies(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    python_version: &PythonVersion,
+) -> Result<(PathBuf, Vec<InstalledPackage>), libcnb::Error<BuildpackError>> {
+    let (pipfile, pipfile_lock) = check_project(&context.app_dir)?;
+
+    let new_metadata = PipenvDependenciesLayerMetadata {
+        arch: context.target.arch.clone(),
+        distro_name: context.target.distro_name.clone(),
+        distro_version: context.target.distro_version.clone(),
+        python_version: python_version.to_string(),
+        pipenv_version: PIPENV_VERSION.to_string(),
+        packages: Vec::new(),
+    };
+
+    let layer = context.cached_layer(
+        // The name of this layer must be alphabetically after that of the `python` layer so that
+        // this layer's `bin/` directory (and thus `python` symlink) is listed first in `PATH`:
+        // https://github.com/buildpacks/spec/blob/main/buildpack.md#layer-paths
+        layer_name!("venv"),
+        CachedLayerDefinition {
+            build: true,
+            launch: true,
+            invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
+            restored_layer_action: &|cached_metadata: &PipenvDependenciesLayerMetadata, _| {
+                if cached_metadata.is_cache_compatible(&new_metadata) {
+                    RestoredLayerAction::KeepLayer
+                } else {
+                    RestoredLayerAction::DeleteLayer
+                }
+            },
+        },
+    )?;
+    telemetry::record_layer_cache("venv", &layer.state);
+    let layer_path = layer.path();
+
+    let is_cached_venv = match layer.state {
+        LayerState::Restored { .. } => {
+            log_info("Using cached virtual environment");
+            true
+        }
+        LayerState::Empty { ref cause } => {
+            match cause {
+                EmptyLayerCause::InvalidMetadataAction { .. }
+                | EmptyLayerCause::RestoredLayerAction { .. } => {
+                    log_info("Discarding cached virtual environment");
+                }
+                EmptyLayerCause::NewlyCreated => {}
+            }
+
+            log_info("Creating virtual environment");
+            utils::run_command_and_stream_output(
+                Command::new("python")
+                    .args(["-m", "venv", "--without-pip", &layer_path.to_string_lossy()])
+                    .env_clear()
+                    .envs(&*env),
+            )
+            .map_err(PipenvDependenciesLayerError::CreateVenvCommand)?;
+            false
+        }
+    };
+
+    let mut layer_env = LayerEnv::new()
+        // For parity with the venv's `bin/activate` script:
+        // https://docs.python.org/3/library/venv.html#how-venvs-work
+        .chainable_insert(
+            Scope::All,
+            ModificationBehavior::Override,
+            "VIRTUAL_ENV",
+            &layer_path,
+        );
+    layer.write_env(&layer_env)?;
+    // Required to pick up the automatic PATH env var. See: https://github.com/heroku/libcnb.rs/issues/842
+    layer_env = layer.read_env()?;
+    env.clone_from(&layer_env.apply(Scope::Build, env));
+
+    if dependency_plan::is_plan_only(env) {
+        show_dependency_plan(&context.app_dir, env)?;
+        return Err(libcnb::Error::BuildpackError(
+            BuildpackError::DependencyPlanOnly,
+        ));
+    }
+
+    pipenv_install(&context.app_dir, env, python_version)?;
+    if is_cached_venv {
+        pipenv_clean(&context.app_dir, env)?;
+    }
+
+    let packages = package_inventory::list_installed_packages(&context.app_dir, env, &[])
+        .map_err(PipenvDependenciesLayerError::ListInstalledPackages)?;
+    dependency_report::write_dependency_report(
+        context,
+        env,
+        &generate_dependency_report(&pipfile, &pipfile_lock, &packages),
+    )?;
+    layer.write_sboms(&package_inventory::packages_sboms(&packages))?;
+    layer.write_metadata(PipenvDependenciesLayerMetadata {
+        packages: packages.clone(),
+        ..new_metadata
+    })?;
+
+    Ok((layer_path, packages))
+}
+
+fn pipenv_install(
+    app_dir: &Path,
+    env: &Env,
+    python_version: &PythonVersion,
+) -> Result<(), PipenvDependenciesLayerError> {
+    utils::log_running_command("pipenv install --deploy");
+    utils::run_command_and_stream_output_with_retries(
+        Command::new("pipenv")
+            // Using `--deploy` so that the build fails if `Pipfile.lock` is out of date
+            // compared to the `Pipfile`, rather than silently installing outdated packages.
+            .args(["install", "--deploy"])
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+        "pipenv install",
+    )
+    .map_err(|error| {
+        PipenvDependenciesLayerError::PipenvInstallCommand(error, python_version.clone())
+    })
+}
+
+fn pipenv_clean(app_dir: &Path, env: &Env) -> Result<(), PipenvDependenciesLayerError> {
+    utils::log_running_command("pipenv clean");
+    utils::run_command_and_stream_output(
+        Command::new("pipenv")
+            .arg("clean")
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+    )
+    .map_err(PipenvDependenciesLayerError::PipenvCleanCommand)
+}
+
+/// Shows the locked packages that would be installed. Pipenv doesn't support a dry-run install,
+/// however, since `--deploy` means the packages are installed exactly as locked, the lockfile's
+/// requirements are the packages that would be installed.
+fn show_dependency_plan(app_dir: &Path, env: &Env) -> Result<(), PipenvDependenciesLayerError> {
+    utils::log_running_command("pipenv requirements");
+    utils::run_command_and_stream_output(
+        Command::new("pipenv")
+            .arg("requirements")
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+    )
+    .map_err(PipenvDependenciesLayerError::PipenvRequirementsCommand)
+}
+
+// The direct dependencies are those declared in the `[packages]` table of the `Pipfile`.
+fn generate_dependency_report(
+    pipfile: &Pipfile,
+    pipfile_lock: &PipfileLock,
+    packages: &[InstalledPackage],
+) -> DependencyReport {
+    let sources = pipfile_lock
+        .default
+        .iter()
+        .filter_map(|(name, package)| {
+            let source = pipfile_lock.package_source(package)?;
+            Some((
+                utils::normalize_package_name(name),
+                redact_url_credentials(&source),
+            ))
+        })
+        .collect();
+    DependencyReport::new(
+        PackageManager::Pipenv.name(),
+        packages,
+        &pipfile.dependency_names(),
+        &sources,
+    )
+}
+
+/// Checks for known problems with the project's Pipenv configuration, that would otherwise
+/// result in unclear error messages when Pipenv is run.
+/// Returns the parsed `Pipfile` and `Pipfile.lock` files for use by later steps.
+fn check_project(app_dir: &Path) -> Result<(Pipfile, PipfileLock), PipenvDependenciesLayerError> {
+    // Without a `Pipfile`, Pipenv would create a new empty one, and then fail the `--deploy`
+    // check with an error that doesn't explain that the `Pipfile` is missing.
+    let pipfile = pipfile::read_pipfile(app_dir)
+        .map_err(PipenvDependenciesLayerError::ReadPipfile)?
+        .ok_or(PipenvDependenciesLayerError::PipfileNotFound)?;
+    let pipfile_lock =
+        pipfile::read_pipfile_lock(app_dir).map_err(PipenvDependenciesLayerError::ReadPipfile)?;
+    Ok((pipfile, pipfile_lock))
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct PipenvDependenciesLayerMetadata {
+    arch: String,
+    distro_name: String,
+    distro_version: String,
+    python_version: String,
+    pipenv_version: String,
+    packages: Vec<InstalledPackage>,
+}
+
+impl PipenvDependenciesLayerMetadata {
+    /// Whether a cached layer with this metadata can be reused for the new metadata.
+    fn is_cache_compatible(&self, new_metadata: &Self) -> bool {
+        // By destructuring here we ensure that any fields added to the metadata in the future
+        // have to be explicitly considered for cache invalidation. The installed packages are
+        // only recorded for reference, and so are intentionally not compared (any changes in
+        // dependencies are handled by Pipenv when it installs from the lockfile).
+        let Self {
+            arch,
+            distro_name,
+            distro_version,
+            python_version,
+            pipenv_version,
+            packages: _,
+        } = self;
+        (
+            arch,
+            distro_name,
+            distro_version,
+            python_version,
+            pipenv_version,
+        ) == (
+            &new_metadata.arch,
+            &new_metadata.distro_name,
+            &new_metadata.distro_version,
+            &new_metadata.python_version,
+            &new_metadata.pipenv_version,
+        )
+    }
+}
+
+/// Errors that can occur when installing the project's dependencies into a layer using Pipenv.
+#[derive(Debug)]
+pub(crate) enum PipenvDependenciesLayerError {
+    CreateVenvCommand(StreamedCommandError),
+    ListInstalledPackages(ListInstalledPackagesError),
+    PipenvCleanCommand(StreamedCommandError),
+    PipenvInstallCommand(RetriedCommandError, PythonVersion),
+    PipenvRequirementsCommand(StreamedCommandError),
+    PipfileNotFound,
+    ReadPipfile(ReadPipfileError),
+}
+
+impl From<PipenvDependenciesLayerError> for libcnb::Error<BuildpackError> {
+    fn from(error: PipenvDependenciesLayerError) -> Self {
+        Self::BuildpackError(BuildpackError::PipenvDependenciesLayer(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_dependency_report_pipenv_basic() {
+        let app_dir = Path::new("tests/fixtures/pipenv_basic");
+        let (pipfile, pipfile_lock) = check_project(app_dir).unwrap();
+        let report = generate_dependency_report(
+            &pipfile,
+            &pipfile_lock,
+            &[InstalledPackage {
+                name: "typing_extensions".to_string(),
+                version: "4.12.2".to_string(),
+                hash: None,
+            }],
+        );
+        assert_eq!(report.package_manager, "Pipenv");
+        assert_eq!(report.packages.len(), 1);
+        assert!(report.packages[0].direct);
+        assert_eq!(
+            report.packages[0].source.as_deref(),
+            Some("https://pypi.org/simple")
+        );
+    }
+
+    #[test]
+    fn check_project_pipfile_not_found() {
+        assert!(matches!(
+            check_project(Path::new("tests/fixtures/empty")),
+            Err(PipenvDependenciesLayerError::PipfileNotFound)
+        ));
+    }
+}
@@ -1,7 +1,12 @@
-use crate::packaging_tool_versions::POETRY_VERSION;
+use crate::build_log::log_info;
+use crate::packaging_tool_install::{self, PackagingToolInstallError};
+use crate::packaging_tool_versions::{
+    POETRY_DYNAMIC_VERSIONING_REQUIREMENTS, POETRY_DYNAMIC_VERSIONING_VERSION, POETRY_REQUIREMENTS,
+    POETRY_VERSION,
+};
+use crate::pyproject_toml::PyprojectToml;
 use crate::python_version::PythonVersion;
-use crate::utils::StreamedCommandError;
-use crate::{utils, BuildpackError, PythonBuildpack};
+use crate::{telemetry, utils, BuildpackError, PythonBuildpack};
 use libcnb::build::BuildContext;
 use libcnb::data::layer_name;
 use libcnb::layer::{
@@ -9,25 +14,30 @@ use libcnb::layer::{
 };
 use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
 use libcnb::Env;
-use libherokubuildpack::log::log_info;
 use serde::{Deserialize, Serialize};
 use std::io;
 use std::path::Path;
-use std::process::Command;
 
-/// Creates a build-only layer containing Poetry.
+/// Creates a build-only layer containing Poetry, along with any Poetry plugins required by the project
+/// or configured using the `poetry-plugins` setting in `project.toml`.
 pub(crate) fn install_poetry(
     context: &BuildContext<PythonBuildpack>,
     env: &mut Env,
     python_version: &PythonVersion,
     python_layer_path: &Path,
+    pyproject_toml: Option<&PyprojectToml>,
+    configured_plugins: &[String],
+    keyring_packages: &[String],
 ) -> Result<(), libcnb::Error<BuildpackError>> {
+    let plugins = required_plugins(pyproject_toml, configured_plugins);
     let new_metadata = PoetryLayerMetadata {
         arch: context.target.arch.clone(),
         distro_name: context.target.distro_name.clone(),
         distro_version: context.target.distro_version.clone(),
         python_version: python_version.to_string(),
         poetry_version: POETRY_VERSION.to_string(),
+        plugins: plugins.clone(),
+        keyring_packages: keyring_packages.to_vec(),
     };
 
     let layer = context.cached_layer(
@@ -46,6 +56,7 @@ pub(crate) fn install_poetry(
             },
         },
     )?;
+    telemetry::record_layer_cache("poetry", &layer.state);
 
     // Move the Python user base directory to this layer instead of under HOME:
     // https://docs.python.org/3/using/cmdline.html#envvar-PYTHONUSERBASE
@@ -76,6 +87,18 @@ pub(crate) fn install_poetry(
             }
 
             log_info(format!("Installing Poetry {POETRY_VERSION}"));
+            if !plugins.is_empty() {
+                log_info(format!("Installing Poetry plugins: {}", plugins.join(", ")));
+            }
+            // Poetry already depends on `keyring`, and uses it to look up index credentials
+            // automatically, so only the backends need to be installed into its environment.
+            // https://python-poetry.org/docs/repositories/#configuring-credentials
+            if !keyring_packages.is_empty() {
+                log_info(format!(
+                    "Installing keyring packages: {}",
+                    keyring_packages.join(", ")
+                ));
+            }
 
             // We use the pip wheel bundled within Python's standard library to install Poetry.
             // Whilst Poetry does still require pip for some tasks (such as package uninstalls),
@@ -86,23 +109,28 @@ pub(crate) fn install_poetry(
                 utils::bundled_pip_module_path(python_layer_path, python_version)
                     .map_err(PoetryLayerError::LocateBundledPip)?;
 
-            utils::run_command_and_stream_output(
-                Command::new("python")
-                    .args([
-                        &bundled_pip_module_path.to_string_lossy(),
-                        "install",
-                        // There is no point using pip's cache here, since the layer itself will be cached.
-                        "--no-cache-dir",
-                        "--no-input",
-                        "--no-warn-script-location",
-                        "--quiet",
-                        "--user",
-                        format!("poetry=={POETRY_VERSION}").as_str(),
-                    ])
-                    .env_clear()
-                    .envs(&layer_env.apply(Scope::Build, env)),
+            // Poetry plugins must be installed into the same environment as Poetry itself:
+            // https://python-poetry.org/docs/plugins/#using-plugins
+            // The buildpack's pinned version of `poetry-dynamic-versioning` is installed using its
+            // hash-pinned requirements, whereas the plugins configured by the app are installed as-is.
+            let mut requirements = vec![POETRY_REQUIREMENTS];
+            let mut additional_requirements = Vec::new();
+            for plugin in &plugins {
+                if *plugin == dynamic_versioning_plugin() {
+                    requirements.push(POETRY_DYNAMIC_VERSIONING_REQUIREMENTS);
+                } else {
+                    additional_requirements.push(plugin.clone());
+                }
+            }
+            additional_requirements.extend(keyring_packages.iter().cloned());
+            packaging_tool_install::install_packaging_tool(
+                &bundled_pip_module_path,
+                &layer_env.apply(Scope::Build, env),
+                &requirements,
+                &additional_requirements,
+                &layer.path(),
             )
-            .map_err(PoetryLayerError::InstallPoetryCommand)?;
+            .map_err(PoetryLayerError::InstallPoetry)?;
 
             layer.write_metadata(new_metadata)?;
         }
@@ -126,12 +154,55 @@ struct PoetryLayerMetadata {
     distro_version: String,
     python_version: String,
     poetry_version: String,
+    // Defaulted so that layer metadata from older buildpack versions can still be read.
+    #[serde(default)]
+    plugins: Vec<String>,
+    #[serde(default)]
+    keyring_packages: Vec<String>,
+}
+
+/// Determine the Poetry plugins (as pip requirement specifiers) that the project needs at build time.
+///
+/// The plugins configured in `project.toml` are installed as-is, and take priority over the
+/// buildpack's pinned version of `poetry-dynamic-versioning` if they include it, since otherwise
+/// pip would fail due to the conflicting requirements.
+fn required_plugins(
+    pyproject_toml: Option<&PyprojectToml>,
+    configured_plugins: &[String],
+) -> Vec<String> {
+    let mut plugins = Vec::new();
+    if pyproject_toml.is_some_and(PyprojectToml::uses_poetry_dynamic_versioning)
+        && !configured_plugins.iter().any(|plugin| {
+            utils::normalize_package_name(requirement_name(plugin)) == "poetry-dynamic-versioning"
+        })
+    {
+        plugins.push(dynamic_versioning_plugin());
+    }
+    plugins.extend(
+        configured_plugins
+            .iter()
+            .map(|plugin| plugin.trim().to_string()),
+    );
+    plugins
+}
+
+// The requirement specifier for the buildpack's pinned version of `poetry-dynamic-versioning`.
+fn dynamic_versioning_plugin() -> String {
+    format!("poetry-dynamic-versioning[plugin]=={POETRY_DYNAMIC_VERSIONING_VERSION}")
+}
+
+fn requirement_name(requirement: &str) -> &str {
+    requirement
+        .trim()
+        .split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .next()
+        .unwrap_or_default()
 }
 
 /// Errors that can occur when installing Poetry into a layer.
 #[derive(Debug)]
 pub(crate) enum PoetryLayerError {
-    InstallPoetryCommand(StreamedCommandError),
+    InstallPoetry(PackagingToolInstallError),
     LocateBundledPip(io::Error),
 }
 
@@ -140,3 +211,35 @@ impl From<PoetryLayerError> for libcnb::Error<BuildpackError> {
         Self::BuildpackError(BuildpackError::PoetryLayer(error))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn required_plugins_configured() {
+        let pyproject_toml: PyprojectToml =
+            toml::from_str("[tool.poetry-dynamic-versioning]\nenable = true\n").unwrap();
+        let dynamic_versioning_plugin = dynamic_versioning_plugin();
+
+        assert!(required_plugins(None, &[]).is_empty());
+        assert_eq!(
+            required_plugins(None, &["poetry-plugin-export==1.9.0".to_string()]),
+            ["poetry-plugin-export==1.9.0"]
+        );
+        assert_eq!(
+            required_plugins(
+                Some(&pyproject_toml),
+                &[" poetry-plugin-export ".to_string()]
+            ),
+            [dynamic_versioning_plugin.as_str(), "poetry-plugin-export"]
+        );
+        assert_eq!(
+            required_plugins(
+                Some(&pyproject_toml),
+                &["Poetry_Dynamic_Versioning[plugin]>=1.4".to_string()]
+            ),
+            ["Poetry_Dynamic_Versioning[plugin]>=1.4"]
+        );
+    }
+}
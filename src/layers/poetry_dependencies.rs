@@ -1,7 +1,18 @@
-use crate::packaging_tool_versions::POETRY_VERSION;
+use crate::build_log::log_info;
+use crate::dependency_plan;
+use crate::hash_verification::{self, HashVerification};
+use crate::layers::dependency_report::{self, DependencyReport};
+use crate::package_inventory::{self, InstalledPackage, ListInstalledPackagesError};
+use crate::package_manager::PackageManager;
+use crate::packaging_tool_versions::{POETRY_MAX_SUPPORTED_LOCK_VERSION, POETRY_VERSION};
+use crate::poetry_lock::{self, PoetryLock, ReadPoetryLockError};
+use crate::pyproject_toml::PyprojectToml;
 use crate::python_version::PythonVersion;
-use crate::utils::StreamedCommandError;
-use crate::{utils, BuildpackError, PythonBuildpack};
+use crate::redact::redact_url_credentials;
+use crate::strict_mode::{StrictMode, WarningClass};
+use crate::utils::{RetriedCommandError, StableHasher, StreamedCommandError};
+use crate::{telemetry, utils, BuildpackError, PythonBuildpack};
+use indoc::formatdoc;
 use libcnb::build::BuildContext;
 use libcnb::data::layer_name;
 use libcnb::layer::{
@@ -9,10 +20,15 @@ use libcnb::layer::{
 };
 use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
 use libcnb::Env;
-use libherokubuildpack::log::log_info;
+use ring::digest;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::{fs, io};
+
+// The package index that Poetry installs packages from, unless another source is configured.
+const PYPI_URL: &str = "https://pypi.org/simple";
 
 /// Creates a layer containing the application's Python dependencies, installed using Poetry.
 //
@@ -30,6 +46,9 @@ use std::process::Command;
 // - It's safe to do so, since `poetry install --sync` fully manages the environment
 //   (including e.g. uninstalling packages when they are removed from the lockfile).
 //
+// If the lockfile's content hash, the project's metadata and the source of any path dependencies
+// are all unchanged since the cached venv was created, then the install is skipped entirely.
+//
 // With the venv cached there is no need to persist Poetry's download/wheel cache in its
 // own layer, so we let Poetry write it to the home directory where it will be discarded
 // at the end of the build. We don't use `--no-cache` since the cache still offers benefits
@@ -38,14 +57,19 @@ pub(crate) fn install_dependencies(
     context: &BuildContext<PythonBuildpack>,
     env: &mut Env,
     python_version: &PythonVersion,
-) -> Result<PathBuf, libcnb::Error<BuildpackError>> {
-    let new_metadata = PoetryDependenciesLayerMetadata {
-        arch: context.target.arch.clone(),
-        distro_name: context.target.distro_name.clone(),
-        distro_version: context.target.distro_version.clone(),
-        python_version: python_version.to_string(),
-        poetry_version: POETRY_VERSION.to_string(),
-    };
+    pyproject_toml: Option<&PyprojectToml>,
+    groups: &[String],
+    strict_mode: &StrictMode,
+) -> Result<(PathBuf, Vec<InstalledPackage>), libcnb::Error<BuildpackError>> {
+    let poetry_lock = check_project(&context.app_dir, env, pyproject_toml, strict_mode)?;
+
+    let new_metadata = PoetryDependenciesLayerMetadata::new(
+        context,
+        python_version,
+        pyproject_toml,
+        &poetry_lock,
+        only_groups(groups),
+    )?;
 
     let layer = context.cached_layer(
         // The name of this layer must be alphabetically after that of the `python` layer so that
@@ -57,21 +81,34 @@ pub(crate) fn install_dependencies(
             launch: true,
             invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
             restored_layer_action: &|cached_metadata: &PoetryDependenciesLayerMetadata, _| {
-                if cached_metadata == &new_metadata {
-                    RestoredLayerAction::KeepLayer
+                if cached_metadata.is_cache_compatible(&new_metadata) {
+                    (RestoredLayerAction::KeepLayer, cached_metadata.clone())
                 } else {
-                    RestoredLayerAction::DeleteLayer
+                    (RestoredLayerAction::DeleteLayer, cached_metadata.clone())
                 }
             },
         },
     )?;
+    telemetry::record_layer_cache("venv", &layer.state);
     let layer_path = layer.path();
 
-    match layer.state {
-        libcnb::layer::LayerState::Restored { .. } => {
+    let unchanged_metadata = match layer.state {
+        libcnb::layer::LayerState::Restored {
+            cause: ref cached_metadata,
+        } => {
             log_info("Using cached virtual environment");
+            uninstall_changed_packages(
+                &layer_path,
+                python_version,
+                pyproject_toml,
+                cached_metadata,
+                &new_metadata,
+            )?;
+            cached_metadata
+                .is_install_unchanged(&new_metadata)
+                .then(|| cached_metadata.clone())
         }
-        libcnb::layer::LayerState::Empty { cause } => {
+        libcnb::layer::LayerState::Empty { ref cause } => {
             match cause {
                 EmptyLayerCause::InvalidMetadataAction { .. }
                 | EmptyLayerCause::RestoredLayerAction { .. } => {
@@ -88,10 +125,9 @@ pub(crate) fn install_dependencies(
                     .envs(&*env),
             )
             .map_err(PoetryDependenciesLayerError::CreateVenvCommand)?;
-
-            layer.write_metadata(new_metadata)?;
+            None
         }
-    }
+    };
 
     let mut layer_env = LayerEnv::new()
         // For parity with the venv's `bin/activate` script:
@@ -107,28 +143,479 @@ pub(crate) fn install_dependencies(
     layer_env = layer.read_env()?;
     env.clone_from(&layer_env.apply(Scope::Build, env));
 
-    log_info("Running 'poetry install --sync --only main'");
-    utils::run_command_and_stream_output(
+    if dependency_plan::is_plan_only(env) {
+        show_dependency_plan(&context.app_dir, env, python_version, &new_metadata.groups)?;
+        return Err(libcnb::Error::BuildpackError(
+            BuildpackError::DependencyPlanOnly,
+        ));
+    }
+
+    let metadata = if let Some(cached_metadata) = unchanged_metadata {
+        log_info("Dependencies unchanged, skipping poetry install");
+        cached_metadata
+    } else {
+        install_packages(
+            &context.app_dir,
+            env,
+            python_version,
+            pyproject_toml,
+            &poetry_lock,
+            strict_mode,
+            new_metadata,
+        )?
+    };
+    dependency_report::write_dependency_report(
+        context,
+        env,
+        &generate_dependency_report(&poetry_lock, pyproject_toml, &metadata.packages),
+    )?;
+    layer.write_sboms(&package_inventory::packages_sboms(&metadata.packages))?;
+    let packages = metadata.packages.clone();
+    layer.write_metadata(metadata)?;
+
+    Ok((layer_path, packages))
+}
+
+// Installs the app's dependencies, returning the new layer metadata with the installed packages.
+fn install_packages(
+    app_dir: &Path,
+    env: &Env,
+    python_version: &PythonVersion,
+    pyproject_toml: Option<&PyprojectToml>,
+    poetry_lock: &PoetryLock,
+    strict_mode: &StrictMode,
+    new_metadata: PoetryDependenciesLayerMetadata,
+) -> Result<PoetryDependenciesLayerMetadata, PoetryDependenciesLayerError> {
+    poetry_install(app_dir, env, python_version, &new_metadata.groups)?;
+    let packages = package_inventory::list_installed_packages(app_dir, env, &[])
+        .map_err(PoetryDependenciesLayerError::ListInstalledPackages)?;
+    Ok(PoetryDependenciesLayerMetadata {
+        hash_verification: Some(verify_package_hashes(
+            poetry_lock,
+            pyproject_toml,
+            &packages,
+            strict_mode,
+        )),
+        packages,
+        ..new_metadata
+    })
+}
+
+// The dependency groups to install, which are the main group, plus any additional groups configured
+// using the `poetry-groups` setting in `project.toml`.
+fn only_groups(groups: &[String]) -> Vec<String> {
+    let mut only_groups = vec!["main".to_string()];
+    for group in groups {
+        if !only_groups.contains(group) {
+            only_groups.push(group.clone());
+        }
+    }
+    only_groups
+}
+
+fn poetry_install(
+    app_dir: &Path,
+    env: &Env,
+    python_version: &PythonVersion,
+    groups: &[String],
+) -> Result<(), PoetryDependenciesLayerError> {
+    let groups = groups.join(",");
+    utils::log_running_command(&format!("poetry install --sync --only {groups}"));
+    utils::run_command_and_stream_output_with_retries(
         Command::new("poetry")
             .args([
                 "install",
                 // Compile Python bytecode up front to improve app boot times (pip does this by default).
                 "--compile",
                 "--only",
-                "main",
+                &groups,
+                "--no-interaction",
+                "--sync",
+            ])
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+        "poetry install",
+    )
+    .map_err(|error| {
+        PoetryDependenciesLayerError::PoetryInstallCommand(error, python_version.clone())
+    })
+}
+
+/// Resolves the app's dependencies without installing them, so that Poetry shows the package
+/// operations that would be performed. If the virtual environment was restored from the cache,
+/// these are the changes compared to the previous build.
+fn show_dependency_plan(
+    app_dir: &Path,
+    env: &Env,
+    python_version: &PythonVersion,
+    groups: &[String],
+) -> Result<(), PoetryDependenciesLayerError> {
+    let groups = groups.join(",");
+    utils::log_running_command(&format!("poetry install --dry-run --sync --only {groups}"));
+    utils::run_command_and_stream_output_with_retries(
+        Command::new("poetry")
+            .args([
+                "install",
+                "--dry-run",
+                "--only",
+                &groups,
                 "--no-interaction",
                 "--sync",
             ])
-            .current_dir(&context.app_dir)
+            .current_dir(app_dir)
             .env_clear()
-            .envs(&*env),
+            .envs(env),
+        "poetry install --dry-run",
     )
-    .map_err(PoetryDependenciesLayerError::PoetryInstallCommand)?;
+    .map_err(|error| {
+        PoetryDependenciesLayerError::PoetryDryRunCommand(error, python_version.clone())
+    })
+}
+
+// The direct dependencies are those declared in `pyproject.toml`. Packages without a source in
+// `poetry.lock` were installed from PyPI. The project itself is excluded, since it's not a dependency.
+fn generate_dependency_report(
+    poetry_lock: &PoetryLock,
+    pyproject_toml: Option<&PyprojectToml>,
+    packages: &[InstalledPackage],
+) -> DependencyReport {
+    let project_name = pyproject_toml
+        .and_then(PyprojectToml::project_name)
+        .map(utils::normalize_package_name);
+    let packages = packages
+        .iter()
+        .filter(|package| Some(utils::normalize_package_name(&package.name)) != project_name)
+        .cloned()
+        .collect::<Vec<_>>();
+    let sources = poetry_lock
+        .packages
+        .iter()
+        .map(|package| {
+            let source = package
+                .source
+                .as_ref()
+                .and_then(|source| source.url.as_deref())
+                .map_or_else(|| PYPI_URL.to_string(), redact_url_credentials);
+            (utils::normalize_package_name(&package.name), source)
+        })
+        .collect();
+    DependencyReport::new(
+        PackageManager::Poetry.name(),
+        &packages,
+        &pyproject_toml
+            .map(PyprojectToml::dependency_names)
+            .unwrap_or_default(),
+        &sources,
+    )
+}
+
+// Verifies the installed packages against the hashes in `poetry.lock`. Poetry installs the project
+// itself too, however, it isn't in the lockfile and so is excluded.
+fn verify_package_hashes(
+    poetry_lock: &PoetryLock,
+    pyproject_toml: Option<&PyprojectToml>,
+    packages: &[InstalledPackage],
+    strict_mode: &StrictMode,
+) -> HashVerification {
+    let verification = hash_verification::verify_installed_packages(
+        "poetry.lock",
+        &hash_verification::poetry_lock_packages(poetry_lock),
+        packages,
+        &pyproject_toml
+            .and_then(PyprojectToml::project_name)
+            .into_iter()
+            .collect::<Vec<_>>(),
+    );
+    hash_verification::log_hash_verification(&verification, strict_mode);
+    verification
+}
+
+// Uninstalls any packages in the cached venv whose source has changed since the previous build,
+// so that Poetry installs them again from the current source.
+fn uninstall_changed_packages(
+    venv_dir: &Path,
+    python_version: &PythonVersion,
+    pyproject_toml: Option<&PyprojectToml>,
+    cached_metadata: &PoetryDependenciesLayerMetadata,
+    new_metadata: &PoetryDependenciesLayerMetadata,
+) -> Result<(), PoetryDependenciesLayerError> {
+    let site_packages_dir = &venv_dir.join(format!(
+        "lib/python{}.{}/site-packages",
+        python_version.major, python_version.minor
+    ));
+    if cached_metadata.project_metadata_hash != new_metadata.project_metadata_hash {
+        uninstall_changed_project(site_packages_dir, pyproject_toml)?;
+    }
+    uninstall_changed_path_dependencies(
+        site_packages_dir,
+        &cached_metadata.path_dependency_hashes,
+        &new_metadata.path_dependency_hashes,
+    )
+}
+
+// Poetry installs the project itself in editable mode, which it only partially reinstalls on each
+// build. If the project's own metadata has changed, we uninstall it first, so that stale files
+// (such as the console scripts of removed entry points) don't persist in the cached venv.
+fn uninstall_changed_project(
+    site_packages_dir: &Path,
+    pyproject_toml: Option<&PyprojectToml>,
+) -> Result<(), PoetryDependenciesLayerError> {
+    let Some(project_name) = pyproject_toml.and_then(PyprojectToml::project_name) else {
+        return Ok(());
+    };
+    if uninstall_distribution(site_packages_dir, project_name)
+        .map_err(PoetryDependenciesLayerError::UninstallPackage)?
+    {
+        log_info(format!(
+            "Reinstalling {project_name}, since the project's metadata has changed"
+        ));
+    }
+    Ok(())
+}
+
+// A hash of the project's build requirements (from the `[build-system]` table in `pyproject.toml`),
+// for detecting when the build backend or its version changes, in which case the venv is recreated
+// rather than mixing files installed by both backends. The requirements are sorted so reordering
+// them doesn't invalidate the cache. Projects without build requirements have no hash, so that
+// caches created by older buildpack versions (which didn't record a hash) are still reused.
+fn build_system_requires_hash(pyproject_toml: Option<&PyprojectToml>) -> Option<String> {
+    let mut requires = pyproject_toml?
+        .build_system
+        .requires
+        .iter()
+        .map(|requirement| requirement.split_whitespace().collect::<String>())
+        .collect::<Vec<_>>();
+    if requires.is_empty() {
+        return None;
+    }
+    requires.sort();
+    let mut hasher = StableHasher::new();
+    hasher.update_all(&requires);
+    Some(hasher.hex_digest())
+}
+
+// Poetry only reinstalls a path dependency if its version changes, so changes to the source of
+// a local package (such as a sibling package in a monorepo) would otherwise not be picked up when
+// using the cached venv.
+fn uninstall_changed_path_dependencies(
+    site_packages_dir: &Path,
+    cached_hashes: &BTreeMap<String, String>,
+    new_hashes: &BTreeMap<String, String>,
+) -> Result<(), PoetryDependenciesLayerError> {
+    for (name, hash) in new_hashes {
+        if cached_hashes
+            .get(name)
+            .is_some_and(|cached_hash| cached_hash != hash)
+            && uninstall_distribution(site_packages_dir, name)
+                .map_err(PoetryDependenciesLayerError::UninstallPackage)?
+        {
+            log_info(format!(
+                "Reinstalling {name}, since the path dependency's source has changed"
+            ));
+        }
+    }
+    Ok(())
+}
+
+// The files that contain the project's own packaging metadata (such as its entry points).
+const PROJECT_METADATA_FILES: [&str; 3] = ["pyproject.toml", "setup.cfg", "setup.py"];
+
+// A hash of the contents of the project's metadata files, for detecting when they change. Files
+// that don't exist are hashed differently to empty files.
+fn project_metadata_hash(app_dir: &Path) -> io::Result<String> {
+    let mut hasher = StableHasher::new();
+    for file in PROJECT_METADATA_FILES {
+        match utils::read_optional_file(&app_dir.join(file))? {
+            Some(contents) => hasher.update_all(&[file, &contents]),
+            None => hasher.update_all::<&str>(&[]),
+        }
+    }
+    Ok(hasher.hex_digest())
+}
+
+// Names of files and directories within path dependencies that don't affect the installed
+// package, and that may be modified without the package's source having changed.
+const PATH_DEPENDENCY_IGNORED_NAMES: [&str; 3] = [".git", ".venv", "__pycache__"];
+
+// Hashes of the contents of each local path dependency, keyed by package name, for detecting
+// when their source changes. Path dependencies that don't exist are omitted, since Poetry will
+// report a clearer error for them than we could here.
+fn path_dependency_hashes(
+    app_dir: &Path,
+    poetry_lock: &PoetryLock,
+) -> io::Result<BTreeMap<String, String>> {
+    let mut dependency_hashes = BTreeMap::new();
+    for (name, path) in poetry_lock.path_dependencies() {
+        let mut context = digest::Context::new(&digest::SHA256);
+        match hash_path_contents(&app_dir.join(path), &mut context) {
+            Ok(()) => {
+                dependency_hashes.insert(
+                    name.to_string(),
+                    utils::hex_encode(context.finish().as_ref()),
+                );
+            }
+            Err(io_error) if io_error.kind() == io::ErrorKind::NotFound => {}
+            Err(io_error) => return Err(io_error),
+        }
+    }
+    Ok(dependency_hashes)
+}
 
-    Ok(layer_path)
+// Hashes the file, or the relative paths and contents of all files in the directory. Symlinks
+// aren't followed (only their target is hashed), to prevent issues with cycles.
+fn hash_path_contents(path: &Path, context: &mut digest::Context) -> io::Result<()> {
+    let file_type = fs::symlink_metadata(path)?.file_type();
+    if file_type.is_symlink() {
+        update_tagged(
+            context,
+            b"L",
+            fs::read_link(path)?.as_os_str().as_encoded_bytes(),
+        );
+    } else if file_type.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        update_tagged(context, b"D", &[]);
+        for file_name in entries {
+            if PATH_DEPENDENCY_IGNORED_NAMES
+                .iter()
+                .any(|ignored_name| file_name == *ignored_name)
+            {
+                continue;
+            }
+            update_tagged(context, b"N", file_name.as_encoded_bytes());
+            hash_path_contents(&path.join(file_name), context)?;
+        }
+        update_tagged(context, b"E", &[]);
+    } else {
+        update_tagged(context, b"F", &fs::read(path)?);
+    }
+    Ok(())
+}
+
+// Adds a value to the hash, prefixed with a type tag and its length, so that (for example)
+// renaming a file, or moving bytes between adjacent files, changes the hash.
+fn update_tagged(context: &mut digest::Context, tag: &[u8], value: &[u8]) {
+    context.update(tag);
+    context.update(&(value.len() as u64).to_le_bytes());
+    context.update(value);
+}
+
+/// Uninstalls a distribution from the venv, by removing the files listed in the `RECORD`
+/// file of its `.dist-info` directory. Returns whether the distribution was installed.
+// https://packaging.python.org/en/latest/specifications/recording-installed-packages/
+fn uninstall_distribution(site_packages_dir: &Path, name: &str) -> io::Result<bool> {
+    let normalized_name = utils::normalize_package_name(name);
+    let mut dist_info_dir = None;
+    for entry in utils::read_optional_dir(site_packages_dir)?
+        .into_iter()
+        .flatten()
+    {
+        let path = entry?.path();
+        let is_matching_dist_info = path.extension().is_some_and(|ext| ext == "dist-info")
+            && path.file_stem().is_some_and(|stem| {
+                let stem = stem.to_string_lossy();
+                let name = stem.split_once('-').map_or(stem.as_ref(), |(name, _)| name);
+                utils::normalize_package_name(name) == normalized_name
+            });
+        if is_matching_dist_info {
+            dist_info_dir = Some(path);
+            break;
+        }
+    }
+    let Some(dist_info_dir) = dist_info_dir else {
+        return Ok(false);
+    };
+
+    let record = fs::read_to_string(dist_info_dir.join("RECORD"))?;
+    for path in record_paths(&record) {
+        match fs::remove_file(site_packages_dir.join(path)) {
+            Err(io_error) if io_error.kind() != io::ErrorKind::NotFound => return Err(io_error),
+            _ => {}
+        }
+    }
+    fs::remove_dir_all(dist_info_dir)?;
+    Ok(true)
+}
+
+// The paths in a `RECORD` file, which is a CSV file whose first column is the path relative
+// to the `site-packages` directory (paths containing commas are quoted).
+fn record_paths(record: &str) -> Vec<&str> {
+    record
+        .lines()
+        .filter_map(|line| {
+            let path = match line.strip_prefix('"') {
+                Some(quoted) => quoted.split_once('"')?.0,
+                None => line.split(',').next()?,
+            };
+            (!path.is_empty()).then_some(path)
+        })
+        .collect()
+}
+
+/// Checks for known problems with the project's Poetry configuration, that would otherwise
+/// result in unclear error messages when Poetry is run.
+/// Returns the parsed `poetry.lock` file for use by later steps.
+fn check_project(
+    app_dir: &Path,
+    env: &Env,
+    pyproject_toml: Option<&PyprojectToml>,
+    strict_mode: &StrictMode,
+) -> Result<PoetryLock, PoetryDependenciesLayerError> {
+    // The poetry-dynamic-versioning plugin determines the package version from the Git metadata
+    // in the `.git/` directory, however, some platforms strip this directory from the app source.
+    // Without this check, the build would fail later with a confusing version resolution error.
+    // The plugin's bypass env var allows setting a fixed version, in which case Git isn't needed.
+    if pyproject_toml.is_some_and(PyprojectToml::uses_poetry_dynamic_versioning)
+        && !env.contains_key("POETRY_DYNAMIC_VERSIONING_BYPASS")
+        && !app_dir
+            .join(".git")
+            .try_exists()
+            .map_err(PoetryDependenciesLayerError::CheckGitDirectoryExists)?
+    {
+        return Err(PoetryDependenciesLayerError::DynamicVersioningGitDirectoryMissing);
+    }
+
+    // Poetry's own error message for a missing path dependency is shown deep in the install output
+    // and doesn't explain why the directory is missing, so we check for this case up front.
+    let poetry_lock = poetry_lock::read_poetry_lock(app_dir)
+        .map_err(PoetryDependenciesLayerError::ReadPoetryLock)?;
+    let external_path_dependencies = poetry_lock.path_dependencies_outside_app_dir(app_dir);
+    if !external_path_dependencies.is_empty() {
+        return Err(PoetryDependenciesLayerError::PathDependenciesOutsideAppDir(
+            external_path_dependencies,
+        ));
+    }
+
+    // Newer Poetry versions can write lockfiles that older Poetry cannot read, which results in
+    // an unhelpful parse error that doesn't mention the version mismatch, so we warn up front.
+    if let Some(lock_version) =
+        poetry_lock.lock_version_if_newer_than(POETRY_MAX_SUPPORTED_LOCK_VERSION)
+    {
+        strict_mode.log_warning(
+            WarningClass::NewerPoetryLockfile,
+            "Lockfile generated by a newer version of Poetry",
+            formatdoc! {"
+                Your 'poetry.lock' file uses lockfile format version {lock_version}, however,
+                the version of Poetry used by this buildpack (Poetry {POETRY_VERSION}) only
+                supports lockfile format versions up to {POETRY_MAX_SUPPORTED_LOCK_VERSION}.
+
+                This usually means the lockfile was generated using a newer version of
+                Poetry than the one used by this buildpack.
+
+                If the dependency installation fails, either regenerate the lockfile
+                using Poetry {POETRY_VERSION} (by running 'poetry lock'), or switch to a
+                newer version of this buildpack that supports your Poetry version.
+            "},
+        );
+    }
+
+    Ok(poetry_lock)
 }
 
-#[derive(Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 struct PoetryDependenciesLayerMetadata {
     arch: String,
@@ -136,13 +623,126 @@ struct PoetryDependenciesLayerMetadata {
     distro_version: String,
     python_version: String,
     poetry_version: String,
+    // Defaulted so that layer metadata from older buildpack versions can still be read.
+    #[serde(default)]
+    build_system_requires_hash: Option<String>,
+    #[serde(default)]
+    packages: Vec<InstalledPackage>,
+    #[serde(default)]
+    project_metadata_hash: Option<String>,
+    #[serde(default)]
+    path_dependency_hashes: BTreeMap<String, String>,
+    #[serde(default)]
+    hash_verification: Option<HashVerification>,
+    #[serde(default)]
+    lock_content_hash: Option<String>,
+    #[serde(default)]
+    groups: Vec<String>,
+}
+
+impl PoetryDependenciesLayerMetadata {
+    fn new(
+        context: &BuildContext<PythonBuildpack>,
+        python_version: &PythonVersion,
+        pyproject_toml: Option<&PyprojectToml>,
+        poetry_lock: &PoetryLock,
+        groups: Vec<String>,
+    ) -> Result<Self, PoetryDependenciesLayerError> {
+        Ok(Self {
+            arch: context.target.arch.clone(),
+            distro_name: context.target.distro_name.clone(),
+            distro_version: context.target.distro_version.clone(),
+            python_version: python_version.to_string(),
+            poetry_version: POETRY_VERSION.to_string(),
+            build_system_requires_hash: build_system_requires_hash(pyproject_toml),
+            packages: Vec::new(),
+            project_metadata_hash: Some(
+                project_metadata_hash(&context.app_dir)
+                    .map_err(PoetryDependenciesLayerError::ReadProjectMetadataFiles)?,
+            ),
+            path_dependency_hashes: path_dependency_hashes(&context.app_dir, poetry_lock)
+                .map_err(PoetryDependenciesLayerError::ReadPathDependencies)?,
+            hash_verification: None,
+            lock_content_hash: poetry_lock.content_hash().map(ToString::to_string),
+            groups,
+        })
+    }
+
+    /// Whether a cached layer with this metadata can be reused for the new metadata.
+    fn is_cache_compatible(&self, new_metadata: &Self) -> bool {
+        // By destructuring here we ensure that any fields added to the metadata in the future
+        // have to be explicitly considered for cache invalidation. The installed packages (and the
+        // result of verifying them against the lockfile) are only recorded for reference, and so
+        // are intentionally not compared (any changes in dependencies are handled by Poetry when
+        // it syncs the cached venv with the lockfile).
+        // Changes to the project's own metadata or to the source of path dependencies only
+        // require reinstalling the affected packages, which is handled separately. Changes to
+        // the installed dependency groups are handled by Poetry when it syncs the venv.
+        let Self {
+            arch,
+            distro_name,
+            distro_version,
+            python_version,
+            poetry_version,
+            build_system_requires_hash,
+            packages: _,
+            project_metadata_hash: _,
+            path_dependency_hashes: _,
+            hash_verification: _,
+            lock_content_hash: _,
+            groups: _,
+        } = self;
+        (
+            arch,
+            distro_name,
+            distro_version,
+            python_version,
+            poetry_version,
+            build_system_requires_hash,
+        ) == (
+            &new_metadata.arch,
+            &new_metadata.distro_name,
+            &new_metadata.distro_version,
+            &new_metadata.python_version,
+            &new_metadata.poetry_version,
+            &new_metadata.build_system_requires_hash,
+        )
+    }
+
+    /// Whether the cached venv already contains exactly the packages that would be installed for
+    /// the new metadata, in which case the install can be skipped. Caches created by older
+    /// buildpack versions (or from lockfiles without a content hash) are never considered unchanged.
+    fn is_install_unchanged(&self, new_metadata: &Self) -> bool {
+        self.lock_content_hash.is_some()
+            && self.hash_verification.is_some()
+            && (
+                &self.lock_content_hash,
+                &self.project_metadata_hash,
+                &self.path_dependency_hashes,
+                &self.groups,
+            ) == (
+                &new_metadata.lock_content_hash,
+                &new_metadata.project_metadata_hash,
+                &new_metadata.path_dependency_hashes,
+                &new_metadata.groups,
+            )
+    }
 }
 
 /// Errors that can occur when installing the project's dependencies into a layer using Poetry.
 #[derive(Debug)]
 pub(crate) enum PoetryDependenciesLayerError {
+    CheckGitDirectoryExists(io::Error),
     CreateVenvCommand(StreamedCommandError),
-    PoetryInstallCommand(StreamedCommandError),
+    DynamicVersioningGitDirectoryMissing,
+    ListInstalledPackages(ListInstalledPackagesError),
+    PathDependenciesOutsideAppDir(Vec<(String, String)>),
+    PoetryDryRunCommand(RetriedCommandError, PythonVersion),
+    PoetryInstallCommand(RetriedCommandError, PythonVersion),
+    ReadPathDependencies(io::Error),
+    ReadPoetryLock(ReadPoetryLockError),
+    ReadProjectMetadataFiles(io::Error),
+    UninstallPackage(io::Error),
 }
 
 impl From<PoetryDependenciesLayerError> for libcnb::Error<BuildpackError> {
@@ -150,3 +750,165 @@ impl From<PoetryDependenciesLayerError> for libcnb::Error<BuildpackError> {
         Self::BuildpackError(BuildpackError::PoetryDependenciesLayer(error))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn record_paths_valid() {
+        assert_eq!(
+            record_paths(concat!(
+                "../../../bin/serve,sha256=abc,123\n",
+                "\"my,app.pth\",sha256=def,45\n",
+                "my_app-0.1.0.dist-info/RECORD,,\n",
+                "\n"
+            )),
+            [
+                "../../../bin/serve",
+                "my,app.pth",
+                "my_app-0.1.0.dist-info/RECORD"
+            ]
+        );
+    }
+
+    #[test]
+    fn project_metadata_hash_changes() {
+        let poetry_basic_hash = project_metadata_hash(Path::new("tests/fixtures/poetry_basic"));
+        assert_eq!(
+            poetry_basic_hash.as_ref().unwrap(),
+            &project_metadata_hash(Path::new("tests/fixtures/poetry_basic")).unwrap()
+        );
+        assert_ne!(
+            poetry_basic_hash.unwrap(),
+            project_metadata_hash(Path::new("tests/fixtures/empty")).unwrap()
+        );
+    }
+
+    #[test]
+    fn build_system_requires_hash_changes() {
+        let hash = |contents: &str| {
+            build_system_requires_hash(Some(&toml::from_str::<PyprojectToml>(contents).unwrap()))
+        };
+        let setuptools_hash = hash("[build-system]\nrequires = ['setuptools>=61', 'wheel']");
+        assert!(setuptools_hash.is_some());
+        assert_eq!(
+            setuptools_hash,
+            hash("[build-system]\nrequires = ['wheel', 'setuptools >= 61']")
+        );
+        assert_ne!(
+            setuptools_hash,
+            hash("[build-system]\nrequires = ['setuptools>=75', 'wheel']")
+        );
+        assert_ne!(
+            setuptools_hash,
+            hash("[build-system]\nrequires = ['hatchling']")
+        );
+        assert_eq!(hash("[build-system]\nrequires = []"), None);
+        assert_eq!(hash(""), None);
+        assert_eq!(build_system_requires_hash(None), None);
+    }
+
+    #[test]
+    fn only_groups_includes_main() {
+        assert_eq!(only_groups(&[]), ["main"]);
+        assert_eq!(
+            only_groups(&["web".to_string(), "main".to_string(), "web".to_string()]),
+            ["main", "web"]
+        );
+    }
+
+    #[test]
+    fn is_install_unchanged() {
+        let metadata = |lock_content_hash: Option<&str>, project_metadata_hash: &str| {
+            PoetryDependenciesLayerMetadata {
+                arch: "amd64".to_string(),
+                distro_name: "ubuntu".to_string(),
+                distro_version: "24.04".to_string(),
+                python_version: "3.13.0".to_string(),
+                poetry_version: POETRY_VERSION.to_string(),
+                build_system_requires_hash: None,
+                packages: Vec::new(),
+                project_metadata_hash: Some(project_metadata_hash.to_string()),
+                path_dependency_hashes: BTreeMap::new(),
+                hash_verification: Some(HashVerification {
+                    lockfile: "poetry.lock".to_string(),
+                    verified_packages: 0,
+                    unverified_packages: Vec::new(),
+                    mismatched_packages: Vec::new(),
+                }),
+                lock_content_hash: lock_content_hash.map(ToString::to_string),
+                groups: vec!["main".to_string()],
+            }
+        };
+        let cached_metadata = metadata(Some("abc"), "123");
+        assert!(cached_metadata.is_install_unchanged(&metadata(Some("abc"), "123")));
+        assert!(!cached_metadata.is_install_unchanged(&metadata(Some("def"), "123")));
+        assert!(!cached_metadata.is_install_unchanged(&metadata(Some("abc"), "456")));
+        assert!(!cached_metadata.is_install_unchanged(&metadata(None, "123")));
+        assert!(!metadata(None, "123").is_install_unchanged(&metadata(None, "123")));
+        assert!(
+            !cached_metadata.is_install_unchanged(&PoetryDependenciesLayerMetadata {
+                groups: only_groups(&["web".to_string()]),
+                ..metadata(Some("abc"), "123")
+            })
+        );
+        assert!(!PoetryDependenciesLayerMetadata {
+            hash_verification: None,
+            ..metadata(Some("abc"), "123")
+        }
+        .is_install_unchanged(&metadata(Some("abc"), "123")));
+    }
+
+    #[test]
+    fn uninstall_distribution_not_installed() {
+        assert!(!uninstall_distribution(Path::new("tests/fixtures/empty"), "my-app").unwrap());
+        assert!(
+            !uninstall_distribution(Path::new("tests/fixtures/non-existent-dir"), "my-app")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn path_dependency_hashes_changes() {
+        let poetry_lock: PoetryLock = toml::from_str(indoc! {r#"
+            [[package]]
+            name = "dir-a"
+            [package.source]
+            type = "directory"
+            url = "poetry_basic"
+
+            [[package]]
+            name = "dir-b"
+            [package.source]
+            type = "directory"
+            url = "pip_basic"
+
+            [[package]]
+            name = "file"
+            [package.source]
+            type = "file"
+            url = "poetry_basic/poetry.lock"
+
+            [[package]]
+            name = "missing"
+            [package.source]
+            type = "directory"
+            url = "non-existent-dir"
+        "#})
+        .unwrap();
+
+        let hashes = path_dependency_hashes(Path::new("tests/fixtures"), &poetry_lock).unwrap();
+        assert_eq!(
+            hashes.keys().collect::<Vec<_>>(),
+            ["dir-a", "dir-b", "file"]
+        );
+        assert_ne!(hashes["dir-a"], hashes["dir-b"]);
+        assert_ne!(hashes["dir-a"], hashes["file"]);
+        assert_eq!(
+            hashes,
+            path_dependency_hashes(Path::new("tests/fixtures"), &poetry_lock).unwrap()
+        );
+    }
+}
@@ -1,39 +1,83 @@
+use crate::build_log::log_info;
+use crate::python_inventory::python_inventory;
 use crate::python_version::PythonVersion;
 use crate::utils::{self, DownloadUnpackArchiveError};
-use crate::{BuildpackError, PythonBuildpack};
+use crate::{telemetry, BuildpackError, PythonBuildpack};
 use libcnb::build::BuildContext;
 use libcnb::data::layer_name;
 use libcnb::layer::{
     CachedLayerDefinition, EmptyLayerCause, InvalidMetadataAction, LayerState, RestoredLayerAction,
 };
 use libcnb::layer_env::{LayerEnv, ModificationBehavior, Scope};
-use libcnb::Env;
-use libherokubuildpack::log::log_info;
+use libcnb::{Env, Target};
 use serde::{Deserialize, Serialize};
+use std::num::NonZeroUsize;
+use std::os::unix::fs::symlink;
 use std::path::{Path, PathBuf};
+use std::{fs, io, thread};
+
+/// The env var that can be set to download the Python runtime archives from a mirror (such as an
+/// internal artifact repository), instead of from the buildpack's S3 bucket. The archives must
+/// use the same file names as those in the bucket.
+pub(crate) const RUNTIME_MIRROR_URL_ENV_VAR: &str = "PYTHON_RUNTIME_MIRROR_URL";
+
+/// The env var containing the value of the `Authorization` header to send when downloading from
+/// the mirror, for mirrors that require authentication. It's never sent to the default location.
+pub(crate) const RUNTIME_MIRROR_AUTHORIZATION_ENV_VAR: &str = "PYTHON_RUNTIME_MIRROR_AUTHORIZATION";
 
 /// Creates a layer containing the Python runtime.
+///
+/// Files that are only needed when building packages (such as the Python headers) are moved to
+/// a separate build-only layer, so that they aren't included in the app image. Symlinks to their
+/// new location are left in the Python layer, so that the build tools can still find them.
+/// If `slim_stdlib` is enabled, parts of the standard library that apps don't use at run-time
+/// (such as its test suite) are also moved to the build-only layer.
 pub(crate) fn install_python(
     context: &BuildContext<PythonBuildpack>,
     env: &mut Env,
     python_version: &PythonVersion,
+    fault_handler: bool,
+    slim_stdlib: bool,
 ) -> Result<PathBuf, libcnb::Error<BuildpackError>> {
     let new_metadata = PythonLayerMetadata {
         arch: context.target.arch.clone(),
         distro_name: context.target.distro_name.clone(),
         distro_version: context.target.distro_version.clone(),
         python_version: python_version.to_string(),
+        slim_stdlib: Some(slim_stdlib),
     };
 
+    let build_only_layer = context.cached_layer(
+        layer_name!("python-build-only"),
+        CachedLayerDefinition {
+            build: true,
+            launch: false,
+            invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
+            restored_layer_action: &|cached_metadata: &PythonLayerMetadata, _| {
+                if cache_invalidation_reasons(cached_metadata, &new_metadata).is_empty() {
+                    RestoredLayerAction::KeepLayer
+                } else {
+                    RestoredLayerAction::DeleteLayer
+                }
+            },
+        },
+    )?;
+    let build_only_files_restored = matches!(build_only_layer.state, LayerState::Restored { .. });
+
     let layer = context.cached_layer(
         layer_name!("python"),
         CachedLayerDefinition {
             build: true,
             launch: true,
             invalid_metadata_action: &|_| InvalidMetadataAction::DeleteLayer,
-            restored_layer_action: &|cached_metadata: &PythonLayerMetadata, _| {
+            restored_layer_action: &|cached_metadata: &PythonLayerMetadata, layer_path| {
                 let cached_python_version = cached_metadata.python_version.clone();
-                let reasons = cache_invalidation_reasons(cached_metadata, &new_metadata);
+                let mut reasons = cache_invalidation_reasons(cached_metadata, &new_metadata);
+                // The build-only layer is created from the contents of the Python layer, so if
+                // it wasn't restored, the symlinks to it in the cached Python layer are broken.
+                if !build_only_files_restored && layer_path.join("include").is_symlink() {
+                    reasons.push("The cached build-only files are missing".to_string());
+                }
                 if reasons.is_empty() {
                     Ok((
                         RestoredLayerAction::KeepLayer,
@@ -48,6 +92,7 @@ pub(crate) fn install_python(
             },
         },
     )?;
+    telemetry::record_layer_cache("python", &layer.state);
     let layer_path = layer.path();
 
     match layer.state {
@@ -76,24 +121,25 @@ pub(crate) fn install_python(
                 EmptyLayerCause::NewlyCreated => {}
             }
             log_info(format!("Installing Python {python_version}"));
-            let archive_url = python_version.url(&context.target);
-            utils::download_and_unpack_zstd_archive(&archive_url, &layer_path).map_err(
-                |error| match error {
-                    // TODO: Remove this once the Python version is validated against a manifest (at
-                    // which point 404s can be treated as an internal error, instead of user error)
-                    DownloadUnpackArchiveError::Request(ureq::Error::Status(404, _)) => {
-                        PythonLayerError::PythonArchiveNotFound {
-                            python_version: python_version.clone(),
-                        }
-                    }
-                    other_error => PythonLayerError::DownloadUnpackPythonArchive(other_error),
-                },
-            )?;
-            layer.write_metadata(new_metadata)?;
+            download_python(context, env, python_version, &layer_path)?;
         }
     }
 
-    let mut layer_env = generate_layer_env(&layer_path, python_version);
+    move_build_only_files(
+        &layer_path,
+        &build_only_layer.path(),
+        python_version,
+        slim_stdlib,
+    )
+    .map_err(PythonLayerError::MoveBuildOnlyFiles)?;
+    // The metadata is also written for restored layers, so that the `slim_stdlib` field is
+    // recorded for layers created by older buildpack versions.
+    layer.write_metadata(new_metadata.clone())?;
+    build_only_layer.write_metadata(new_metadata)?;
+
+    // Any errors are treated as there being a single CPU, since that's the build tools' default.
+    let build_jobs = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let mut layer_env = generate_layer_env(&layer_path, python_version, build_jobs, fault_handler);
     layer.write_env(layer_env)?;
     // Required to pick up the automatic env vars such as PATH. See: https://github.com/heroku/libcnb.rs/issues/842
     layer_env = layer.read_env()?;
@@ -102,6 +148,179 @@ pub(crate) fn install_python(
     Ok(layer_path)
 }
 
+pub(crate) fn download_python(
+    context: &BuildContext<PythonBuildpack>,
+    env: &Env,
+    python_version: &PythonVersion,
+    layer_path: &Path,
+) -> Result<(), PythonLayerError> {
+    // Some Python versions aren't built for all targets (for example, EOL Python versions aren't
+    // built for newer distros), so we check the inventory before attempting the download.
+    let inventory = python_inventory();
+    inventory
+        .latest_release(python_version.major, python_version.minor)
+        .filter(|release| release.is_available_for(&context.target))
+        .ok_or_else(|| PythonLayerError::PythonArchiveNotFound {
+            python_version: python_version.clone(),
+        })?;
+    // The archive is verified if the inventory lists a checksum for it. Checksums are recorded by
+    // the `update_inventory` maintenance tool, so may not yet be listed for every archive.
+    let expected_sha256 = inventory.archive_sha256(&python_version.archive_name(&context.target));
+
+    let mirror_url = non_empty_env_var(env, RUNTIME_MIRROR_URL_ENV_VAR);
+    let archive_url = archive_url(python_version, &context.target, mirror_url.as_deref());
+    // The credentials are only for the mirror, so must never be sent to the default location.
+    let authorization = mirror_url
+        .as_ref()
+        .and_then(|_| non_empty_env_var(env, RUNTIME_MIRROR_AUTHORIZATION_ENV_VAR));
+
+    utils::download_and_unpack_zstd_archive(
+        &archive_url,
+        authorization.as_deref(),
+        expected_sha256,
+        layer_path,
+    )
+    .map_err(|error| match error {
+        // The inventory only lists the latest patch release of each Python version, so an
+        // older patch release requested explicitly may not have been built for this target.
+        DownloadUnpackArchiveError::Request(ureq::Error::Status(404, _)) => {
+            PythonLayerError::PythonArchiveNotFound {
+                python_version: python_version.clone(),
+            }
+        }
+        DownloadUnpackArchiveError::Request(ureq::Error::Status(status @ (401 | 403), _))
+            if mirror_url.is_some() =>
+        {
+            PythonLayerError::RuntimeMirrorAccessDenied(status)
+        }
+        other_error => PythonLayerError::DownloadUnpackPythonArchive(other_error),
+    })
+}
+
+/// Returns the URL of the Python runtime archive, which is downloaded from the mirror if one
+/// has been configured.
+fn archive_url(
+    python_version: &PythonVersion,
+    target: &Target,
+    mirror_url: Option<&str>,
+) -> String {
+    match mirror_url {
+        Some(mirror_url) => format!(
+            "{}/{}",
+            mirror_url.trim_end_matches('/'),
+            python_version.archive_name(target)
+        ),
+        None => python_version.url(target),
+    }
+}
+
+fn non_empty_env_var(env: &Env, name: &str) -> Option<String> {
+    env.get_string_lossy(name)
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Moves the files in the Python layer that are only needed at build time to the build-only
+/// layer, replacing them with symlinks to their new location.
+///
+/// Files that have already been moved (such as those in a cached Python layer) are skipped.
+/// At run-time, the symlinks are dangling, since the build-only layer isn't exported.
+fn move_build_only_files(
+    python_layer_path: &Path,
+    build_only_layer_path: &Path,
+    python_version: &PythonVersion,
+    slim_stdlib: bool,
+) -> io::Result<()> {
+    for relative_path in build_only_paths(python_layer_path, python_version, slim_stdlib)? {
+        let source_path = python_layer_path.join(&relative_path);
+        if source_path.is_symlink() {
+            continue;
+        }
+        // A subdirectory is used so that lifecycle doesn't add the layer's `include/` and `lib/`
+        // directories to the env, since the build tools find these files via the symlinks.
+        let destination_path = build_only_layer_path.join("files").join(&relative_path);
+        // The build-only layer may contain files from a previous install of the same Python
+        // version, if the Python layer was discarded (for example, due to invalid metadata).
+        match fs::symlink_metadata(&destination_path) {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&destination_path)?,
+            Ok(_) => fs::remove_file(&destination_path)?,
+            Err(io_error) if io_error.kind() == io::ErrorKind::NotFound => {}
+            Err(io_error) => return Err(io_error),
+        }
+        if let Some(parent_dir) = destination_path.parent() {
+            fs::create_dir_all(parent_dir)?;
+        }
+        fs::rename(&source_path, &destination_path)?;
+        symlink(&destination_path, &source_path)?;
+    }
+    Ok(())
+}
+
+// The standard library packages that are excluded from the app image when `slim_stdlib` is enabled.
+const SLIM_STDLIB_PACKAGES: [&str; 4] = ["ensurepip", "idlelib", "test", "tkinter"];
+
+// The paths (relative to the Python layer) of files that are only needed when building packages
+// from source or when bootstrapping pip, and so can be excluded from the app image:
+// - The Python headers (`include/`).
+// - The pkg-config files (`lib/pkgconfig/`).
+// - Any static libraries (`*.a`), in `lib/` or the `config-X.Y-*` directory used by `python-config`.
+// - The pip and setuptools wheels bundled with `ensurepip` (which the pip and Poetry layers use
+//   to bootstrap the chosen pip version).
+//
+// If `slim_stdlib` is enabled, the following parts of the standard library are also included,
+// since they aren't used by apps at run-time, but together make up a large part of its size:
+// - The standard library's own test suite (the `test` package).
+// - The IDLE editor (`idlelib`), and Tk GUI toolkit bindings (`tkinter`).
+// - The `ensurepip` package as a whole (which replaces the entry for its bundled wheels above).
+fn build_only_paths(
+    python_layer_path: &Path,
+    python_version: &PythonVersion,
+    slim_stdlib: bool,
+) -> io::Result<Vec<PathBuf>> {
+    let stdlib_dir = PathBuf::from(format!(
+        "lib/python{}.{}",
+        python_version.major, python_version.minor
+    ));
+    let mut paths = vec![PathBuf::from("include"), PathBuf::from("lib/pkgconfig")];
+    if slim_stdlib {
+        paths.extend(
+            SLIM_STDLIB_PACKAGES
+                .iter()
+                .map(|package| stdlib_dir.join(package)),
+        );
+    } else {
+        paths.push(stdlib_dir.join("ensurepip/_bundled"));
+    }
+
+    let mut static_library_dirs = vec![PathBuf::from("lib")];
+    for entry in utils::read_optional_dir(&python_layer_path.join(&stdlib_dir))?
+        .into_iter()
+        .flatten()
+    {
+        let file_name = entry?.file_name();
+        if file_name.to_string_lossy().starts_with("config-") {
+            static_library_dirs.push(stdlib_dir.join(file_name));
+        }
+    }
+    for dir in static_library_dirs {
+        for entry in utils::read_optional_dir(&python_layer_path.join(&dir))?
+            .into_iter()
+            .flatten()
+        {
+            let file_name = entry?.file_name();
+            if Path::new(&file_name)
+                .extension()
+                .is_some_and(|ext| ext == "a")
+            {
+                paths.push(dir.join(file_name));
+            }
+        }
+    }
+
+    paths.retain(|path| python_layer_path.join(path).exists());
+    Ok(paths)
+}
+
 #[derive(Clone, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 struct PythonLayerMetadata {
@@ -109,6 +328,10 @@ struct PythonLayerMetadata {
     distro_name: String,
     distro_version: String,
     python_version: String,
+    // Optional so that layer metadata from older buildpack versions (whose layers contain the
+    // whole standard library) can still be read.
+    #[serde(default)]
+    slim_stdlib: Option<bool>,
 }
 
 /// Compare cached layer metadata to the new layer metadata to determine if the cache should be
@@ -127,6 +350,7 @@ fn cache_invalidation_reasons(
         distro_name: cached_distro_name,
         distro_version: cached_distro_version,
         python_version: cached_python_version,
+        slim_stdlib: cached_slim_stdlib,
     } = cached_metadata;
 
     let PythonLayerMetadata {
@@ -134,6 +358,7 @@ fn cache_invalidation_reasons(
         distro_name,
         distro_version,
         python_version,
+        slim_stdlib,
     } = new_metadata;
 
     let mut reasons = Vec::new();
@@ -156,11 +381,23 @@ fn cache_invalidation_reasons(
         ));
     }
 
+    // Layers from older buildpack versions contain the whole standard library, from which the
+    // files can still be moved if slimming is now enabled. However, once moved they can't be
+    // restored from the cache if slimming is later disabled, so the layer has to be reinstalled.
+    if cached_slim_stdlib.is_some_and(|cached| Some(cached) != *slim_stdlib) {
+        reasons.push("The slim-stdlib setting has changed".to_string());
+    }
+
     reasons
 }
 
-fn generate_layer_env(layer_path: &Path, python_version: &PythonVersion) -> LayerEnv {
-    LayerEnv::new()
+fn generate_layer_env(
+    layer_path: &Path,
+    python_version: &PythonVersion,
+    build_jobs: usize,
+    fault_handler: bool,
+) -> LayerEnv {
+    let layer_env = LayerEnv::new()
         // We have to set `CPATH` explicitly, since:
         // - The automatic path set by lifecycle/libcnb is `<layer>/include/` whereas Python's
         //   headers are at `<layer>/include/pythonX.Y/` (compilers don't recursively search).
@@ -234,13 +471,54 @@ fn generate_layer_env(layer_path: &Path, python_version: &PythonVersion) -> Laye
             // https://github.com/buildpacks/lifecycle/blob/v0.20.1/archive/writer.go#L12
             "315532801",
         )
+        // By default, the build tools used when compiling packages from source (such as `make`,
+        // CMake and NumPy's build backend) only use a single CPU, making source builds of packages
+        // with native extensions (such as numpy, grpcio or pillow) much slower than necessary.
+        // These are set as defaults, so that users can override them (for example, to reduce
+        // memory usage) by setting the env vars themselves.
+        .chainable_insert(
+            Scope::Build,
+            ModificationBehavior::Default,
+            "MAKEFLAGS",
+            format!("-j{build_jobs}"),
+        )
+        .chainable_insert(
+            Scope::Build,
+            ModificationBehavior::Default,
+            "CMAKE_BUILD_PARALLEL_LEVEL",
+            build_jobs.to_string(),
+        )
+        .chainable_insert(
+            Scope::Build,
+            ModificationBehavior::Default,
+            "NPY_NUM_BUILD_JOBS",
+            build_jobs.to_string(),
+        );
+
+    // Makes Python output a traceback if the process crashes due to a fatal error (such as a
+    // segfault in a native extension), which otherwise exits without any indication why. This
+    // is opt-in since the handler also logs tracebacks for some signals (such as `SIGABRT`).
+    // It's set as a default, so that it can still be disabled at run-time.
+    // https://docs.python.org/3/library/faulthandler.html
+    if fault_handler {
+        layer_env.chainable_insert(
+            Scope::Launch,
+            ModificationBehavior::Default,
+            "PYTHONFAULTHANDLER",
+            "1",
+        )
+    } else {
+        layer_env
+    }
 }
 
 /// Errors that can occur when installing Python into a layer.
 #[derive(Debug)]
 pub(crate) enum PythonLayerError {
     DownloadUnpackPythonArchive(DownloadUnpackArchiveError),
+    MoveBuildOnlyFiles(io::Error),
     PythonArchiveNotFound { python_version: PythonVersion },
+    RuntimeMirrorAccessDenied(u16),
 }
 
 impl From<PythonLayerError> for libcnb::Error<BuildpackError> {
@@ -253,12 +531,37 @@ impl From<PythonLayerError> for libcnb::Error<BuildpackError> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn archive_url_mirror() {
+        let target = Target {
+            os: "linux".to_string(),
+            arch: "arm64".to_string(),
+            arch_variant: None,
+            distro_name: "ubuntu".to_string(),
+            distro_version: "24.04".to_string(),
+        };
+        let python_version = PythonVersion::new(3, 13, 1);
+        assert_eq!(
+            archive_url(&python_version, &target, None),
+            "https://heroku-buildpack-python.s3.us-east-1.amazonaws.com/python-3.13.1-ubuntu-24.04-arm64.tar.zst"
+        );
+        assert_eq!(
+            archive_url(
+                &python_version,
+                &target,
+                Some("https://artifacts.example.com/python/")
+            ),
+            "https://artifacts.example.com/python/python-3.13.1-ubuntu-24.04-arm64.tar.zst"
+        );
+    }
+
     fn example_layer_metadata() -> PythonLayerMetadata {
         PythonLayerMetadata {
             arch: "amd64".to_string(),
             distro_name: "ubuntu".to_string(),
             distro_version: "22.04".to_string(),
             python_version: "3.11.0".to_string(),
+            slim_stdlib: Some(true),
         }
     }
 
@@ -293,6 +596,7 @@ mod tests {
             distro_name: "debian".to_string(),
             distro_version: "12".to_string(),
             python_version: "3.11.1".to_string(),
+            slim_stdlib: Some(false),
         };
         assert_eq!(
             cache_invalidation_reasons(&cached_metadata, &new_metadata),
@@ -300,23 +604,46 @@ mod tests {
                 "The CPU architecture has changed from amd64 to arm64",
                 "The OS has changed from ubuntu-22.04 to debian-12",
                 "The Python version has changed from 3.11.0 to 3.11.1",
+                "The slim-stdlib setting has changed",
             ]
         );
     }
 
+    #[test]
+    fn cache_invalidation_reasons_slim_stdlib_unknown() {
+        let cached_metadata = PythonLayerMetadata {
+            slim_stdlib: None,
+            ..example_layer_metadata()
+        };
+        let new_metadata = example_layer_metadata();
+        assert_eq!(
+            cache_invalidation_reasons(&cached_metadata, &new_metadata),
+            Vec::<String>::new()
+        );
+    }
+
     #[test]
     fn python_layer_env() {
         let mut base_env = Env::new();
         base_env.insert("CPATH", "/base");
+        base_env.insert("MAKEFLAGS", "-j2");
         base_env.insert("PKG_CONFIG_PATH", "/base");
         base_env.insert("PYTHONUNBUFFERED", "this-should-be-overridden");
 
-        let layer_env = generate_layer_env(Path::new("/layer-dir"), &PythonVersion::new(3, 11, 1));
+        let layer_env = generate_layer_env(
+            Path::new("/layer-dir"),
+            &PythonVersion::new(3, 11, 1),
+            4,
+            false,
+        );
 
         assert_eq!(
             utils::environment_as_sorted_vector(&layer_env.apply(Scope::Build, &base_env)),
             [
+                ("CMAKE_BUILD_PARALLEL_LEVEL", "4"),
                 ("CPATH", "/layer-dir/include/python3.11:/base"),
+                ("MAKEFLAGS", "-j2"),
+                ("NPY_NUM_BUILD_JOBS", "4"),
                 ("PKG_CONFIG_PATH", "/layer-dir/lib/pkgconfig:/base"),
                 ("PYTHONUNBUFFERED", "1"),
                 ("SOURCE_DATE_EPOCH", "315532801"),
@@ -326,9 +653,38 @@ mod tests {
             utils::environment_as_sorted_vector(&layer_env.apply(Scope::Launch, &base_env)),
             [
                 ("CPATH", "/base"),
+                ("MAKEFLAGS", "-j2"),
                 ("PKG_CONFIG_PATH", "/base"),
                 ("PYTHONUNBUFFERED", "1"),
             ]
         );
     }
+
+    #[test]
+    fn python_layer_env_fault_handler() {
+        let layer_env = generate_layer_env(
+            Path::new("/layer-dir"),
+            &PythonVersion::new(3, 11, 1),
+            4,
+            true,
+        );
+        assert!(!layer_env
+            .apply(Scope::Build, &Env::new())
+            .contains_key("PYTHONFAULTHANDLER"));
+        assert_eq!(
+            layer_env
+                .apply(Scope::Launch, &Env::new())
+                .get_string_lossy("PYTHONFAULTHANDLER"),
+            Some("1".to_string())
+        );
+
+        let mut base_env = Env::new();
+        base_env.insert("PYTHONFAULTHANDLER", "");
+        assert_eq!(
+            layer_env
+                .apply(Scope::Launch, &base_env)
+                .get_string_lossy("PYTHONFAULTHANDLER"),
+            Some(String::new())
+        );
+    }
 }
@@ -0,0 +1,126 @@
+use crate::{BuildpackError, PythonBuildpack};
+use libcnb::build::BuildContext;
+use libcnb::data::layer_name;
+use libcnb::layer::UncachedLayerDefinition;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// The name of the wrapper script, which is on `PATH` at run-time.
+const PYTHON_ENV_RUN_COMMAND: &str = "python-env-run";
+
+// The `PATH` used if it isn't set at all, matching that of the base images.
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Creates a launch layer containing a `python-env-run` wrapper script, which runs the command
+/// passed to it with the venv and the Python layer's env vars active.
+//
+// At run-time, the CNB launcher sets these env vars for processes, however, there are contexts
+// in which the launcher isn't used (or the env is otherwise reset), such as one-off commands run
+// using `docker exec`, cron jobs and release commands run by other tooling. The script allows
+// apps to guarantee the environment is correct, without having to hardcode the layer paths.
+//
+// The script only contains the paths of the layers, so this layer is cheap to regenerate
+// and doesn't need to be cached.
+pub(crate) fn install_python_env_run(
+    context: &BuildContext<PythonBuildpack>,
+    python_layer_path: &Path,
+    dependencies_layer_dir: &Path,
+    fault_handler: bool,
+) -> Result<(), libcnb::Error<BuildpackError>> {
+    let layer = context.uncached_layer(
+        layer_name!("python-env-run"),
+        UncachedLayerDefinition {
+            build: false,
+            launch: true,
+        },
+    )?;
+
+    let bin_dir = layer.path().join("bin");
+    let script_path = bin_dir.join(PYTHON_ENV_RUN_COMMAND);
+    fs::create_dir_all(&bin_dir)
+        .and_then(|()| {
+            fs::write(
+                &script_path,
+                generate_script(python_layer_path, dependencies_layer_dir, fault_handler),
+            )
+        })
+        .and_then(|()| fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755)))
+        .map_err(BuildpackError::PythonEnvRunLayer)?;
+
+    Ok(())
+}
+
+// The env vars set here must be kept in sync with the run-time env of the Python and venv layers.
+fn generate_script(
+    python_layer_path: &Path,
+    dependencies_layer_dir: &Path,
+    fault_handler: bool,
+) -> String {
+    let python_layer_path = python_layer_path.to_string_lossy();
+    let venv_path = dependencies_layer_dir.to_string_lossy();
+    let fault_handler = if fault_handler {
+        "export PYTHONFAULTHANDLER=\"${PYTHONFAULTHANDLER-1}\"\n"
+    } else {
+        ""
+    };
+    format!(
+        r#"#!/usr/bin/env bash
+# Generated by the Python buildpack.
+#
+# Runs the specified command with the app's Python environment active, for use in contexts
+# where the environment set by the CNB launcher isn't available (such as cron jobs).
+#
+# Usage: {PYTHON_ENV_RUN_COMMAND} <command> [<args>...]
+
+set -euo pipefail
+
+if [[ $# -eq 0 ]]; then
+  echo "Usage: {PYTHON_ENV_RUN_COMMAND} <command> [<args>...]" >&2
+  exit 64
+fi
+
+export PATH="{venv_path}/bin:{python_layer_path}/bin:${{PATH:-{DEFAULT_PATH}}}"
+export LD_LIBRARY_PATH="{venv_path}/lib:{python_layer_path}/lib${{LD_LIBRARY_PATH:+:${{LD_LIBRARY_PATH}}}}"
+export VIRTUAL_ENV="{venv_path}"
+export PYTHONUNBUFFERED=1
+{fault_handler}
+exec "$@"
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_script_exports_env() {
+        let script = generate_script(
+            Path::new("/layers/heroku_python/python"),
+            Path::new("/layers/heroku_python/venv"),
+            false,
+        );
+        assert!(script.starts_with("#!/usr/bin/env bash\n"));
+        assert!(script.contains(
+            r#"export PATH="/layers/heroku_python/venv/bin:/layers/heroku_python/python/bin:${PATH:-/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin}""#
+        ));
+        assert!(script.contains(
+            r#"export LD_LIBRARY_PATH="/layers/heroku_python/venv/lib:/layers/heroku_python/python/lib${LD_LIBRARY_PATH:+:${LD_LIBRARY_PATH}}""#
+        ));
+        assert!(script.contains(r#"export VIRTUAL_ENV="/layers/heroku_python/venv""#));
+        assert!(script.ends_with("\nexport PYTHONUNBUFFERED=1\n\nexec \"$@\"\n"));
+    }
+
+    #[test]
+    fn generate_script_fault_handler() {
+        let script = generate_script(
+            Path::new("/layers/heroku_python/python"),
+            Path::new("/layers/heroku_python/venv"),
+            true,
+        );
+        assert!(script.ends_with(
+            "\nexport PYTHONUNBUFFERED=1\nexport PYTHONFAULTHANDLER=\"${PYTHONFAULTHANDLER-1}\"\n\nexec \"$@\"\n"
+        ));
+    }
+}
@@ -1,34 +1,100 @@
+mod build_config;
+mod build_env_file;
+mod build_hooks;
+mod build_log;
 mod checks;
+mod dependency_plan;
 mod detect;
+mod diagnostics;
+mod disk_space;
 mod django;
 mod errors;
+mod gunicorn;
+mod hash_verification;
+mod import_check;
+mod install_failures;
 mod layers;
+mod package_index;
+mod package_inventory;
 mod package_manager;
+mod packaging_tool_install;
 mod packaging_tool_versions;
+mod pdm_lock;
+mod pip_extra_args;
+mod pipfile;
+mod poetry_lock;
+mod processes;
+mod procfile;
+mod project_files;
+mod project_root;
+mod project_toml;
+mod pyproject_toml;
+mod python_inventory;
+mod python_path;
 mod python_version;
 mod python_version_file;
+mod redact;
+mod resolve;
 mod runtime_txt;
+mod site_packages;
+mod source_version;
+mod strict_mode;
+mod system_libraries;
+mod telemetry;
 mod utils;
+mod version_check;
 
+use crate::build_env_file::BuildEnvFileError;
+use crate::build_hooks::{BuildHook, BuildHookError};
+use crate::build_log::{log_header, log_info, BuildLogError};
 use crate::checks::ChecksError;
-use crate::django::DjangoCollectstaticError;
+use crate::diagnostics::DiagnosticsError;
+use crate::django::{DjangoCollectstaticError, DjangoDeployCheckError, DjangoMigrateError};
+use crate::gunicorn::GunicornChecksError;
+use crate::import_check::ImportCheckError;
+use crate::layers::additional_python::{self, AdditionalPythonError};
+use crate::layers::assets::AssetsLayerError;
+use crate::layers::cached_packages::CachedPackagesLayerError;
+use crate::layers::dependency_report::DependencyReportLayerError;
+use crate::layers::nltk_data::{self, NltkDataLayerError};
+use crate::layers::pdm::PdmLayerError;
+use crate::layers::pdm_dependencies::PdmDependenciesLayerError;
 use crate::layers::pip::PipLayerError;
+use crate::layers::pip_cache::PipCacheLayerError;
 use crate::layers::pip_dependencies::PipDependenciesLayerError;
+use crate::layers::pipenv::PipenvLayerError;
+use crate::layers::pipenv_dependencies::PipenvDependenciesLayerError;
 use crate::layers::poetry::PoetryLayerError;
 use crate::layers::poetry_dependencies::PoetryDependenciesLayerError;
 use crate::layers::python::PythonLayerError;
-use crate::layers::{pip, pip_cache, pip_dependencies, poetry, poetry_dependencies, python};
+use crate::layers::{
+    assets, pdm, pdm_dependencies, pip, pip_cache, pip_dependencies, pipenv, pipenv_dependencies,
+    poetry, poetry_dependencies, python, python_env_run,
+};
+use crate::package_index::PackageIndexError;
+use crate::package_inventory::InstalledPackage;
 use crate::package_manager::{DeterminePackageManagerError, PackageManager};
+use crate::processes::ConfiguredProcessesError;
+use crate::project_root::ProjectRootError;
+use crate::project_toml::{PythonBuildpackConfig, ReadProjectTomlError};
+use crate::pyproject_toml::ReadPyprojectTomlError;
+use crate::python_path::PythonPathError;
 use crate::python_version::{
-    PythonVersionOrigin, RequestedPythonVersionError, ResolvePythonVersionError,
+    PythonVersion, PythonVersionOrigin, RequestedPythonVersion, RequestedPythonVersionError,
+    ResolvePythonVersionError, PYTHON_VERSION_ENV_VAR,
 };
-use indoc::formatdoc;
+use crate::strict_mode::{StrictMode, StrictModeError, WarningClass};
+use crate::utils::StreamedCommandError;
+use indoc::{formatdoc, indoc};
 use libcnb::build::{BuildContext, BuildResult, BuildResultBuilder};
+use libcnb::data::build_plan::BuildPlanBuilder;
+use libcnb::data::launch::Launch;
 use libcnb::detect::{DetectContext, DetectResult, DetectResultBuilder};
 use libcnb::generic::{GenericMetadata, GenericPlatform};
-use libcnb::{buildpack_main, Buildpack, Env};
-use libherokubuildpack::log::{log_header, log_info};
-use std::io;
+use libcnb::{Buildpack, Env};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, io, process};
 
 struct PythonBuildpack;
 
@@ -38,17 +104,46 @@ impl Buildpack for PythonBuildpack {
     type Error = BuildpackError;
 
     fn detect(&self, context: DetectContext<Self>) -> libcnb::Result<DetectResult, Self::Error> {
-        // In the future we will add support for requiring this buildpack through the build plan,
-        // but we first need a better understanding of real-world use-cases, so that we can work
-        // out how best to support them without sacrificing existing error handling UX (such as
-        // wanting to show a clear error when requirements.txt is missing).
-        if detect::is_python_project_directory(&context.app_dir)
+        // We always provide `python`, so that other buildpacks (such as a frontend buildpack that
+        // needs Python at build time) can require it via the build plan. However, we only require
+        // `python` ourselves if the app looks like a Python project, since otherwise the group
+        // would pass detection for every app. Apps that are missing a package manager file still
+        // pass detection in both cases, so that we can show a clear error during the build.
+        if detect::is_python_project(&context.app_dir, &Env::from_current())
             .map_err(BuildpackError::BuildpackDetection)?
         {
-            DetectResultBuilder::pass().build()
+            DetectResultBuilder::pass()
+                .build_plan(
+                    BuildPlanBuilder::new()
+                        .provides("python")
+                        .requires("python")
+                        .build(),
+                )
+                .build()
         } else {
-            log_info("No Python project files found (such as pyproject.toml, requirements.txt or poetry.lock).");
-            DetectResultBuilder::fail().build()
+            // Detection only fails if no later buildpack requires `python`, which isn't known
+            // at this point, so the message has to cover both cases.
+            log_info(indoc! {"
+                No Python project files found (such as pyproject.toml, requirements.txt or poetry.lock).
+                Python will only be installed if another buildpack requires it."
+            });
+            let problems = project_files::check_project_files(&context.app_dir)
+                .map_err(BuildpackError::BuildpackDetection)?;
+            if !problems.is_empty() {
+                log_info(format!(
+                    "If this is a Python app, check whether these are misnamed Python project files:\n{}",
+                    problems
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ));
+            }
+            // If no later buildpack requires `python`, then the lifecycle fails detection for
+            // this buildpack, since nothing uses what it provides.
+            DetectResultBuilder::pass()
+                .build_plan(BuildPlanBuilder::new().provides("python").build())
+                .build()
         }
     }
 
@@ -60,65 +155,121 @@ impl Buildpack for PythonBuildpack {
         // checks feature and making sure that buildpack env vars take precedence in layers envs.
         let mut env = Env::from_current();
 
+        start_build_reporting(&context, &env)?;
         checks::check_environment(&env).map_err(BuildpackError::Checks)?;
+        let mut strict_mode = StrictMode::from_env(&env).map_err(BuildpackError::StrictMode)?;
+
+        source_version::expose_source_version(&mut env, &context.layers_dir);
 
         // We perform all project analysis up front, so the build can fail early if the config is invalid.
-        // TODO: Add a "Build config" header and list all config in one place?
-        let package_manager = package_manager::determine_package_manager(&context.app_dir)
-            .map_err(BuildpackError::DeterminePackageManager)?;
-
-        log_header("Determining Python version");
-
-        let requested_python_version =
-            python_version::read_requested_python_version(&context.app_dir)
-                .map_err(BuildpackError::RequestedPythonVersion)?;
-        let python_version = python_version::resolve_python_version(&requested_python_version)
-            .map_err(BuildpackError::ResolvePythonVersion)?;
-
-        match requested_python_version.origin {
-            PythonVersionOrigin::BuildpackDefault => log_info(formatdoc! {"
-                No Python version specified, using the current default of Python {requested_python_version}.
-                We recommend setting an explicit version. In the root of your app create
-                a '.python-version' file, containing a Python version like '{requested_python_version}'."
-            }),
-            PythonVersionOrigin::PythonVersionFile => log_info(format!(
-                "Using Python version {requested_python_version} specified in .python-version"
-            )),
-            // TODO: Add a deprecation message for runtime.txt once .python-version support has been
-            // released for both the CNB and the classic buildpack.
-            PythonVersionOrigin::RuntimeTxt => log_info(format!(
-                "Using Python version {requested_python_version} specified in runtime.txt"
-            )),
-        }
+        let project_toml = project_toml::read_project_toml(&context.app_dir)
+            .map_err(BuildpackError::ReadProjectToml)?
+            .unwrap_or_default();
+        let python_config = project_toml.python_config();
+        let app_dir = context.app_dir.clone();
+        // The project's files are read (and its dependencies installed) relative to the context's
+        // app directory, so it's replaced by the project root, if a subdirectory was configured.
+        let context = BuildContext {
+            app_dir: prepare_build_environment(&app_dir, &mut env, python_config)?,
+            ..context
+        };
+        let package_manager =
+            package_manager::determine_package_manager(&context.app_dir, python_config)
+                .map_err(BuildpackError::DeterminePackageManager)?;
+        telemetry::record_package_manager(package_manager);
+        strict_mode.suppress(&python_config.suppressed_warnings);
+        version_check::check_buildpack_version(
+            &env,
+            &context.buildpack_descriptor.buildpack.version,
+            &strict_mode,
+        );
 
-        log_header("Installing Python");
-        let python_layer_path = python::install_python(&context, &mut env, &python_version)?;
+        check_app_source(
+            &context.app_dir,
+            package_manager,
+            python_config,
+            &strict_mode,
+        )?;
 
-        let dependencies_layer_dir = match package_manager {
-            PackageManager::Pip => {
-                log_header("Installing pip");
-                pip::install_pip(&context, &mut env, &python_version, &python_layer_path)?;
-                log_header("Installing dependencies using pip");
-                pip_cache::prepare_pip_cache(&context, &mut env, &python_version)?;
-                pip_dependencies::install_dependencies(&context, &mut env)?
-            }
-            PackageManager::Poetry => {
-                log_header("Installing Poetry");
-                poetry::install_poetry(&context, &mut env, &python_version, &python_layer_path)?;
-                log_header("Installing dependencies using Poetry");
-                poetry_dependencies::install_dependencies(&context, &mut env, &python_version)?
-            }
-        };
+        let (requested_python_version, python_version) =
+            determine_python_version(&context.app_dir, &env, python_config)?;
 
-        if django::is_django_installed(&dependencies_layer_dir)
-            .map_err(BuildpackError::DjangoDetection)?
-        {
-            log_header("Generating Django static files");
-            django::run_django_collectstatic(&context.app_dir, &env)
-                .map_err(BuildpackError::DjangoCollectstatic)?;
+        let additional_python_versions = additional_python::resolve_additional_python_versions(
+            &python_config.additional_python_versions,
+            &python_version,
+        )
+        .map_err(BuildpackError::AdditionalPython)?;
+
+        python_version::warn_if_eol(&python_version, &requested_python_version, &strict_mode)
+            .map_err(BuildpackError::StrictMode)?;
+
+        build_config::log_build_config(
+            &context,
+            &app_dir,
+            &env,
+            package_manager,
+            python_config,
+            &requested_python_version,
+            &python_version,
+        );
+
+        let python_layer_path = install_python(
+            &context,
+            &mut env,
+            &python_version,
+            &additional_python_versions,
+            python_config,
+        )?;
+
+        let (dependencies_layer_dir, installed_packages) = install_dependencies(
+            &context,
+            &mut env,
+            package_manager,
+            &python_version,
+            &python_layer_path,
+            python_config,
+            &strict_mode,
+        )?;
+        configure_run_environment(
+            &context,
+            &python_layer_path,
+            &dependencies_layer_dir,
+            &python_version,
+            python_config,
+        )?;
+
+        run_post_dependencies_steps(&app_dir, &context.app_dir, &env, python_config)?;
+
+        if let Some(assets_config) = &python_config.assets {
+            build_log::timed_section("Preparing assets", || {
+                assets::install_assets(&context, &mut env, assets_config)
+            })?;
         }
 
-        BuildResultBuilder::new().build()
+        run_django_steps(
+            &context.app_dir,
+            &env,
+            &dependencies_layer_dir,
+            &python_config.django_project_dirs,
+            &strict_mode,
+        )?;
+
+        let mut launch = configure_processes(
+            &app_dir,
+            &env,
+            &dependencies_layer_dir,
+            &python_version,
+            python_config,
+            &strict_mode,
+        )?;
+        launch
+            .labels
+            .push(package_inventory::packages_label(&installed_packages));
+
+        build_log::finish(true);
+        telemetry::send_build_success();
+
+        BuildResultBuilder::new().launch(launch).build()
     }
 
     fn on_error(&self, error: libcnb::Error<Self::Error>) {
@@ -126,32 +277,620 @@ impl Buildpack for PythonBuildpack {
     }
 }
 
+/// Configure the build log format and start collecting telemetry, which has to happen before
+/// anything else is logged, so that all of the build output uses the requested format.
+fn start_build_reporting(
+    context: &BuildContext<PythonBuildpack>,
+    env: &Env,
+) -> Result<(), BuildpackError> {
+    build_log::configure(env).map_err(BuildpackError::BuildLog)?;
+    telemetry::start(
+        env,
+        context.buildpack_descriptor.buildpack.version.to_string(),
+        build_config::is_build_cache_available(&context.layers_dir),
+    );
+    Ok(())
+}
+
+/// Check the app's `Procfile` and configured processes for problems, returning the launch
+/// configuration for the processes configured in `project.toml`.
+fn configure_processes(
+    app_dir: &Path,
+    env: &Env,
+    dependencies_layer_dir: &Path,
+    python_version: &PythonVersion,
+    python_config: &PythonBuildpackConfig,
+    strict_mode: &StrictMode,
+) -> Result<Launch, BuildpackError> {
+    let mut process_diagnostics =
+        processes::check_procfile_commands(app_dir, env).map_err(BuildpackError::ProcfileChecks)?;
+    process_diagnostics.extend(
+        gunicorn::check_gunicorn_config(
+            app_dir,
+            dependencies_layer_dir,
+            python_version,
+            &python_config.processes,
+        )
+        .map_err(BuildpackError::GunicornChecks)?,
+    );
+    diagnostics::log_diagnostics(&process_diagnostics, strict_mode)
+        .map_err(BuildpackError::StrictMode)?;
+
+    let is_django_app = django::is_django_installed(dependencies_layer_dir)
+        .and_then(|installed| Ok(installed && django::has_management_script(app_dir)?))
+        .map_err(BuildpackError::DjangoDetection)?;
+
+    processes::configured_launch(
+        &python_config.processes,
+        python_config.release.as_deref(),
+        processes::console_command(is_django_app),
+        app_dir,
+        env,
+    )
+    .map_err(BuildpackError::ConfiguredProcesses)
+}
+
+/// Install the app's Python version (and any additional Python versions), returning the path
+/// to the layer containing the app's Python version.
+fn install_python(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    python_version: &PythonVersion,
+    additional_python_versions: &[PythonVersion],
+    python_config: &PythonBuildpackConfig,
+) -> libcnb::Result<PathBuf, BuildpackError> {
+    telemetry::time_phase("install_python", || {
+        build_log::timed_section("Installing Python", || {
+            let python_layer_path = python::install_python(
+                context,
+                env,
+                python_version,
+                python_config.fault_handler,
+                python_config.slim_stdlib(),
+            )?;
+            if !additional_python_versions.is_empty() {
+                additional_python::install_additional_pythons(
+                    context,
+                    env,
+                    additional_python_versions,
+                )?;
+            }
+            Ok(python_layer_path)
+        })
+    })
+}
+
+/// Install the package manager and the app's dependencies, returning the path to the layer
+/// containing the installed dependencies, and the list of installed packages (which are also
+/// written to a file in that layer, so they can be audited from the app image). Any NLTK data
+/// listed in the app's `nltk.txt` file is then downloaded, since it's used like a dependency.
+fn install_dependencies(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    package_manager: PackageManager,
+    python_version: &PythonVersion,
+    python_layer_path: &Path,
+    python_config: &PythonBuildpackConfig,
+    strict_mode: &StrictMode,
+) -> libcnb::Result<(PathBuf, Vec<InstalledPackage>), BuildpackError> {
+    let (dependencies_layer_dir, installed_packages) = install_package_manager_and_dependencies(
+        context,
+        env,
+        package_manager,
+        python_version,
+        python_layer_path,
+        python_config,
+        strict_mode,
+    )?;
+    package_inventory::log_installed_packages(&installed_packages);
+    package_inventory::write_packages_file(&dependencies_layer_dir, &installed_packages)
+        .map_err(BuildpackError::PackagesFile)?;
+    if python_config.slim_site_packages {
+        remove_test_suites(&dependencies_layer_dir, python_version)?;
+    }
+    if let Some(corpora) = nltk_data::read_nltk_txt(&context.app_dir)? {
+        build_log::timed_section("Installing NLTK data", || {
+            nltk_data::install_nltk_data(context, env, &corpora, &installed_packages)
+        })?;
+    }
+    Ok((dependencies_layer_dir, installed_packages))
+}
+
+/// Remove the test suites bundled with the installed packages (for the `slim-site-packages`
+/// setting), logging how much space was saved.
+fn remove_test_suites(
+    dependencies_layer_dir: &Path,
+    python_version: &PythonVersion,
+) -> Result<(), BuildpackError> {
+    let site_packages_dir =
+        site_packages::site_packages_dir(dependencies_layer_dir, python_version);
+    let removed_size = site_packages::remove_test_suites(&site_packages_dir)
+        .map_err(BuildpackError::SlimSitePackages)?;
+    if removed_size > 0 {
+        log_info(format!(
+            "Removed {} of test suites from the installed packages",
+            disk_space::format_gigabytes(removed_size)
+        ));
+    }
+    Ok(())
+}
+
+fn install_package_manager_and_dependencies(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    package_manager: PackageManager,
+    python_version: &PythonVersion,
+    python_layer_path: &Path,
+    python_config: &PythonBuildpackConfig,
+    strict_mode: &StrictMode,
+) -> libcnb::Result<(PathBuf, Vec<InstalledPackage>), BuildpackError> {
+    telemetry::time_phase("install_dependencies", || match package_manager {
+        PackageManager::Pdm => {
+            warn_unsupported_settings(
+                PackageManager::Pdm,
+                WarningClass::UnsupportedPdmSettings,
+                &python_config.unsupported_settings(PackageManager::Pdm),
+                strict_mode,
+            );
+            let pyproject_toml = pyproject_toml::read_pyproject_toml(&context.app_dir)
+                .map_err(BuildpackError::ReadPyprojectToml)?;
+            build_log::timed_section("Installing PDM", || {
+                pdm::install_pdm(
+                    context,
+                    env,
+                    python_version,
+                    python_layer_path,
+                    &python_config.keyring_packages,
+                )
+            })?;
+            build_log::timed_section("Installing dependencies using PDM", || {
+                pdm_dependencies::install_dependencies(
+                    context,
+                    env,
+                    python_version,
+                    pyproject_toml.as_ref(),
+                )
+            })
+        }
+        PackageManager::Pip => install_pip_and_dependencies(
+            context,
+            env,
+            python_version,
+            python_layer_path,
+            python_config,
+            strict_mode,
+        ),
+        PackageManager::Pipenv => {
+            warn_unsupported_settings(
+                PackageManager::Pipenv,
+                WarningClass::UnsupportedPipenvSettings,
+                &python_config.unsupported_settings(PackageManager::Pipenv),
+                strict_mode,
+            );
+            build_log::timed_section("Installing Pipenv", || {
+                pipenv::install_pipenv(context, env, python_version, python_layer_path)
+            })?;
+            build_log::timed_section("Installing dependencies using Pipenv", || {
+                pipenv_dependencies::install_dependencies(context, env, python_version)
+            })
+        }
+        PackageManager::Poetry => {
+            warn_unsupported_settings(
+                PackageManager::Poetry,
+                WarningClass::UnsupportedPoetrySettings,
+                &python_config.unsupported_settings(PackageManager::Poetry),
+                strict_mode,
+            );
+            let pyproject_toml = pyproject_toml::read_pyproject_toml(&context.app_dir)
+                .map_err(BuildpackError::ReadPyprojectToml)?;
+            build_log::timed_section("Installing Poetry", || {
+                poetry::install_poetry(
+                    context,
+                    env,
+                    python_version,
+                    python_layer_path,
+                    pyproject_toml.as_ref(),
+                    &python_config.poetry_plugins,
+                    &python_config.keyring_packages,
+                )
+            })?;
+            build_log::timed_section("Installing dependencies using Poetry", || {
+                package_index::configure_package_indexes(
+                    env,
+                    PackageManager::Poetry,
+                    &python_config.package_indexes,
+                )
+                .map_err(BuildpackError::PackageIndex)?;
+                poetry_dependencies::install_dependencies(
+                    context,
+                    env,
+                    python_version,
+                    pyproject_toml.as_ref(),
+                    &python_config.poetry_groups,
+                    strict_mode,
+                )
+            })
+        }
+    })
+}
+
+/// Install pip, and then the app's dependencies using pip (after configuring any private
+/// package indexes, and preparing the pip cache).
+fn install_pip_and_dependencies(
+    context: &BuildContext<PythonBuildpack>,
+    env: &mut Env,
+    python_version: &PythonVersion,
+    python_layer_path: &Path,
+    python_config: &PythonBuildpackConfig,
+    strict_mode: &StrictMode,
+) -> libcnb::Result<(PathBuf, Vec<InstalledPackage>), BuildpackError> {
+    build_log::timed_section("Installing pip", || {
+        pip::install_pip(
+            context,
+            env,
+            python_version,
+            python_layer_path,
+            &python_config.keyring_packages,
+        )
+    })?;
+    build_log::timed_section("Installing dependencies using pip", || {
+        package_index::configure_package_indexes(
+            env,
+            PackageManager::Pip,
+            &python_config.package_indexes,
+        )
+        .map_err(BuildpackError::PackageIndex)?;
+        let pip_cache_dir = pip_cache::prepare_pip_cache(context, env, python_version)?;
+        pip_dependencies::install_dependencies(
+            context,
+            env,
+            python_version,
+            python_config,
+            &pip_cache_dir,
+            strict_mode,
+        )
+    })
+}
+
+/// Warn that the settings configured in `project.toml` aren't supported by the package manager,
+/// and so will be ignored.
+fn warn_unsupported_settings(
+    package_manager: PackageManager,
+    warning_class: WarningClass,
+    unsupported_settings: &[&str],
+    strict_mode: &StrictMode,
+) {
+    if !unsupported_settings.is_empty() {
+        strict_mode.log_warning(
+            warning_class,
+            &format!("Settings not supported for {}", package_manager.name()),
+            formatdoc! {"
+                The following settings in your app's 'project.toml' file aren't
+                supported when using {}, and so will be ignored: {}
+                ",
+                package_manager.name(),
+                unsupported_settings.join(", ")
+            },
+        );
+    }
+}
+
+/// Configure the app's `sys.path` and install the `python-env-run` helper, which both need to
+/// know the locations of the Python and dependencies layers.
+fn configure_run_environment(
+    context: &BuildContext<PythonBuildpack>,
+    python_layer_path: &Path,
+    dependencies_layer_dir: &Path,
+    python_version: &PythonVersion,
+    python_config: &PythonBuildpackConfig,
+) -> libcnb::Result<(), BuildpackError> {
+    python_path::configure_python_path(
+        &context.app_dir,
+        dependencies_layer_dir,
+        python_version,
+        &python_config.python_path.directories(),
+    )
+    .map_err(BuildpackError::PythonPath)?;
+    python_env_run::install_python_env_run(
+        context,
+        python_layer_path,
+        dependencies_layer_dir,
+        python_config.fault_handler,
+    )
+}
+
+/// Load the app's build env file (if any), and run the app's pre-compile build hook, before
+/// determining the directory containing the app's Python project. The hook may generate files
+/// used by the rest of the build (such as a requirements file), so has to be run before the
+/// project is analysed.
+fn prepare_build_environment(
+    app_dir: &Path,
+    env: &mut Env,
+    python_config: &PythonBuildpackConfig,
+) -> Result<PathBuf, BuildpackError> {
+    build_env_file::load_build_env_file(app_dir, env, python_config.build_env_file.as_deref())
+        .map_err(BuildpackError::BuildEnvFile)?;
+    // The env file may have set env vars that would break the build.
+    checks::check_environment(env).map_err(BuildpackError::Checks)?;
+    build_hooks::run_build_hook(app_dir, env, BuildHook::PreCompile)
+        .map_err(BuildpackError::BuildHook)?;
+    project_root::determine_project_dir(app_dir, env, python_config.project_root.as_deref())
+        .map_err(BuildpackError::ProjectRoot)
+}
+
+/// Run the app's post-dependency build hook, and then check the installed dependencies, so that
+/// the checks include any changes made by the hook. The hook is always run from the app root,
+/// whereas the checks are run from the project root.
+fn run_post_dependencies_steps(
+    app_dir: &Path,
+    project_dir: &Path,
+    env: &Env,
+    python_config: &PythonBuildpackConfig,
+) -> Result<(), BuildpackError> {
+    build_hooks::run_build_hook(app_dir, env, BuildHook::PostCompile)
+        .map_err(BuildpackError::BuildHook)?;
+    verify_dependencies(project_dir, env, python_config)
+}
+
+/// Check that the installed dependencies work, using the import check (if enabled via its env
+/// var) and the verification command configured in `project.toml` (if any).
+fn verify_dependencies(
+    app_dir: &Path,
+    env: &Env,
+    python_config: &PythonBuildpackConfig,
+) -> Result<(), BuildpackError> {
+    if let Some(modules) = import_check::import_check_modules(env) {
+        build_log::timed_section("Checking installed packages can be imported", || {
+            import_check::check_imports(app_dir, env, &modules)
+        })
+        .map_err(BuildpackError::ImportCheck)?;
+    }
+
+    if let Some(verify_command) = &python_config.verify_command {
+        build_log::timed_section("Running the verification command", || {
+            utils::log_running_command(verify_command);
+            // Run using Bash (as for process commands), so that shell features such as `&&` work.
+            utils::run_command_and_stream_output(
+                Command::new("bash")
+                    .args(["-c", verify_command])
+                    .current_dir(app_dir)
+                    .env_clear()
+                    .envs(env),
+            )
+        })
+        .map_err(BuildpackError::VerifyCommand)?;
+    }
+    Ok(())
+}
+
+/// Generate the Django static files (and if enabled, run the database migrations and deployment
+/// checks), if the app uses Django.
+fn run_django_steps(
+    app_dir: &Path,
+    env: &Env,
+    dependencies_layer_dir: &Path,
+    django_project_dirs: &[String],
+    strict_mode: &StrictMode,
+) -> Result<(), BuildpackError> {
+    if !django::is_django_installed(dependencies_layer_dir)
+        .map_err(BuildpackError::DjangoDetection)?
+    {
+        return Ok(());
+    }
+
+    telemetry::time_phase("django", || {
+        let (project_dirs, project_envs) =
+            build_log::timed_section("Generating Django static files", || {
+                run_django_collectstatic(app_dir, env, django_project_dirs, strict_mode)
+            })?;
+
+        if django::is_migrate_enabled(env) {
+            build_log::timed_section("Running Django database migrations", || {
+                project_dirs
+                    .iter()
+                    .zip(&project_envs)
+                    .try_for_each(|(project_dir, env)| django::run_django_migrate(project_dir, env))
+            })
+            .map_err(BuildpackError::DjangoMigrate)?;
+        }
+
+        if strict_mode.is_enabled(WarningClass::DjangoDeployCheck) {
+            build_log::timed_section("Running Django deployment checks", || {
+                project_dirs
+                    .iter()
+                    .zip(&project_envs)
+                    .try_for_each(|(project_dir, env)| {
+                        django::run_django_deploy_check(project_dir, env)
+                    })
+            })
+            .map_err(BuildpackError::DjangoDeployCheck)?;
+        }
+        Ok(())
+    })
+}
+
+/// Run Django's `collectstatic` command for each of the app's Django projects, returning the
+/// project directories along with the env for each project (which has its settings module set).
+fn run_django_collectstatic(
+    app_dir: &Path,
+    env: &Env,
+    django_project_dirs: &[String],
+    strict_mode: &StrictMode,
+) -> Result<(Vec<PathBuf>, Vec<Env>), BuildpackError> {
+    let project_dirs = django::project_dirs(app_dir, django_project_dirs)
+        .map_err(BuildpackError::DjangoCollectstatic)?;
+    // Each project has its own env, since the settings module is configured per project.
+    let mut project_envs = Vec::new();
+    for project_dir in &project_dirs {
+        if !django_project_dirs.is_empty() {
+            let relative_dir = project_dir.strip_prefix(app_dir).unwrap_or(project_dir);
+            log_info(format!(
+                "Running collectstatic for the Django project in '{}'",
+                relative_dir.display()
+            ));
+        }
+        let mut env = env.clone();
+        django::configure_settings_module(project_dir, &mut env, strict_mode)
+            .map_err(BuildpackError::DjangoSettingsModule)?;
+        django::run_django_collectstatic(project_dir, &env)
+            .map_err(BuildpackError::DjangoCollectstatic)?;
+        project_envs.push(env);
+    }
+    Ok((project_dirs, project_envs))
+}
+
+fn check_app_source(
+    app_dir: &Path,
+    package_manager: PackageManager,
+    python_config: &PythonBuildpackConfig,
+    strict_mode: &StrictMode,
+) -> Result<(), BuildpackError> {
+    let diagnostics =
+        diagnostics::run_diagnostics(app_dir, package_manager, python_config, strict_mode)
+            .map_err(BuildpackError::Diagnostics)?;
+    diagnostics::log_diagnostics(&diagnostics, strict_mode).map_err(BuildpackError::StrictMode)
+}
+
+fn determine_python_version(
+    app_dir: &Path,
+    env: &Env,
+    python_config: &PythonBuildpackConfig,
+) -> Result<(RequestedPythonVersion, PythonVersion), BuildpackError> {
+    log_header("Determining Python version");
+
+    let requested_python_version = python_version::read_requested_python_version(
+        app_dir,
+        env,
+        python_config.version.as_deref(),
+    )
+    .map_err(BuildpackError::RequestedPythonVersion)?;
+    let python_version = python_version::resolve_python_version(&requested_python_version)
+        .map_err(BuildpackError::ResolvePythonVersion)?;
+    telemetry::record_python_version(&python_version);
+
+    log_requested_python_version(&requested_python_version);
+
+    Ok((requested_python_version, python_version))
+}
+
+fn log_requested_python_version(requested_python_version: &RequestedPythonVersion) {
+    match requested_python_version.origin {
+        PythonVersionOrigin::BuildpackDefault => log_info(formatdoc! {"
+            No Python version specified, using the current default of Python {requested_python_version}.
+            We recommend setting an explicit version. In the root of your app create
+            a '.python-version' file, containing a Python version like '{requested_python_version}'."
+        }),
+        PythonVersionOrigin::EnvVar => log_info(format!(
+            "Using Python version {requested_python_version} specified by the {PYTHON_VERSION_ENV_VAR} env var"
+        )),
+        PythonVersionOrigin::ProjectToml => log_info(format!(
+            "Using Python version {requested_python_version} specified in project.toml"
+        )),
+        PythonVersionOrigin::PyprojectToml => log_info(format!(
+            "Using Python version {requested_python_version}, the newest version compatible with requires-python in pyproject.toml"
+        )),
+        PythonVersionOrigin::PythonVersionFile => log_info(format!(
+            "Using Python version {requested_python_version} specified in .python-version"
+        )),
+        // TODO: Add a deprecation message for runtime.txt once .python-version support has been
+        // released for both the CNB and the classic buildpack.
+        PythonVersionOrigin::RuntimeTxt => log_info(format!(
+            "Using Python version {requested_python_version} specified in runtime.txt"
+        )),
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum BuildpackError {
+    /// Errors resolving or installing the additional Python versions.
+    AdditionalPython(AdditionalPythonError),
+    /// Errors preparing the cached assets layer, or running the assets prefetch command.
+    AssetsLayer(AssetsLayerError),
+    /// Errors loading the build env file.
+    BuildEnvFile(BuildEnvFileError),
+    /// Errors configuring the build log format.
+    BuildLog(BuildLogError),
+    /// Errors running the app's `bin/pre_compile` or `bin/post_compile` build hooks.
+    BuildHook(BuildHookError),
     /// I/O errors when performing buildpack detection.
     BuildpackDetection(io::Error),
     /// Errors due to one of the environment checks failing.
     Checks(ChecksError),
+    /// Errors installing the packages in the cached packages layer.
+    CachedPackagesLayer(CachedPackagesLayerError),
+    /// Errors converting the processes configured in the project's `project.toml` file.
+    ConfiguredProcesses(ConfiguredProcessesError),
+    /// The build was stopped after showing the dependency plan, since dependency plan mode
+    /// was enabled.
+    DependencyPlanOnly,
+    /// Errors writing the dependency report layer.
+    DependencyReportLayer(DependencyReportLayerError),
     /// Errors determining which Python package manager to use for a project.
     DeterminePackageManager(DeterminePackageManagerError),
+    /// Errors running the read-only diagnostic checks on the app source.
+    Diagnostics(DiagnosticsError),
     /// Errors running the Django collectstatic command.
     DjangoCollectstatic(DjangoCollectstaticError),
+    /// Errors running the Django deployment checks.
+    DjangoDeployCheck(DjangoDeployCheckError),
     /// I/O errors when detecting whether Django is installed.
     DjangoDetection(io::Error),
+    /// Errors running the Django database migrations.
+    DjangoMigrate(DjangoMigrateError),
+    /// I/O errors when searching for the Django settings module.
+    DjangoSettingsModule(io::Error),
+    /// Errors checking the app's Gunicorn configuration.
+    GunicornChecks(GunicornChecksError),
+    /// Errors running the opt-in import check of the installed packages.
+    ImportCheck(ImportCheckError),
+    /// I/O errors when writing the installed packages file to the dependencies layer.
+    PackagesFile(io::Error),
+    /// Errors configuring the private package indexes from the `package-indexes` setting.
+    PackageIndex(PackageIndexError),
+    /// Errors installing the NLTK data listed in `nltk.txt` into a layer.
+    NltkDataLayer(NltkDataLayerError),
+    /// Errors installing the project's dependencies into a layer using PDM.
+    PdmDependenciesLayer(PdmDependenciesLayerError),
+    /// Errors installing PDM into a layer.
+    PdmLayer(PdmLayerError),
+    /// Errors preparing the layer for pip's download/wheel cache.
+    PipCacheLayer(PipCacheLayerError),
     /// Errors installing the project's dependencies into a layer using pip.
     PipDependenciesLayer(PipDependenciesLayerError),
     /// Errors installing pip into a layer.
     PipLayer(PipLayerError),
+    /// Errors installing the project's dependencies into a layer using Pipenv.
+    PipenvDependenciesLayer(PipenvDependenciesLayerError),
+    /// Errors installing Pipenv into a layer.
+    PipenvLayer(PipenvLayerError),
     /// Errors installing the project's dependencies into a layer using Poetry.
     PoetryDependenciesLayer(PoetryDependenciesLayerError),
     /// Errors installing Poetry into a layer.
     PoetryLayer(PoetryLayerError),
+    /// I/O errors when checking the commands in the app's `Procfile`.
+    ProcfileChecks(io::Error),
+    /// Errors determining the directory containing the app's Python project.
+    ProjectRoot(ProjectRootError),
+    /// I/O errors when creating the `python-env-run` wrapper script layer.
+    PythonEnvRunLayer(io::Error),
     /// Errors installing Python into a layer.
     PythonLayer(PythonLayerError),
+    /// Errors adding the directories configured in `project.toml` to the Python path.
+    PythonPath(PythonPathError),
+    /// Errors reading or parsing the project's `project.toml` file.
+    ReadProjectToml(ReadProjectTomlError),
+    /// Errors reading or parsing the project's `pyproject.toml` file.
+    ReadPyprojectToml(ReadPyprojectTomlError),
     /// Errors determining which Python version was requested for a project.
     RequestedPythonVersion(RequestedPythonVersionError),
     /// Errors resolving a requested Python version to a specific Python version.
     ResolvePythonVersion(ResolvePythonVersionError),
+    /// I/O errors when removing the installed packages' test suites for `slim-site-packages`.
+    SlimSitePackages(io::Error),
+    /// Errors due to strict mode being misconfigured, or a warning being promoted to an error.
+    StrictMode(StrictModeError),
+    /// Errors running the verification command configured in `project.toml`.
+    VerifyCommand(StreamedCommandError),
 }
 
 impl From<BuildpackError> for libcnb::Error<BuildpackError> {
@@ -160,7 +899,19 @@ impl From<BuildpackError> for libcnb::Error<BuildpackError> {
     }
 }
 
-buildpack_main!(PythonBuildpack);
+fn main() {
+    // In addition to the CNB `detect` and `build` entrypoints handled by libcnb, the buildpack
+    // binary supports a `resolve <app-dir>` command for debugging a project's configuration
+    // locally (for example via `cargo run --bin python-buildpack -- resolve path/to/app`).
+    let args: Vec<String> = env::args().collect();
+    if let [_, command, app_dir] = &args[..] {
+        if command == "resolve" {
+            process::exit(resolve::resolve(Path::new(app_dir)));
+        }
+    }
+
+    libcnb::libcnb_runtime(&PythonBuildpack);
+}
 
 // The integration tests are imported into the crate so that they can have access to private
 // APIs and constants, saving having to (a) run a dual binary/library crate, (b) expose APIs
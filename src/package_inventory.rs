@@ -0,0 +1,375 @@
+use crate::build_log::{log_header, log_info};
+use crate::utils::{self, CapturedCommandError};
+use libcnb::data::launch::Label;
+use libcnb::data::sbom::SbomFormat;
+use libcnb::sbom::Sbom;
+use libcnb::Env;
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{fs, io};
+
+// Lists the distributions installed in the venv's site-packages directory, along with any
+// additional directories passed as arguments. The search path is passed explicitly so that
+// packages from other locations on `sys.path` aren't included. The hash
+// is of the distribution's `RECORD` file, which itself contains the hashes of every installed file,
+// and so acts as a fingerprint of the exact installed contents of the package.
+const LIST_PACKAGES_SCRIPT: &str = r#"
+import hashlib, importlib.metadata as metadata, json, sysconfig
+paths = sorted({sysconfig.get_path("purelib"), sysconfig.get_path("platlib"), *sys.argv[1:]})
+packages = []
+for dist in metadata.distributions(path=paths):
+    record = dist.read_text("RECORD")
+    packages.append({
+        "name": dist.metadata["Name"],
+        "version": dist.version,
+        "hash": "sha256:" + hashlib.sha256(record.encode()).hexdigest() if record else None,
+    })
+print(json.dumps(sorted(packages, key=lambda package: package["name"].lower())))
+"#;
+
+/// A Python package that has been installed into the dependencies layer.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct InstalledPackage {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    pub(crate) hash: Option<String>,
+}
+
+/// List the packages installed in the currently active virtual environment, and in the specified
+/// additional `site-packages` directories (such as that of the cached packages layer).
+pub(crate) fn list_installed_packages(
+    app_dir: &Path,
+    env: &Env,
+    additional_dirs: &[PathBuf],
+) -> Result<Vec<InstalledPackage>, ListInstalledPackagesError> {
+    let output = utils::run_command_and_capture_output(
+        Command::new("python")
+            .args(["-c", LIST_PACKAGES_SCRIPT])
+            .args(additional_dirs)
+            .current_dir(app_dir)
+            .env_clear()
+            .envs(env),
+    )
+    .map_err(ListInstalledPackagesError::Command)?;
+
+    serde_json::from_slice(&output.stdout).map_err(ListInstalledPackagesError::Parse)
+}
+
+/// Log the list of installed packages, so that the exact versions used can be seen in the build log.
+///
+/// The list is logged in its own section after the dependencies have been installed, so that the
+/// packages can't be confused with the end of the timed installation section.
+pub(crate) fn log_installed_packages(packages: &[InstalledPackage]) {
+    log_header("Installed packages");
+    if packages.is_empty() {
+        log_info("No packages were installed");
+        return;
+    }
+
+    log_info(
+        packages
+            .iter()
+            .map(|package| format!("- {} {}", package.name, package.version))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    );
+}
+
+/// The image label containing a snapshot of the installed packages, so that what shipped in a
+/// given release can be inspected (for example using `docker inspect`) without running the image.
+pub(crate) const PACKAGES_LABEL_KEY: &str = "com.heroku.buildpacks.python.packages";
+
+/// Returns a `pip freeze` style snapshot of the installed packages, with one `name==version`
+/// entry per line.
+pub(crate) fn freeze_snapshot(packages: &[InstalledPackage]) -> String {
+    packages
+        .iter()
+        .map(|package| format!("{}=={}", package.name, package.version))
+        .chain(std::iter::once(String::new()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the image label containing the snapshot of the installed packages.
+pub(crate) fn packages_label(packages: &[InstalledPackage]) -> Label {
+    Label {
+        key: PACKAGES_LABEL_KEY.to_string(),
+        value: freeze_snapshot(packages),
+    }
+}
+
+/// The file in the dependencies layer that lists the installed packages, so that exactly which
+/// versions shipped (including transitive dependencies) can be audited from the app image.
+pub(crate) const PACKAGES_FILE_NAME: &str = "dependencies.json";
+
+/// Writes the installed packages to the [`PACKAGES_FILE_NAME`] file in the dependencies layer,
+/// in the same format as `pip list --format=json`.
+pub(crate) fn write_packages_file(
+    dependencies_layer_dir: &Path,
+    packages: &[InstalledPackage],
+) -> io::Result<()> {
+    let packages = packages
+        .iter()
+        .map(|package| json!({"name": package.name, "version": package.version}))
+        .collect::<Vec<_>>();
+    let contents = serde_json::to_string_pretty(&packages)?;
+    fs::write(dependencies_layer_dir.join(PACKAGES_FILE_NAME), contents)
+}
+
+/// Returns the software bills of materials (in both the `CycloneDX` and SPDX formats) listing the
+/// installed packages, for attaching to the dependencies layer so that image scanning tools can
+/// find them.
+//
+// The SBOMs are reproducible for the same set of packages, so don't include the build time or
+// a random serial number. The package hashes aren't included, since they are of the installed
+// files rather than of the distribution archives that SBOM consumers expect.
+pub(crate) fn packages_sboms(packages: &[InstalledPackage]) -> [Sbom; 2] {
+    [cyclonedx_sbom(packages), spdx_sbom(packages)]
+}
+
+fn cyclonedx_sbom(packages: &[InstalledPackage]) -> Sbom {
+    let components = packages
+        .iter()
+        .map(|package| {
+            let purl = package_url(package);
+            json!({
+                "type": "library",
+                "bom-ref": purl,
+                "name": package.name,
+                "version": package.version,
+                "purl": purl,
+            })
+        })
+        .collect::<Vec<_>>();
+    let sbom = json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "components": components,
+    });
+    Sbom::from_bytes(SbomFormat::CycloneDxJson, sbom.to_string())
+}
+
+// SPDX requires a creation time and a unique document namespace, so the fixed timestamp that the
+// CNB lifecycle uses for reproducible images is used, along with a namespace derived from a hash
+// of the installed packages.
+fn spdx_sbom(packages: &[InstalledPackage]) -> Sbom {
+    let spdx_packages = packages
+        .iter()
+        .map(|package| {
+            json!({
+                "SPDXID": spdx_package_id(package),
+                "name": package.name,
+                "versionInfo": package.version,
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": package_url(package),
+                }],
+            })
+        })
+        .collect::<Vec<_>>();
+    let relationships = packages
+        .iter()
+        .map(|package| {
+            json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": spdx_package_id(package),
+            })
+        })
+        .collect::<Vec<_>>();
+    let packages_digest = digest::digest(&digest::SHA256, freeze_snapshot(packages).as_bytes());
+    let sbom = json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": "python-packages",
+        "documentNamespace": format!(
+            "https://github.com/heroku/buildpacks-python/spdx/python-packages-{}",
+            utils::hex_encode(packages_digest.as_ref())
+        ),
+        "creationInfo": {
+            "created": "1980-01-01T00:00:01Z",
+            "creators": ["Tool: heroku/python"],
+        },
+        "packages": spdx_packages,
+        "relationships": relationships,
+    });
+    Sbom::from_bytes(SbomFormat::SpdxJson, sbom.to_string())
+}
+
+// SPDX identifiers can only contain letters, numbers, `.` and `-`, which the normalized package
+// name (which is unique within the venv) satisfies.
+fn spdx_package_id(package: &InstalledPackage) -> String {
+    format!(
+        "SPDXRef-Package-{}",
+        utils::normalize_package_name(&package.name)
+    )
+}
+
+// The Package URL for a package from PyPI, which uses the normalized package name. Local version
+// labels (such as `2.5.1+cpu`) contain a `+`, which has to be percent-encoded.
+// https://github.com/package-url/purl-spec/blob/master/PURL-TYPES.rst#pypi
+fn package_url(package: &InstalledPackage) -> String {
+    format!(
+        "pkg:pypi/{}@{}",
+        utils::normalize_package_name(&package.name),
+        package.version.replace('+', "%2B")
+    )
+}
+
+/// Errors that can occur when listing the installed packages.
+#[derive(Debug)]
+pub(crate) enum ListInstalledPackagesError {
+    Command(CapturedCommandError),
+    Parse(serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn installed_package_deserialize() {
+        let packages: Vec<InstalledPackage> = serde_json::from_str(
+            r#"[{"name": "Django", "version": "5.1.4", "hash": "sha256:abc"}, {"name": "legacy", "version": "1.0", "hash": null}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            packages,
+            [
+                InstalledPackage {
+                    name: "Django".to_string(),
+                    version: "5.1.4".to_string(),
+                    hash: Some("sha256:abc".to_string()),
+                },
+                InstalledPackage {
+                    name: "legacy".to_string(),
+                    version: "1.0".to_string(),
+                    hash: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn freeze_snapshot_packages() {
+        assert_eq!(
+            freeze_snapshot(&[
+                InstalledPackage {
+                    name: "Django".to_string(),
+                    version: "5.1.4".to_string(),
+                    hash: Some("sha256:abc".to_string()),
+                },
+                InstalledPackage {
+                    name: "typing_extensions".to_string(),
+                    version: "4.12.2".to_string(),
+                    hash: None,
+                },
+            ]),
+            "Django==5.1.4\ntyping_extensions==4.12.2\n"
+        );
+        assert_eq!(freeze_snapshot(&[]), "");
+    }
+
+    #[test]
+    fn cyclonedx_sbom_components() {
+        let sbom = cyclonedx_sbom(&[
+            InstalledPackage {
+                name: "typing_extensions".to_string(),
+                version: "4.12.2".to_string(),
+                hash: None,
+            },
+            InstalledPackage {
+                name: "torch".to_string(),
+                version: "2.5.1+cpu".to_string(),
+                hash: Some("sha256:abc".to_string()),
+            },
+        ]);
+        assert_eq!(sbom.format, SbomFormat::CycloneDxJson);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&sbom.data).unwrap(),
+            json!({
+                "bomFormat": "CycloneDX",
+                "specVersion": "1.5",
+                "version": 1,
+                "components": [
+                    {
+                        "type": "library",
+                        "bom-ref": "pkg:pypi/typing-extensions@4.12.2",
+                        "name": "typing_extensions",
+                        "version": "4.12.2",
+                        "purl": "pkg:pypi/typing-extensions@4.12.2",
+                    },
+                    {
+                        "type": "library",
+                        "bom-ref": "pkg:pypi/torch@2.5.1%2Bcpu",
+                        "name": "torch",
+                        "version": "2.5.1+cpu",
+                        "purl": "pkg:pypi/torch@2.5.1%2Bcpu",
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn spdx_sbom_packages() {
+        let sbom = spdx_sbom(&[InstalledPackage {
+            name: "typing_extensions".to_string(),
+            version: "4.12.2".to_string(),
+            hash: None,
+        }]);
+        assert_eq!(sbom.format, SbomFormat::SpdxJson);
+        let sbom = serde_json::from_slice::<serde_json::Value>(&sbom.data).unwrap();
+        assert_eq!(sbom["spdxVersion"], "SPDX-2.3");
+        assert_eq!(
+            sbom["packages"],
+            json!([{
+                "SPDXID": "SPDXRef-Package-typing-extensions",
+                "name": "typing_extensions",
+                "versionInfo": "4.12.2",
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": "pkg:pypi/typing-extensions@4.12.2",
+                }],
+            }])
+        );
+        assert_eq!(
+            sbom["relationships"],
+            json!([{
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": "SPDXRef-Package-typing-extensions",
+            }])
+        );
+        // The namespace must differ for different sets of packages, but be reproducible.
+        assert_eq!(
+            sbom["documentNamespace"],
+            serde_json::from_slice::<serde_json::Value>(
+                &spdx_sbom(&[InstalledPackage {
+                    name: "typing_extensions".to_string(),
+                    version: "4.12.2".to_string(),
+                    hash: Some("sha256:abc".to_string()),
+                }])
+                .data
+            )
+            .unwrap()["documentNamespace"]
+        );
+        assert_ne!(
+            sbom["documentNamespace"],
+            serde_json::from_slice::<serde_json::Value>(&spdx_sbom(&[]).data).unwrap()
+                ["documentNamespace"]
+        );
+    }
+}
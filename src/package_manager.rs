@@ -1,41 +1,102 @@
+use crate::packaging_tool_versions::{PDM_VERSION, PIPENV_VERSION, PIP_VERSION, POETRY_VERSION};
+use crate::project_files::{self, ProjectFileProblem};
+use crate::project_toml::PythonBuildpackConfig;
+use crate::pyproject_toml::{self, ReadPyprojectTomlError};
 use std::io;
 use std::path::Path;
 
-pub(crate) const SUPPORTED_PACKAGE_MANAGERS: [PackageManager; 2] =
-    [PackageManager::Pip, PackageManager::Poetry];
+/// The file pip installs the app from, when the app doesn't have a requirements file and instead
+/// declares its dependencies in the `[project]` table of its `pyproject.toml` (see PEP 621).
+pub(crate) const PIP_PROJECT_FILE: &str = "pyproject.toml";
+
+pub(crate) const SUPPORTED_PACKAGE_MANAGERS: [PackageManager; 4] = [
+    PackageManager::Pdm,
+    PackageManager::Pip,
+    PackageManager::Pipenv,
+    PackageManager::Poetry,
+];
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) enum PackageManager {
+    Pdm,
     Pip,
+    Pipenv,
     Poetry,
 }
 
 impl PackageManager {
     pub(crate) fn name(self) -> &'static str {
         match self {
+            PackageManager::Pdm => "PDM",
             PackageManager::Pip => "pip",
+            PackageManager::Pipenv => "Pipenv",
             PackageManager::Poetry => "Poetry",
         }
     }
 
+    /// The version of the package manager that the buildpack installs.
+    pub(crate) fn version(self) -> &'static str {
+        match self {
+            PackageManager::Pdm => PDM_VERSION,
+            PackageManager::Pip => PIP_VERSION,
+            PackageManager::Pipenv => PIPENV_VERSION,
+            PackageManager::Poetry => POETRY_VERSION,
+        }
+    }
+
     pub(crate) fn packages_file(self) -> &'static str {
         match self {
+            PackageManager::Pdm => "pdm.lock",
             PackageManager::Pip => "requirements.txt",
+            PackageManager::Pipenv => "Pipfile.lock",
             PackageManager::Poetry => "poetry.lock",
         }
     }
+
+    /// The file the package manager installs the app's packages from, taking into account the
+    /// app's configured pip requirements file (see `PythonBuildpackConfig::requirements_file`).
+    pub(crate) fn configured_packages_file(self, requirements_file: &str) -> &str {
+        match self {
+            PackageManager::Pip => requirements_file,
+            _ => self.packages_file(),
+        }
+    }
+
+    /// The file the package manager installs the app's packages from. This is the same as
+    /// `configured_packages_file`, except for pip apps that don't have a requirements file,
+    /// which are installed from their `pyproject.toml` file instead.
+    pub(crate) fn installs_from<'a>(self, app_dir: &Path, requirements_file: &'a str) -> &'a str {
+        if self == PackageManager::Pip && is_pip_project_install(app_dir, requirements_file) {
+            PIP_PROJECT_FILE
+        } else {
+            self.configured_packages_file(requirements_file)
+        }
+    }
+}
+
+/// Whether pip should install the app's project itself (using `pip install .`), rather than
+/// installing from a requirements file. This is only the case for apps that were detected as
+/// using pip due to their `pyproject.toml` (see `determine_package_manager`), since they don't
+/// have a requirements file.
+pub(crate) fn is_pip_project_install(app_dir: &Path, requirements_file: &str) -> bool {
+    !app_dir.join(requirements_file).exists()
 }
 
 /// Determine the Python package manager to use for a project, or return an error if either
 /// multiple supported package manager files are found, or none are.
+///
+/// If none are found, but the app's `pyproject.toml` has a `[project]` table, then pip is used
+/// to install the project itself, since pip supports installing projects whose dependencies are
+/// declared in `pyproject.toml` (as standardised by PEP 621), without needing a lockfile.
 pub(crate) fn determine_package_manager(
     app_dir: &Path,
+    python_config: &PythonBuildpackConfig,
 ) -> Result<PackageManager, DeterminePackageManagerError> {
     let package_managers_found = SUPPORTED_PACKAGE_MANAGERS
         .into_iter()
         .filter_map(|package_manager| {
             app_dir
-                .join(package_manager.packages_file())
+                .join(package_manager.configured_packages_file(python_config.requirements_file()))
                 .try_exists()
                 .map_err(DeterminePackageManagerError::CheckFileExists)
                 .map(|exists| exists.then_some(package_manager))
@@ -45,19 +106,31 @@ pub(crate) fn determine_package_manager(
 
     match package_managers_found[..] {
         [package_manager] => Ok(package_manager),
-        [] => Err(DeterminePackageManagerError::NoneFound),
+        [] if has_pyproject_project(app_dir)? => Ok(PackageManager::Pip),
+        [] => Err(DeterminePackageManagerError::NoneFound(
+            project_files::check_project_files(app_dir)
+                .map_err(DeterminePackageManagerError::CheckFileExists)?,
+        )),
         _ => Err(DeterminePackageManagerError::MultipleFound(
             package_managers_found,
         )),
     }
 }
 
+// The `name` field is required in the `[project]` table, so is used to check the table exists.
+fn has_pyproject_project(app_dir: &Path) -> Result<bool, DeterminePackageManagerError> {
+    Ok(pyproject_toml::read_pyproject_toml(app_dir)
+        .map_err(DeterminePackageManagerError::ReadPyprojectToml)?
+        .is_some_and(|pyproject_toml| pyproject_toml.project.name.is_some()))
+}
+
 /// Errors that can occur when determining which Python package manager to use for a project.
 #[derive(Debug)]
 pub(crate) enum DeterminePackageManagerError {
     CheckFileExists(io::Error),
     MultipleFound(Vec<PackageManager>),
-    NoneFound,
+    NoneFound(Vec<ProjectFileProblem>),
+    ReadPyprojectToml(ReadPyprojectTomlError),
 }
 
 #[cfg(test)]
@@ -67,15 +140,80 @@ mod tests {
     #[test]
     fn determine_package_manager_requirements_txt() {
         assert_eq!(
-            determine_package_manager(Path::new("tests/fixtures/pip_basic")).unwrap(),
+            determine_package_manager(
+                Path::new("tests/fixtures/pip_basic"),
+                &PythonBuildpackConfig::default()
+            )
+            .unwrap(),
+            PackageManager::Pip
+        );
+    }
+
+    #[test]
+    fn determine_package_manager_configured_requirements_file() {
+        assert_eq!(
+            determine_package_manager(
+                Path::new("tests/fixtures/pip_requirements_file"),
+                &PythonBuildpackConfig {
+                    requirements_file: Some("requirements/production.txt".to_string()),
+                    ..PythonBuildpackConfig::default()
+                }
+            )
+            .unwrap(),
             PackageManager::Pip
         );
     }
 
+    #[test]
+    fn determine_package_manager_pyproject_project() {
+        let app_dir = Path::new("tests/fixtures/pip_pyproject");
+        assert_eq!(
+            determine_package_manager(app_dir, &PythonBuildpackConfig::default()).unwrap(),
+            PackageManager::Pip
+        );
+        assert_eq!(
+            PackageManager::Pip.installs_from(app_dir, "requirements.txt"),
+            "pyproject.toml"
+        );
+        assert_eq!(
+            PackageManager::Pip
+                .installs_from(Path::new("tests/fixtures/pip_basic"), "requirements.txt"),
+            "requirements.txt"
+        );
+    }
+
+    #[test]
+    fn determine_package_manager_pdm_lock() {
+        assert_eq!(
+            determine_package_manager(
+                Path::new("tests/fixtures/pdm_basic"),
+                &PythonBuildpackConfig::default()
+            )
+            .unwrap(),
+            PackageManager::Pdm
+        );
+    }
+
+    #[test]
+    fn determine_package_manager_pipfile_lock() {
+        assert_eq!(
+            determine_package_manager(
+                Path::new("tests/fixtures/pipenv_basic"),
+                &PythonBuildpackConfig::default()
+            )
+            .unwrap(),
+            PackageManager::Pipenv
+        );
+    }
+
     #[test]
     fn determine_package_manager_poetry_lock() {
         assert_eq!(
-            determine_package_manager(Path::new("tests/fixtures/poetry_basic")).unwrap(),
+            determine_package_manager(
+                Path::new("tests/fixtures/poetry_basic"),
+                &PythonBuildpackConfig::default()
+            )
+            .unwrap(),
             PackageManager::Poetry
         );
     }
@@ -83,7 +221,7 @@ mod tests {
     #[test]
     fn determine_package_manager_multiple() {
         assert!(matches!(
-            determine_package_manager(Path::new("tests/fixtures/pip_and_poetry")).unwrap_err(),
+            determine_package_manager(Path::new("tests/fixtures/pip_and_poetry"), &PythonBuildpackConfig::default()).unwrap_err(),
             DeterminePackageManagerError::MultipleFound(found) if found == [PackageManager::Pip, PackageManager::Poetry]
         ));
     }
@@ -91,8 +229,20 @@ mod tests {
     #[test]
     fn determine_package_manager_none() {
         assert!(matches!(
-            determine_package_manager(Path::new("tests/fixtures/pyproject_toml_only")).unwrap_err(),
-            DeterminePackageManagerError::NoneFound
+            determine_package_manager(
+                Path::new("tests/fixtures/pyproject_toml_only"),
+                &PythonBuildpackConfig::default()
+            )
+            .unwrap_err(),
+            DeterminePackageManagerError::NoneFound(problems) if problems.is_empty()
+        ));
+        assert!(matches!(
+            determine_package_manager(
+                Path::new("tests/fixtures/misnamed_project_files"),
+                &PythonBuildpackConfig::default()
+            )
+            .unwrap_err(),
+            DeterminePackageManagerError::NoneFound(problems) if problems.len() == 3
         ));
     }
 }
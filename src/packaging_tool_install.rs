@@ -0,0 +1,89 @@
+use crate::layers::pip_dependencies::{self, HashCheckingFailure};
+use crate::utils::{self, RetriedCommandError};
+use libcnb::Env;
+use std::path::Path;
+use std::process::Command;
+use std::{fs, io};
+
+// The name of the file in the packaging tool's layer that its requirements are written to, since
+// pip only supports `--hash` options in requirements files (and not on the command line).
+const REQUIREMENTS_FILENAME: &str = "requirements.txt";
+
+/// Installs a packaging tool (such as pip or Poetry) from the buildpack's pinned requirements.
+/// If the requirements include hashes, pip's hash-checking mode is used (via `--require-hashes`)
+/// so that every package downloaded from the index is verified.
+///
+/// The tool is installed into the user site-packages (which must be configured using
+/// `PYTHONUSERBASE` in the env) using the pip wheel bundled in Python's standard library.
+/// Any `additional_requirements` (such as the keyring backends or Poetry plugins configured by
+/// the app) aren't pinned by the buildpack, so are installed afterwards by a separate command
+/// that doesn't use hash-checking mode.
+pub(crate) fn install_packaging_tool(
+    bundled_pip_module_path: &Path,
+    env: &Env,
+    requirements: &[&str],
+    additional_requirements: &[String],
+    layer_path: &Path,
+) -> Result<(), PackagingToolInstallError> {
+    let pip_install_command = || {
+        let mut command = Command::new("python");
+        command
+            .args([
+                &bundled_pip_module_path.to_string_lossy(),
+                "install",
+                // There is no point using pip's cache here, since the layer itself will be cached.
+                "--no-cache-dir",
+                "--no-input",
+                "--no-warn-script-location",
+                "--quiet",
+                "--user",
+            ])
+            .env_clear()
+            .envs(env);
+        command
+    };
+
+    let requirements_file = layer_path.join(REQUIREMENTS_FILENAME);
+    fs::write(&requirements_file, requirements.concat())
+        .map_err(PackagingToolInstallError::WriteRequirementsFile)?;
+
+    let hash_pinned = requirements
+        .iter()
+        .all(|requirements| requirements.contains("--hash="));
+    utils::run_command_and_stream_output_with_retries(
+        pip_install_command()
+            .args(hash_pinned.then_some("--require-hashes"))
+            .arg("--requirement")
+            .arg(&requirements_file),
+        "pip install",
+    )
+    .map_err(|error| match error {
+        RetriedCommandError::NonZeroExitStatus(_, ref output) => {
+            match pip_dependencies::hash_checking_failure(output) {
+                Some(HashCheckingFailure::HashMismatch(packages)) => {
+                    PackagingToolInstallError::HashMismatch(packages)
+                }
+                _ => PackagingToolInstallError::InstallCommand(error),
+            }
+        }
+        RetriedCommandError::Io(_) => PackagingToolInstallError::InstallCommand(error),
+    })?;
+
+    if additional_requirements.is_empty() {
+        return Ok(());
+    }
+    utils::run_command_and_stream_output_with_retries(
+        pip_install_command().args(additional_requirements),
+        "pip install",
+    )
+    .map_err(PackagingToolInstallError::InstallCommand)
+}
+
+/// Errors that can occur when installing a packaging tool using `install_packaging_tool`.
+#[derive(Debug)]
+pub(crate) enum PackagingToolInstallError {
+    /// The hashes of the downloaded packages don't match those in the buildpack's requirements.
+    HashMismatch(Vec<String>),
+    InstallCommand(RetriedCommandError),
+    WriteRequirementsFile(io::Error),
+}
@@ -1,31 +1,99 @@
 use std::str;
 
-// We store these versions in requirements files so that Dependabot can update them.
-// Each file must contain a single package specifier in the format `package==1.2.3`,
-// from which we extract/validate the version substring at compile time.
-pub(crate) const PIP_VERSION: &str =
-    extract_requirement_version(include_str!("../requirements/pip.txt"))
-        .expect("pip.txt must contain 'pip==VERSION'");
-pub(crate) const POETRY_VERSION: &str =
-    extract_requirement_version(include_str!("../requirements/poetry.txt"))
-        .expect("poetry.txt must contain 'poetry==VERSION'");
-
-// Extract the version substring from an exact-version package specifier (such as `foo==1.2.3`).
+// The packaging tools' requirements files are generated by the `update_inventory` maintenance
+// tool (rather than being edited by hand), which pins the exact version of each tool and all of
+// its dependencies, along with their hashes so that pip's hash-checking mode can be used. The
+// tool's version is extracted from its requirements file at compile time.
+pub(crate) const KEYRING_REQUIREMENTS: &str = include_str!("../requirements/keyring.txt");
+pub(crate) const PDM_REQUIREMENTS: &str = include_str!("../requirements/pdm.txt");
+pub(crate) const PIP_REQUIREMENTS: &str = include_str!("../requirements/pip.txt");
+pub(crate) const PIPENV_REQUIREMENTS: &str = include_str!("../requirements/pipenv.txt");
+pub(crate) const POETRY_REQUIREMENTS: &str = include_str!("../requirements/poetry.txt");
+// Only contains the dependencies that aren't already in `poetry.txt`, since the plugin is
+// installed into the same environment as Poetry.
+pub(crate) const POETRY_DYNAMIC_VERSIONING_REQUIREMENTS: &str =
+    include_str!("../requirements/poetry-dynamic-versioning.txt");
+
+pub(crate) const KEYRING_VERSION: &str =
+    extract_requirement_version(KEYRING_REQUIREMENTS, "keyring")
+        .expect("keyring.txt must contain 'keyring==VERSION'");
+pub(crate) const PDM_VERSION: &str = extract_requirement_version(PDM_REQUIREMENTS, "pdm")
+    .expect("pdm.txt must contain 'pdm==VERSION'");
+pub(crate) const PIP_VERSION: &str = extract_requirement_version(PIP_REQUIREMENTS, "pip")
+    .expect("pip.txt must contain 'pip==VERSION'");
+pub(crate) const PIPENV_VERSION: &str = extract_requirement_version(PIPENV_REQUIREMENTS, "pipenv")
+    .expect("pipenv.txt must contain 'pipenv==VERSION'");
+pub(crate) const POETRY_VERSION: &str = extract_requirement_version(POETRY_REQUIREMENTS, "poetry")
+    .expect("poetry.txt must contain 'poetry==VERSION'");
+pub(crate) const POETRY_DYNAMIC_VERSIONING_VERSION: &str = extract_requirement_version(
+    POETRY_DYNAMIC_VERSIONING_REQUIREMENTS,
+    "poetry-dynamic-versioning",
+)
+.expect("poetry-dynamic-versioning.txt must contain 'poetry-dynamic-versioning==VERSION'");
+
+// The newest `poetry.lock` format version (the `lock-version` metadata field) that can be read by
+// the Poetry version above. This must be updated whenever Poetry is upgraded to a version that
+// supports a newer lockfile format: https://github.com/python-poetry/poetry/blob/main/src/poetry/packages/locker.py
+pub(crate) const POETRY_MAX_SUPPORTED_LOCK_VERSION: &str = "2.0";
+
+// Extract the version of a package from the line in a requirements file that pins it to an exact
+// version (such as `foo==1.2.3 \`, followed by its `--hash` options on the next lines).
 // This function should only be used to extract the version constants from the buildpack's own
 // requirements files, which are controlled by us and don't require a full PEP 508 version parser.
 // Note: Since this is a `const fn` we cannot use iterators and most methods on `str` / `Result`.
-const fn extract_requirement_version(requirement: &'static str) -> Option<&'static str> {
-    let mut bytes = requirement.as_bytes();
-    while let [_, rest @ ..] = bytes {
-        if let [b'=', b'=', version @ ..] = rest {
-            if let Ok(version) = str::from_utf8(version.trim_ascii()) {
-                return Some(version);
+const fn extract_requirement_version(
+    requirements: &'static str,
+    package_name: &str,
+) -> Option<&'static str> {
+    let mut bytes = requirements.as_bytes();
+    let name = package_name.as_bytes();
+    loop {
+        if let Some(version) = version_if_pinned(bytes, name) {
+            return Some(version);
+        }
+        // Skip to the start of the next line.
+        while let [byte, rest @ ..] = bytes {
+            bytes = rest;
+            if *byte == b'\n' {
+                break;
             }
-            break;
         }
-        bytes = rest;
+        if bytes.is_empty() {
+            return None;
+        }
+    }
+}
+
+// Returns the version if the line starts with `<name>==<version>`, where the version ends at
+// the first whitespace, backslash or environment marker separator.
+const fn version_if_pinned(line: &'static [u8], name: &[u8]) -> Option<&'static str> {
+    if line.len() < name.len() + 2 {
+        return None;
+    }
+    let mut index = 0;
+    while index < name.len() {
+        if line[index] != name[index] {
+            return None;
+        }
+        index += 1;
+    }
+    let (_, rest) = line.split_at(index);
+    let [b'=', b'=', version @ ..] = rest else {
+        return None;
+    };
+    let mut end = 0;
+    while end < version.len()
+        && !matches!(version[end], b' ' | b'\t' | b'\r' | b'\n' | b'\\' | b';')
+    {
+        end += 1;
+    }
+    if end == 0 {
+        return None;
+    }
+    match str::from_utf8(version.split_at(end).0) {
+        Ok(version) => Some(version),
+        Err(_) => None,
     }
-    None
 }
 
 #[cfg(test)]
@@ -34,17 +102,55 @@ mod tests {
 
     #[test]
     fn extract_requirement_version_valid() {
-        assert_eq!(extract_requirement_version("package==1.2.3"), Some("1.2.3"));
         assert_eq!(
-            extract_requirement_version("\npackage == 0.12\n"),
+            extract_requirement_version("package==1.2.3", "package"),
+            Some("1.2.3")
+        );
+        assert_eq!(
+            extract_requirement_version(
+                "other==0.1 \\\n    --hash=sha256:abc\npackage==0.12 \\\n    --hash=sha256:def\n",
+                "package"
+            ),
             Some("0.12")
         );
+        assert_eq!(
+            extract_requirement_version("package==1.0 ; python_version < '3.9'\n", "package"),
+            Some("1.0")
+        );
     }
 
     #[test]
     fn extract_requirement_version_invalid() {
-        assert_eq!(extract_requirement_version(""), None);
-        assert_eq!(extract_requirement_version("package"), None);
-        assert_eq!(extract_requirement_version("package=<1.2.3"), None);
+        assert_eq!(extract_requirement_version("", "package"), None);
+        assert_eq!(extract_requirement_version("package", "package"), None);
+        assert_eq!(
+            extract_requirement_version("package=<1.2.3", "package"),
+            None
+        );
+        assert_eq!(
+            extract_requirement_version("package-extra==1.0", "package"),
+            None
+        );
+        assert_eq!(
+            extract_requirement_version("# package==1.0", "package"),
+            None
+        );
+    }
+
+    #[test]
+    fn packaging_tool_versions_valid() {
+        for version in [
+            KEYRING_VERSION,
+            PDM_VERSION,
+            PIP_VERSION,
+            PIPENV_VERSION,
+            POETRY_VERSION,
+            POETRY_DYNAMIC_VERSIONING_VERSION,
+        ] {
+            assert!(!version.is_empty());
+            assert!(version
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.'));
+        }
     }
 }
@@ -0,0 +1,113 @@
+use serde::Deserialize;
+use std::path::Path;
+use std::{fs, io};
+
+/// The subset of the contents of a `pdm.lock` file that is used by the buildpack.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PdmLock {
+    #[serde(default, rename = "package")]
+    pub(crate) packages: Vec<PdmLockPackage>,
+}
+
+/// A package entry (`[[package]]` table) in a `pdm.lock` file. Packages that aren't installed
+/// from a package index have one of `git`, `path` or `url` set.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PdmLockPackage {
+    pub(crate) name: String,
+    pub(crate) git: Option<String>,
+    pub(crate) path: Option<String>,
+    pub(crate) url: Option<String>,
+}
+
+impl PdmLockPackage {
+    /// Returns where the package is installed from (its Git URL, local path or URL), if it
+    /// isn't installed from a package index.
+    pub(crate) fn source(&self) -> Option<&str> {
+        self.git
+            .as_deref()
+            .or(self.path.as_deref())
+            .or(self.url.as_deref())
+    }
+}
+
+/// Read and parse the `pdm.lock` file in the specified app directory.
+pub(crate) fn read_pdm_lock(app_dir: &Path) -> Result<PdmLock, ReadPdmLockError> {
+    let contents = fs::read_to_string(app_dir.join("pdm.lock")).map_err(ReadPdmLockError::Io)?;
+    parse(&contents)
+}
+
+fn parse(contents: &str) -> Result<PdmLock, ReadPdmLockError> {
+    toml::from_str(contents).map_err(ReadPdmLockError::Parse)
+}
+
+/// Errors that can occur when reading and parsing a `pdm.lock` file.
+#[derive(Debug)]
+pub(crate) enum ReadPdmLockError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn parse_valid() {
+        let pdm_lock = parse(indoc! {r#"
+            [metadata]
+            groups = ["default"]
+
+            [[package]]
+            name = "typing-extensions"
+            version = "4.12.2"
+
+            [[package]]
+            name = "shared-lib"
+            version = "0.1.0"
+            path = "./packages/shared-lib"
+
+            [[package]]
+            name = "my-fork"
+            version = "1.0.0"
+            git = "https://github.com/example/my-fork.git"
+            revision = "abc"
+        "#})
+        .unwrap();
+
+        assert_eq!(
+            pdm_lock
+                .packages
+                .iter()
+                .map(|package| (package.name.as_str(), package.source()))
+                .collect::<Vec<_>>(),
+            [
+                ("typing-extensions", None),
+                ("shared-lib", Some("./packages/shared-lib")),
+                ("my-fork", Some("https://github.com/example/my-fork.git")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(matches!(
+            parse("[[package]]\nversion = 1"),
+            Err(ReadPdmLockError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn read_pdm_lock_fixture() {
+        let pdm_lock = read_pdm_lock(Path::new("tests/fixtures/pdm_basic")).unwrap();
+        assert_eq!(pdm_lock.packages.len(), 6);
+    }
+
+    #[test]
+    fn read_pdm_lock_io_error() {
+        assert!(matches!(
+            read_pdm_lock(Path::new("tests/fixtures/empty")),
+            Err(ReadPdmLockError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,212 @@
+use libcnb::Env;
+
+/// The env var used to pass additional options to pip when installing the app's requirements.
+pub(crate) const PIP_EXTRA_ARGS_ENV_VAR: &str = "PIP_EXTRA_ARGS";
+
+// The pip options that can be passed using `PIP_EXTRA_ARGS`, along with whether they take a value.
+// Options that change where or how the buildpack installs packages (such as `--target`, `--user`
+// or `--requirement`) would break later build steps, so only options affecting which
+// distributions pip selects (and from where) are supported.
+const SUPPORTED_OPTIONS: [(&str, bool); 12] = [
+    ("--config-settings", true),
+    ("--extra-index-url", true),
+    ("--find-links", true),
+    ("--index-url", true),
+    ("--no-binary", true),
+    ("--only-binary", true),
+    ("--pre", false),
+    ("--prefer-binary", false),
+    ("--retries", true),
+    ("--timeout", true),
+    ("--trusted-host", true),
+    ("--use-pep517", false),
+];
+
+// The short forms of the supported options.
+const SHORT_OPTIONS: [(&str, &str); 3] = [
+    ("-C", "--config-settings"),
+    ("-f", "--find-links"),
+    ("-i", "--index-url"),
+];
+
+/// Reads the additional pip options from the `PIP_EXTRA_ARGS` env var, if set.
+///
+/// The value is split into arguments the same way as a shell would (so values containing spaces
+/// can be quoted), and each option is checked against the list of supported options.
+pub(crate) fn read_pip_extra_args(env: &Env) -> Result<Vec<String>, PipExtraArgsError> {
+    let Some(value) = env.get_string_lossy(PIP_EXTRA_ARGS_ENV_VAR) else {
+        return Ok(Vec::new());
+    };
+    let args = split_args(&value)?;
+    check_args(&args)?;
+    Ok(args)
+}
+
+// Splits the value into arguments on whitespace, handling single quotes (whose contents are used
+// as-is), double quotes (within which a backslash escapes `"` and `\`), and backslash escapes.
+fn split_args(value: &str) -> Result<Vec<String>, PipExtraArgsError> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            char if char.is_whitespace() => args.extend(current.take()),
+            '\'' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(char) => arg.push(char),
+                        None => return Err(PipExtraArgsError::UnterminatedQuote),
+                    }
+                }
+            }
+            '"' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(char @ ('"' | '\\')) => arg.push(char),
+                            Some(char) => {
+                                arg.push('\\');
+                                arg.push(char);
+                            }
+                            None => return Err(PipExtraArgsError::UnterminatedQuote),
+                        },
+                        Some(char) => arg.push(char),
+                        None => return Err(PipExtraArgsError::UnterminatedQuote),
+                    }
+                }
+            }
+            '\\' => {
+                if let Some(char) = chars.next() {
+                    current.get_or_insert_with(String::new).push(char);
+                }
+            }
+            char => current.get_or_insert_with(String::new).push(char),
+        }
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+// Checks that all of the arguments are supported options, and that the options which take a value
+// have one (either as the next argument, or for long options, using `--option=value`).
+fn check_args(args: &[String]) -> Result<(), PipExtraArgsError> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value)),
+            _ => (arg.as_str(), None),
+        };
+        let name = SHORT_OPTIONS
+            .iter()
+            .find_map(|(short, long)| (*short == name).then_some(*long))
+            .unwrap_or(name);
+        let Some((_, takes_value)) = SUPPORTED_OPTIONS.iter().find(|(option, _)| *option == name)
+        else {
+            return Err(PipExtraArgsError::UnsupportedArgument(arg.clone()));
+        };
+        match (takes_value, inline_value) {
+            (true, None) if args.next().is_none() => {
+                return Err(PipExtraArgsError::MissingValue(arg.clone()));
+            }
+            (false, Some(_)) => return Err(PipExtraArgsError::UnsupportedArgument(arg.clone())),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Errors that can occur when reading the additional pip options from `PIP_EXTRA_ARGS`.
+#[derive(Debug, PartialEq)]
+pub(crate) enum PipExtraArgsError {
+    MissingValue(String),
+    UnsupportedArgument(String),
+    UnterminatedQuote,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(value: &str) -> Result<Vec<String>, PipExtraArgsError> {
+        let mut env = Env::new();
+        env.insert(PIP_EXTRA_ARGS_ENV_VAR, value);
+        read_pip_extra_args(&env)
+    }
+
+    #[test]
+    fn read_pip_extra_args_unset() {
+        assert_eq!(read_pip_extra_args(&Env::new()), Ok(Vec::new()));
+        assert_eq!(read(" \n"), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn read_pip_extra_args_valid() {
+        assert_eq!(
+            read("--pre --no-binary :all: -i https://example.com/simple"),
+            Ok(vec![
+                "--pre".to_string(),
+                "--no-binary".to_string(),
+                ":all:".to_string(),
+                "-i".to_string(),
+                "https://example.com/simple".to_string(),
+            ])
+        );
+        assert_eq!(
+            read("--only-binary=numpy,scipy --prefer-binary"),
+            Ok(vec![
+                "--only-binary=numpy,scipy".to_string(),
+                "--prefer-binary".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn read_pip_extra_args_quoting() {
+        assert_eq!(
+            read(r#"-C 'key=a value' --config-settings "other=\"b\" \c" --config-settings=x\ y"#),
+            Ok(vec![
+                "-C".to_string(),
+                "key=a value".to_string(),
+                "--config-settings".to_string(),
+                r#"other="b" \c"#.to_string(),
+                "--config-settings=x y".to_string(),
+            ])
+        );
+        assert_eq!(
+            read("--pre ''"),
+            Err(PipExtraArgsError::UnsupportedArgument(String::new()))
+        );
+        assert_eq!(read("-C 'key"), Err(PipExtraArgsError::UnterminatedQuote));
+        assert_eq!(read("-C \"key"), Err(PipExtraArgsError::UnterminatedQuote));
+    }
+
+    #[test]
+    fn read_pip_extra_args_invalid() {
+        assert_eq!(
+            read("--pre --target /tmp"),
+            Err(PipExtraArgsError::UnsupportedArgument(
+                "--target".to_string()
+            ))
+        );
+        assert_eq!(
+            read("requests"),
+            Err(PipExtraArgsError::UnsupportedArgument(
+                "requests".to_string()
+            ))
+        );
+        assert_eq!(
+            read("--pre=1"),
+            Err(PipExtraArgsError::UnsupportedArgument(
+                "--pre=1".to_string()
+            ))
+        );
+        assert_eq!(
+            read("--pre --index-url"),
+            Err(PipExtraArgsError::MissingValue("--index-url".to_string()))
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use crate::utils;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::{fs, io};
+
+/// The subset of the contents of a `Pipfile` that is used by the buildpack.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Pipfile {
+    /// The app's direct dependencies (excluding development dependencies), keyed by name.
+    #[serde(default)]
+    pub(crate) packages: toml::Table,
+}
+
+/// The subset of the contents of a `Pipfile.lock` file that is used by the buildpack.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PipfileLock {
+    #[serde(rename = "_meta")]
+    pub(crate) meta: PipfileLockMeta,
+    /// The locked packages (excluding development dependencies), keyed by name.
+    #[serde(default)]
+    pub(crate) default: BTreeMap<String, PipfileLockPackage>,
+}
+
+/// The `_meta` object of a `Pipfile.lock` file.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PipfileLockMeta {
+    #[serde(default)]
+    pub(crate) sources: Vec<PipfileLockSource>,
+}
+
+/// A package index in the `sources` array of the `_meta` object.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PipfileLockSource {
+    pub(crate) name: String,
+    pub(crate) url: String,
+}
+
+/// A package entry in the `default` object of a `Pipfile.lock` file. Exactly one of the
+/// fields is set, depending on where the package is installed from.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PipfileLockPackage {
+    /// The name of the package index source, for packages installed from an index.
+    pub(crate) index: Option<String>,
+    pub(crate) git: Option<String>,
+    pub(crate) path: Option<String>,
+    pub(crate) file: Option<String>,
+}
+
+impl Pipfile {
+    /// Returns the normalized names of the app's direct dependencies.
+    pub(crate) fn dependency_names(&self) -> Vec<String> {
+        self.packages
+            .keys()
+            .map(|name| utils::normalize_package_name(name))
+            .collect()
+    }
+}
+
+impl PipfileLock {
+    /// Returns where the package was installed from (the URL of its package index, or its
+    /// Git URL, local path or file URL), if known.
+    pub(crate) fn package_source(&self, package: &PipfileLockPackage) -> Option<String> {
+        match package {
+            PipfileLockPackage {
+                index: Some(index), ..
+            } => self
+                .meta
+                .sources
+                .iter()
+                .find(|source| &source.name == index)
+                .map(|source| source.url.clone()),
+            PipfileLockPackage { git: Some(url), .. }
+            | PipfileLockPackage {
+                path: Some(url), ..
+            }
+            | PipfileLockPackage {
+                file: Some(url), ..
+            } => Some(url.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Read and parse the `Pipfile` in the specified app directory, if it exists.
+pub(crate) fn read_pipfile(app_dir: &Path) -> Result<Option<Pipfile>, ReadPipfileError> {
+    utils::read_optional_file(&app_dir.join("Pipfile"))
+        .map_err(ReadPipfileError::ReadPipfile)?
+        .map(|contents| toml::from_str(&contents).map_err(ReadPipfileError::ParsePipfile))
+        .transpose()
+}
+
+/// Read and parse the `Pipfile.lock` file in the specified app directory.
+pub(crate) fn read_pipfile_lock(app_dir: &Path) -> Result<PipfileLock, ReadPipfileError> {
+    let contents = fs::read_to_string(app_dir.join("Pipfile.lock"))
+        .map_err(ReadPipfileError::ReadPipfileLock)?;
+    parse_pipfile_lock(&contents)
+}
+
+fn parse_pipfile_lock(contents: &str) -> Result<PipfileLock, ReadPipfileError> {
+    serde_json::from_str(contents).map_err(ReadPipfileError::ParsePipfileLock)
+}
+
+/// Errors that can occur when reading and parsing a `Pipfile` or `Pipfile.lock` file.
+#[derive(Debug)]
+pub(crate) enum ReadPipfileError {
+    ParsePipfile(toml::de::Error),
+    ParsePipfileLock(serde_json::Error),
+    ReadPipfile(io::Error),
+    ReadPipfileLock(io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn read_pipfile_valid() {
+        let pipfile = read_pipfile(Path::new("tests/fixtures/pipenv_basic"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(pipfile.dependency_names(), ["typing-extensions"]);
+    }
+
+    #[test]
+    fn read_pipfile_missing() {
+        assert!(read_pipfile(Path::new("tests/fixtures/empty"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn read_pipfile_lock_valid() {
+        let pipfile_lock = read_pipfile_lock(Path::new("tests/fixtures/pipenv_basic")).unwrap();
+        assert_eq!(
+            pipfile_lock.default.keys().collect::<Vec<_>>(),
+            ["typing-extensions"]
+        );
+    }
+
+    #[test]
+    fn read_pipfile_lock_io_error() {
+        assert!(matches!(
+            read_pipfile_lock(Path::new("tests/fixtures/empty")),
+            Err(ReadPipfileError::ReadPipfileLock(_))
+        ));
+    }
+
+    #[test]
+    fn parse_pipfile_lock_invalid() {
+        assert!(matches!(
+            parse_pipfile_lock("{\"default\": {}}"),
+            Err(ReadPipfileError::ParsePipfileLock(_))
+        ));
+    }
+
+    #[test]
+    fn package_source() {
+        let pipfile_lock = parse_pipfile_lock(indoc! {r#"
+            {
+                "_meta": {
+                    "sources": [
+                        {"name": "pypi", "url": "https://pypi.org/simple", "verify_ssl": true}
+                    ]
+                },
+                "default": {
+                    "typing-extensions": {"index": "pypi", "version": "==4.12.2"},
+                    "shared-lib": {"editable": true, "path": "./packages/shared-lib"},
+                    "my-fork": {"git": "https://github.com/example/my-fork.git", "ref": "abc"},
+                    "unknown-index": {"index": "private", "version": "==1.0.0"}
+                }
+            }
+        "#})
+        .unwrap();
+        assert_eq!(
+            pipfile_lock
+                .default
+                .values()
+                .map(|package| pipfile_lock.package_source(package))
+                .collect::<Vec<_>>(),
+            [
+                Some("https://github.com/example/my-fork.git".to_string()),
+                Some("./packages/shared-lib".to_string()),
+                Some("https://pypi.org/simple".to_string()),
+                None,
+            ]
+        );
+    }
+}
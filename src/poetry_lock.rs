@@ -0,0 +1,288 @@
+use serde::Deserialize;
+use std::path::{Component, Path};
+use std::{fs, io};
+
+/// The subset of the contents of a `poetry.lock` file that is used by the buildpack.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PoetryLock {
+    #[serde(default, rename = "package")]
+    pub(crate) packages: Vec<PoetryLockPackage>,
+    pub(crate) metadata: Option<PoetryLockMetadata>,
+}
+
+/// The `[metadata]` table of a `poetry.lock` file.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct PoetryLockMetadata {
+    pub(crate) lock_version: Option<String>,
+    /// A hash of the dependency-related parts of `pyproject.toml`, which Poetry uses to detect
+    /// whether the lockfile is out of date.
+    pub(crate) content_hash: Option<String>,
+}
+
+/// A package entry (`[[package]]` table) in a `poetry.lock` file.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PoetryLockPackage {
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) version: String,
+    /// The package's distribution files, which are empty for packages not installed from an index.
+    #[serde(default)]
+    pub(crate) files: Vec<PoetryLockPackageFile>,
+    pub(crate) source: Option<PoetryLockPackageSource>,
+}
+
+/// An entry in the `files` array of a package entry.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PoetryLockPackageFile {
+    pub(crate) hash: String,
+}
+
+/// The `[package.source]` table of a package entry, which is only present for packages that
+/// are not installed from the default package index (such as Git or local path dependencies).
+#[derive(Debug, Deserialize)]
+pub(crate) struct PoetryLockPackageSource {
+    #[serde(rename = "type")]
+    pub(crate) source_type: String,
+    pub(crate) url: Option<String>,
+}
+
+impl PoetryLock {
+    /// Returns the lockfile's content hash, if it has one.
+    pub(crate) fn content_hash(&self) -> Option<&str> {
+        self.metadata.as_ref()?.content_hash.as_deref()
+    }
+
+    /// Returns the lockfile format version if it is newer than the specified supported version.
+    ///
+    /// Versions that cannot be parsed are ignored, since it's preferable to let Poetry
+    /// report any problems with the lockfile than for the buildpack to show a false positive.
+    pub(crate) fn lock_version_if_newer_than(&self, supported_version: &str) -> Option<&str> {
+        let lock_version = self.metadata.as_ref()?.lock_version.as_deref()?;
+        (parse_version_numbers(lock_version)? > parse_version_numbers(supported_version)?)
+            .then_some(lock_version)
+    }
+
+    /// Returns the local path dependencies (`directory` and `file` sources), as
+    /// `(package_name, path)` tuples. The paths are relative to the project directory.
+    pub(crate) fn path_dependencies(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.packages.iter().filter_map(|package| {
+            let source = package.source.as_ref()?;
+            let url = source.url.as_ref()?;
+            matches!(source.source_type.as_str(), "directory" | "file")
+                .then_some((package.name.as_str(), url.as_str()))
+        })
+    }
+
+    /// Returns the local path dependencies (`directory` and `file` sources) whose path
+    /// resolves to a location outside of the app directory, as `(package_name, path)` tuples.
+    pub(crate) fn path_dependencies_outside_app_dir(
+        &self,
+        app_dir: &Path,
+    ) -> Vec<(String, String)> {
+        self.path_dependencies()
+            .filter(|(_, path)| !is_path_inside_dir(Path::new(path), app_dir))
+            .map(|(name, path)| (name.to_string(), path.to_string()))
+            .collect()
+    }
+}
+
+/// Read and parse the `poetry.lock` file in the specified app directory.
+pub(crate) fn read_poetry_lock(app_dir: &Path) -> Result<PoetryLock, ReadPoetryLockError> {
+    let contents =
+        fs::read_to_string(app_dir.join("poetry.lock")).map_err(ReadPoetryLockError::Io)?;
+    parse(&contents)
+}
+
+fn parse(contents: &str) -> Result<PoetryLock, ReadPoetryLockError> {
+    toml::from_str(contents).map_err(ReadPoetryLockError::Parse)
+}
+
+fn parse_version_numbers(version: &str) -> Option<Vec<u16>> {
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+// Poetry records path dependencies relative to the project directory, however, the target
+// directory may not exist in the build environment (which is the reason we're checking), so
+// we can't use `fs::canonicalize` and instead have to resolve the path lexically.
+fn is_path_inside_dir(path: &Path, dir: &Path) -> bool {
+    if path.is_absolute() {
+        return path.starts_with(dir);
+    }
+
+    let mut depth: usize = 0;
+    for component in path.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(new_depth) => depth = new_depth,
+                None => return false,
+            },
+            Component::CurDir | Component::Prefix(_) | Component::RootDir => {}
+        }
+    }
+    true
+}
+
+/// Errors that can occur when reading and parsing a `poetry.lock` file.
+#[derive(Debug)]
+pub(crate) enum ReadPoetryLockError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn parse_valid() {
+        let poetry_lock = parse(indoc! {r#"
+            [[package]]
+            name = "typing-extensions"
+            version = "4.12.2"
+
+            [[package]]
+            name = "shared-lib"
+            version = "0.1.0"
+            develop = true
+
+            [package.source]
+            type = "directory"
+            url = "../shared-lib"
+
+            [metadata]
+            lock-version = "2.0"
+            content-hash = "56ec6342f4a39b40"
+        "#})
+        .unwrap();
+
+        assert_eq!(poetry_lock.content_hash(), Some("56ec6342f4a39b40"));
+        assert_eq!(poetry_lock.packages.len(), 2);
+        assert!(poetry_lock.packages[0].source.is_none());
+        let source = poetry_lock.packages[1].source.as_ref().unwrap();
+        assert_eq!(source.source_type, "directory");
+        assert_eq!(source.url.as_deref(), Some("../shared-lib"));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(matches!(
+            parse("[[package]]\nversion = 1"),
+            Err(ReadPoetryLockError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn read_poetry_lock_io_error() {
+        assert!(matches!(
+            read_poetry_lock(Path::new("tests/fixtures/empty")),
+            Err(ReadPoetryLockError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn lock_version_if_newer_than() {
+        let poetry_lock = parse("[metadata]\nlock-version = \"2.1\"").unwrap();
+        assert_eq!(poetry_lock.lock_version_if_newer_than("2.0"), Some("2.1"));
+        assert_eq!(poetry_lock.lock_version_if_newer_than("2.1"), None);
+        assert_eq!(poetry_lock.lock_version_if_newer_than("2.1.1"), None);
+        assert_eq!(poetry_lock.lock_version_if_newer_than("10.0"), None);
+
+        let poetry_lock = parse("[metadata]\nlock-version = \"2.a\"").unwrap();
+        assert_eq!(poetry_lock.lock_version_if_newer_than("2.0"), None);
+
+        let poetry_lock = parse("").unwrap();
+        assert_eq!(poetry_lock.lock_version_if_newer_than("2.0"), None);
+    }
+
+    #[test]
+    fn path_dependencies() {
+        let poetry_lock = parse(indoc! {r#"
+            [[package]]
+            name = "typing-extensions"
+
+            [[package]]
+            name = "shared-lib"
+            [package.source]
+            type = "directory"
+            url = "packages/shared-lib"
+
+            [[package]]
+            name = "wheel-file"
+            [package.source]
+            type = "file"
+            url = "dist/wheel_file-1.0-py3-none-any.whl"
+
+            [[package]]
+            name = "git-dependency"
+            [package.source]
+            type = "git"
+            url = "https://github.com/example/git-dependency.git"
+        "#})
+        .unwrap();
+
+        assert_eq!(
+            poetry_lock.path_dependencies().collect::<Vec<_>>(),
+            [
+                ("shared-lib", "packages/shared-lib"),
+                ("wheel-file", "dist/wheel_file-1.0-py3-none-any.whl"),
+            ]
+        );
+    }
+
+    #[test]
+    fn path_dependencies_outside_app_dir() {
+        let poetry_lock = parse(indoc! {r#"
+            [[package]]
+            name = "inside"
+            [package.source]
+            type = "directory"
+            url = "packages/inside"
+
+            [[package]]
+            name = "inside-via-parent"
+            [package.source]
+            type = "directory"
+            url = "packages/../inside"
+
+            [[package]]
+            name = "sibling"
+            [package.source]
+            type = "directory"
+            url = "../shared-lib"
+
+            [[package]]
+            name = "wheel-file"
+            [package.source]
+            type = "file"
+            url = "../dist/wheel_file-1.0-py3-none-any.whl"
+
+            [[package]]
+            name = "absolute"
+            [package.source]
+            type = "directory"
+            url = "/opt/absolute"
+
+            [[package]]
+            name = "git-dependency"
+            [package.source]
+            type = "git"
+            url = "https://github.com/example/git-dependency.git"
+        "#})
+        .unwrap();
+
+        assert_eq!(
+            poetry_lock.path_dependencies_outside_app_dir(Path::new("/workspace")),
+            [
+                ("sibling".to_string(), "../shared-lib".to_string()),
+                (
+                    "wheel-file".to_string(),
+                    "../dist/wheel_file-1.0-py3-none-any.whl".to_string()
+                ),
+                ("absolute".to_string(), "/opt/absolute".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use crate::build_log::{log_header, log_info};
+use crate::diagnostics::{Diagnostic, DiagnosticCategory};
+use crate::procfile;
+use crate::strict_mode::WarningClass;
+use indoc::formatdoc;
+use libcnb::data::launch::{Launch, LaunchBuilder, Process, ProcessBuilder, ProcessType};
+use libcnb::data::process_type;
+use libcnb::Env;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::{env, io};
+
+// Commands that are built into Bash, and so won't be found on the `PATH`. If a process command
+// starts with one of these (such as `cd backend && gunicorn ...`) its executable isn't checked.
+const SHELL_BUILTINS: [&str; 12] = [
+    ".", ":", "[", "[[", "cd", "eval", "export", "if", "set", "source", "test", "ulimit",
+];
+
+// The process type used for the release command, which platforms run after each build is
+// deployed (such as to run database migrations), rather than as a long-running process.
+const RELEASE_PROCESS_TYPE: &str = "release";
+
+// The process type used for an interactive Python console, for parity with `heroku run console`.
+const CONSOLE_PROCESS_TYPE: &str = "console";
+
+/// Build the launch configuration for the processes and release command configured in
+/// `project.toml` (if any), along with a `console` process that runs the specified command
+/// (unless a `console` process has been configured explicitly).
+pub(crate) fn configured_launch(
+    processes: &BTreeMap<String, String>,
+    release: Option<&str>,
+    console_command: &str,
+    app_dir: &Path,
+    env: &Env,
+) -> Result<Launch, ConfiguredProcessesError> {
+    if release.is_some() && processes.contains_key(RELEASE_PROCESS_TYPE) {
+        return Err(ConfiguredProcessesError::ConflictingReleaseProcess);
+    }
+
+    log_header("Configuring processes");
+    let mut launch = LaunchBuilder::new();
+    launch.processes(configured_processes(processes, app_dir, env)?);
+    if let Some(command) = release {
+        launch.process(configured_release_process(command, app_dir, env)?);
+    }
+    if !processes.contains_key(CONSOLE_PROCESS_TYPE) {
+        launch.process(console_process(console_command));
+    }
+    Ok(launch.build())
+}
+
+/// The command for the `console` process, which is Django's shell for Django apps (so that the
+/// app's settings and models are available), and otherwise the Python REPL.
+pub(crate) fn console_command(is_django_app: bool) -> &'static str {
+    if is_django_app {
+        "python manage.py shell"
+    } else {
+        "python"
+    }
+}
+
+// The console process's executable isn't checked, since it's always Python (or `manage.py`),
+// which is provided by the buildpack.
+fn console_process(command: &str) -> Process {
+    log_info(format!(
+        "Adding process '{CONSOLE_PROCESS_TYPE}': {command}"
+    ));
+    ProcessBuilder::new(process_type!("console"), ["bash", "-c", command]).build()
+}
+
+/// Convert the processes configured in `project.toml` into launch processes, checking that
+/// each command's executable can be found in the build environment (such as in the venv).
+///
+/// Commands are run using Bash (as is the case for `Procfile` processes), so that they can
+/// use shell features such as env var interpolation (for example `--bind 0.0.0.0:$PORT`).
+fn configured_processes(
+    processes: &BTreeMap<String, String>,
+    app_dir: &Path,
+    env: &Env,
+) -> Result<Vec<Process>, ConfiguredProcessesError> {
+    processes
+        .iter()
+        .map(|(name, command)| configured_process(name, command, app_dir, env))
+        .collect()
+}
+
+/// Convert the release command configured in `project.toml` into a `release` launch process.
+fn configured_release_process(
+    command: &str,
+    app_dir: &Path,
+    env: &Env,
+) -> Result<Process, ConfiguredProcessesError> {
+    configured_process(RELEASE_PROCESS_TYPE, command, app_dir, env).map_err(|error| match error {
+        ConfiguredProcessesError::EmptyCommand(_) => ConfiguredProcessesError::EmptyReleaseCommand,
+        ConfiguredProcessesError::ExecutableNotFound { executable, .. } => {
+            ConfiguredProcessesError::ReleaseExecutableNotFound(executable)
+        }
+        error => error,
+    })
+}
+
+fn configured_process(
+    name: &str,
+    command: &str,
+    app_dir: &Path,
+    env: &Env,
+) -> Result<Process, ConfiguredProcessesError> {
+    let process_type = name
+        .parse::<ProcessType>()
+        .map_err(|_| ConfiguredProcessesError::InvalidProcessType(name.to_string()))?;
+    let executable = command_executable(command)
+        .ok_or_else(|| ConfiguredProcessesError::EmptyCommand(name.to_string()))?;
+    if !is_shell_builtin(executable) && !executable_exists(executable, app_dir, env) {
+        return Err(ConfiguredProcessesError::ExecutableNotFound {
+            process_type: name.to_string(),
+            executable: executable.to_string(),
+        });
+    }
+    log_info(format!("Adding process '{name}': {command}"));
+    Ok(ProcessBuilder::new(process_type, ["bash", "-c", command])
+        .default(name == "web")
+        .build())
+}
+
+/// Check that the executables used by the `Procfile` process commands can be found in the
+/// build environment (such as in the venv), since otherwise the process will fail to boot
+/// with a "command not found" error.
+pub(crate) fn check_procfile_commands(app_dir: &Path, env: &Env) -> io::Result<Vec<Diagnostic>> {
+    Ok(procfile::read_procfile(app_dir)?
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(process_type, command)| {
+            let executable = command_executable(&command)?;
+            (!is_shell_builtin(executable) && !executable_exists(executable, app_dir, env)).then(
+                || Diagnostic {
+                    category: DiagnosticCategory::Processes,
+                    warning_class: WarningClass::ProcessCommandNotFound,
+                    message: formatdoc! {"
+                        The '{executable}' command used by the '{process_type}' process in your
+                        'Procfile' could not be found, so the process will fail to start with
+                        a 'command not found' error.
+
+                        Check that the package providing the command is listed in your app's
+                        dependencies, and that the command name is spelt correctly.
+                    "},
+                },
+            )
+        })
+        .collect())
+}
+
+/// Returns the executable that a command will run, skipping any leading env var assignments
+/// or `exec` (such as `FOO=bar exec gunicorn ...`).
+fn command_executable(command: &str) -> Option<&str> {
+    command.split_whitespace().find(|&word| {
+        word != "exec"
+            && !word
+                .split_once('=')
+                .is_some_and(|(name, _)| is_env_var_name(name))
+    })
+}
+
+fn is_shell_builtin(executable: &str) -> bool {
+    SHELL_BUILTINS.contains(&executable)
+}
+
+/// Whether the executable can be found, either on the `PATH` of the build environment (which
+/// includes the venv and Python layers), or at a path relative to the app directory.
+fn executable_exists(executable: &str, app_dir: &Path, env: &Env) -> bool {
+    if executable.contains('/') {
+        return app_dir.join(executable).is_file();
+    }
+    env.get("PATH")
+        .is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(executable).is_file()))
+}
+
+fn is_env_var_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Errors that can occur when converting the processes configured in `project.toml`.
+#[derive(Debug)]
+pub(crate) enum ConfiguredProcessesError {
+    ConflictingReleaseProcess,
+    EmptyCommand(String),
+    EmptyReleaseCommand,
+    ExecutableNotFound {
+        process_type: String,
+        executable: String,
+    },
+    InvalidProcessType(String),
+    ReleaseExecutableNotFound(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_executable_valid() {
+        assert_eq!(
+            command_executable("gunicorn --bind 0.0.0.0:$PORT myapp.wsgi"),
+            Some("gunicorn")
+        );
+        assert_eq!(
+            command_executable("  DEBUG=1 WEB_CONCURRENCY=4 celery worker"),
+            Some("celery")
+        );
+        assert_eq!(command_executable("./bin/start.sh"), Some("./bin/start.sh"));
+        assert_eq!(
+            command_executable("exec gunicorn myapp.wsgi"),
+            Some("gunicorn")
+        );
+        assert_eq!(command_executable(" "), None);
+        assert_eq!(command_executable("FOO=bar"), None);
+    }
+
+    #[test]
+    fn executable_exists_on_path_or_in_app() {
+        let mut env = Env::new();
+        env.insert("PATH", "/nonexistent:tests/fixtures/testing_buildpack/bin");
+        let app_dir = Path::new("tests/fixtures/testing_buildpack");
+        assert!(executable_exists("build", app_dir, &env));
+        assert!(executable_exists("bin/build", app_dir, &env));
+        assert!(!executable_exists("gunicorn", app_dir, &env));
+        assert!(!executable_exists("bin/gunicorn", app_dir, &env));
+        assert!(!executable_exists("build", app_dir, &Env::new()));
+    }
+
+    #[test]
+    fn check_procfile_commands_not_found() {
+        let mut env = Env::new();
+        env.insert("PATH", "tests/fixtures/testing_buildpack/bin");
+        let diagnostics =
+            check_procfile_commands(Path::new("tests/fixtures/pipenv_basic"), &env).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.starts_with(
+            "The 'python' command used by the 'web' process in your\n'Procfile' could not be found"
+        ));
+
+        assert_eq!(
+            check_procfile_commands(Path::new("tests/fixtures/empty"), &env).unwrap(),
+            []
+        );
+    }
+
+    #[test]
+    fn configured_processes_invalid() {
+        let env = Env::new();
+        let app_dir = Path::new("tests/fixtures/empty");
+        assert!(matches!(
+            configured_processes(
+                &BTreeMap::from([("my process".to_string(), "python app.py".to_string())]),
+                app_dir,
+                &env
+            ),
+            Err(ConfiguredProcessesError::InvalidProcessType(name)) if name == "my process"
+        ));
+        assert!(matches!(
+            configured_processes(
+                &BTreeMap::from([("web".to_string(), String::new())]),
+                app_dir,
+                &env
+            ),
+            Err(ConfiguredProcessesError::EmptyCommand(name)) if name == "web"
+        ));
+        assert!(matches!(
+            configured_processes(
+                &BTreeMap::from([("web".to_string(), "gunicorn myapp.wsgi".to_string())]),
+                app_dir,
+                &env
+            ),
+            Err(ConfiguredProcessesError::ExecutableNotFound { executable, .. }) if executable == "gunicorn"
+        ));
+    }
+
+    #[test]
+    fn configured_launch_release() {
+        let mut env = Env::new();
+        env.insert("PATH", "tests/fixtures/testing_buildpack/bin");
+        let app_dir = Path::new("tests/fixtures/empty");
+        let launch = configured_launch(
+            &BTreeMap::new(),
+            Some("build --migrate"),
+            "python",
+            app_dir,
+            &env,
+        )
+        .unwrap();
+        assert_eq!(launch.processes.len(), 2);
+        assert_eq!(launch.processes[0].r#type.as_str(), "release");
+        assert_eq!(
+            launch.processes[0].command,
+            ["bash", "-c", "build --migrate"]
+        );
+        assert!(!launch.processes[0].default);
+    }
+
+    #[test]
+    fn configured_launch_console() {
+        let env = Env::new();
+        let app_dir = Path::new("tests/fixtures/empty");
+        let launch = configured_launch(
+            &BTreeMap::new(),
+            None,
+            "python manage.py shell",
+            app_dir,
+            &env,
+        )
+        .unwrap();
+        assert_eq!(launch.processes.len(), 1);
+        assert_eq!(launch.processes[0].r#type.as_str(), "console");
+        assert_eq!(
+            launch.processes[0].command,
+            ["bash", "-c", "python manage.py shell"]
+        );
+        assert!(!launch.processes[0].default);
+
+        let mut env = Env::new();
+        env.insert("PATH", "tests/fixtures/testing_buildpack/bin");
+        let launch = configured_launch(
+            &BTreeMap::from([("console".to_string(), "build --shell".to_string())]),
+            None,
+            "python",
+            app_dir,
+            &env,
+        )
+        .unwrap();
+        assert_eq!(launch.processes.len(), 1);
+        assert_eq!(launch.processes[0].command, ["bash", "-c", "build --shell"]);
+    }
+
+    #[test]
+    fn console_command_django() {
+        assert_eq!(console_command(true), "python manage.py shell");
+        assert_eq!(console_command(false), "python");
+    }
+
+    #[test]
+    fn configured_launch_release_invalid() {
+        let env = Env::new();
+        let app_dir = Path::new("tests/fixtures/empty");
+        assert!(matches!(
+            configured_launch(&BTreeMap::new(), Some(" "), "python", app_dir, &env),
+            Err(ConfiguredProcessesError::EmptyReleaseCommand)
+        ));
+        assert!(matches!(
+            configured_launch(&BTreeMap::new(), Some("python manage.py migrate"), "python", app_dir, &env),
+            Err(ConfiguredProcessesError::ReleaseExecutableNotFound(executable)) if executable == "python"
+        ));
+        assert!(matches!(
+            configured_launch(
+                &BTreeMap::from([("release".to_string(), "./release.sh".to_string())]),
+                Some("./release.sh"),
+                "python",
+                app_dir,
+                &env
+            ),
+            Err(ConfiguredProcessesError::ConflictingReleaseProcess)
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::utils;
+use std::io;
+use std::path::Path;
+
+/// Read and parse the `Procfile` in the specified app directory, if it exists, returning
+/// the process types and their commands in the order they are declared.
+pub(crate) fn read_procfile(app_dir: &Path) -> io::Result<Option<Vec<(String, String)>>> {
+    utils::read_optional_file(&app_dir.join("Procfile"))
+        .map(|contents| contents.as_deref().map(parse))
+}
+
+// Lines that aren't of the form `<process type>: <command>` are ignored, matching the
+// behaviour of the Procfile buildpack (which is what uses the Procfile at build time).
+fn parse(contents: &str) -> Vec<(String, String)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (process_type, command) = line.split_once(':')?;
+            let process_type = process_type.trim();
+            let command = command.trim();
+            (!process_type.is_empty()
+                && !process_type.starts_with('#')
+                && process_type
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+                && !command.is_empty())
+            .then(|| (process_type.to_string(), command.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn read_procfile_valid() {
+        assert_eq!(
+            read_procfile(Path::new("tests/fixtures/pipenv_basic")).unwrap(),
+            Some(vec![(
+                "web".to_string(),
+                "python -m http.server $PORT".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn read_procfile_missing() {
+        assert_eq!(
+            read_procfile(Path::new("tests/fixtures/empty")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_skips_invalid_lines() {
+        assert_eq!(
+            parse(indoc! {"
+                # A comment: with a colon
+                web: gunicorn --bind 0.0.0.0:$PORT myapp.wsgi
+
+                invalid line
+                release:
+                worker:celery --app myapp worker
+            "}),
+            [
+                (
+                    "web".to_string(),
+                    "gunicorn --bind 0.0.0.0:$PORT myapp.wsgi".to_string()
+                ),
+                (
+                    "worker".to_string(),
+                    "celery --app myapp worker".to_string()
+                ),
+            ]
+        );
+    }
+}
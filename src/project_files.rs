@@ -0,0 +1,167 @@
+use std::fmt::{self, Display};
+use std::io;
+use std::path::Path;
+
+// The project files that the buildpack (or the package managers it runs) looks for in the root
+// of the app. Filenames that only differ from these by case are treated as misnamed, since the
+// filenames are case-sensitive on Linux (unlike the default filesystems on macOS and Windows).
+const PROJECT_FILES: [&str; 10] = [
+    ".python-version",
+    "Pipfile",
+    "Pipfile.lock",
+    "Procfile",
+    "pdm.lock",
+    "poetry.lock",
+    "project.toml",
+    "pyproject.toml",
+    "requirements.txt",
+    "runtime.txt",
+];
+
+// Common misspellings of the project files (in lowercase), along with the expected filename.
+const MISNAMED_PROJECT_FILES: [(&str, &str); 13] = [
+    (".python_version", ".python-version"),
+    (".python-version.txt", ".python-version"),
+    ("pipfile.lock.txt", "Pipfile.lock"),
+    ("pipfile.txt", "Pipfile"),
+    ("poetry.lock.txt", "poetry.lock"),
+    ("procfile.txt", "Procfile"),
+    ("pyproject.tml", "pyproject.toml"),
+    ("python-version", ".python-version"),
+    ("requirement.txt", "requirements.txt"),
+    ("requirements", "requirements.txt"),
+    ("requirements.text", "requirements.txt"),
+    ("requirements.txt.txt", "requirements.txt"),
+    ("runtime.text", "runtime.txt"),
+];
+
+/// A file in the root of the app that looks like it's meant to be a project file, but which
+/// will be ignored by the buildpack.
+#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum ProjectFileProblem {
+    /// A file whose name is a near miss for the name of a project file (which doesn't exist).
+    Misnamed {
+        found: String,
+        expected: &'static str,
+    },
+    /// A `Pipfile` without a `Pipfile.lock`, which is required to install using Pipenv.
+    PipfileWithoutLockfile,
+}
+
+impl Display for ProjectFileProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Misnamed { found, expected } => {
+                write!(f, "- '{found}' should be named '{expected}'")
+            }
+            Self::PipfileWithoutLockfile => write!(
+                f,
+                "- 'Pipfile' has no 'Pipfile.lock' (generate one using 'pipenv lock')"
+            ),
+        }
+    }
+}
+
+/// Check the root of the app for files that look like misnamed project files (such as
+/// `Requirements.txt` or `.python_version`), and for a `Pipfile` without a `Pipfile.lock`.
+///
+/// These are otherwise silently ignored, which leads to confusing detection failures, package
+/// manager errors or the default Python version being used.
+pub(crate) fn check_project_files(app_dir: &Path) -> io::Result<Vec<ProjectFileProblem>> {
+    let mut problems = Vec::new();
+    for entry in app_dir.read_dir()? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let filename = entry.file_name().to_string_lossy().to_string();
+        if let Some(expected) = expected_filename(&filename) {
+            if !app_dir.join(expected).try_exists()? {
+                problems.push(ProjectFileProblem::Misnamed {
+                    found: filename,
+                    expected,
+                });
+            }
+        }
+    }
+    problems.sort();
+
+    if app_dir.join("Pipfile").try_exists()? && !app_dir.join("Pipfile.lock").try_exists()? {
+        problems.push(ProjectFileProblem::PipfileWithoutLockfile);
+    }
+    Ok(problems)
+}
+
+// Returns the project file that the filename is a near miss for, if any.
+fn expected_filename(filename: &str) -> Option<&'static str> {
+    if PROJECT_FILES.contains(&filename) {
+        return None;
+    }
+    let lowercase_filename = filename.to_lowercase();
+    PROJECT_FILES
+        .into_iter()
+        .find(|project_file| project_file.to_lowercase() == lowercase_filename)
+        .or_else(|| {
+            MISNAMED_PROJECT_FILES
+                .into_iter()
+                .find_map(|(misnamed, expected)| {
+                    (misnamed == lowercase_filename).then_some(expected)
+                })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_filename_near_misses() {
+        assert_eq!(
+            expected_filename("Requirements.txt"),
+            Some("requirements.txt")
+        );
+        assert_eq!(
+            expected_filename("requirements.text"),
+            Some("requirements.txt")
+        );
+        assert_eq!(expected_filename("POETRY.LOCK.txt"), Some("poetry.lock"));
+        assert_eq!(
+            expected_filename(".python_version"),
+            Some(".python-version")
+        );
+        assert_eq!(expected_filename("procfile"), Some("Procfile"));
+        assert_eq!(expected_filename("requirements.txt"), None);
+        assert_eq!(expected_filename("requirements-dev.txt"), None);
+        assert_eq!(expected_filename("Pipfile"), None);
+    }
+
+    #[test]
+    fn check_project_files_misnamed() {
+        assert_eq!(
+            check_project_files(Path::new("tests/fixtures/misnamed_project_files")).unwrap(),
+            [
+                ProjectFileProblem::Misnamed {
+                    found: ".python_version".to_string(),
+                    expected: ".python-version"
+                },
+                ProjectFileProblem::Misnamed {
+                    found: "Requirements.txt".to_string(),
+                    expected: "requirements.txt"
+                },
+                ProjectFileProblem::PipfileWithoutLockfile,
+            ]
+        );
+    }
+
+    #[test]
+    fn check_project_files_valid() {
+        assert!(check_project_files(Path::new("tests/fixtures/pip_basic"))
+            .unwrap()
+            .is_empty());
+        assert!(
+            check_project_files(Path::new("tests/fixtures/pipenv_basic"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use libcnb::Env;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// The env var that can be used to build a Python project that's in a subdirectory of the app,
+/// such as in a monorepo containing both a backend and a frontend.
+pub(crate) const PROJECT_ROOT_ENV_VAR: &str = "PYTHON_PROJECT_ROOT";
+
+/// Determine the directory containing the app's Python project, which is the app directory
+/// unless a subdirectory has been configured using the `PYTHON_PROJECT_ROOT` env var or the
+/// `project-root` setting in `project.toml` (with the env var taking priority).
+///
+/// The project files (such as `requirements.txt`, the lockfiles and `.python-version`) are read
+/// from this directory, and the dependencies are installed from it.
+pub(crate) fn determine_project_dir(
+    app_dir: &Path,
+    env: &Env,
+    configured_project_root: Option<&str>,
+) -> Result<PathBuf, ProjectRootError> {
+    let Some(project_root) = env
+        .get_string_lossy(PROJECT_ROOT_ENV_VAR)
+        .or_else(|| configured_project_root.map(ToString::to_string))
+    else {
+        return Ok(app_dir.to_path_buf());
+    };
+
+    let project_dir = resolve_project_root(app_dir, &project_root)
+        .ok_or_else(|| ProjectRootError::InvalidPath(project_root.clone()))?;
+    if project_dir
+        .try_exists()
+        .map_err(ProjectRootError::CheckDirectoryExists)?
+        && project_dir.is_dir()
+    {
+        Ok(project_dir)
+    } else {
+        Err(ProjectRootError::DirectoryNotFound(project_root))
+    }
+}
+
+// The project root must be a relative path within the app directory, since the app's other files
+// aren't guaranteed to be present at run-time.
+fn resolve_project_root(app_dir: &Path, project_root: &str) -> Option<PathBuf> {
+    let project_root = project_root.trim();
+    if project_root.is_empty() {
+        return None;
+    }
+    let mut path = app_dir.to_path_buf();
+    for component in Path::new(project_root).components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(name) => path.push(name),
+            Component::ParentDir | Component::Prefix(_) | Component::RootDir => return None,
+        }
+    }
+    Some(path)
+}
+
+/// Errors that can occur when determining the directory containing the app's Python project.
+#[derive(Debug)]
+pub(crate) enum ProjectRootError {
+    CheckDirectoryExists(io::Error),
+    DirectoryNotFound(String),
+    InvalidPath(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn determine_project_dir_unset() {
+        assert_eq!(
+            determine_project_dir(Path::new("tests/fixtures"), &Env::new(), None).unwrap(),
+            Path::new("tests/fixtures")
+        );
+    }
+
+    #[test]
+    fn determine_project_dir_configured() {
+        let app_dir = Path::new("tests/fixtures");
+        assert_eq!(
+            determine_project_dir(app_dir, &Env::new(), Some("./pip_basic/")).unwrap(),
+            Path::new("tests/fixtures/pip_basic")
+        );
+        let mut env = Env::new();
+        env.insert(PROJECT_ROOT_ENV_VAR, "poetry_basic");
+        assert_eq!(
+            determine_project_dir(app_dir, &env, Some("pip_basic")).unwrap(),
+            Path::new("tests/fixtures/poetry_basic")
+        );
+    }
+
+    #[test]
+    fn determine_project_dir_invalid() {
+        let app_dir = Path::new("tests/fixtures");
+        for project_root in ["", " ", "../fixtures", "pip_basic/../..", "/tmp"] {
+            assert!(matches!(
+                determine_project_dir(app_dir, &Env::new(), Some(project_root)),
+                Err(ProjectRootError::InvalidPath(path)) if path == project_root
+            ));
+        }
+        for project_root in ["non_existent", "pip_basic/requirements.txt"] {
+            assert!(matches!(
+                determine_project_dir(app_dir, &Env::new(), Some(project_root)),
+                Err(ProjectRootError::DirectoryNotFound(path)) if path == project_root
+            ));
+        }
+    }
+}
@@ -0,0 +1,652 @@
+use crate::package_manager::PackageManager;
+use crate::strict_mode::WarningClass;
+use crate::utils;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+/// The subset of the contents of a `project.toml` file that is used by the buildpack.
+// https://github.com/buildpacks/spec/blob/main/extensions/project-descriptor.md
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct ProjectToml {
+    #[serde(default)]
+    com: ComTable,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ComTable {
+    #[serde(default)]
+    heroku: HerokuTable,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HerokuTable {
+    #[serde(default)]
+    buildpacks: BuildpacksTable,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BuildpacksTable {
+    #[serde(default)]
+    python: PythonBuildpackConfig,
+}
+
+/// The `[com.heroku.buildpacks.python]` table of a `project.toml` file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct PythonBuildpackConfig {
+    /// Additional Python versions to install alongside the app's Python version, for apps that
+    /// test against multiple Python versions (such as using tox or nox).
+    #[serde(default)]
+    pub(crate) additional_python_versions: Vec<String>,
+    /// The configuration for the cached layer used for ML models and other downloaded assets,
+    /// which is only created if the table exists.
+    pub(crate) assets: Option<AssetsConfig>,
+    /// The env file to load into the build environment, instead of `.env.build`.
+    pub(crate) build_env_file: Option<String>,
+    /// Packages to install into the venv before the app's dependencies, for use by packages
+    /// installed without build isolation.
+    #[serde(default)]
+    pub(crate) build_requirements: Vec<String>,
+    /// Packages to install into a separate cached layer, rather than the venv, so that changes
+    /// to the app's other requirements don't cause these (typically very large) packages to be
+    /// reinstalled.
+    #[serde(default)]
+    pub(crate) cached_packages: Vec<String>,
+    /// The directories (relative to the app root) of the Django projects to run the Django build
+    /// steps (such as `collectstatic`) in, for repositories containing multiple Django services.
+    /// Defaults to the app root.
+    #[serde(default)]
+    pub(crate) django_project_dirs: Vec<String>,
+    /// Whether to enable Python's fault handler at run-time, using `PYTHONFAULTHANDLER`.
+    #[serde(default)]
+    pub(crate) fault_handler: bool,
+    /// Keyring backend packages to install alongside pip or Poetry, for authenticating with
+    /// private package indexes.
+    #[serde(default)]
+    pub(crate) keyring_packages: Vec<String>,
+    /// Which packages to install without build isolation.
+    #[serde(default)]
+    pub(crate) no_build_isolation: NoBuildIsolation,
+    /// Private package indexes to install the app's dependencies from, along with the env vars
+    /// containing their credentials.
+    #[serde(default)]
+    pub(crate) package_indexes: Vec<PackageIndexConfig>,
+    /// Processes to register for the app, as a mapping of process type to command.
+    #[serde(default)]
+    pub(crate) processes: BTreeMap<String, String>,
+    /// The subdirectory (relative to the app root) containing the app's Python project, for
+    /// repositories that contain other projects (such as a frontend) alongside it.
+    pub(crate) project_root: Option<String>,
+    /// Additional Poetry dependency groups to install, alongside the main group.
+    #[serde(default)]
+    pub(crate) poetry_groups: Vec<String>,
+    /// Poetry plugins (as pip requirement specifiers) to install alongside Poetry, for projects
+    /// whose build relies on them.
+    #[serde(default)]
+    pub(crate) poetry_plugins: Vec<String>,
+    /// Directories to add to `sys.path` using a `.pth` file in the venv.
+    #[serde(default)]
+    pub(crate) python_path: PythonPath,
+    /// A command to register as the app's `release` process, which is run on each deploy.
+    pub(crate) release: Option<String>,
+    /// The pip requirements file to install the app's dependencies from (relative to the app
+    /// root), instead of `requirements.txt`.
+    pub(crate) requirements_file: Option<String>,
+    /// Whether to exclude parts of the standard library that aren't used at run-time (such as
+    /// its test suite) from the app image. Defaults to enabled.
+    #[serde(default)]
+    pub(crate) slim_stdlib: Option<bool>,
+    /// Whether to remove the test suites bundled with the installed packages from the venv, to
+    /// reduce the size of the app image.
+    #[serde(default)]
+    pub(crate) slim_site_packages: bool,
+    /// Warning classes that shouldn't be shown, for warnings the app's team has chosen to accept.
+    #[serde(default)]
+    pub(crate) suppressed_warnings: Vec<WarningClass>,
+    /// Whether to install the CPU-only variant of `torch`, rather than the default variant
+    /// (which on Linux includes several gigabytes of CUDA libraries).
+    #[serde(default)]
+    pub(crate) torch_cpu: bool,
+    /// A command to run after the app's dependencies are installed, to check that the
+    /// environment works (such as importing the app), with the build failing if it fails.
+    pub(crate) verify_command: Option<String>,
+    /// The Python version to install (such as `3.13`), for apps that can't use a `.python-version`
+    /// file in the app root (such as in a monorepo). Takes priority over the version files.
+    pub(crate) version: Option<String>,
+}
+
+/// The `[com.heroku.buildpacks.python.assets]` table of a `project.toml` file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AssetsConfig {
+    /// A command to run at build time to download the app's assets into the cache.
+    pub(crate) prefetch: Option<String>,
+}
+
+/// An entry in the `[[com.heroku.buildpacks.python.package-indexes]]` array of a `project.toml`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct PackageIndexConfig {
+    /// The name of the index, which when using Poetry must match the name of a package source
+    /// in `pyproject.toml`.
+    pub(crate) name: String,
+    /// The URL of the index (without credentials), which is required when using pip.
+    pub(crate) url: Option<String>,
+    /// Whether the index is used instead of the default index, rather than as an extra index.
+    #[serde(default)]
+    pub(crate) default: bool,
+    /// The env var containing the username to authenticate with.
+    pub(crate) username_env: Option<String>,
+    /// The env var containing the password (or token) to authenticate with.
+    pub(crate) password_env: Option<String>,
+}
+
+/// The `no-build-isolation` setting, which can be either a boolean (to disable build isolation
+/// for all packages), or a list of package names.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum NoBuildIsolation {
+    All(bool),
+    Packages(Vec<String>),
+}
+
+impl Default for NoBuildIsolation {
+    fn default() -> Self {
+        Self::All(false)
+    }
+}
+
+/// The `python-path` setting, which can be either a boolean (to add the app directory to the
+/// Python path), or a list of directories relative to the app directory.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum PythonPath {
+    AppDir(bool),
+    Directories(Vec<String>),
+}
+
+impl Default for PythonPath {
+    fn default() -> Self {
+        Self::AppDir(false)
+    }
+}
+
+impl PythonPath {
+    /// The directories to add to the Python path, relative to the app directory.
+    pub(crate) fn directories(&self) -> Vec<&str> {
+        match self {
+            Self::AppDir(true) => vec!["."],
+            Self::AppDir(false) => Vec::new(),
+            Self::Directories(directories) => directories.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+impl PythonBuildpackConfig {
+    /// Whether the `slim-stdlib` setting is enabled, which it is unless explicitly disabled.
+    pub(crate) fn slim_stdlib(&self) -> bool {
+        self.slim_stdlib.unwrap_or(true)
+    }
+
+    /// The pip requirements file to install from, which is `requirements.txt` unless the
+    /// `requirements-file` setting is configured.
+    pub(crate) fn requirements_file(&self) -> &str {
+        self.requirements_file
+            .as_deref()
+            .unwrap_or(PackageManager::Pip.packages_file())
+    }
+
+    /// The names of the settings that have been configured which aren't supported when using
+    /// the specified package manager.
+    pub(crate) fn unsupported_settings(
+        &self,
+        package_manager: PackageManager,
+    ) -> Vec<&'static str> {
+        use PackageManager::{Pdm, Pip, Poetry};
+        // Pipenv installs packages using its own vendored copy of pip, which can't use keyring
+        // backends installed alongside Pipenv, so keyring packages aren't supported for it.
+        let settings: [(&str, bool, &[PackageManager]); 9] = [
+            (
+                "build-requirements",
+                !self.build_requirements.is_empty(),
+                &[Pip],
+            ),
+            ("cached-packages", !self.cached_packages.is_empty(), &[Pip]),
+            (
+                "keyring-packages",
+                !self.keyring_packages.is_empty(),
+                &[Pdm, Pip, Poetry],
+            ),
+            (
+                "no-build-isolation",
+                self.no_build_isolation.is_enabled(),
+                &[Pip],
+            ),
+            (
+                "package-indexes",
+                !self.package_indexes.is_empty(),
+                &[Pip, Poetry],
+            ),
+            ("poetry-groups", !self.poetry_groups.is_empty(), &[Poetry]),
+            ("poetry-plugins", !self.poetry_plugins.is_empty(), &[Poetry]),
+            (
+                "requirements-file",
+                self.requirements_file.is_some(),
+                &[Pip],
+            ),
+            ("torch-cpu", self.torch_cpu, &[Pip]),
+        ];
+        settings
+            .into_iter()
+            .filter(|(_, configured, supported_package_managers)| {
+                *configured && !supported_package_managers.contains(&package_manager)
+            })
+            .map(|(name, _, _)| name)
+            .collect()
+    }
+}
+
+impl NoBuildIsolation {
+    /// Whether build isolation has been disabled for any packages.
+    pub(crate) fn is_enabled(&self) -> bool {
+        match self {
+            Self::All(all) => *all,
+            Self::Packages(packages) => !packages.is_empty(),
+        }
+    }
+}
+
+impl ProjectToml {
+    /// The buildpack's configuration, which will be empty if the table doesn't exist.
+    pub(crate) fn python_config(&self) -> &PythonBuildpackConfig {
+        &self.com.heroku.buildpacks.python
+    }
+}
+
+/// Read and parse the `project.toml` file in the specified app directory, if it exists.
+pub(crate) fn read_project_toml(
+    app_dir: &Path,
+) -> Result<Option<ProjectToml>, ReadProjectTomlError> {
+    utils::read_optional_file(&app_dir.join("project.toml"))
+        .map_err(ReadProjectTomlError::Io)?
+        .map(|contents| parse(&contents))
+        .transpose()
+}
+
+fn parse(contents: &str) -> Result<ProjectToml, ReadProjectTomlError> {
+    toml::from_str(contents).map_err(ReadProjectTomlError::Parse)
+}
+
+/// Errors that can occur when reading and parsing a `project.toml` file.
+#[derive(Debug)]
+pub(crate) enum ReadProjectTomlError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn read_project_toml_missing() {
+        assert!(read_project_toml(Path::new("tests/fixtures/empty"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn read_project_toml_io_error() {
+        assert!(matches!(
+            read_project_toml(Path::new("tests/fixtures/empty/.gitkeep")),
+            Err(ReadProjectTomlError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn parse_processes() {
+        let project_toml = parse(indoc! {r#"
+            [_]
+            schema-version = "0.2"
+
+            [com.heroku.buildpacks.python.processes]
+            web = "gunicorn myapp.wsgi"
+            worker = "celery --app myapp worker"
+        "#})
+        .unwrap();
+        assert_eq!(
+            project_toml.python_config().processes,
+            BTreeMap::from([
+                ("web".to_string(), "gunicorn myapp.wsgi".to_string()),
+                (
+                    "worker".to_string(),
+                    "celery --app myapp worker".to_string()
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_version() {
+        let project_toml = parse(indoc! {r#"
+            [com.heroku.buildpacks.python]
+            version = "3.12"
+        "#})
+        .unwrap();
+        assert_eq!(
+            project_toml.python_config().version.as_deref(),
+            Some("3.12")
+        );
+        assert_eq!(parse("").unwrap().python_config().version, None);
+    }
+
+    #[test]
+    fn parse_release() {
+        let project_toml = parse(indoc! {r#"
+            [com.heroku.buildpacks.python]
+            release = "python manage.py migrate"
+        "#})
+        .unwrap();
+        assert_eq!(
+            project_toml.python_config().release.as_deref(),
+            Some("python manage.py migrate")
+        );
+        assert_eq!(parse("").unwrap().python_config().release, None);
+    }
+
+    #[test]
+    fn parse_verify_command() {
+        let project_toml = parse(indoc! {r#"
+            [com.heroku.buildpacks.python]
+            verify-command = "pytest --collect-only"
+        "#})
+        .unwrap();
+        assert_eq!(
+            project_toml.python_config().verify_command.as_deref(),
+            Some("pytest --collect-only")
+        );
+    }
+
+    #[test]
+    fn parse_without_buildpack_config() {
+        let project_toml = parse(indoc! {r#"
+            [_]
+            schema-version = "0.2"
+
+            [io.buildpacks]
+            exclude = ["*.md"]
+        "#})
+        .unwrap();
+        assert!(project_toml.python_config().processes.is_empty());
+    }
+
+    #[test]
+    fn parse_build_isolation() {
+        let project_toml = parse(indoc! {r#"
+            [com.heroku.buildpacks.python]
+            build-requirements = ["setuptools", "wheel"]
+            no-build-isolation = ["flash-attn"]
+        "#})
+        .unwrap();
+        let python_config = project_toml.python_config();
+        assert_eq!(python_config.build_requirements, ["setuptools", "wheel"]);
+        assert_eq!(
+            python_config.no_build_isolation,
+            NoBuildIsolation::Packages(vec!["flash-attn".to_string()])
+        );
+        assert!(python_config.no_build_isolation.is_enabled());
+
+        let project_toml =
+            parse("[com.heroku.buildpacks.python]\nno-build-isolation = true").unwrap();
+        assert_eq!(
+            project_toml.python_config().no_build_isolation,
+            NoBuildIsolation::All(true)
+        );
+
+        let project_toml = parse("").unwrap();
+        assert!(!project_toml.python_config().no_build_isolation.is_enabled());
+    }
+
+    #[test]
+    fn parse_python_path() {
+        let project_toml = parse(indoc! {"
+            [com.heroku.buildpacks.python]
+            python-path = true
+        "})
+        .unwrap();
+        assert_eq!(
+            project_toml.python_config().python_path.directories(),
+            ["."]
+        );
+
+        let project_toml = parse(indoc! {r#"
+            [com.heroku.buildpacks.python]
+            python-path = ["src", "lib/vendor"]
+        "#})
+        .unwrap();
+        assert_eq!(
+            project_toml.python_config().python_path.directories(),
+            ["src", "lib/vendor"]
+        );
+
+        assert!(parse("")
+            .unwrap()
+            .python_config()
+            .python_path
+            .directories()
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_assets() {
+        let project_toml = parse(indoc! {r#"
+            [com.heroku.buildpacks.python.assets]
+            prefetch = "python -m myapp.download_models"
+        "#})
+        .unwrap();
+        assert_eq!(
+            project_toml
+                .python_config()
+                .assets
+                .as_ref()
+                .unwrap()
+                .prefetch
+                .as_deref(),
+            Some("python -m myapp.download_models")
+        );
+
+        let project_toml = parse("[com.heroku.buildpacks.python.assets]").unwrap();
+        assert!(project_toml
+            .python_config()
+            .assets
+            .as_ref()
+            .unwrap()
+            .prefetch
+            .is_none());
+
+        assert!(parse("").unwrap().python_config().assets.is_none());
+        assert!(parse("[com.heroku.buildpacks.python.assets]\nunknown = 1").is_err());
+    }
+
+    #[test]
+    fn parse_slim_site_packages() {
+        assert!(!parse("").unwrap().python_config().slim_site_packages);
+        assert!(
+            parse("[com.heroku.buildpacks.python]\nslim-site-packages = true")
+                .unwrap()
+                .python_config()
+                .slim_site_packages
+        );
+        assert!(parse("[com.heroku.buildpacks.python]\nslim-site-packages = 1").is_err());
+    }
+
+    #[test]
+    fn parse_slim_stdlib() {
+        assert!(parse("").unwrap().python_config().slim_stdlib());
+        assert!(parse("[com.heroku.buildpacks.python]\nslim-stdlib = true")
+            .unwrap()
+            .python_config()
+            .slim_stdlib());
+        assert!(
+            !parse("[com.heroku.buildpacks.python]\nslim-stdlib = false")
+                .unwrap()
+                .python_config()
+                .slim_stdlib()
+        );
+    }
+
+    #[test]
+    fn parse_requirements_file() {
+        assert_eq!(
+            parse("").unwrap().python_config().requirements_file(),
+            "requirements.txt"
+        );
+        assert_eq!(
+            parse("[com.heroku.buildpacks.python]\nrequirements-file = \"requirements/prod.txt\"")
+                .unwrap()
+                .python_config()
+                .requirements_file(),
+            "requirements/prod.txt"
+        );
+    }
+
+    #[test]
+    fn unsupported_settings() {
+        let project_toml = parse(indoc! {r#"
+            [com.heroku.buildpacks.python]
+            keyring-packages = ["keyrings.google-artifactregistry-auth"]
+            no-build-isolation = true
+            poetry-groups = ["web"]
+            poetry-plugins = ["poetry-plugin-export"]
+            torch-cpu = true
+
+            [[com.heroku.buildpacks.python.package-indexes]]
+            name = "private"
+        "#})
+        .unwrap();
+        let python_config = project_toml.python_config();
+        assert_eq!(
+            python_config.unsupported_settings(PackageManager::Pip),
+            ["poetry-groups", "poetry-plugins"]
+        );
+        assert_eq!(
+            python_config.unsupported_settings(PackageManager::Poetry),
+            ["no-build-isolation", "torch-cpu"]
+        );
+        assert_eq!(
+            python_config.unsupported_settings(PackageManager::Pdm),
+            [
+                "no-build-isolation",
+                "package-indexes",
+                "poetry-groups",
+                "poetry-plugins",
+                "torch-cpu"
+            ]
+        );
+        assert_eq!(
+            python_config.unsupported_settings(PackageManager::Pipenv),
+            [
+                "keyring-packages",
+                "no-build-isolation",
+                "package-indexes",
+                "poetry-groups",
+                "poetry-plugins",
+                "torch-cpu"
+            ]
+        );
+        assert!(parse("")
+            .unwrap()
+            .python_config()
+            .unsupported_settings(PackageManager::Pipenv)
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_package_indexes() {
+        let project_toml = parse(indoc! {r#"
+            [[com.heroku.buildpacks.python.package-indexes]]
+            name = "private"
+            url = "https://example.com/simple"
+            default = true
+            username-env = "PRIVATE_INDEX_USERNAME"
+            password-env = "PRIVATE_INDEX_PASSWORD"
+
+            [[com.heroku.buildpacks.python.package-indexes]]
+            name = "other"
+        "#})
+        .unwrap();
+        assert_eq!(
+            project_toml.python_config().package_indexes,
+            [
+                PackageIndexConfig {
+                    name: "private".to_string(),
+                    url: Some("https://example.com/simple".to_string()),
+                    default: true,
+                    username_env: Some("PRIVATE_INDEX_USERNAME".to_string()),
+                    password_env: Some("PRIVATE_INDEX_PASSWORD".to_string()),
+                },
+                PackageIndexConfig {
+                    name: "other".to_string(),
+                    url: None,
+                    default: false,
+                    username_env: None,
+                    password_env: None,
+                },
+            ]
+        );
+        assert!(parse(
+            "[[com.heroku.buildpacks.python.package-indexes]]
+name = \"a\"\ntoken = \"b\""
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parse_poetry_groups() {
+        let project_toml = parse(indoc! {r#"
+            [com.heroku.buildpacks.python]
+            poetry-groups = ["web", "worker"]
+        "#})
+        .unwrap();
+        assert_eq!(
+            project_toml.python_config().poetry_groups,
+            ["web", "worker"]
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(matches!(
+            parse("[com.heroku.buildpacks.python]\nunknown = 1"),
+            Err(ReadProjectTomlError::Parse(_))
+        ));
+        assert!(matches!(
+            parse("[com.heroku.buildpacks.python.processes]\nweb = 1"),
+            Err(ReadProjectTomlError::Parse(_))
+        ));
+        assert!(matches!(
+            parse("[com.heroku.buildpacks.python]\nno-build-isolation = \"flash-attn\""),
+            Err(ReadProjectTomlError::Parse(_))
+        ));
+        assert!(matches!(
+            parse("[com.heroku.buildpacks.python]\nsuppressed-warnings = [\"eol\"]"),
+            Err(ReadProjectTomlError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn parse_suppressed_warnings() {
+        let project_toml = parse(indoc! {r#"
+            [com.heroku.buildpacks.python]
+            suppressed-warnings = ["eol-python", "large-files"]
+        "#})
+        .unwrap();
+        assert_eq!(
+            project_toml.python_config().suppressed_warnings,
+            [WarningClass::EolPython, WarningClass::LargeFiles]
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use crate::utils;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+/// The subset of the contents of a `pyproject.toml` file that is used by the buildpack.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PyprojectToml {
+    #[serde(default, rename = "build-system")]
+    pub(crate) build_system: PyprojectTomlBuildSystem,
+    #[serde(default)]
+    pub(crate) project: PyprojectTomlProject,
+    #[serde(default)]
+    pub(crate) tool: PyprojectTomlTool,
+}
+
+/// The `[build-system]` table of a `pyproject.toml` file.
+// https://packaging.python.org/en/latest/specifications/pyproject-toml/#declaring-build-system-dependencies-the-build-system-table
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PyprojectTomlBuildSystem {
+    #[serde(default)]
+    pub(crate) requires: Vec<String>,
+}
+
+/// The `[project]` table of a `pyproject.toml` file.
+// https://packaging.python.org/en/latest/specifications/pyproject-toml/#entry-points
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PyprojectTomlProject {
+    pub(crate) name: Option<String>,
+    #[serde(default)]
+    pub(crate) dependencies: Vec<String>,
+    #[serde(rename = "requires-python")]
+    pub(crate) requires_python: Option<String>,
+    #[serde(default)]
+    pub(crate) scripts: BTreeMap<String, toml::Value>,
+}
+
+/// The `[tool]` table of a `pyproject.toml` file.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PyprojectTomlTool {
+    pub(crate) poetry: Option<PoetryConfig>,
+    #[serde(rename = "poetry-dynamic-versioning")]
+    pub(crate) poetry_dynamic_versioning: Option<PoetryDynamicVersioningConfig>,
+}
+
+/// The `[tool.poetry]` table of a `pyproject.toml` file.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PoetryConfig {
+    pub(crate) name: Option<String>,
+    #[serde(default)]
+    pub(crate) dependencies: BTreeMap<String, toml::Value>,
+    #[serde(default)]
+    pub(crate) scripts: BTreeMap<String, toml::Value>,
+}
+
+/// The `[tool.poetry-dynamic-versioning]` table of a `pyproject.toml` file.
+// https://github.com/mtkennerly/poetry-dynamic-versioning#configuration
+#[derive(Debug, Deserialize)]
+pub(crate) struct PoetryDynamicVersioningConfig {
+    #[serde(default)]
+    pub(crate) enable: bool,
+}
+
+impl PyprojectToml {
+    /// The names of the scripts (console entry points) declared by the project, using either
+    /// the standard `[project.scripts]` table, or Poetry's legacy `[tool.poetry.scripts]` table.
+    pub(crate) fn script_names(&self) -> Vec<&str> {
+        self.project
+            .scripts
+            .keys()
+            .chain(
+                self.tool
+                    .poetry
+                    .iter()
+                    .flat_map(|poetry| poetry.scripts.keys()),
+            )
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The name of the project, using either the standard `[project.name]` field, or Poetry's
+    /// legacy `[tool.poetry.name]` field.
+    pub(crate) fn project_name(&self) -> Option<&str> {
+        self.project
+            .name
+            .as_deref()
+            .or_else(|| self.tool.poetry.as_ref()?.name.as_deref())
+    }
+
+    /// The normalized names of the project's direct (main group) dependencies, from either the
+    /// standard `[project.dependencies]` array, or Poetry's legacy `[tool.poetry.dependencies]`
+    /// table (excluding its `python` entry, which is the supported Python version range).
+    pub(crate) fn dependency_names(&self) -> Vec<String> {
+        self.project
+            .dependencies
+            .iter()
+            .map(|requirement| {
+                requirement
+                    .split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+                    .next()
+                    .unwrap_or_default()
+            })
+            .chain(
+                self.tool
+                    .poetry
+                    .iter()
+                    .flat_map(|poetry| poetry.dependencies.keys().map(String::as_str))
+                    .filter(|name| *name != "python"),
+            )
+            .filter(|name| !name.is_empty())
+            .map(utils::normalize_package_name)
+            .collect()
+    }
+
+    /// Whether the project has enabled the `poetry-dynamic-versioning` Poetry plugin.
+    pub(crate) fn uses_poetry_dynamic_versioning(&self) -> bool {
+        self.tool
+            .poetry_dynamic_versioning
+            .as_ref()
+            .is_some_and(|config| config.enable)
+    }
+}
+
+/// Read and parse the `pyproject.toml` file in the specified app directory, if it exists.
+pub(crate) fn read_pyproject_toml(
+    app_dir: &Path,
+) -> Result<Option<PyprojectToml>, ReadPyprojectTomlError> {
+    utils::read_optional_file(&app_dir.join("pyproject.toml"))
+        .map_err(ReadPyprojectTomlError::Io)?
+        .map(|contents| parse(&contents))
+        .transpose()
+}
+
+fn parse(contents: &str) -> Result<PyprojectToml, ReadPyprojectTomlError> {
+    toml::from_str(contents).map_err(ReadPyprojectTomlError::Parse)
+}
+
+/// Errors that can occur when reading and parsing a `pyproject.toml` file.
+#[derive(Debug)]
+pub(crate) enum ReadPyprojectTomlError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn read_pyproject_toml_valid() {
+        assert!(
+            read_pyproject_toml(Path::new("tests/fixtures/pyproject_toml_only"))
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn read_pyproject_toml_missing() {
+        assert!(read_pyproject_toml(Path::new("tests/fixtures/empty"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn read_pyproject_toml_io_error() {
+        assert!(matches!(
+            read_pyproject_toml(Path::new("tests/fixtures/empty/.gitkeep")),
+            Err(ReadPyprojectTomlError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(matches!(
+            parse("[tool"),
+            Err(ReadPyprojectTomlError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn parse_build_system() {
+        assert_eq!(
+            parse(indoc! {r#"
+                [build-system]
+                requires = ["hatchling>=1.26", "hatch-vcs"]
+                build-backend = "hatchling.build"
+            "#})
+            .unwrap()
+            .build_system
+            .requires,
+            ["hatchling>=1.26", "hatch-vcs"]
+        );
+        assert!(parse("").unwrap().build_system.requires.is_empty());
+    }
+
+    #[test]
+    fn script_names() {
+        assert_eq!(
+            parse(indoc! {r#"
+                [project.scripts]
+                serve = "myapp.server:main"
+
+                [tool.poetry.scripts]
+                worker = "myapp.worker:main"
+            "#})
+            .unwrap()
+            .script_names(),
+            ["serve", "worker"]
+        );
+        assert!(parse("").unwrap().script_names().is_empty());
+    }
+
+    #[test]
+    fn project_name() {
+        assert_eq!(
+            parse("[project]\nname = 'my-app'").unwrap().project_name(),
+            Some("my-app")
+        );
+        assert_eq!(
+            parse("[tool.poetry]\nname = 'legacy-app'")
+                .unwrap()
+                .project_name(),
+            Some("legacy-app")
+        );
+        assert_eq!(parse("").unwrap().project_name(), None);
+    }
+
+    #[test]
+    fn dependency_names() {
+        assert_eq!(
+            parse(indoc! {r#"
+                [project]
+                dependencies = ["Django>=5.1", "typing_extensions ; python_version < '3.13'"]
+
+                [tool.poetry.dependencies]
+                python = "^3.12"
+                gunicorn = "^23.0"
+            "#})
+            .unwrap()
+            .dependency_names(),
+            ["django", "typing-extensions", "gunicorn"]
+        );
+        assert!(parse("").unwrap().dependency_names().is_empty());
+    }
+
+    #[test]
+    fn uses_poetry_dynamic_versioning() {
+        assert!(parse(indoc! {"
+            [tool.poetry-dynamic-versioning]
+            enable = true
+        "})
+        .unwrap()
+        .uses_poetry_dynamic_versioning());
+
+        assert!(!parse(indoc! {"
+            [tool.poetry-dynamic-versioning]
+            vcs = \"git\"
+        "})
+        .unwrap()
+        .uses_poetry_dynamic_versioning());
+
+        assert!(!parse("[tool.poetry]\npackage-mode = false")
+            .unwrap()
+            .uses_poetry_dynamic_versioning());
+    }
+}
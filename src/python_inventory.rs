@@ -0,0 +1,216 @@
+use crate::python_version::PythonVersion;
+use libcnb::Target;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+// The inventory is embedded in the buildpack binary at compile time, so that it's always
+// consistent with the buildpack version, and so can't be tampered with independently of it.
+static PYTHON_INVENTORY: LazyLock<PythonInventory> = LazyLock::new(|| {
+    toml::from_str(include_str!("../python-inventory.toml"))
+        .expect("python-inventory.toml must be a valid Python inventory")
+});
+
+/// The catalogue of Python releases that can be installed by the buildpack.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct PythonInventory {
+    base_url: String,
+    /// The latest release of each supported Python `X.Y` version, newest first.
+    pub(crate) releases: Vec<PythonRelease>,
+    /// The SHA-256 checksums of the Python runtime archives (as hex strings), keyed by archive
+    /// name. Covers every patch release of the supported `X.Y` versions, not just the latest.
+    #[serde(default)]
+    checksums: BTreeMap<String, String>,
+}
+
+/// A Python release in the inventory, along with the targets it's available for.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct PythonRelease {
+    #[serde(deserialize_with = "deserialize_python_version")]
+    pub(crate) version: PythonVersion,
+    targets: Vec<String>,
+}
+
+/// Returns the Python inventory embedded in the buildpack.
+pub(crate) fn python_inventory() -> &'static PythonInventory {
+    &PYTHON_INVENTORY
+}
+
+impl PythonInventory {
+    /// The latest release of the specified Python `X.Y` version, if it's supported by the buildpack.
+    pub(crate) fn latest_release(&self, major: u16, minor: u16) -> Option<&PythonRelease> {
+        self.releases
+            .iter()
+            .find(|release| (release.version.major, release.version.minor) == (major, minor))
+    }
+
+    /// The oldest Python release supported by the buildpack.
+    pub(crate) fn oldest_release(&self) -> Option<&PythonRelease> {
+        self.releases.last()
+    }
+
+    /// The URL from which the specified archive can be downloaded.
+    pub(crate) fn archive_url(&self, archive_name: &str) -> String {
+        format!("{}/{archive_name}", self.base_url)
+    }
+
+    /// The expected SHA-256 checksum of the specified archive, if it's listed in the inventory.
+    pub(crate) fn archive_sha256(&self, archive_name: &str) -> Option<&str> {
+        self.checksums.get(archive_name).map(String::as_str)
+    }
+}
+
+impl PythonRelease {
+    /// Whether this release has been built for the specified target.
+    pub(crate) fn is_available_for(&self, target: &Target) -> bool {
+        self.targets.contains(&target_name(target))
+    }
+}
+
+// The name of a target in the inventory, in the format `<distro_name>-<distro_version>-<arch>`.
+fn target_name(target: &Target) -> String {
+    let Target {
+        arch,
+        distro_name,
+        distro_version,
+        ..
+    } = target;
+    format!("{distro_name}-{distro_version}-{arch}")
+}
+
+fn deserialize_python_version<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<PythonVersion, D::Error> {
+    let version = String::deserialize(deserializer)?;
+    match version
+        .split('.')
+        .map(str::parse)
+        .collect::<Result<Vec<u16>, _>>()
+        .unwrap_or_default()[..]
+    {
+        [major, minor, patch] => Ok(PythonVersion::new(major, minor, patch)),
+        _ => Err(serde::de::Error::custom(format!(
+            "invalid Python version '{version}', must be in the format 'X.Y.Z'"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(distro_version: &str, arch: &str) -> Target {
+        Target {
+            os: "linux".to_string(),
+            arch: arch.to_string(),
+            arch_variant: None,
+            distro_name: "ubuntu".to_string(),
+            distro_version: distro_version.to_string(),
+        }
+    }
+
+    #[test]
+    fn python_inventory_is_valid() {
+        let releases = &python_inventory().releases;
+        assert!(!releases.is_empty());
+        for pair in releases.windows(2) {
+            // Releases must be ordered newest first, with only one release per `X.Y` version.
+            assert!(
+                (pair[0].version.major, pair[0].version.minor)
+                    > (pair[1].version.major, pair[1].version.minor),
+                "{} must be newer than {}",
+                pair[0].version,
+                pair[1].version
+            );
+        }
+        // Checksums can only be listed for the patch releases of the supported `X.Y` versions (up
+        // to the latest one), and for the targets those versions have been built for.
+        let mut archive_names = Vec::new();
+        for release in releases {
+            assert!(!release.targets.is_empty());
+            for patch in 0..=release.version.patch {
+                let python_version =
+                    PythonVersion::new(release.version.major, release.version.minor, patch);
+                for target_name in &release.targets {
+                    let [_, distro_version, arch] = target_name.split('-').collect::<Vec<_>>()[..]
+                    else {
+                        panic!("invalid target {target_name}");
+                    };
+                    archive_names.push(python_version.archive_name(&target(distro_version, arch)));
+                }
+            }
+        }
+        for (archive_name, checksum) in &python_inventory().checksums {
+            assert!(
+                archive_names.contains(archive_name),
+                "unknown archive {archive_name}"
+            );
+            assert!(
+                checksum.len() == 64 && checksum.chars().all(|c| c.is_ascii_hexdigit()),
+                "invalid checksum for {archive_name}"
+            );
+        }
+    }
+
+    #[test]
+    fn python_inventory_latest_release() {
+        let inventory = python_inventory();
+        assert_eq!(
+            inventory
+                .latest_release(3, 13)
+                .map(|release| &release.version),
+            inventory.releases.first().map(|release| &release.version)
+        );
+        assert!(inventory.latest_release(3, 7).is_none());
+        assert!(inventory.latest_release(4, 0).is_none());
+    }
+
+    #[test]
+    fn python_release_is_available_for() {
+        let release: PythonRelease = toml::from_str(
+            r#"
+                version = "3.8.20"
+                targets = ["ubuntu-20.04-amd64"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(release.version, PythonVersion::new(3, 8, 20));
+        assert!(release.is_available_for(&target("20.04", "amd64")));
+        assert!(!release.is_available_for(&target("24.04", "amd64")));
+        assert!(!release.is_available_for(&target("20.04", "arm64")));
+    }
+
+    #[test]
+    fn python_inventory_archive_sha256() {
+        let inventory: PythonInventory = toml::from_str(
+            r#"
+                base_url = "https://example.com"
+                releases = []
+                [checksums]
+                "python-3.13.1-ubuntu-24.04-amd64.tar.zst" = "abc123"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            inventory.archive_sha256("python-3.13.1-ubuntu-24.04-amd64.tar.zst"),
+            Some("abc123")
+        );
+        assert_eq!(
+            inventory.archive_sha256("python-3.13.1-ubuntu-24.04-arm64.tar.zst"),
+            None
+        );
+    }
+
+    #[test]
+    fn python_release_invalid_version() {
+        assert!(toml::from_str::<PythonRelease>(
+            r#"
+                version = "3.13"
+                targets = ["ubuntu-24.04-amd64"]
+            "#,
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::build_log::log_info;
+use crate::python_version::PythonVersion;
+use std::path::{Component, Path, PathBuf};
+use std::{fs, io};
+
+// The name of the `.pth` file written to the venv's `site-packages` directory. Python adds each
+// directory listed in `.pth` files in `site-packages` to `sys.path` on startup:
+// https://docs.python.org/3/library/site.html
+const PTH_FILE_NAME: &str = "_heroku_python_path.pth";
+
+/// Add the specified directories (which are relative to the app directory) to the Python path of
+/// the venv, using a `.pth` file.
+///
+/// This allows processes that aren't run from the app directory (such as scripts and background
+/// workers started from elsewhere) to import the app's modules, without having to set `PYTHONPATH`.
+/// If no directories are configured, any `.pth` file from a previous build is removed, since the
+/// Poetry venv layer is cached between builds.
+pub(crate) fn configure_python_path(
+    app_dir: &Path,
+    dependencies_layer_dir: &Path,
+    python_version: &PythonVersion,
+    directories: &[&str],
+) -> Result<(), PythonPathError> {
+    let pth_file_path = dependencies_layer_dir
+        .join(format!(
+            "lib/python{}.{}/site-packages",
+            python_version.major, python_version.minor
+        ))
+        .join(PTH_FILE_NAME);
+
+    if directories.is_empty() {
+        return match fs::remove_file(&pth_file_path) {
+            Err(io_error) if io_error.kind() != io::ErrorKind::NotFound => {
+                Err(PythonPathError::WritePthFile(io_error))
+            }
+            _ => Ok(()),
+        };
+    }
+
+    let mut paths = Vec::new();
+    for directory in directories {
+        let path = resolve_directory(app_dir, directory)
+            .ok_or_else(|| PythonPathError::InvalidDirectory((*directory).to_string()))?;
+        if !path
+            .try_exists()
+            .map_err(PythonPathError::CheckDirectoryExists)?
+        {
+            return Err(PythonPathError::DirectoryNotFound((*directory).to_string()));
+        }
+        log_info(format!("Adding {} to the Python path", path.display()));
+        paths.push(path.to_string_lossy().into_owned());
+    }
+
+    fs::write(&pth_file_path, format!("{}\n", paths.join("\n")))
+        .map_err(PythonPathError::WritePthFile)
+}
+
+// Directories must be within the app directory, since the app's other files aren't
+// guaranteed to be present at run-time.
+fn resolve_directory(app_dir: &Path, directory: &str) -> Option<PathBuf> {
+    let mut path = app_dir.to_path_buf();
+    for component in Path::new(directory).components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(name) => path.push(name),
+            Component::ParentDir | Component::Prefix(_) | Component::RootDir => return None,
+        }
+    }
+    Some(path)
+}
+
+/// Errors that can occur when adding the configured directories to the Python path.
+#[derive(Debug)]
+pub(crate) enum PythonPathError {
+    CheckDirectoryExists(io::Error),
+    DirectoryNotFound(String),
+    InvalidDirectory(String),
+    WritePthFile(io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_directory_valid() {
+        let app_dir = Path::new("/workspace");
+        assert_eq!(
+            resolve_directory(app_dir, "."),
+            Some(PathBuf::from("/workspace"))
+        );
+        assert_eq!(
+            resolve_directory(app_dir, "./src/"),
+            Some(PathBuf::from("/workspace/src"))
+        );
+        assert_eq!(
+            resolve_directory(app_dir, "lib/vendor"),
+            Some(PathBuf::from("/workspace/lib/vendor"))
+        );
+    }
+
+    #[test]
+    fn resolve_directory_outside_app_dir() {
+        let app_dir = Path::new("/workspace");
+        assert_eq!(resolve_directory(app_dir, "/usr/lib"), None);
+        assert_eq!(resolve_directory(app_dir, "src/../../other"), None);
+    }
+}
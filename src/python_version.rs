@@ -1,11 +1,19 @@
+use crate::pyproject_toml::{self, ReadPyprojectTomlError};
+use crate::python_inventory::python_inventory;
 use crate::python_version_file::{self, ParsePythonVersionFileError};
 use crate::runtime_txt::{self, ParseRuntimeTxtError};
+use crate::strict_mode::{StrictMode, StrictModeError, WarningClass};
 use crate::utils;
-use libcnb::Target;
+use indoc::formatdoc;
+use libcnb::{Env, Target};
+use std::cmp::Ordering;
 use std::fmt::{self, Display};
 use std::io;
 use std::path::Path;
 
+/// The env var that can be used to request a Python version, overriding any version files.
+pub(crate) const PYTHON_VERSION_ENV_VAR: &str = "PYTHON_VERSION";
+
 /// The Python version that will be installed if the project does not specify an explicit version.
 pub(crate) const DEFAULT_PYTHON_VERSION: RequestedPythonVersion = RequestedPythonVersion {
     major: 3,
@@ -13,14 +21,20 @@ pub(crate) const DEFAULT_PYTHON_VERSION: RequestedPythonVersion = RequestedPytho
     patch: None,
     origin: PythonVersionOrigin::BuildpackDefault,
 };
-pub(crate) const DEFAULT_PYTHON_FULL_VERSION: PythonVersion = LATEST_PYTHON_3_13;
 
-pub(crate) const LATEST_PYTHON_3_8: PythonVersion = PythonVersion::new(3, 8, 20);
-pub(crate) const LATEST_PYTHON_3_9: PythonVersion = PythonVersion::new(3, 9, 21);
-pub(crate) const LATEST_PYTHON_3_10: PythonVersion = PythonVersion::new(3, 10, 16);
-pub(crate) const LATEST_PYTHON_3_11: PythonVersion = PythonVersion::new(3, 11, 11);
-pub(crate) const LATEST_PYTHON_3_12: PythonVersion = PythonVersion::new(3, 12, 8);
-pub(crate) const LATEST_PYTHON_3_13: PythonVersion = PythonVersion::new(3, 13, 1);
+/// The specific Python version that will be installed if the project does not specify an
+/// explicit version, which is the latest release of [`DEFAULT_PYTHON_VERSION`].
+pub(crate) fn default_python_full_version() -> PythonVersion {
+    latest_python_version(DEFAULT_PYTHON_VERSION.major, DEFAULT_PYTHON_VERSION.minor)
+        .expect("The default Python version must be in the Python inventory")
+}
+
+/// The latest release of the specified Python `X.Y` version, if it's supported by the buildpack.
+pub(crate) fn latest_python_version(major: u16, minor: u16) -> Option<PythonVersion> {
+    python_inventory()
+        .latest_release(major, minor)
+        .map(|release| release.version.clone())
+}
 
 /// The Python version that was requested for a project.
 #[derive(Clone, Debug, PartialEq)]
@@ -51,14 +65,30 @@ impl Display for RequestedPythonVersion {
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) enum PythonVersionOrigin {
     BuildpackDefault,
+    EnvVar,
+    ProjectToml,
+    PyprojectToml,
     PythonVersionFile,
     RuntimeTxt,
 }
 
+impl PythonVersionOrigin {
+    /// Where the requested version can be changed, for use in error and warning messages.
+    pub(crate) fn config_location(&self) -> String {
+        match self {
+            Self::EnvVar => format!("the {PYTHON_VERSION_ENV_VAR} env var"),
+            origin => format!("the {origin} file"),
+        }
+    }
+}
+
 impl Display for PythonVersionOrigin {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::BuildpackDefault => write!(f, "buildpack default"),
+            Self::EnvVar => write!(f, "{PYTHON_VERSION_ENV_VAR}"),
+            Self::ProjectToml => write!(f, "project.toml"),
+            Self::PyprojectToml => write!(f, "pyproject.toml"),
             Self::PythonVersionFile => write!(f, ".python-version"),
             Self::RuntimeTxt => write!(f, "runtime.txt"),
         }
@@ -82,8 +112,19 @@ impl PythonVersion {
         }
     }
 
-    // TODO: (W-11474658) Switch to tracking versions/URLs via a manifest file.
+    /// Whether this Python version has reached its upstream end-of-life (and so no longer receives
+    /// security updates), but is still supported by the buildpack for compatibility.
+    pub(crate) fn is_eol(&self) -> bool {
+        matches!((self.major, self.minor), (3, ..=8))
+    }
+
+    /// The URL of the Python runtime archive for this version and target, from the inventory.
     pub(crate) fn url(&self, target: &Target) -> String {
+        python_inventory().archive_url(&self.archive_name(target))
+    }
+
+    /// The file name of the Python runtime archive for this version and target.
+    pub(crate) fn archive_name(&self, target: &Target) -> String {
         let Self {
             major,
             minor,
@@ -95,9 +136,7 @@ impl PythonVersion {
             distro_version,
             ..
         } = target;
-        format!(
-            "https://heroku-buildpack-python.s3.us-east-1.amazonaws.com/python-{major}.{minor}.{patch}-{distro_name}-{distro_version}-{arch}.tar.zst"
-        )
+        format!("python-{major}.{minor}.{patch}-{distro_name}-{distro_version}-{arch}.tar.zst")
     }
 }
 
@@ -114,11 +153,26 @@ impl Display for PythonVersion {
 
 /// Determine the Python version that has been requested for the project.
 ///
-/// If no known version specifier file is found a default Python version will be used.
+/// A version set using the `PYTHON_VERSION` env var or the `version` setting in `project.toml`
+/// takes priority over the version specifier files, for apps (such as in monorepos) that can't
+/// add a `.python-version` file to the app root.
+///
+/// If no known version specifier file is found, then the newest supported Python version
+/// compatible with the `requires-python` field in `pyproject.toml` is used, and failing that,
+/// a default Python version.
 pub(crate) fn read_requested_python_version(
     app_dir: &Path,
+    env: &Env,
+    configured_version: Option<&str>,
 ) -> Result<RequestedPythonVersion, RequestedPythonVersionError> {
-    if let Some(contents) = utils::read_optional_file(&app_dir.join("runtime.txt"))
+    if let Some(version) = env.get_string_lossy(PYTHON_VERSION_ENV_VAR) {
+        parse_configured_version(&version, PythonVersionOrigin::EnvVar)
+            .ok_or(RequestedPythonVersionError::InvalidEnvVarVersion(version))
+    } else if let Some(version) = configured_version {
+        parse_configured_version(version, PythonVersionOrigin::ProjectToml).ok_or_else(|| {
+            RequestedPythonVersionError::InvalidProjectTomlVersion(version.to_string())
+        })
+    } else if let Some(contents) = utils::read_optional_file(&app_dir.join("runtime.txt"))
         .map_err(RequestedPythonVersionError::ReadRuntimeTxt)?
     {
         runtime_txt::parse(&contents).map_err(RequestedPythonVersionError::ParseRuntimeTxt)
@@ -127,24 +181,183 @@ pub(crate) fn read_requested_python_version(
     {
         python_version_file::parse(&contents)
             .map_err(RequestedPythonVersionError::ParsePythonVersionFile)
+    } else if let Some(requires_python) = pyproject_toml::read_pyproject_toml(app_dir)
+        .map_err(RequestedPythonVersionError::ReadPyprojectToml)?
+        .and_then(|pyproject_toml| pyproject_toml.project.requires_python)
+    {
+        resolve_requires_python(&requires_python)
+            .map_err(RequestedPythonVersionError::ResolveRequiresPython)
     } else {
         Ok(DEFAULT_PYTHON_VERSION)
     }
 }
 
+// Parses a version from the env var or `project.toml`, which (as for `.python-version`) can be
+// either `X.Y`, `X.Y.*` or `X.Y.Z`.
+fn parse_configured_version(
+    version: &str,
+    origin: PythonVersionOrigin,
+) -> Option<RequestedPythonVersion> {
+    let (major, minor, patch) = match version
+        .trim()
+        .split('.')
+        .map(|part| match part {
+            "*" => Ok(None),
+            _ => part.parse().map(Some),
+        })
+        .collect::<Result<Vec<Option<u16>>, _>>()
+        .ok()?[..]
+    {
+        [Some(major), Some(minor), Some(patch)] => (major, minor, Some(patch)),
+        [Some(major), Some(minor)] | [Some(major), Some(minor), None] => (major, minor, None),
+        _ => return None,
+    };
+    Some(RequestedPythonVersion {
+        major,
+        minor,
+        patch,
+        origin,
+    })
+}
+
 /// Errors that can occur when determining which Python version was requested for a project.
 #[derive(Debug)]
 pub(crate) enum RequestedPythonVersionError {
+    /// The Python version in the `PYTHON_VERSION` env var isn't in a supported format.
+    InvalidEnvVarVersion(String),
+    /// The Python version in the `version` setting in `project.toml` isn't in a supported format.
+    InvalidProjectTomlVersion(String),
     /// Errors parsing a `.python-version` file.
     ParsePythonVersionFile(ParsePythonVersionFileError),
     /// Errors parsing a `runtime.txt` file.
     ParseRuntimeTxt(ParseRuntimeTxtError),
+    /// Errors reading a `pyproject.toml` file.
+    ReadPyprojectToml(ReadPyprojectTomlError),
     /// Errors reading a `.python-version` file.
     ReadPythonVersionFile(io::Error),
     /// Errors reading a `runtime.txt` file.
     ReadRuntimeTxt(io::Error),
+    /// Errors finding a Python version compatible with `requires-python` in `pyproject.toml`.
+    ResolveRequiresPython(ResolveRequiresPythonError),
 }
 
+/// Find the newest supported Python `X.Y` version whose latest release satisfies the
+/// specified `requires-python` version specifiers (such as `>=3.11,<3.13`).
+// https://packaging.python.org/en/latest/specifications/version-specifiers/
+fn resolve_requires_python(
+    requires_python: &str,
+) -> Result<RequestedPythonVersion, ResolveRequiresPythonError> {
+    let specifiers = requires_python
+        .split(',')
+        .map(str::trim)
+        .filter(|clause| !clause.is_empty())
+        .map(VersionSpecifier::parse)
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| ResolveRequiresPythonError::InvalidSpecifier(requires_python.to_string()))?;
+
+    python_inventory()
+        .releases
+        .iter()
+        .map(|release| &release.version)
+        .find(|version| {
+            specifiers
+                .iter()
+                .all(|specifier| specifier.is_satisfied_by(version))
+        })
+        .map(|version| RequestedPythonVersion {
+            major: version.major,
+            minor: version.minor,
+            patch: None,
+            origin: PythonVersionOrigin::PyprojectToml,
+        })
+        .ok_or_else(|| ResolveRequiresPythonError::NoCompatibleVersion(requires_python.to_string()))
+}
+
+/// A single clause of a version specifier, such as `>=3.11` or `==3.12.*`.
+///
+/// Only versions consisting of a numeric release segment are supported, since pre-release
+/// and local versions aren't meaningful for choosing a Python version.
+struct VersionSpecifier {
+    operator: &'static str,
+    release: Vec<u16>,
+    wildcard: bool,
+}
+
+impl VersionSpecifier {
+    fn parse(clause: &str) -> Option<Self> {
+        // Longer operators must be checked first, since e.g. `==` is a prefix of `===`.
+        let operator = ["===", "~=", "==", "!=", "<=", ">=", "<", ">"]
+            .into_iter()
+            .find(|operator| clause.starts_with(operator))?;
+        let version = clause[operator.len()..].trim();
+        let (version, wildcard) = match version.strip_suffix(".*") {
+            Some(version) if matches!(operator, "==" | "!=") => (version, true),
+            _ => (version, false),
+        };
+        let release = version
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect::<Option<Vec<u16>>>()?;
+        if operator == "~=" && release.len() < 2 {
+            return None;
+        }
+        Some(Self {
+            operator,
+            release,
+            wildcard,
+        })
+    }
+
+    fn is_satisfied_by(&self, version: &PythonVersion) -> bool {
+        let version = [version.major, version.minor, version.patch];
+        let ordering = compare_releases(&version, &self.release);
+        match (self.operator, self.wildcard) {
+            ("==", true) => has_release_prefix(&version, &self.release),
+            ("!=", true) => !has_release_prefix(&version, &self.release),
+            ("==" | "===", _) => ordering == Ordering::Equal,
+            ("!=", _) => ordering != Ordering::Equal,
+            ("<", _) => ordering == Ordering::Less,
+            ("<=", _) => ordering != Ordering::Greater,
+            (">", _) => ordering == Ordering::Greater,
+            (">=", _) => ordering != Ordering::Less,
+            // `~=X.Y.Z` is equivalent to `>=X.Y.Z, ==X.Y.*`.
+            _ => {
+                ordering != Ordering::Less
+                    && has_release_prefix(&version, &self.release[..self.release.len() - 1])
+            }
+        }
+    }
+}
+
+/// Compares two release segments, treating any missing trailing components as zero.
+fn compare_releases(left: &[u16], right: &[u16]) -> Ordering {
+    (0..left.len().max(right.len()))
+        .map(|index| {
+            let left = left.get(index).unwrap_or(&0);
+            let right = right.get(index).unwrap_or(&0);
+            left.cmp(right)
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+fn has_release_prefix(release: &[u16], prefix: &[u16]) -> bool {
+    prefix
+        .iter()
+        .enumerate()
+        .all(|(index, component)| release.get(index).unwrap_or(&0) == component)
+}
+
+/// Errors that can occur when resolving the `requires-python` field in `pyproject.toml`.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ResolveRequiresPythonError {
+    InvalidSpecifier(String),
+    NoCompatibleVersion(String),
+}
+
+/// Resolve a requested Python version to a specific Python version, using the releases in the
+/// Python inventory. `X.Y` versions resolve to the latest release of that version, whereas exact
+/// `X.Y.Z` versions are used as-is, as long as they aren't newer than that latest release.
 pub(crate) fn resolve_python_version(
     requested_python_version: &RequestedPythonVersion,
 ) -> Result<PythonVersion, ResolvePythonVersionError> {
@@ -155,21 +368,52 @@ pub(crate) fn resolve_python_version(
         ..
     } = requested_python_version;
 
-    match (major, minor, patch) {
-        (..3, _, _) | (3, ..8, _) => Err(ResolvePythonVersionError::EolVersion(
-            requested_python_version.clone(),
-        )),
-        (3, 8, None) => Ok(LATEST_PYTHON_3_8),
-        (3, 9, None) => Ok(LATEST_PYTHON_3_9),
-        (3, 10, None) => Ok(LATEST_PYTHON_3_10),
-        (3, 11, None) => Ok(LATEST_PYTHON_3_11),
-        (3, 12, None) => Ok(LATEST_PYTHON_3_12),
-        (3, 13, None) => Ok(LATEST_PYTHON_3_13),
-        (3, 14.., _) | (4.., _, _) => Err(ResolvePythonVersionError::UnknownVersion(
-            requested_python_version.clone(),
-        )),
-        (major, minor, Some(patch)) => Ok(PythonVersion::new(major, minor, patch)),
+    let latest_release = latest_python_version(major, minor);
+    let python_version = match patch {
+        None => latest_release,
+        Some(patch) => latest_release
+            .filter(|latest_release| patch <= latest_release.patch)
+            .map(|_| PythonVersion::new(major, minor, patch)),
+    };
+
+    python_version.ok_or_else(|| {
+        let is_older_than_supported = python_inventory()
+            .oldest_release()
+            .is_some_and(|oldest| (major, minor) < (oldest.version.major, oldest.version.minor));
+        if is_older_than_supported {
+            ResolvePythonVersionError::EolVersion(requested_python_version.clone())
+        } else {
+            ResolvePythonVersionError::UnknownVersion(requested_python_version.clone())
+        }
+    })
+}
+
+/// Warn if the Python version has reached its upstream end-of-life, or fail the build instead
+/// if strict mode has been enabled for EOL Python versions.
+pub(crate) fn warn_if_eol(
+    python_version: &PythonVersion,
+    requested_python_version: &RequestedPythonVersion,
+    strict_mode: &StrictMode,
+) -> Result<(), StrictModeError> {
+    if !python_version.is_eol() {
+        return Ok(());
     }
+
+    let PythonVersion { major, minor, .. } = python_version;
+    let config_location = requested_python_version.origin.config_location();
+    strict_mode.warn(
+        WarningClass::EolPython,
+        "Python version has reached end-of-life",
+        &formatdoc! {"
+            Python {major}.{minor} has reached its upstream end-of-life, and is
+            therefore no longer receiving security updates:
+            https://devguide.python.org/versions/#supported-versions
+
+            Support for this Python version will be removed from this buildpack
+            in the future. Upgrade to a newer Python version as soon as possible,
+            by updating the version configured via {config_location}.
+        "},
+    )
 }
 
 /// Errors that can occur when resolving a requested Python version to a specific Python version.
@@ -210,12 +454,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn python_version_is_eol() {
+        assert!(PythonVersion::new(3, 8, 20).is_eol());
+        assert!(!PythonVersion::new(3, 9, 21).is_eol());
+        assert!(!default_python_full_version().is_eol());
+    }
+
     #[test]
     fn read_requested_python_version_runtime_txt() {
         assert_eq!(
-            read_requested_python_version(Path::new(
-                "tests/fixtures/runtime_txt_and_python_version_file"
-            ))
+            read_requested_python_version(
+                Path::new("tests/fixtures/runtime_txt_and_python_version_file"),
+                &Env::new(),
+                None
+            )
             .unwrap(),
             RequestedPythonVersion {
                 major: 3,
@@ -225,13 +478,21 @@ mod tests {
             }
         );
         assert!(matches!(
-            read_requested_python_version(Path::new("tests/fixtures/runtime_txt_invalid_unicode"))
-                .unwrap_err(),
+            read_requested_python_version(
+                Path::new("tests/fixtures/runtime_txt_invalid_unicode"),
+                &Env::new(),
+                None
+            )
+            .unwrap_err(),
             RequestedPythonVersionError::ReadRuntimeTxt(_)
         ));
         assert!(matches!(
-            read_requested_python_version(Path::new("tests/fixtures/runtime_txt_invalid_version"))
-                .unwrap_err(),
+            read_requested_python_version(
+                Path::new("tests/fixtures/runtime_txt_invalid_version"),
+                &Env::new(),
+                None
+            )
+            .unwrap_err(),
             RequestedPythonVersionError::ParseRuntimeTxt(_)
         ));
     }
@@ -239,7 +500,12 @@ mod tests {
     #[test]
     fn read_requested_python_version_python_version_file() {
         assert_eq!(
-            read_requested_python_version(Path::new("tests/fixtures/python_3.7")).unwrap(),
+            read_requested_python_version(
+                Path::new("tests/fixtures/python_3.7"),
+                &Env::new(),
+                None
+            )
+            .unwrap(),
             RequestedPythonVersion {
                 major: 3,
                 minor: 7,
@@ -248,26 +514,96 @@ mod tests {
             }
         );
         assert!(matches!(
-            read_requested_python_version(Path::new(
-                "tests/fixtures/python_version_file_invalid_unicode"
-            ))
+            read_requested_python_version(
+                Path::new("tests/fixtures/python_version_file_invalid_unicode"),
+                &Env::new(),
+                None
+            )
             .unwrap_err(),
             RequestedPythonVersionError::ReadPythonVersionFile(_)
         ));
         assert!(matches!(
-            read_requested_python_version(Path::new(
-                "tests/fixtures/python_version_file_invalid_version"
-            ))
+            read_requested_python_version(
+                Path::new("tests/fixtures/python_version_file_invalid_version"),
+                &Env::new(),
+                None
+            )
             .unwrap_err(),
             RequestedPythonVersionError::ParsePythonVersionFile(_)
         ));
     }
 
+    #[test]
+    fn read_requested_python_version_requires_python() {
+        assert_eq!(
+            read_requested_python_version(
+                Path::new("tests/fixtures/python_version_requires_python"),
+                &Env::new(),
+                None
+            )
+            .unwrap(),
+            RequestedPythonVersion {
+                major: 3,
+                minor: 12,
+                patch: None,
+                origin: PythonVersionOrigin::PyprojectToml,
+            }
+        );
+    }
+
+    #[test]
+    fn resolve_requires_python_valid() {
+        let resolved_minor_version = |requires_python| {
+            resolve_requires_python(requires_python)
+                .map(|requested_python_version| requested_python_version.minor)
+        };
+        assert_eq!(
+            resolved_minor_version(">=3.8"),
+            Ok(NEWEST_SUPPORTED_PYTHON_3_MINOR_VERSION)
+        );
+        assert_eq!(resolved_minor_version(">=3.11,<3.13"), Ok(12));
+        assert_eq!(resolved_minor_version(" >= 3.9 , < 3.12 "), Ok(11));
+        assert_eq!(resolved_minor_version("<3.12.0"), Ok(11));
+        assert_eq!(resolved_minor_version("<=3.12"), Ok(11));
+        assert_eq!(resolved_minor_version(">3.10,!=3.12.*"), Ok(13));
+        assert_eq!(resolved_minor_version("==3.10.*"), Ok(10));
+        assert_eq!(resolved_minor_version("~=3.9"), Ok(13));
+        assert_eq!(resolved_minor_version("~=3.9.0"), Ok(9));
+        assert_eq!(
+            resolved_minor_version(""),
+            Ok(NEWEST_SUPPORTED_PYTHON_3_MINOR_VERSION)
+        );
+    }
+
+    #[test]
+    fn resolve_requires_python_invalid() {
+        for requires_python in ["3.12", ">=3.12a1", "~=3", ">=3.*", "=>3.11"] {
+            assert_eq!(
+                resolve_requires_python(requires_python),
+                Err(ResolveRequiresPythonError::InvalidSpecifier(
+                    requires_python.to_string()
+                ))
+            );
+        }
+        for requires_python in [">=3.14", "<3.8", "==3.12.0"] {
+            assert_eq!(
+                resolve_requires_python(requires_python),
+                Err(ResolveRequiresPythonError::NoCompatibleVersion(
+                    requires_python.to_string()
+                ))
+            );
+        }
+    }
+
     #[test]
     fn read_requested_python_version_none_specified() {
         assert_eq!(
-            read_requested_python_version(Path::new("tests/fixtures/python_version_unspecified"))
-                .unwrap(),
+            read_requested_python_version(
+                Path::new("tests/fixtures/python_version_unspecified"),
+                &Env::new(),
+                None
+            )
+            .unwrap(),
             RequestedPythonVersion {
                 major: 3,
                 minor: 13,
@@ -277,12 +613,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn read_requested_python_version_configured() {
+        let app_dir = Path::new("tests/fixtures/runtime_txt_and_python_version_file");
+        let mut env = Env::new();
+        assert_eq!(
+            read_requested_python_version(app_dir, &env, Some("3.12.*")).unwrap(),
+            RequestedPythonVersion {
+                major: 3,
+                minor: 12,
+                patch: None,
+                origin: PythonVersionOrigin::ProjectToml,
+            }
+        );
+        env.insert(PYTHON_VERSION_ENV_VAR, " 3.11.9\n");
+        assert_eq!(
+            read_requested_python_version(app_dir, &env, Some("3.12")).unwrap(),
+            RequestedPythonVersion {
+                major: 3,
+                minor: 11,
+                patch: Some(9),
+                origin: PythonVersionOrigin::EnvVar,
+            }
+        );
+        env.insert(PYTHON_VERSION_ENV_VAR, "python-3.11");
+        assert!(matches!(
+            read_requested_python_version(app_dir, &env, None).unwrap_err(),
+            RequestedPythonVersionError::InvalidEnvVarVersion(version) if version == "python-3.11"
+        ));
+        assert!(matches!(
+            read_requested_python_version(app_dir, &Env::new(), Some("3")).unwrap_err(),
+            RequestedPythonVersionError::InvalidProjectTomlVersion(version) if version == "3"
+        ));
+    }
+
     #[test]
     fn resolve_python_version_valid() {
         // Buildpack default version
         assert_eq!(
             resolve_python_version(&DEFAULT_PYTHON_VERSION),
-            Ok(DEFAULT_PYTHON_FULL_VERSION)
+            Ok(default_python_full_version())
         );
 
         for minor in
@@ -393,5 +763,20 @@ mod tests {
                 requested_python_version
             ))
         );
+
+        // An exact version newer than the latest release in the inventory.
+        let latest_release = default_python_full_version();
+        let requested_python_version = RequestedPythonVersion {
+            major: latest_release.major,
+            minor: latest_release.minor,
+            patch: Some(latest_release.patch + 1),
+            origin: PythonVersionOrigin::PythonVersionFile,
+        };
+        assert_eq!(
+            resolve_python_version(&requested_python_version),
+            Err(ResolvePythonVersionError::UnknownVersion(
+                requested_python_version
+            ))
+        );
     }
 }
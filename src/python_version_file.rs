@@ -2,8 +2,9 @@ use crate::python_version::{PythonVersionOrigin, RequestedPythonVersion};
 
 /// Parse the contents of a `.python-version` file into a [`RequestedPythonVersion`].
 ///
-/// The file is expected to contain a string of form `X.Y` or `X.Y.Z`. Leading and trailing
-/// whitespace will be removed from each line. Lines which are either comments (that begin
+/// The file is expected to contain a string of form `X.Y` or `X.Y.Z`. A wildcard patch version
+/// (`X.Y.*`) is also accepted, and is treated the same as `X.Y` (that is, it requests the latest
+/// patch release). Leading and trailing whitespace will be removed from each line. Lines which are either comments (that begin
 /// with `#`) or are empty will be ignored. Multiple Python versions are not permitted.
 pub(crate) fn parse(contents: &str) -> Result<RequestedPythonVersion, ParsePythonVersionFileError> {
     let versions = contents
@@ -21,22 +22,27 @@ pub(crate) fn parse(contents: &str) -> Result<RequestedPythonVersion, ParsePytho
     match versions.as_slice() {
         [version] => match version
             .split('.')
-            .map(str::parse)
-            .collect::<Result<Vec<u16>, _>>()
+            .map(|part| match part {
+                "*" => Ok(None),
+                _ => part.parse().map(Some),
+            })
+            .collect::<Result<Vec<Option<u16>>, _>>()
             .unwrap_or_default()[..]
         {
-            [major, minor, patch] => Ok(RequestedPythonVersion {
+            [Some(major), Some(minor), Some(patch)] => Ok(RequestedPythonVersion {
                 major,
                 minor,
                 patch: Some(patch),
                 origin: PythonVersionOrigin::PythonVersionFile,
             }),
-            [major, minor] => Ok(RequestedPythonVersion {
-                major,
-                minor,
-                patch: None,
-                origin: PythonVersionOrigin::PythonVersionFile,
-            }),
+            [Some(major), Some(minor)] | [Some(major), Some(minor), None] => {
+                Ok(RequestedPythonVersion {
+                    major,
+                    minor,
+                    patch: None,
+                    origin: PythonVersionOrigin::PythonVersionFile,
+                })
+            }
             _ => Err(ParsePythonVersionFileError::InvalidVersion(version.clone())),
         },
         [] => Err(ParsePythonVersionFileError::NoVersion),
@@ -85,6 +91,15 @@ mod tests {
                 origin: PythonVersionOrigin::PythonVersionFile,
             })
         );
+        assert_eq!(
+            parse("1.2.*"),
+            Ok(RequestedPythonVersion {
+                major: 1,
+                minor: 2,
+                patch: None,
+                origin: PythonVersionOrigin::PythonVersionFile,
+            })
+        );
         assert_eq!(
             parse("  # Comment 1\n  1.2.3  \n  # Comment 2"),
             Ok(RequestedPythonVersion {
@@ -126,6 +141,24 @@ mod tests {
                 "1.2rc1".to_string()
             ))
         );
+        assert_eq!(
+            parse("1.*"),
+            Err(ParsePythonVersionFileError::InvalidVersion(
+                "1.*".to_string()
+            ))
+        );
+        assert_eq!(
+            parse("1.2.3.*"),
+            Err(ParsePythonVersionFileError::InvalidVersion(
+                "1.2.3.*".to_string()
+            ))
+        );
+        assert_eq!(
+            parse("1.2.x"),
+            Err(ParsePythonVersionFileError::InvalidVersion(
+                "1.2.x".to_string()
+            ))
+        );
         assert_eq!(
             parse("1.2.3-dev"),
             Err(ParsePythonVersionFileError::InvalidVersion(
@@ -0,0 +1,134 @@
+use crate::build_config::python_version_source;
+use crate::package_manager::{self, PackageManager};
+use crate::python_version::{self, PythonVersion, RequestedPythonVersion};
+use crate::{detect, errors, BuildpackError};
+use crate::{project_root, project_toml};
+use libcnb::Env;
+use std::path::Path;
+
+/// Runs buildpack detection, package manager determination and Python version resolution against
+/// a local app directory, and prints the outcome. This allows users to debug their project's
+/// configuration without having to run a full build (which requires Docker).
+///
+/// Returns the exit code for the process.
+pub(crate) fn resolve(app_dir: &Path) -> i32 {
+    match resolve_project(app_dir) {
+        Ok(Some((package_manager, packages_file, requested_python_version, python_version))) => {
+            println!("Detection: Passed");
+            println!(
+                "Package manager: {} (using {packages_file})",
+                package_manager.name()
+            );
+            println!(
+                "Python version: {python_version} ({})",
+                python_version_source(&requested_python_version)
+            );
+            0
+        }
+        Ok(None) => {
+            println!("Detection: Failed");
+            println!(
+                "No Python project files found (such as pyproject.toml, requirements.txt or poetry.lock)."
+            );
+            1
+        }
+        Err(error) => {
+            errors::on_error(libcnb::Error::BuildpackError(error));
+            1
+        }
+    }
+}
+
+fn resolve_project(
+    app_dir: &Path,
+) -> Result<
+    Option<(
+        PackageManager,
+        String,
+        RequestedPythonVersion,
+        PythonVersion,
+    )>,
+    BuildpackError,
+> {
+    // The env of the local shell isn't used, since unlike the build env, it wasn't set for the
+    // app (for example, Python's Docker images set `PYTHON_VERSION` to their own version).
+    let env = Env::new();
+    if !detect::is_python_project(app_dir, &env).map_err(BuildpackError::BuildpackDetection)? {
+        return Ok(None);
+    }
+
+    let project_toml = project_toml::read_project_toml(app_dir)
+        .map_err(BuildpackError::ReadProjectToml)?
+        .unwrap_or_default();
+    let python_config = project_toml.python_config();
+    let app_dir =
+        &project_root::determine_project_dir(app_dir, &env, python_config.project_root.as_deref())
+            .map_err(BuildpackError::ProjectRoot)?;
+    let package_manager = package_manager::determine_package_manager(app_dir, python_config)
+        .map_err(BuildpackError::DeterminePackageManager)?;
+    let packages_file = package_manager
+        .installs_from(app_dir, python_config.requirements_file())
+        .to_string();
+    let requested_python_version = python_version::read_requested_python_version(
+        app_dir,
+        &env,
+        python_config.version.as_deref(),
+    )
+    .map_err(BuildpackError::RequestedPythonVersion)?;
+    let python_version = python_version::resolve_python_version(&requested_python_version)
+        .map_err(BuildpackError::ResolvePythonVersion)?;
+
+    Ok(Some((
+        package_manager,
+        packages_file,
+        requested_python_version,
+        python_version,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_project_pip() {
+        let (package_manager, _, requested_python_version, python_version) =
+            resolve_project(Path::new("tests/fixtures/python_3.12"))
+                .unwrap()
+                .unwrap();
+        assert_eq!(package_manager, PackageManager::Pip);
+        assert_eq!(
+            python_version_source(&requested_python_version),
+            "requested 3.12 in .python-version"
+        );
+        assert_eq!(
+            Some(python_version),
+            python_version::latest_python_version(3, 12)
+        );
+    }
+
+    #[test]
+    fn resolve_project_configured_requirements_file() {
+        let (package_manager, packages_file, _, _) =
+            resolve_project(Path::new("tests/fixtures/pip_requirements_file"))
+                .unwrap()
+                .unwrap();
+        assert_eq!(package_manager, PackageManager::Pip);
+        assert_eq!(packages_file, "requirements/production.txt");
+    }
+
+    #[test]
+    fn resolve_project_not_detected() {
+        assert!(resolve_project(Path::new("tests/fixtures/empty"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn resolve_project_error() {
+        assert!(matches!(
+            resolve_project(Path::new("tests/fixtures/pip_and_poetry")),
+            Err(BuildpackError::DeterminePackageManager(_))
+        ));
+    }
+}
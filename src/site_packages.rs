@@ -0,0 +1,104 @@
+use crate::python_version::PythonVersion;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// The names of the directories containing the test suites of installed packages. Only `tests` is
+// used (and not `test` or `testing`), since those are more often modules that packages (or apps)
+// import at run-time, such as `django.test` or `numpy.testing`.
+const TEST_SUITE_DIR_NAMES: [&str; 1] = ["tests"];
+
+/// Returns the `site-packages` directory of the venv in the dependencies layer.
+pub(crate) fn site_packages_dir(venv_dir: &Path, python_version: &PythonVersion) -> PathBuf {
+    venv_dir.join(format!(
+        "lib/python{}.{}/site-packages",
+        python_version.major, python_version.minor
+    ))
+}
+
+/// Removes the test suites bundled with the installed packages (the `tests/` directories inside
+/// each package), returning the total size of the files removed. Used by the `slim-site-packages`
+/// setting.
+///
+/// Large packages (such as `pandas`, `scipy` and `sklearn`) ship test suites that apps never use
+/// at run-time, but which together can make up hundreds of megabytes of the app image. Bytecode
+/// (`__pycache__`) is kept for the remaining modules, since otherwise it would have to be
+/// regenerated each time the app boots.
+pub(crate) fn remove_test_suites(site_packages_dir: &Path) -> io::Result<u64> {
+    let mut removed_size = 0;
+    for test_suite_dir in find_test_suites(site_packages_dir)? {
+        removed_size += dir_size(&test_suite_dir)?;
+        fs::remove_dir_all(&test_suite_dir)?;
+    }
+    Ok(removed_size)
+}
+
+fn find_test_suites(site_packages_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut test_suite_dirs = Vec::new();
+    for entry in site_packages_dir.read_dir()? {
+        let entry = entry?;
+        // Top-level `tests` directories aren't removed, since they aren't inside a package.
+        if entry.file_type()?.is_dir() && !is_package_metadata_dir(&entry.path()) {
+            find_nested_test_suites(&entry.path(), &mut test_suite_dirs)?;
+        }
+    }
+    test_suite_dirs.sort();
+    Ok(test_suite_dirs)
+}
+
+fn find_nested_test_suites(dir: &Path, test_suite_dirs: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in dir.read_dir()? {
+        let entry = entry?;
+        // The `DirEntry` methods don't follow symlinks, so only directories inside the
+        // `site-packages` directory are removed (and not the targets of any symlinks).
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if TEST_SUITE_DIR_NAMES.contains(&entry.file_name().to_string_lossy().as_ref()) {
+            test_suite_dirs.push(entry.path());
+        } else {
+            find_nested_test_suites(&entry.path(), test_suite_dirs)?;
+        }
+    }
+    Ok(())
+}
+
+fn is_package_metadata_dir(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "dist-info" || extension == "egg-info")
+}
+
+fn dir_size(dir: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in dir.read_dir()? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_test_suites_nested_only() {
+        let site_packages_dir = Path::new("tests/fixtures/site_packages_with_tests");
+        assert_eq!(
+            find_test_suites(site_packages_dir).unwrap(),
+            [
+                site_packages_dir.join("pandas/core/tests"),
+                site_packages_dir.join("pandas/tests"),
+            ]
+        );
+        assert_eq!(
+            dir_size(&site_packages_dir.join("pandas/tests")).unwrap(),
+            "def test_frame():\n    pass\n".len() as u64
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use crate::utils;
+use libcnb::Env;
+use serde::Deserialize;
+use std::path::Path;
+
+// The env var that the source version is exposed to build steps as, for parity with the
+// classic Heroku build environment.
+const SOURCE_VERSION_ENV_VAR: &str = "SOURCE_VERSION";
+
+// Env vars set by common CI providers to the commit SHA being built, in order of precedence.
+const CI_COMMIT_ENV_VARS: [&str; 3] = ["GITHUB_SHA", "CI_COMMIT_SHA", "GIT_COMMIT"];
+
+/// Sets `SOURCE_VERSION` to the version of the app source (typically the Git commit SHA) if the
+/// platform provides it, for use by build steps such as Django's collectstatic. Asset pipelines
+/// and Sentry release scripts often need this, however, CNB platforms don't necessarily set it.
+pub(crate) fn expose_source_version(env: &mut Env, layers_dir: &Path) {
+    if let Some(source_version) = determine_source_version(env, layers_dir) {
+        env.insert(SOURCE_VERSION_ENV_VAR, source_version);
+    }
+}
+
+/// Determine the version of the app source, if the platform provides it.
+///
+/// The `SOURCE_VERSION` env var is used if already set, otherwise the version is read from the
+/// project metadata that the CNB platform can provide, falling back to the env vars set by some
+/// CI providers. Problems reading the project metadata are ignored, since it's optional.
+// https://github.com/buildpacks/spec/blob/main/platform.md#project-metadatatoml-toml
+fn determine_source_version(env: &Env, layers_dir: &Path) -> Option<String> {
+    env_var_value(env, SOURCE_VERSION_ENV_VAR)
+        .or_else(|| {
+            // The project metadata file is in the root of the layers directory, whereas the
+            // layers directory provided to buildpacks is a buildpack-specific subdirectory.
+            let contents =
+                utils::read_optional_file(&layers_dir.parent()?.join("project-metadata.toml"))
+                    .ok()??;
+            project_metadata_commit(&contents)
+        })
+        .or_else(|| {
+            CI_COMMIT_ENV_VARS
+                .into_iter()
+                .find_map(|name| env_var_value(env, name))
+        })
+}
+
+fn env_var_value(env: &Env, name: &str) -> Option<String> {
+    env.get_string_lossy(name)
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn project_metadata_commit(contents: &str) -> Option<String> {
+    let project_metadata: ProjectMetadata = toml::from_str(contents).ok()?;
+    project_metadata
+        .source?
+        .version?
+        .commit
+        .filter(|commit| !commit.is_empty())
+}
+
+#[derive(Deserialize)]
+struct ProjectMetadata {
+    source: Option<ProjectMetadataSource>,
+}
+
+#[derive(Deserialize)]
+struct ProjectMetadataSource {
+    version: Option<ProjectMetadataSourceVersion>,
+}
+
+#[derive(Deserialize)]
+struct ProjectMetadataSourceVersion {
+    commit: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn determine_source_version_env_vars() {
+        let layers_dir = Path::new("tests/fixtures/empty/heroku_python");
+
+        let mut env = Env::new();
+        assert_eq!(determine_source_version(&env, layers_dir), None);
+
+        env.insert("GIT_COMMIT", "fedcba");
+        assert_eq!(
+            determine_source_version(&env, layers_dir).as_deref(),
+            Some("fedcba")
+        );
+
+        env.insert("GITHUB_SHA", "123456");
+        assert_eq!(
+            determine_source_version(&env, layers_dir).as_deref(),
+            Some("123456")
+        );
+
+        env.insert("SOURCE_VERSION", " abcdef\n");
+        assert_eq!(
+            determine_source_version(&env, layers_dir).as_deref(),
+            Some("abcdef")
+        );
+
+        env.insert("SOURCE_VERSION", "");
+        assert_eq!(
+            determine_source_version(&env, layers_dir).as_deref(),
+            Some("123456")
+        );
+    }
+
+    #[test]
+    fn expose_source_version_env_var() {
+        let layers_dir = Path::new("tests/fixtures/empty/heroku_python");
+
+        let mut env = Env::new();
+        expose_source_version(&mut env, layers_dir);
+        assert!(!env.contains_key("SOURCE_VERSION"));
+
+        env.insert("CI_COMMIT_SHA", "abcdef");
+        expose_source_version(&mut env, layers_dir);
+        assert_eq!(
+            env.get_string_lossy("SOURCE_VERSION").as_deref(),
+            Some("abcdef")
+        );
+    }
+
+    #[test]
+    fn project_metadata_commit_valid() {
+        assert_eq!(
+            project_metadata_commit(indoc! {r#"
+                [source]
+                type = "git"
+
+                [source.version]
+                commit = "7c3b2a1f0e9d8c7b6a5f4e3d2c1b0a9f8e7d6c5b"
+
+                [source.metadata]
+                repository = "https://github.com/example/app"
+                refs = ["main"]
+            "#})
+            .as_deref(),
+            Some("7c3b2a1f0e9d8c7b6a5f4e3d2c1b0a9f8e7d6c5b")
+        );
+    }
+
+    #[test]
+    fn project_metadata_commit_missing() {
+        assert_eq!(project_metadata_commit(""), None);
+        assert_eq!(project_metadata_commit("[source]\ntype = 'image'"), None);
+        assert_eq!(
+            project_metadata_commit("[source.version]\ncommit = ''"),
+            None
+        );
+        assert_eq!(project_metadata_commit("[source"), None);
+    }
+}
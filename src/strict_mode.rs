@@ -0,0 +1,303 @@
+use crate::build_log::log_warning;
+use libcnb::Env;
+use serde::Deserialize;
+use std::fmt::{self, Display};
+
+/// The env var used to enable strict mode, which causes selected classes of warnings to fail
+/// the build instead, for teams that want to enforce these policies at build time.
+pub(crate) const STRICT_MODE_ENV_VAR: &str = "PYTHON_STRICT_MODE";
+
+/// The classes of warnings shown by the buildpack. The name of each class is a stable identifier,
+/// which is used to suppress warnings (using the `suppressed-warnings` setting in `project.toml`),
+/// or for selected classes, to promote them to errors using strict mode.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum WarningClass {
+    /// Packages that will have to be built from source, since they don't provide compatible wheels.
+    BuildFromSource,
+    /// Problems found by `pip check` with the installed packages.
+    DependencyConflicts,
+    /// Findings from Django's deployment checks (`manage.py check --deploy`).
+    DjangoDeployCheck,
+    /// The Django settings module had to be found automatically, since it isn't configured.
+    DjangoSettingsModule,
+    /// The Python version has reached its upstream end-of-life.
+    EolPython,
+    /// Gunicorn is configured to listen on a local-only address or hardcoded port.
+    GunicornBind,
+    /// Gunicorn is using a worker class that doesn't support the app's ASGI framework.
+    GunicornWorkerClass,
+    /// Files in the app source that are large enough to noticeably increase the image size.
+    LargeFiles,
+    /// Installed packages whose version doesn't match the hashed version in the lockfile.
+    LockfileMismatch,
+    /// Files in the app root that look like misnamed project files (such as `Requirements.txt`).
+    MisnamedProjectFiles,
+    /// Packages that have to be built from source, whose builds need system libraries that
+    /// aren't installed.
+    MissingSystemLibraries,
+    /// The `poetry.lock` file uses a newer lockfile format than the buildpack's Poetry supports.
+    NewerPoetryLockfile,
+    /// No processes are defined for the app, so it won't have a `web` process.
+    NoWebProcess,
+    /// The buildpack version is significantly older than the latest release.
+    OutdatedBuildpack,
+    /// A `Procfile` process's command could not be found in the build environment.
+    ProcessCommandNotFound,
+    /// Both a `runtime.txt` and a `.python-version` file exist.
+    RuntimeTxtIgnored,
+    /// Packages in `requirements.txt` that aren't pinned to an exact version.
+    UnpinnedDependencies,
+    /// Files for package managers other than the one being used by the buildpack.
+    UnsupportedPackageManagerFile,
+    /// Settings in `project.toml` that aren't supported when using PDM.
+    UnsupportedPdmSettings,
+    /// Settings in `project.toml` that aren't supported when using Pipenv.
+    UnsupportedPipenvSettings,
+    /// Settings in `project.toml` that are only supported when using pip.
+    UnsupportedPoetrySettings,
+}
+
+impl WarningClass {
+    /// The warning classes that can be promoted to errors using strict mode.
+    const STRICT: [Self; 3] = [
+        Self::DjangoDeployCheck,
+        Self::EolPython,
+        Self::UnpinnedDependencies,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::BuildFromSource => "build-from-source",
+            Self::DependencyConflicts => "dependency-conflicts",
+            Self::DjangoDeployCheck => "django-deploy-check",
+            Self::DjangoSettingsModule => "django-settings-module",
+            Self::EolPython => "eol-python",
+            Self::GunicornBind => "gunicorn-bind",
+            Self::GunicornWorkerClass => "gunicorn-worker-class",
+            Self::LargeFiles => "large-files",
+            Self::LockfileMismatch => "lockfile-mismatch",
+            Self::MisnamedProjectFiles => "misnamed-project-files",
+            Self::MissingSystemLibraries => "missing-system-libraries",
+            Self::NewerPoetryLockfile => "newer-poetry-lockfile",
+            Self::NoWebProcess => "no-web-process",
+            Self::OutdatedBuildpack => "outdated-buildpack",
+            Self::ProcessCommandNotFound => "process-command-not-found",
+            Self::RuntimeTxtIgnored => "runtime-txt-ignored",
+            Self::UnpinnedDependencies => "unpinned-dependencies",
+            Self::UnsupportedPackageManagerFile => "unsupported-package-manager-file",
+            Self::UnsupportedPdmSettings => "unsupported-pdm-settings",
+            Self::UnsupportedPipenvSettings => "unsupported-pipenv-settings",
+            Self::UnsupportedPoetrySettings => "unsupported-poetry-settings",
+        }
+    }
+}
+
+impl Display for WarningClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The warning classes for which strict mode has been enabled, and those that have been suppressed.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct StrictMode {
+    enabled_classes: Vec<WarningClass>,
+    suppressed_classes: Vec<WarningClass>,
+}
+
+impl StrictMode {
+    /// Read the strict mode configuration from `PYTHON_STRICT_MODE`, which can be set to either
+    /// `1` (to enable all warning classes), or to a comma or space separated list of class names.
+    pub(crate) fn from_env(env: &Env) -> Result<Self, StrictModeError> {
+        let Some(value) = env.get_string_lossy(STRICT_MODE_ENV_VAR) else {
+            return Ok(Self::default());
+        };
+        let enabled_classes = match value.trim() {
+            "" | "0" | "false" => Vec::new(),
+            "1" | "true" => WarningClass::STRICT.to_vec(),
+            names => names
+                .split([',', ' '])
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    WarningClass::STRICT
+                        .into_iter()
+                        .find(|class| class.name() == name)
+                        .ok_or_else(|| StrictModeError::UnknownWarningClass(name.to_string()))
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        Ok(Self {
+            enabled_classes,
+            suppressed_classes: Vec::new(),
+        })
+    }
+
+    /// Suppress the specified warning classes (as configured in `project.toml`), so that warnings
+    /// of those classes are no longer shown. Strict mode takes precedence over suppression.
+    pub(crate) fn suppress(&mut self, classes: &[WarningClass]) {
+        self.suppressed_classes.extend_from_slice(classes);
+    }
+
+    pub(crate) fn is_enabled(&self, class: WarningClass) -> bool {
+        self.enabled_classes.contains(&class)
+    }
+
+    pub(crate) fn is_suppressed(&self, class: WarningClass) -> bool {
+        self.suppressed_classes.contains(&class) && !self.is_enabled(class)
+    }
+
+    /// Log a warning, or if strict mode is enabled for the warning's class, return an error instead.
+    pub(crate) fn warn(
+        &self,
+        class: WarningClass,
+        header: &str,
+        body: &str,
+    ) -> Result<(), StrictModeError> {
+        if self.is_enabled(class) {
+            Err(StrictModeError::PromotedWarning {
+                class,
+                header: header.to_string(),
+                body: body.to_string(),
+            })
+        } else {
+            self.log_warning(class, header, body);
+            Ok(())
+        }
+    }
+
+    /// Log a warning, unless its class has been suppressed.
+    pub(crate) fn log_warning(&self, class: WarningClass, header: &str, body: impl AsRef<str>) {
+        if !self.is_suppressed(class) {
+            log_warning(header, body);
+        }
+    }
+}
+
+/// Errors due to strict mode being misconfigured, or a warning being promoted to an error.
+#[derive(Debug)]
+pub(crate) enum StrictModeError {
+    PromotedWarning {
+        class: WarningClass,
+        header: String,
+        body: String,
+    },
+    UnknownWarningClass(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_mode_from_env_disabled() {
+        assert_eq!(
+            StrictMode::from_env(&Env::new()).unwrap(),
+            StrictMode::default()
+        );
+
+        let mut env = Env::new();
+        env.insert(STRICT_MODE_ENV_VAR, "0");
+        assert_eq!(StrictMode::from_env(&env).unwrap(), StrictMode::default());
+    }
+
+    #[test]
+    fn strict_mode_from_env_enabled() {
+        let mut env = Env::new();
+        env.insert(STRICT_MODE_ENV_VAR, "1");
+        let strict_mode = StrictMode::from_env(&env).unwrap();
+        assert!(WarningClass::STRICT
+            .into_iter()
+            .all(|class| strict_mode.is_enabled(class)));
+
+        let mut env = Env::new();
+        env.insert(STRICT_MODE_ENV_VAR, "eol-python, unpinned-dependencies");
+        let strict_mode = StrictMode::from_env(&env).unwrap();
+        assert!(strict_mode.is_enabled(WarningClass::EolPython));
+        assert!(strict_mode.is_enabled(WarningClass::UnpinnedDependencies));
+        assert!(!strict_mode.is_enabled(WarningClass::DjangoDeployCheck));
+    }
+
+    #[test]
+    fn strict_mode_from_env_unknown_class() {
+        let mut env = Env::new();
+        env.insert(STRICT_MODE_ENV_VAR, "eol-python,unpinned-deps");
+        assert!(matches!(
+            StrictMode::from_env(&env),
+            Err(StrictModeError::UnknownWarningClass(name)) if name == "unpinned-deps"
+        ));
+    }
+
+    #[test]
+    fn strict_mode_warn() {
+        let mut env = Env::new();
+        env.insert(STRICT_MODE_ENV_VAR, "eol-python");
+        let strict_mode = StrictMode::from_env(&env).unwrap();
+        assert!(matches!(
+            strict_mode.warn(WarningClass::EolPython, "Header", "Body"),
+            Err(StrictModeError::PromotedWarning {
+                class: WarningClass::EolPython,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn strict_mode_from_env_non_strict_class() {
+        let mut env = Env::new();
+        env.insert(STRICT_MODE_ENV_VAR, "large-files");
+        assert!(matches!(
+            StrictMode::from_env(&env),
+            Err(StrictModeError::UnknownWarningClass(name)) if name == "large-files"
+        ));
+    }
+
+    #[test]
+    fn strict_mode_suppress() {
+        let mut env = Env::new();
+        env.insert(STRICT_MODE_ENV_VAR, "eol-python");
+        let mut strict_mode = StrictMode::from_env(&env).unwrap();
+        strict_mode.suppress(&[WarningClass::EolPython, WarningClass::LargeFiles]);
+        assert!(strict_mode.is_suppressed(WarningClass::LargeFiles));
+        assert!(!strict_mode.is_suppressed(WarningClass::NoWebProcess));
+        // Strict mode takes precedence over suppression.
+        assert!(!strict_mode.is_suppressed(WarningClass::EolPython));
+        assert!(strict_mode
+            .warn(WarningClass::EolPython, "Header", "Body")
+            .is_err());
+    }
+
+    #[test]
+    fn warning_class_deserialize_names() {
+        for class in [
+            WarningClass::BuildFromSource,
+            WarningClass::DependencyConflicts,
+            WarningClass::DjangoDeployCheck,
+            WarningClass::DjangoSettingsModule,
+            WarningClass::EolPython,
+            WarningClass::GunicornBind,
+            WarningClass::GunicornWorkerClass,
+            WarningClass::LargeFiles,
+            WarningClass::LockfileMismatch,
+            WarningClass::MisnamedProjectFiles,
+            WarningClass::MissingSystemLibraries,
+            WarningClass::NewerPoetryLockfile,
+            WarningClass::NoWebProcess,
+            WarningClass::OutdatedBuildpack,
+            WarningClass::ProcessCommandNotFound,
+            WarningClass::RuntimeTxtIgnored,
+            WarningClass::UnpinnedDependencies,
+            WarningClass::UnsupportedPackageManagerFile,
+            WarningClass::UnsupportedPdmSettings,
+            WarningClass::UnsupportedPipenvSettings,
+            WarningClass::UnsupportedPoetrySettings,
+        ] {
+            assert_eq!(
+                toml::Value::String(class.name().to_string())
+                    .try_into::<WarningClass>()
+                    .unwrap(),
+                class
+            );
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::diagnostics::{Diagnostic, DiagnosticCategory};
+use crate::package_manager::{self, PackageManager};
+use crate::project_toml::PythonBuildpackConfig;
+use crate::strict_mode::WarningClass;
+use crate::{pdm_lock, pipfile, poetry_lock, pyproject_toml, utils};
+use indoc::formatdoc;
+use std::path::Path;
+
+// The directory in which system packages install the C headers needed to build against them.
+const SYSTEM_INCLUDE_DIR: &str = "/usr/include";
+
+/// A package that doesn't provide wheels for Linux, so is always built from source, and whose
+/// build fails unless the development files for a system library are installed.
+#[derive(Debug, PartialEq)]
+struct SourceBuildPackage {
+    name: &'static str,
+    /// Headers installed by the required system packages (relative to the include directory),
+    /// any one of which means the library is installed.
+    headers: &'static [&'static str],
+    /// The Ubuntu packages that provide the library's development files.
+    system_packages: &'static str,
+    /// A package that can be used instead, which provides pre-built wheels.
+    alternative: Option<&'static str>,
+}
+
+static SOURCE_BUILD_PACKAGES: [SourceBuildPackage; 5] = [
+    SourceBuildPackage {
+        name: "mysqlclient",
+        headers: &["mysql/mysql.h", "mariadb/mysql.h"],
+        system_packages: "'pkg-config' and 'libmysqlclient-dev' (or 'libmariadb-dev')",
+        alternative: Some("PyMySQL"),
+    },
+    SourceBuildPackage {
+        name: "psycopg2",
+        headers: &["postgresql/libpq-fe.h"],
+        system_packages: "'libpq-dev'",
+        alternative: Some("psycopg2-binary"),
+    },
+    SourceBuildPackage {
+        name: "pycairo",
+        headers: &["cairo/cairo.h"],
+        system_packages: "'libcairo2-dev'",
+        alternative: None,
+    },
+    SourceBuildPackage {
+        name: "pygraphviz",
+        headers: &["graphviz/cgraph.h"],
+        system_packages: "'libgraphviz-dev'",
+        alternative: None,
+    },
+    SourceBuildPackage {
+        name: "python-ldap",
+        headers: &["ldap.h"],
+        system_packages: "'libldap-dev' and 'libsasl2-dev'",
+        alternative: None,
+    },
+];
+
+/// Check the app's dependencies for packages that will have to be built from source, and whose
+/// builds require system libraries that aren't installed, so that the problem (and how to fix it)
+/// is explained before the install fails with a compiler error buried in the build output.
+///
+/// The packages are read from the lockfile (or for pip, the requirements file) without resolving
+/// the dependencies, so transitive dependencies are only checked when using a lockfile.
+pub(crate) fn check_system_libraries(
+    app_dir: &Path,
+    package_manager: PackageManager,
+    python_config: &PythonBuildpackConfig,
+) -> Option<Diagnostic> {
+    let package_names = dependency_names(app_dir, package_manager, python_config);
+    let packages = packages_missing_libraries(&package_names, Path::new(SYSTEM_INCLUDE_DIR));
+    if packages.is_empty() {
+        return None;
+    }
+
+    let package_list = packages
+        .iter()
+        .map(|package| {
+            let alternative = package.alternative.map_or_else(String::new, |alternative| {
+                format!("\n  (or use '{alternative}' instead, which provides pre-built wheels)")
+            });
+            format!(
+                "- {}: requires {}{alternative}",
+                package.name, package.system_packages
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(Diagnostic {
+        category: DiagnosticCategory::Dependencies,
+        warning_class: WarningClass::MissingSystemLibraries,
+        message: formatdoc! {"
+            The following packages don't provide pre-built wheels for Linux, so will
+            be built from source. However, the system libraries needed to build them
+            aren't installed, so the install will likely fail:
+
+            {package_list}
+
+            To fix this, either install the listed system packages (for example, using
+            a buildpack that installs apt packages, added before the Python buildpack),
+            or switch to the suggested alternative packages.
+        "},
+    })
+}
+
+/// Returns the normalized names of the app's dependencies, as read from the lockfile (or for pip,
+/// the requirements file). Files that are missing or invalid are reported by the dependency
+/// install step (with a more specific error), so are skipped here.
+pub(crate) fn dependency_names(
+    app_dir: &Path,
+    package_manager: PackageManager,
+    python_config: &PythonBuildpackConfig,
+) -> Vec<String> {
+    match package_manager {
+        PackageManager::Pdm => pdm_lock::read_pdm_lock(app_dir)
+            .map(|pdm_lock| {
+                pdm_lock
+                    .packages
+                    .iter()
+                    .map(|package| utils::normalize_package_name(&package.name))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        PackageManager::Pip => {
+            let requirements_file = python_config.requirements_file();
+            if package_manager::is_pip_project_install(app_dir, requirements_file) {
+                pyproject_toml::read_pyproject_toml(app_dir)
+                    .ok()
+                    .flatten()
+                    .map(|pyproject_toml| pyproject_toml.dependency_names())
+                    .unwrap_or_default()
+            } else {
+                utils::read_optional_file(&app_dir.join(requirements_file))
+                    .ok()
+                    .flatten()
+                    .map(|contents| requirement_names(&contents))
+                    .unwrap_or_default()
+            }
+        }
+        PackageManager::Pipenv => pipfile::read_pipfile_lock(app_dir)
+            .map(|pipfile_lock| {
+                pipfile_lock
+                    .default
+                    .keys()
+                    .map(|name| utils::normalize_package_name(name))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        PackageManager::Poetry => poetry_lock::read_poetry_lock(app_dir)
+            .map(|poetry_lock| {
+                poetry_lock
+                    .packages
+                    .iter()
+                    .map(|package| utils::normalize_package_name(&package.name))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+// Returns the normalized names of the requirements in a requirements file, skipping comments
+// and pip options (such as `-r` or `--hash`).
+fn requirement_names(contents: &str) -> Vec<String> {
+    contents
+        .replace("\\\n", " ")
+        .lines()
+        .map(|line| line.split(" #").next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', '-']))
+        .map(|line| {
+            line.split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+                .next()
+                .unwrap_or_default()
+        })
+        .filter(|name| !name.is_empty())
+        .map(utils::normalize_package_name)
+        .collect()
+}
+
+fn packages_missing_libraries(
+    package_names: &[String],
+    include_dir: &Path,
+) -> Vec<&'static SourceBuildPackage> {
+    SOURCE_BUILD_PACKAGES
+        .iter()
+        .filter(|package| package_names.contains(&utils::normalize_package_name(package.name)))
+        .filter(|package| {
+            !package
+                .headers
+                .iter()
+                .any(|header| include_dir.join(header).exists())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn requirement_names_parsing() {
+        assert_eq!(
+            requirement_names(indoc! {"
+                # A comment
+                -r other.txt
+                psycopg2==2.9.10 \\
+                    --hash=sha256:abc
+                Python_LDAP>=3.4  # Needed for SSO
+                mysqlclient[extra]; python_version >= '3.9'
+            "}),
+            ["psycopg2", "python-ldap", "mysqlclient"]
+        );
+    }
+
+    #[test]
+    fn packages_missing_libraries_headers() {
+        let package_names = ["psycopg2", "mysqlclient", "requests"].map(ToString::to_string);
+        let missing_packages = |include_dir: &str| {
+            packages_missing_libraries(&package_names, Path::new(include_dir))
+                .iter()
+                .map(|package| package.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            missing_packages("tests/fixtures/empty"),
+            ["mysqlclient", "psycopg2"]
+        );
+        // Contains the MariaDB version of the MySQL client library headers.
+        assert_eq!(
+            missing_packages("tests/fixtures/system_libraries_include_dir"),
+            ["psycopg2"]
+        );
+    }
+
+    #[test]
+    fn dependency_names_lockfiles() {
+        let python_config = PythonBuildpackConfig::default();
+        assert_eq!(
+            dependency_names(
+                Path::new("tests/fixtures/poetry_basic"),
+                PackageManager::Poetry,
+                &python_config
+            ),
+            [
+                "colorama",
+                "iniconfig",
+                "packaging",
+                "pluggy",
+                "pytest",
+                "typing-extensions"
+            ]
+        );
+        assert_eq!(
+            dependency_names(
+                Path::new("tests/fixtures/pip_basic"),
+                PackageManager::Pip,
+                &python_config
+            ),
+            ["typing-extensions"]
+        );
+        assert_eq!(
+            dependency_names(
+                Path::new("tests/fixtures/empty"),
+                PackageManager::Pdm,
+                &python_config
+            ),
+            Vec::<String>::new()
+        );
+    }
+}
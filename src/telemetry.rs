@@ -0,0 +1,297 @@
+use crate::package_manager::PackageManager;
+use crate::python_version::PythonVersion;
+use crate::BuildpackError;
+use libcnb::layer::LayerState;
+use libcnb::Env;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The env var used by the platform to provide the URL that build telemetry is sent to.
+/// If it's not set, no telemetry is collected.
+pub(crate) const TELEMETRY_URL_ENV_VAR: &str = "PYTHON_BUILDPACK_TELEMETRY_URL";
+
+/// The env var users can set to opt out of build telemetry.
+pub(crate) const TELEMETRY_OPT_OUT_ENV_VAR: &str = "PYTHON_BUILDPACK_TELEMETRY_OPT_OUT";
+
+// Telemetry must never slow down or break the build, so requests use a short timeout.
+const TELEMETRY_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// `Buildpack::on_error` isn't passed the build context, so the state has to be tracked globally
+// in order for telemetry to be sent for failed builds too.
+static TELEMETRY: Mutex<Option<Telemetry>> = Mutex::new(None);
+
+struct Telemetry {
+    url: String,
+    start_time: Instant,
+    buildpack_version: String,
+    build_cache_available: bool,
+    package_manager: Option<PackageManager>,
+    python_version: Option<String>,
+    layer_cache_hits: BTreeMap<&'static str, bool>,
+    phase_durations: BTreeMap<&'static str, Duration>,
+}
+
+/// The data sent for each build. This intentionally doesn't include anything that could
+/// identify the app or its owner (such as the app name, source code, env vars or file paths).
+#[derive(Debug, PartialEq, Serialize)]
+struct TelemetryEvent {
+    buildpack_version: String,
+    build_cache_available: bool,
+    duration_ms: u64,
+    error_code: Option<&'static str>,
+    package_manager: Option<&'static str>,
+    python_version: Option<String>,
+    /// Whether each cached layer was restored from the build cache, keyed by layer name.
+    layer_cache_hits: BTreeMap<&'static str, bool>,
+    /// The duration of each of the main build phases that was started, keyed by phase name.
+    phase_durations_ms: BTreeMap<&'static str, u64>,
+}
+
+/// Start collecting telemetry for the build, if it has been enabled by the platform
+/// and the user hasn't opted out.
+pub(crate) fn start(env: &Env, buildpack_version: String, build_cache_available: bool) {
+    let Some(url) = telemetry_url(env) else {
+        return;
+    };
+    if let Ok(mut telemetry) = TELEMETRY.lock() {
+        *telemetry = Some(Telemetry {
+            url,
+            start_time: Instant::now(),
+            buildpack_version,
+            build_cache_available,
+            package_manager: None,
+            python_version: None,
+            layer_cache_hits: BTreeMap::new(),
+            phase_durations: BTreeMap::new(),
+        });
+    }
+}
+
+/// Record the package manager used by the build.
+pub(crate) fn record_package_manager(package_manager: PackageManager) {
+    with_telemetry(|telemetry| telemetry.package_manager = Some(package_manager));
+}
+
+/// Record the Python version used by the build. Only the major and minor version are recorded.
+pub(crate) fn record_python_version(python_version: &PythonVersion) {
+    let python_version = format!("{}.{}", python_version.major, python_version.minor);
+    with_telemetry(|telemetry| telemetry.python_version = Some(python_version));
+}
+
+/// Record whether the specified cached layer was restored from the build cache.
+pub(crate) fn record_layer_cache<MAC, RAC>(layer_name: &'static str, state: &LayerState<MAC, RAC>) {
+    let cache_hit = matches!(state, LayerState::Restored { .. });
+    with_telemetry(|telemetry| {
+        telemetry.layer_cache_hits.insert(layer_name, cache_hit);
+    });
+}
+
+/// Run the specified build phase, recording how long it took (regardless of whether it failed).
+pub(crate) fn time_phase<T>(phase: &'static str, f: impl FnOnce() -> T) -> T {
+    let start_time = Instant::now();
+    let result = f();
+    let duration = start_time.elapsed();
+    with_telemetry(|telemetry| {
+        telemetry.phase_durations.insert(phase, duration);
+    });
+    result
+}
+
+/// Send the telemetry for a build that succeeded.
+pub(crate) fn send_build_success() {
+    send(None);
+}
+
+/// Send the telemetry for a build that failed with the specified error code.
+pub(crate) fn send_build_failure(error_code: &'static str) {
+    send(Some(error_code));
+}
+
+/// A stable identifier for the type of error, which (unlike the error details)
+/// can't contain any app-specific information.
+pub(crate) fn error_code(error: &libcnb::Error<BuildpackError>) -> &'static str {
+    match error {
+        libcnb::Error::BuildpackError(buildpack_error) => buildpack_error_code(buildpack_error),
+        _ => "internal_error",
+    }
+}
+
+fn telemetry_url(env: &Env) -> Option<String> {
+    if env.contains_key(TELEMETRY_OPT_OUT_ENV_VAR) {
+        return None;
+    }
+    env.get_string_lossy(TELEMETRY_URL_ENV_VAR)
+        .filter(|url| !url.trim().is_empty())
+}
+
+fn with_telemetry(f: impl FnOnce(&mut Telemetry)) {
+    if let Ok(mut telemetry) = TELEMETRY.lock() {
+        if let Some(telemetry) = telemetry.as_mut() {
+            f(telemetry);
+        }
+    }
+}
+
+fn send(error_code: Option<&'static str>) {
+    let Some(telemetry) = TELEMETRY
+        .lock()
+        .ok()
+        .and_then(|mut telemetry| telemetry.take())
+    else {
+        return;
+    };
+    let event = telemetry_event(&telemetry, error_code);
+    let Ok(body) = serde_json::to_string(&event) else {
+        return;
+    };
+
+    // Any errors are ignored, since the telemetry endpoint being unavailable
+    // shouldn't cause the build to fail or show warnings to the user.
+    let _ = ureq::AgentBuilder::new()
+        .timeout(TELEMETRY_REQUEST_TIMEOUT)
+        .build()
+        .post(&telemetry.url)
+        .set("Content-Type", "application/json")
+        .send_string(&body);
+}
+
+fn telemetry_event(telemetry: &Telemetry, error_code: Option<&'static str>) -> TelemetryEvent {
+    TelemetryEvent {
+        buildpack_version: telemetry.buildpack_version.clone(),
+        build_cache_available: telemetry.build_cache_available,
+        duration_ms: duration_ms(telemetry.start_time.elapsed()),
+        error_code,
+        package_manager: telemetry.package_manager.map(PackageManager::name),
+        python_version: telemetry.python_version.clone(),
+        layer_cache_hits: telemetry.layer_cache_hits.clone(),
+        phase_durations_ms: telemetry
+            .phase_durations
+            .iter()
+            .map(|(&phase, &duration)| (phase, duration_ms(duration)))
+            .collect(),
+    }
+}
+
+pub(crate) fn duration_ms(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+fn buildpack_error_code(error: &BuildpackError) -> &'static str {
+    match error {
+        BuildpackError::AdditionalPython(_) => "additional_python",
+        BuildpackError::AssetsLayer(_) => "assets_layer",
+        BuildpackError::BuildEnvFile(_) => "build_env_file",
+        BuildpackError::BuildHook(_) => "build_hook",
+        BuildpackError::BuildLog(_) => "build_log",
+        BuildpackError::BuildpackDetection(_) => "buildpack_detection",
+        BuildpackError::CachedPackagesLayer(_) => "cached_packages_layer",
+        BuildpackError::Checks(_) => "checks",
+        BuildpackError::ConfiguredProcesses(_) => "configured_processes",
+        BuildpackError::DependencyPlanOnly => "dependency_plan_only",
+        BuildpackError::DependencyReportLayer(_) => "dependency_report_layer",
+        BuildpackError::DeterminePackageManager(_) => "determine_package_manager",
+        BuildpackError::Diagnostics(_) => "diagnostics",
+        BuildpackError::DjangoCollectstatic(_) => "django_collectstatic",
+        BuildpackError::DjangoDeployCheck(_) => "django_deploy_check",
+        BuildpackError::DjangoDetection(_) => "django_detection",
+        BuildpackError::DjangoMigrate(_) => "django_migrate",
+        BuildpackError::DjangoSettingsModule(_) => "django_settings_module",
+        BuildpackError::GunicornChecks(_) => "gunicorn_checks",
+        BuildpackError::ImportCheck(_) => "import_check",
+        BuildpackError::NltkDataLayer(_) => "nltk_data_layer",
+        BuildpackError::PackageIndex(_) => "package_index",
+        BuildpackError::PackagesFile(_) => "packages_file",
+        BuildpackError::PdmDependenciesLayer(_) => "pdm_dependencies_layer",
+        BuildpackError::PdmLayer(_) => "pdm_layer",
+        BuildpackError::PipCacheLayer(_) => "pip_cache_layer",
+        BuildpackError::PipDependenciesLayer(_) => "pip_dependencies_layer",
+        BuildpackError::PipLayer(_) => "pip_layer",
+        BuildpackError::PipenvDependenciesLayer(_) => "pipenv_dependencies_layer",
+        BuildpackError::PipenvLayer(_) => "pipenv_layer",
+        BuildpackError::PoetryDependenciesLayer(_) => "poetry_dependencies_layer",
+        BuildpackError::PoetryLayer(_) => "poetry_layer",
+        BuildpackError::ProcfileChecks(_) => "procfile_checks",
+        BuildpackError::ProjectRoot(_) => "project_root",
+        BuildpackError::PythonEnvRunLayer(_) => "python_env_run_layer",
+        BuildpackError::PythonLayer(_) => "python_layer",
+        BuildpackError::PythonPath(_) => "python_path",
+        BuildpackError::ReadProjectToml(_) => "read_project_toml",
+        BuildpackError::ReadPyprojectToml(_) => "read_pyproject_toml",
+        BuildpackError::RequestedPythonVersion(_) => "requested_python_version",
+        BuildpackError::ResolvePythonVersion(_) => "resolve_python_version",
+        BuildpackError::SlimSitePackages(_) => "slim_site_packages",
+        BuildpackError::StrictMode(_) => "strict_mode",
+        BuildpackError::VerifyCommand(_) => "verify_command",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::ChecksError;
+
+    #[test]
+    fn telemetry_url_enabled() {
+        let mut env = Env::new();
+        env.insert(
+            TELEMETRY_URL_ENV_VAR,
+            "https://telemetry.example.com/events",
+        );
+        assert_eq!(
+            telemetry_url(&env).as_deref(),
+            Some("https://telemetry.example.com/events")
+        );
+    }
+
+    #[test]
+    fn telemetry_url_disabled() {
+        assert_eq!(telemetry_url(&Env::new()), None);
+
+        let mut env = Env::new();
+        env.insert(TELEMETRY_URL_ENV_VAR, "");
+        assert_eq!(telemetry_url(&env), None);
+
+        let mut env = Env::new();
+        env.insert(
+            TELEMETRY_URL_ENV_VAR,
+            "https://telemetry.example.com/events",
+        );
+        env.insert(TELEMETRY_OPT_OUT_ENV_VAR, "1");
+        assert_eq!(telemetry_url(&env), None);
+    }
+
+    #[test]
+    fn telemetry_event_contents() {
+        let telemetry = Telemetry {
+            url: "https://telemetry.example.com/events".to_string(),
+            start_time: Instant::now(),
+            buildpack_version: "1.2.3".to_string(),
+            build_cache_available: true,
+            package_manager: Some(PackageManager::Poetry),
+            python_version: Some("3.13".to_string()),
+            layer_cache_hits: BTreeMap::from([("poetry", true), ("python", false)]),
+            phase_durations: BTreeMap::from([("install_python", Duration::from_millis(1500))]),
+        };
+        let event = telemetry_event(
+            &telemetry,
+            Some(error_code(&libcnb::Error::BuildpackError(
+                BuildpackError::Checks(ChecksError::ForbiddenEnvVar("PYTHONHOME".to_string())),
+            ))),
+        );
+        assert_eq!(event.buildpack_version, "1.2.3");
+        assert!(event.build_cache_available);
+        assert_eq!(event.error_code, Some("checks"));
+        assert_eq!(event.package_manager, Some("Poetry"));
+        assert_eq!(event.python_version.as_deref(), Some("3.13"));
+        assert_eq!(
+            event.layer_cache_hits,
+            BTreeMap::from([("poetry", true), ("python", false)])
+        );
+        assert_eq!(
+            event.phase_durations_ms,
+            BTreeMap::from([("install_python", 1500)])
+        );
+    }
+}
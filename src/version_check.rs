@@ -0,0 +1,143 @@
+use crate::strict_mode::{StrictMode, WarningClass};
+use indoc::formatdoc;
+use libcnb::data::buildpack::BuildpackVersion;
+use libcnb::Env;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// The env var users can set to skip checking for a newer buildpack release (for example,
+/// for builds that run without network access).
+pub(crate) const VERSION_CHECK_OPT_OUT_ENV_VAR: &str = "PYTHON_BUILDPACK_VERSION_CHECK_OPT_OUT";
+
+// The same registry API used for the version badge in the README.
+const LATEST_RELEASE_URL: &str = "https://registry.buildpacks.io/api/v1/buildpacks/heroku/python";
+
+// The check must never noticeably slow down the build, so the request uses a short timeout.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+// How many minor releases behind the latest release the buildpack can be before it's considered
+// significantly outdated. Small differences aren't warned about, since it's reasonable for apps
+// to pin the buildpack version and only update it periodically.
+const OUTDATED_MINOR_VERSIONS: u64 = 5;
+
+/// Warn if the buildpack version being used is significantly older than the latest release.
+///
+/// Apps that pin an old buildpack version don't get support for newly released Python versions
+/// (which is a common cause of "Python version isn't available" build failures), or other fixes.
+/// Problems fetching the latest version (such as there being no network access) are ignored,
+/// since the check is only advisory.
+pub(crate) fn check_buildpack_version(
+    env: &Env,
+    current_version: &BuildpackVersion,
+    strict_mode: &StrictMode,
+) {
+    if env.contains_key(VERSION_CHECK_OPT_OUT_ENV_VAR) {
+        return;
+    }
+    let Some(latest_version) = fetch_latest_version() else {
+        return;
+    };
+    if is_significantly_outdated(current_version, &latest_version) {
+        strict_mode.log_warning(
+            WarningClass::OutdatedBuildpack,
+            "Outdated buildpack version",
+            formatdoc! {"
+                This build is using version {current_version} of the Python buildpack,
+                however, the latest version is {latest_version}.
+
+                Older buildpack versions don't support the latest Python versions,
+                and are missing bug fixes and other improvements. Update the
+                buildpack version in your app's builder or 'project.toml' file.
+
+                To disable this check, set the {VERSION_CHECK_OPT_OUT_ENV_VAR}
+                environment variable.
+            "},
+        );
+    }
+}
+
+fn fetch_latest_version() -> Option<BuildpackVersion> {
+    let body = ureq::AgentBuilder::new()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .get(LATEST_RELEASE_URL)
+        .call()
+        .ok()?
+        .into_string()
+        .ok()?;
+    parse_latest_version(&body)
+}
+
+fn parse_latest_version(body: &str) -> Option<BuildpackVersion> {
+    let response: RegistryResponse = serde_json::from_str(body).ok()?;
+    Some(response.latest.version)
+}
+
+fn is_significantly_outdated(
+    current_version: &BuildpackVersion,
+    latest_version: &BuildpackVersion,
+) -> bool {
+    latest_version.major > current_version.major
+        || (latest_version.major == current_version.major
+            && latest_version.minor >= current_version.minor + OUTDATED_MINOR_VERSIONS)
+}
+
+#[derive(Deserialize)]
+struct RegistryResponse {
+    latest: RegistryLatestRelease,
+}
+
+#[derive(Deserialize)]
+struct RegistryLatestRelease {
+    version: BuildpackVersion,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_latest_version_valid() {
+        assert_eq!(
+            parse_latest_version(
+                r#"{"latest": {"namespace": "heroku", "name": "python", "version": "0.26.1"}, "versions": []}"#
+            ),
+            Some(BuildpackVersion::new(0, 26, 1))
+        );
+    }
+
+    #[test]
+    fn parse_latest_version_invalid() {
+        assert_eq!(parse_latest_version(""), None);
+        assert_eq!(parse_latest_version(r#"{"latest": {}}"#), None);
+        assert_eq!(
+            parse_latest_version(r#"{"latest": {"version": "1.0"}}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn is_significantly_outdated_versions() {
+        let current_version = BuildpackVersion::new(0, 21, 0);
+        assert!(!is_significantly_outdated(
+            &current_version,
+            &BuildpackVersion::new(0, 21, 0)
+        ));
+        assert!(!is_significantly_outdated(
+            &current_version,
+            &BuildpackVersion::new(0, 25, 3)
+        ));
+        assert!(!is_significantly_outdated(
+            &current_version,
+            &BuildpackVersion::new(0, 20, 0)
+        ));
+        assert!(is_significantly_outdated(
+            &current_version,
+            &BuildpackVersion::new(0, 26, 0)
+        ));
+        assert!(is_significantly_outdated(
+            &current_version,
+            &BuildpackVersion::new(1, 0, 0)
+        ));
+    }
+}
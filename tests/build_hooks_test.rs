@@ -0,0 +1,111 @@
+use crate::tests::default_build_config;
+use indoc::indoc;
+use libcnb_test::{assert_contains, PackResult, TestRunner};
+use std::fs;
+
+#[test]
+#[ignore = "integration test"]
+fn pre_compile_hook() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::remove_file(app_dir.join("requirements.txt")).unwrap();
+        fs::create_dir_all(app_dir.join("bin")).unwrap();
+        fs::write(
+            app_dir.join("bin/pre_compile"),
+            "echo 'typing-extensions==4.12.2' > requirements.txt\n",
+        )
+        .unwrap();
+    });
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            indoc! {"
+                [Running the pre_compile hook]
+                Running 'bin/pre_compile'
+            "}
+        );
+        assert_contains!(
+            context.pack_stdout,
+            "Successfully installed typing-extensions-4.12.2"
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn post_compile_hook() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::create_dir_all(app_dir.join("bin")).unwrap();
+        fs::write(
+            app_dir.join("bin/post_compile"),
+            indoc! {"
+                set -euo pipefail
+                python -c 'import typing_extensions; print(\"Hook can import packages\")'
+                echo 'generated' > generated.txt
+            "},
+        )
+        .unwrap();
+    });
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            indoc! {"
+                [Running the post_compile hook]
+                Running 'bin/post_compile'
+                Hook can import packages
+            "}
+        );
+        let command_output = context.run_shell_command("cat generated.txt");
+        assert_eq!(command_output.stdout, "generated\n");
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn post_compile_hook_failure() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config
+        .app_dir_preprocessor(|app_dir| {
+            fs::create_dir_all(app_dir.join("bin")).unwrap();
+            fs::write(app_dir.join("bin/post_compile"), "exit 3\n").unwrap();
+        })
+        .expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Error: The post_compile hook failed]
+                The 'bin/post_compile' script in your app failed (exit status: 3).
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn pre_compile_hook_failure() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config
+        .app_dir_preprocessor(|app_dir| {
+            fs::create_dir_all(app_dir.join("bin")).unwrap();
+            fs::write(app_dir.join("bin/pre_compile"), "exit 1\n").unwrap();
+        })
+        .expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Error: The pre_compile hook failed]
+                The 'bin/pre_compile' script in your app failed (exit status: 1).
+
+                This script is run before your app's dependencies are installed, and
+                must exit successfully for the build to continue.
+            "}
+        );
+    });
+}
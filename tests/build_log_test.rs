@@ -0,0 +1,49 @@
+use crate::tests::default_build_config;
+use indoc::indoc;
+use libcnb_test::{assert_contains, assert_not_contains, PackResult, TestRunner};
+
+#[test]
+#[ignore = "integration test"]
+fn build_log_json_format() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.env("BUILD_LOG_FORMAT", "json");
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            r#"{"event":"section_start","section":"Installing Python"}"#
+        );
+        assert_contains!(
+            context.pack_stdout,
+            r#"{"event":"section_end","section":"Installing Python","duration_ms":"#
+        );
+        assert_contains!(
+            context.pack_stdout,
+            r#"{"event":"output","section":"Installing dependencies using pip","stream":"stdout","line":"Successfully installed typing-extensions-4.12.2"}"#
+        );
+        assert_contains!(context.pack_stdout, r#"{"event":"build_end","success":true,"#);
+        assert_not_contains!(context.pack_stdout, "[Installing Python]");
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn build_log_unknown_format() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config
+        .env("BUILD_LOG_FORMAT", "yaml")
+        .expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Error: Invalid build log format]
+                The 'BUILD_LOG_FORMAT' environment variable is set to an
+                unknown build log format: 'yaml'
+                
+                Set it to either 'text' (the default) or 'json'.
+            "}
+        );
+    });
+}
@@ -1,6 +1,8 @@
+use crate::python_version::default_python_full_version;
 use crate::tests::default_build_config;
 use indoc::indoc;
-use libcnb_test::{assert_contains, PackResult, TestRunner};
+use libcnb_test::{assert_contains, BuildpackReference, PackResult, TestRunner};
+use std::fs;
 
 #[test]
 #[ignore = "integration test"]
@@ -12,9 +14,93 @@ fn detect_rejects_non_python_projects() {
                 context.pack_stdout,
                 indoc! {"========
                     No Python project files found (such as pyproject.toml, requirements.txt or poetry.lock).
+                    Python will only be installed if another buildpack requires it.
                     ======== Results ========
                 "}
             );
         },
     );
 }
+
+#[test]
+#[ignore = "integration test"]
+fn detect_rejects_non_python_projects_misnamed_files() {
+    let mut config = default_build_config("tests/fixtures/empty");
+    config
+        .app_dir_preprocessor(|app_dir| {
+            fs::write(app_dir.join("Requirements.txt"), "").unwrap();
+        })
+        .expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            indoc! {"
+                No Python project files found (such as pyproject.toml, requirements.txt or poetry.lock).
+                Python will only be installed if another buildpack requires it.
+                If this is a Python app, check whether these are misnamed Python project files:
+                - 'Requirements.txt' should be named 'requirements.txt'
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn detect_required_via_build_plan() {
+    let default_python_full_version = default_python_full_version();
+    // The app's requirements file is in a subdirectory (configured using `project.toml`), so the
+    // buildpack doesn't require `python` itself, and is only run since the other buildpack does.
+    let mut config = default_build_config("tests/fixtures/pip_requirements_file");
+    config
+        .buildpacks([
+            BuildpackReference::CurrentCrate,
+            BuildpackReference::Other(
+                "file://tests/fixtures/requires_python_buildpack".to_string(),
+            ),
+        ])
+        .app_dir_preprocessor(|app_dir| {
+            fs::remove_file(app_dir.join(".python-version")).unwrap();
+        });
+
+    TestRunner::default().build(&config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            indoc! {"
+                No Python project files found (such as pyproject.toml, requirements.txt or poetry.lock).
+                Python will only be installed if another buildpack requires it.
+            "}
+        );
+        assert_contains!(
+            context.pack_stdout,
+            &format!("Python {default_python_full_version}\n")
+        );
+        assert_contains!(
+            context.pack_stdout,
+            "Successfully installed six-1.17.0 typing-extensions-4.12.2"
+        );
+    });
+}
+
+// When another buildpack requires Python, but the app has no Python project files, the build
+// (rather than detection) should fail with a clear error about the missing package manager file.
+#[test]
+#[ignore = "integration test"]
+fn detect_required_via_build_plan_no_package_manager_file() {
+    let mut config = default_build_config("tests/fixtures/empty");
+    config
+        .buildpacks([
+            BuildpackReference::CurrentCrate,
+            BuildpackReference::Other(
+                "file://tests/fixtures/requires_python_buildpack".to_string(),
+            ),
+        ])
+        .expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(&config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            "[Error: Couldn't find any supported Python package manager files]"
+        );
+    });
+}
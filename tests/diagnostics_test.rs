@@ -0,0 +1,65 @@
+use crate::tests::default_build_config;
+use indoc::indoc;
+use libcnb_test::{assert_contains, TestRunner};
+
+#[test]
+#[ignore = "integration test"]
+fn diagnostics_no_web_process() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/no_web_process"),
+        |context| {
+            assert_contains!(
+                context.pack_stderr,
+                indoc! {"
+                    [Warning: Possible problems with your app's processes]
+                    No processes are defined for your app, so it won't have a 'web' process
+                    and so won't be able to receive HTTP requests once deployed.
+                    
+                    A Django project was found in the 'testproject' directory.
+                    
+                    To define a web process, create a 'Procfile' in the root of your app's
+                    source code that specifies the command used to start your app, such as:
+                    web: gunicorn testproject.wsgi
+                "}
+            );
+        },
+    );
+}
+
+#[test]
+#[ignore = "integration test"]
+fn diagnostics_unpinned_requirements() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/pip_unpinned_requirements"),
+        |context| {
+            assert_contains!(
+                context.pack_stderr,
+                indoc! {"
+                    [Warning: Possible problems with your app's dependencies]
+                    The following packages in your 'requirements.txt' file are not pinned
+                    to an exact version:
+                    
+                    six
+                "}
+            );
+        },
+    );
+}
+
+#[test]
+#[ignore = "integration test"]
+fn diagnostics_runtime_txt_leftover() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/runtime_txt_leftover"),
+        |context| {
+            assert_contains!(
+                context.pack_stderr,
+                indoc! {"
+                    [Warning: Possible problems with your app's configuration]
+                    Both a 'runtime.txt' and a '.python-version' file were found, which
+                    request the same Python version (3.12.8).
+                "}
+            );
+        },
+    );
+}
@@ -13,6 +13,7 @@ fn django_staticfiles_latest_django() {
             .env("EXPECTED_ENV_VAR", "1"),
         |context| {
             assert_empty!(context.pack_stderr);
+            assert_contains!(context.pack_stdout, "Django: Detected (found manage.py)");
             assert_contains!(
                 context.pack_stdout,
                 indoc! {"
@@ -22,6 +23,34 @@ fn django_staticfiles_latest_django() {
                     1 static file symlinked to '/workspace/backend/staticfiles'.
                 "}
             );
+            assert_contains!(
+                context.pack_stdout,
+                "Adding process 'console': python manage.py shell"
+            );
+        },
+    );
+}
+
+#[test]
+#[ignore = "integration test"]
+fn django_multiple_projects() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/django_multiple_projects"),
+        |context| {
+            assert_contains!(
+                context.pack_stdout,
+                indoc! {"
+                    [Generating Django static files]
+                    Running collectstatic for the Django project in 'services/api'
+                    Running 'manage.py collectstatic'
+                    
+                    1 static file symlinked to '/workspace/services/api/staticfiles'.
+                    Running collectstatic for the Django project in 'services/admin'
+                    Running 'manage.py collectstatic'
+                    
+                    1 static file symlinked to '/workspace/services/admin/staticfiles'.
+                "}
+            );
         },
     );
 }
@@ -49,6 +78,36 @@ fn django_staticfiles_legacy_django() {
     );
 }
 
+#[test]
+#[ignore = "integration test"]
+fn django_split_settings_module_discovered() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/django_split_settings"),
+        |context| {
+            assert_contains!(
+                context.pack_stderr,
+                indoc! {"
+                    [Warning: Django settings module not configured]
+                    The DJANGO_SETTINGS_MODULE environment variable isn't set, and your app's
+                    'manage.py' script sets a default settings module of 'testproject.settings', which doesn't exist.
+                    
+                    Using the settings module 'testproject.settings.production' instead, which was found
+                    in your app's source code.
+                "}
+            );
+            assert_contains!(
+                context.pack_stdout,
+                indoc! {"
+                    [Generating Django static files]
+                    Running 'manage.py collectstatic'
+                    
+                    1 static file symlinked to '/workspace/staticfiles'.
+                "}
+            );
+        },
+    );
+}
+
 #[test]
 #[ignore = "integration test"]
 fn django_no_manage_py() {
@@ -167,3 +226,57 @@ fn django_staticfiles_misconfigured() {
         },
     );
 }
+
+#[test]
+#[ignore = "integration test"]
+fn django_migrations() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/django_migrations")
+            .env("DJANGO_RUN_MIGRATIONS_DURING_BUILD", "1"),
+        |context| {
+            assert_empty!(context.pack_stderr);
+            assert_contains!(
+                context.pack_stdout,
+                "Environment variable overrides: DJANGO_RUN_MIGRATIONS_DURING_BUILD"
+            );
+            assert_contains!(
+                context.pack_stdout,
+                indoc! {"
+                    [Running Django database migrations]
+                    Running 'manage.py migrate --noinput'
+                    Operations to perform:
+                      Apply all migrations: contenttypes
+                    Running migrations:
+                      Applying contenttypes.0001_initial... OK
+                "}
+            );
+        },
+    );
+}
+
+#[test]
+#[ignore = "integration test"]
+fn django_migrations_failure() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/django_staticfiles_latest_django")
+            .env("EXPECTED_ENV_VAR", "1")
+            .env("DJANGO_RUN_MIGRATIONS_DURING_BUILD", "1")
+            .expected_pack_result(PackResult::Failure),
+        |context| {
+            assert_contains!(
+                context.pack_stderr,
+                indoc! {"
+                    django.core.exceptions.ImproperlyConfigured: settings.DATABASES is improperly configured. Please supply the ENGINE value. Check settings documentation for more details.
+                "}
+            );
+            assert_contains!(
+                context.pack_stderr,
+                indoc! {"
+                    [Error: Unable to run Django database migrations]
+                    The 'python manage.py migrate --noinput' Django management command
+                    to apply your app's database migrations failed (exit status: 1).
+                "}
+            );
+        },
+    );
+}
@@ -0,0 +1 @@
+# Settings are split into one module per environment.
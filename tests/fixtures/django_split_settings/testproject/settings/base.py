@@ -0,0 +1,11 @@
+from pathlib import Path
+
+BASE_DIR = Path(__file__).resolve().parent.parent.parent
+
+INSTALLED_APPS = [
+    "django.contrib.staticfiles",
+    "testapp",
+]
+
+STATIC_ROOT = BASE_DIR / "staticfiles"
+STATIC_URL = "static/"
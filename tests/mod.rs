@@ -3,13 +3,22 @@
 //! These tests are not run via automatic integration test discovery, but instead are
 //! imported in main.rs so that they have access to private APIs (see comment in main.rs).
 
+mod build_hooks_test;
+mod build_log_test;
 mod checks_test;
 mod detect_test;
+mod diagnostics_test;
 mod django_test;
+mod nltk_test;
 mod package_manager_test;
+mod pdm_test;
 mod pip_test;
+mod pipenv_test;
 mod poetry_test;
+mod processes_test;
+mod project_root_test;
 mod python_version_test;
+mod strict_mode_test;
 
 use libcnb_test::BuildConfig;
 use std::env;
@@ -43,6 +52,8 @@ fn default_build_config(fixture_path: impl AsRef<Path>) -> BuildConfig {
         ("PKG_CONFIG_PATH", "/invalid"),
         ("PYTHONPATH", "/invalid"),
     ]);
+    // Otherwise the build output would depend on whether a newer buildpack version has been released.
+    config.env("PYTHON_BUILDPACK_VERSION_CHECK_OPT_OUT", "1");
 
     config
 }
@@ -50,3 +61,18 @@ fn default_build_config(fixture_path: impl AsRef<Path>) -> BuildConfig {
 fn builder() -> String {
     env::var("INTEGRATION_TEST_BUILDER").unwrap_or(DEFAULT_BUILDER.to_string())
 }
+
+// The durations shown at the end of the timed build steps (such as `- Done (1.2s)`) vary between
+// builds, so are replaced with a placeholder before comparing against the expected output.
+fn normalize_durations(output: &str) -> String {
+    output
+        .lines()
+        .map(|line| {
+            if line.starts_with("- Done (") {
+                "- Done (<duration>)\n".to_string()
+            } else {
+                format!("{line}\n")
+            }
+        })
+        .collect()
+}
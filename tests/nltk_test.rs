@@ -0,0 +1,56 @@
+use crate::tests::default_build_config;
+use indoc::indoc;
+use libcnb_test::{assert_contains, PackResult, TestRunner};
+use std::fs;
+
+#[test]
+#[ignore = "integration test"]
+fn nltk_data_install_and_cache_reuse() {
+    let config = default_build_config("tests/fixtures/nltk_basic");
+
+    TestRunner::default().build(&config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            indoc! {"
+                [Installing NLTK data]
+                Running 'python -m nltk.downloader -d /layers/heroku_python/nltk-data stopwords'
+            "}
+        );
+        let command_output = context.run_shell_command(
+            "python -c 'from nltk.corpus import stopwords; print(stopwords.words(\"english\")[0])'",
+        );
+        assert_eq!(command_output.stdout, "i\n");
+
+        context.rebuild(&config, |rebuild_context| {
+            assert_contains!(
+                rebuild_context.pack_stdout,
+                indoc! {"
+                    [Installing NLTK data]
+                    Using cached NLTK data
+                "}
+            );
+        });
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn nltk_invalid_corpus_name() {
+    let mut config = default_build_config("tests/fixtures/nltk_basic");
+    config
+        .app_dir_preprocessor(|app_dir| {
+            fs::write(app_dir.join("nltk.txt"), "stopwords --quiet\n").unwrap();
+        })
+        .expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Error: Invalid nltk.txt file]
+                The NLTK data package name '--quiet' in your app's 'nltk.txt' file
+                isn't valid.
+            "}
+        );
+    });
+}
@@ -13,9 +13,10 @@ fn no_package_manager_detected() {
                 context.pack_stderr,
                 indoc! {"
                     [Error: Couldn't find any supported Python package manager files]
-                    Your app must have either a pip requirements file ('requirements.txt')
-                    or Poetry lockfile ('poetry.lock') in the root directory of its source
-                    code, so your app's dependencies can be installed.
+                    Your app must have either a pip requirements file ('requirements.txt'),
+                    Pipenv lockfile ('Pipfile.lock'), Poetry lockfile ('poetry.lock') or PDM
+                    lockfile ('pdm.lock') in the root directory of its source code, so your
+                    app's dependencies can be installed.
                     
                     If your app already has one of those files, check that it:
                     
@@ -25,6 +26,38 @@ fn no_package_manager_detected() {
                     
                     Otherwise, add a package manager file to your app. If your app has
                     no dependencies, then create an empty 'requirements.txt' file.
+                    
+                    If your app's pip requirements file is in a subdirectory, set the
+                    'requirements-file' setting in 'project.toml' to its path.
+                    
+                    Alternatively, if your app declares its dependencies in the '[project]'
+                    table of its 'pyproject.toml' file, then check that the table exists
+                    and has a 'name' field, so that pip can install the project.
+                "}
+            );
+        },
+    );
+}
+
+#[test]
+#[ignore = "integration test"]
+fn no_package_manager_detected_misnamed_files() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/misnamed_project_files")
+            .expected_pack_result(PackResult::Failure),
+        |context| {
+            assert_contains!(
+                context.pack_stderr,
+                indoc! {"
+                    app's dependencies can be installed.
+                    
+                    The following files look like misnamed (or incomplete) project files:
+                    
+                    - '.python_version' should be named '.python-version'
+                    - 'Requirements.txt' should be named 'requirements.txt'
+                    - 'Pipfile' has no 'Pipfile.lock' (generate one using 'pipenv lock')
+                    
+                    If your app already has one of those files, check that it:
                 "}
             );
         },
@@ -0,0 +1,81 @@
+use crate::packaging_tool_versions::PDM_VERSION;
+use crate::python_version::{default_python_full_version, DEFAULT_PYTHON_VERSION};
+use crate::tests::{default_build_config, normalize_durations};
+use indoc::{formatdoc, indoc};
+use libcnb_test::{assert_contains, assert_empty, TestRunner};
+
+#[test]
+#[ignore = "integration test"]
+fn pdm_basic_install_and_cache_reuse() {
+    let default_python_full_version = default_python_full_version();
+    let config = default_build_config("tests/fixtures/pdm_basic");
+
+    // PDM writes its progress output to stderr, so unlike the pip and Poetry tests,
+    // we can't assert that stderr is empty.
+    TestRunner::default().build(&config, |context| {
+        assert_contains!(
+            normalize_durations(&context.pack_stdout),
+            &formatdoc! {"
+                [Build configuration]
+                Python version: {default_python_full_version} (requested {DEFAULT_PYTHON_VERSION} in .python-version)
+                Package manager: PDM {PDM_VERSION} (using pdm.lock)
+                Environment variable overrides: None
+                Build cache: Not available (first build, or the cache was cleared)
+
+                [Installing Python]
+                Installing Python {default_python_full_version}
+                - Done (<duration>)
+
+                [Installing PDM]
+                Installing PDM {PDM_VERSION}
+                - Done (<duration>)
+
+                [Installing dependencies using PDM]
+                Creating virtual environment
+                Running 'pdm sync --prod --clean'
+            "}
+        );
+        // The packages in the `test` dependency group (such as pytest) must not be installed.
+        assert_contains!(
+            normalize_durations(&context.pack_stdout),
+            indoc! {"
+                - Done (<duration>)
+
+                [Installed packages]
+                - typing_extensions 4.12.2
+
+                [Configuring processes]
+            "}
+        );
+
+        // Check that at run-time:
+        // - PDM isn't available.
+        // - Python can find the typing-extensions package.
+        let command_output = context.run_shell_command(indoc! {"
+            set -euo pipefail
+            ! command -v pdm > /dev/null || { echo 'PDM unexpectedly found!' && exit 1; }
+            python -c 'import typing_extensions'
+            echo \"VIRTUAL_ENV=${VIRTUAL_ENV}\"
+        "});
+        assert_empty!(command_output.stderr);
+        assert_eq!(
+            command_output.stdout,
+            "VIRTUAL_ENV=/layers/heroku_python/venv\n"
+        );
+
+        context.rebuild(&config, |rebuild_context| {
+            assert_contains!(
+                normalize_durations(&rebuild_context.pack_stdout),
+                &formatdoc! {"
+                    [Installing PDM]
+                    Using cached PDM {PDM_VERSION}
+                    - Done (<duration>)
+
+                    [Installing dependencies using PDM]
+                    Using cached virtual environment
+                    Running 'pdm sync --prod --clean'
+                "}
+            );
+        });
+    });
+}
@@ -0,0 +1,125 @@
+use crate::packaging_tool_versions::PIPENV_VERSION;
+use crate::python_version::{default_python_full_version, DEFAULT_PYTHON_VERSION};
+use crate::tests::{default_build_config, normalize_durations};
+use indoc::{formatdoc, indoc};
+use libcnb_test::{assert_contains, assert_empty, PackResult, TestRunner};
+
+#[test]
+#[ignore = "integration test"]
+fn pipenv_basic_install_and_cache_reuse() {
+    let default_python_full_version = default_python_full_version();
+    let config = default_build_config("tests/fixtures/pipenv_basic");
+
+    // Pipenv writes its progress output to stderr, so unlike the pip and Poetry tests,
+    // we can't assert that stderr is empty.
+    TestRunner::default().build(&config, |context| {
+        assert_contains!(
+            normalize_durations(&context.pack_stdout),
+            &formatdoc! {"
+                [Build configuration]
+                Python version: {default_python_full_version} (requested {DEFAULT_PYTHON_VERSION} in .python-version)
+                Package manager: Pipenv {PIPENV_VERSION} (using Pipfile.lock)
+                Environment variable overrides: None
+                Build cache: Not available (first build, or the cache was cleared)
+
+                [Installing Python]
+                Installing Python {default_python_full_version}
+                - Done (<duration>)
+
+                [Installing Pipenv]
+                Installing Pipenv {PIPENV_VERSION}
+                - Done (<duration>)
+
+                [Installing dependencies using Pipenv]
+                Creating virtual environment
+                Running 'pipenv install --deploy'
+            "}
+        );
+        // The dev packages (such as pytest) must not be installed.
+        assert_contains!(
+            normalize_durations(&context.pack_stdout),
+            indoc! {"
+                - Done (<duration>)
+
+                [Installed packages]
+                - typing_extensions 4.12.2
+
+                [Configuring processes]
+            "}
+        );
+
+        // Check that at run-time:
+        // - Pipenv isn't available.
+        // - Python can find the typing-extensions package.
+        let command_output = context.run_shell_command(indoc! {"
+            set -euo pipefail
+            ! command -v pipenv > /dev/null || { echo 'Pipenv unexpectedly found!' && exit 1; }
+            python -c 'import typing_extensions'
+            echo \"VIRTUAL_ENV=${VIRTUAL_ENV}\"
+        "});
+        assert_empty!(command_output.stderr);
+        assert_eq!(
+            command_output.stdout,
+            "VIRTUAL_ENV=/layers/heroku_python/venv\n"
+        );
+
+        context.rebuild(&config, |rebuild_context| {
+            assert_contains!(
+                normalize_durations(&rebuild_context.pack_stdout),
+                &formatdoc! {"
+                    [Installing Pipenv]
+                    Using cached Pipenv {PIPENV_VERSION}
+                    - Done (<duration>)
+
+                    [Installing dependencies using Pipenv]
+                    Using cached virtual environment
+                    Running 'pipenv install --deploy'
+                "}
+            );
+            assert_contains!(rebuild_context.pack_stdout, "Running 'pipenv clean'");
+        });
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn pipenv_lockfile_out_of_date() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/pipenv_lockfile_out_of_date")
+            .expected_pack_result(PackResult::Failure),
+        |context| {
+            assert_contains!(
+                context.pack_stderr,
+                indoc! {"
+                    [Error: Unable to install dependencies using Pipenv]
+                    The 'pipenv install --deploy' command to install the app's
+                    dependencies failed (exit status: 1).
+
+                    See the log output above for more information.
+
+                    If the log output says that your 'Pipfile.lock' is out of date, then
+                    update it by running 'pipenv lock', and commit the changes.
+                "}
+            );
+        },
+    );
+}
+
+#[test]
+#[ignore = "integration test"]
+fn pipenv_no_pipfile() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/pipenv_no_pipfile")
+            .expected_pack_result(PackResult::Failure),
+        |context| {
+            assert_contains!(
+                context.pack_stderr,
+                indoc! {"
+                    [Error: No Pipfile found]
+                    A 'Pipfile.lock' file was found, however, there is no 'Pipfile' in the
+                    root directory of your app's source code.
+                "}
+            );
+        },
+    );
+}
@@ -1,12 +1,15 @@
 use crate::packaging_tool_versions::POETRY_VERSION;
-use crate::python_version::{DEFAULT_PYTHON_FULL_VERSION, DEFAULT_PYTHON_VERSION};
-use crate::tests::default_build_config;
+use crate::python_version::{default_python_full_version, DEFAULT_PYTHON_VERSION};
+use crate::tests::{default_build_config, normalize_durations};
 use indoc::{formatdoc, indoc};
 use libcnb_test::{assert_contains, assert_empty, BuildpackReference, PackResult, TestRunner};
+use std::fs;
 
 #[test]
 #[ignore = "integration test"]
+#[allow(clippy::too_many_lines)]
 fn poetry_basic_install_and_cache_reuse() {
+    let default_python_full_version = default_python_full_version();
     let mut config = default_build_config("tests/fixtures/poetry_basic");
     config.buildpacks(vec![
         BuildpackReference::CurrentCrate,
@@ -16,16 +19,24 @@ fn poetry_basic_install_and_cache_reuse() {
     TestRunner::default().build(&config, |context| {
         assert_empty!(context.pack_stderr);
         assert_contains!(
-            context.pack_stdout,
+            normalize_durations(&context.pack_stdout),
             &formatdoc! {"
                 [Determining Python version]
                 Using Python version {DEFAULT_PYTHON_VERSION} specified in .python-version
                 
+                [Build configuration]
+                Python version: {default_python_full_version} (requested {DEFAULT_PYTHON_VERSION} in .python-version)
+                Package manager: Poetry {POETRY_VERSION} (using poetry.lock)
+                Environment variable overrides: None
+                Build cache: Not available (first build, or the cache was cleared)
+                
                 [Installing Python]
-                Installing Python {DEFAULT_PYTHON_FULL_VERSION}
+                Installing Python {default_python_full_version}
+                - Done (<duration>)
                 
                 [Installing Poetry]
                 Installing Poetry {POETRY_VERSION}
+                - Done (<duration>)
                 
                 [Installing dependencies using Poetry]
                 Creating virtual environment
@@ -35,6 +46,14 @@ fn poetry_basic_install_and_cache_reuse() {
                 Package operations: 1 install, 0 updates, 0 removals
                 
                   - Installing typing-extensions (4.12.2)
+                Installed packages verified against the hashes in poetry.lock: 1
+                - Done (<duration>)
+                
+                [Installed packages]
+                - typing_extensions 4.12.2
+                
+                [Configuring processes]
+                Adding process 'console': python
                 
                 ## Testing buildpack ##
                 CPATH=/layers/heroku_python/venv/include:/layers/heroku_python/python/include/python3.13:/layers/heroku_python/python/include
@@ -44,6 +63,7 @@ fn poetry_basic_install_and_cache_reuse() {
                 PKG_CONFIG_PATH=/layers/heroku_python/python/lib/pkgconfig
                 PYTHONUNBUFFERED=1
                 PYTHONUSERBASE=/layers/heroku_python/poetry
+                PYTHON_DEPENDENCY_REPORT=/layers/heroku_python/dependency-report/dependency-report.json
                 SOURCE_DATE_EPOCH=315532801
                 VIRTUAL_ENV=/layers/heroku_python/venv
                 
@@ -76,7 +96,7 @@ fn poetry_basic_install_and_cache_reuse() {
             command_output.stdout,
             formatdoc! {"
                 LD_LIBRARY_PATH=/layers/heroku_python/venv/lib:/layers/heroku_python/python/lib
-                PATH=/layers/heroku_python/venv/bin:/layers/heroku_python/python/bin:/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin
+                PATH=/layers/heroku_python/venv/bin:/layers/heroku_python/python-env-run/bin:/layers/heroku_python/python/bin:/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin
                 PYTHONUNBUFFERED=1
                 VIRTUAL_ENV=/layers/heroku_python/venv
             "}
@@ -85,23 +105,32 @@ fn poetry_basic_install_and_cache_reuse() {
         context.rebuild(&config, |rebuild_context| {
             assert_empty!(rebuild_context.pack_stderr);
             assert_contains!(
-                rebuild_context.pack_stdout,
+                normalize_durations(&rebuild_context.pack_stdout),
                 &formatdoc! {"
                     [Determining Python version]
                     Using Python version {DEFAULT_PYTHON_VERSION} specified in .python-version
                     
+                    [Build configuration]
+                    Python version: {default_python_full_version} (requested {DEFAULT_PYTHON_VERSION} in .python-version)
+                    Package manager: Poetry {POETRY_VERSION} (using poetry.lock)
+                    Environment variable overrides: None
+                    Build cache: Available from a previous build
+                    
                     [Installing Python]
-                    Using cached Python {DEFAULT_PYTHON_FULL_VERSION}
+                    Using cached Python {default_python_full_version}
+                    - Done (<duration>)
                     
                     [Installing Poetry]
                     Using cached Poetry {POETRY_VERSION}
+                    - Done (<duration>)
                     
                     [Installing dependencies using Poetry]
                     Using cached virtual environment
-                    Running 'poetry install --sync --only main'
-                    Installing dependencies from lock file
+                    Dependencies unchanged, skipping poetry install
+                    - Done (<duration>)
                     
-                    No dependencies to install or update
+                    [Installed packages]
+                    - typing_extensions 4.12.2
                 "}
             );
         });
@@ -111,6 +140,7 @@ fn poetry_basic_install_and_cache_reuse() {
 #[test]
 #[ignore = "integration test"]
 fn poetry_cache_invalidation_package_manager_changed() {
+    let default_python_full_version = default_python_full_version();
     let config = default_build_config("tests/fixtures/pip_basic");
     let rebuild_config = default_build_config("tests/fixtures/poetry_basic");
 
@@ -118,16 +148,24 @@ fn poetry_cache_invalidation_package_manager_changed() {
         context.rebuild(rebuild_config, |rebuild_context| {
             assert_empty!(rebuild_context.pack_stderr);
             assert_contains!(
-                rebuild_context.pack_stdout,
+                normalize_durations(&rebuild_context.pack_stdout),
                 &formatdoc! {"
                     [Determining Python version]
                     Using Python version {DEFAULT_PYTHON_VERSION} specified in .python-version
                     
+                    [Build configuration]
+                    Python version: {default_python_full_version} (requested {DEFAULT_PYTHON_VERSION} in .python-version)
+                    Package manager: Poetry {POETRY_VERSION} (using poetry.lock)
+                    Environment variable overrides: None
+                    Build cache: Available from a previous build
+                    
                     [Installing Python]
-                    Using cached Python {DEFAULT_PYTHON_FULL_VERSION}
+                    Using cached Python {default_python_full_version}
+                    - Done (<duration>)
                     
                     [Installing Poetry]
                     Installing Poetry {POETRY_VERSION}
+                    - Done (<duration>)
                     
                     [Installing dependencies using Poetry]
                     Creating virtual environment
@@ -148,6 +186,7 @@ fn poetry_cache_invalidation_package_manager_changed() {
 #[test]
 #[ignore = "integration test"]
 fn poetry_cache_previous_buildpack_version() {
+    let default_python_full_version = default_python_full_version();
     let mut config = default_build_config("tests/fixtures/poetry_basic");
     config.buildpacks([BuildpackReference::Other(
         "docker://docker.io/heroku/buildpack-python:0.19.0".to_string(),
@@ -158,19 +197,27 @@ fn poetry_cache_previous_buildpack_version() {
         context.rebuild(rebuild_config, |rebuild_context| {
             assert_empty!(rebuild_context.pack_stderr);
             assert_contains!(
-                rebuild_context.pack_stdout,
+                normalize_durations(&rebuild_context.pack_stdout),
                 &formatdoc! {"
                     [Determining Python version]
                     Using Python version {DEFAULT_PYTHON_VERSION} specified in .python-version
                     
+                    [Build configuration]
+                    Python version: {default_python_full_version} (requested {DEFAULT_PYTHON_VERSION} in .python-version)
+                    Package manager: Poetry {POETRY_VERSION} (using poetry.lock)
+                    Environment variable overrides: None
+                    Build cache: Available from a previous build
+                    
                     [Installing Python]
                     Discarding cached Python 3.13.0 since:
-                     - The Python version has changed from 3.13.0 to {DEFAULT_PYTHON_FULL_VERSION}
-                    Installing Python {DEFAULT_PYTHON_FULL_VERSION}
+                     - The Python version has changed from 3.13.0 to {default_python_full_version}
+                    Installing Python {default_python_full_version}
+                    - Done (<duration>)
                     
                     [Installing Poetry]
                     Discarding cached Poetry 1.8.3
                     Installing Poetry {POETRY_VERSION}
+                    - Done (<duration>)
                     
                     [Installing dependencies using Poetry]
                     Discarding cached virtual environment
@@ -222,6 +269,32 @@ fn poetry_editable_git_compiled() {
     });
 }
 
+#[test]
+#[ignore = "integration test"]
+fn poetry_groups() {
+    let mut config = default_build_config("tests/fixtures/poetry_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            "[com.heroku.buildpacks.python]\npoetry-groups = [\"test\"]\n",
+        )
+        .unwrap();
+    });
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            "Running 'poetry install --sync --only main,test'"
+        );
+        assert_contains!(context.pack_stdout, "- pytest ");
+        assert_empty!(
+            context
+                .run_shell_command("python -c 'import pytest, typing_extensions'")
+                .stderr
+        );
+    });
+}
+
 #[test]
 #[ignore = "integration test"]
 fn poetry_install_error() {
@@ -254,3 +327,26 @@ fn poetry_install_error() {
         );
     });
 }
+
+#[test]
+#[ignore = "integration test"]
+fn poetry_path_dependency_outside_app_dir() {
+    let mut config = default_build_config("tests/fixtures/poetry_path_dependency_outside_app_dir");
+    config.expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Error: Poetry path dependency is outside the app directory]
+                The following path dependencies in your 'poetry.lock' file point to
+                locations outside of your app's source directory:
+
+                shared-lib (../shared-lib)
+
+                Only the app directory is available during the build, so these
+                dependencies cannot be installed.
+            "}
+        );
+    });
+}
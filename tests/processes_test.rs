@@ -0,0 +1,371 @@
+use crate::tests::default_build_config;
+use indoc::indoc;
+use libcnb_test::{assert_contains, assert_empty, PackResult, TestRunner};
+use std::fs;
+
+#[test]
+#[ignore = "integration test"]
+fn project_toml_processes() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/project_toml_processes"),
+        |context| {
+            assert_empty!(context.pack_stderr);
+            assert_contains!(
+                context.pack_stdout,
+                indoc! {r#"
+                    [Configuring processes]
+                    Adding process 'web': python -m http.server $PORT
+                    Adding process 'worker': python -c 'print("worker")'
+                    Adding process 'console': python
+                "#}
+            );
+            assert_contains!(context.pack_stdout, "Setting default process type 'web'");
+        },
+    );
+}
+
+#[test]
+#[ignore = "integration test"]
+fn project_toml_processes_command_not_found() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            "[com.heroku.buildpacks.python.processes]\nweb = \"gunicorn myapp.wsgi\"\n",
+        )
+        .unwrap();
+    });
+    config.expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Error: Process command not found]
+                The 'gunicorn' command used by the 'web' process configured
+                in the '[com.heroku.buildpacks.python.processes]' table of your app's
+                'project.toml' file could not be found.
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn project_toml_release() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            "[com.heroku.buildpacks.python]\nrelease = \"python -c 'print(\\\"Releasing\\\")'\"\n",
+        )
+        .unwrap();
+    });
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            indoc! {r#"
+                [Configuring processes]
+                Adding process 'release': python -c 'print("Releasing")'
+            "#}
+        );
+        assert_contains!(
+            context.run_shell_command("/cnb/process/release").stdout,
+            "Releasing"
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn gunicorn_misconfigured() {
+    TestRunner::default().build(
+        default_build_config("tests/fixtures/gunicorn_misconfigured"),
+        |context| {
+            assert_contains!(
+                context.pack_stderr,
+                indoc! {"
+                    [Warning: Possible problems with your app's processes]
+                    The Gunicorn server for the 'web' process is configured to listen
+                    on a hardcoded port (0.0.0.0:8000), however, the port your app must listen on is
+                    assigned by the platform using the 'PORT' environment variable.
+
+                    Either remove the bind setting (since Gunicorn uses the 'PORT' environment
+                    variable by default), or change it to: '--bind 0.0.0.0:$PORT'
+                "}
+            );
+        },
+    );
+}
+
+#[test]
+#[ignore = "integration test"]
+fn procfile_command_not_found() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(app_dir.join("Procfile"), "web: gunicorn app:app\n").unwrap();
+    });
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Warning: Possible problems with your app's processes]
+                The 'gunicorn' command used by the 'web' process in your
+                'Procfile' could not be found, so the process will fail to start with
+                a 'command not found' error.
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn python_env_run() {
+    TestRunner::default().build(default_build_config("tests/fixtures/pip_basic"), |context| {
+        // The wrapper must set up the environment even when the launcher's env vars are missing.
+        let command_output = context.run_shell_command(indoc! {"
+            set -euo pipefail
+            env -i /layers/heroku_python/python-env-run/bin/python-env-run bash -c 'printenv | sort | grep -vE \"^(_|OLDPWD|PWD|SHLVL)=\"'
+            env -i /layers/heroku_python/python-env-run/bin/python-env-run python -c 'import typing_extensions'
+        "});
+        assert_empty!(command_output.stderr);
+        assert_eq!(
+            command_output.stdout,
+            indoc! {"
+                LD_LIBRARY_PATH=/layers/heroku_python/venv/lib:/layers/heroku_python/python/lib
+                PATH=/layers/heroku_python/venv/bin:/layers/heroku_python/python/bin:/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin
+                PYTHONUNBUFFERED=1
+                VIRTUAL_ENV=/layers/heroku_python/venv
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn project_toml_python_path() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            indoc! {"
+                [com.heroku.buildpacks.python]
+                python-path = ['.', 'src']
+            "},
+        )
+        .unwrap();
+        fs::create_dir(app_dir.join("src")).unwrap();
+        fs::write(app_dir.join("src/mypackage.py"), "").unwrap();
+        fs::write(app_dir.join("mymodule.py"), "").unwrap();
+    });
+
+    TestRunner::default().build(config, |context| {
+        assert_empty!(context.pack_stderr);
+        assert_contains!(
+            context.pack_stdout,
+            indoc! {"
+                Adding /workspace to the Python path
+                Adding /workspace/src to the Python path
+            "}
+        );
+        // The modules must be importable from outside the app directory.
+        let command_output =
+            context.run_shell_command("cd / && python -c 'import mymodule, mypackage'");
+        assert_empty!(command_output.stderr);
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn project_toml_fault_handler() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            indoc! {"
+                [com.heroku.buildpacks.python]
+                fault-handler = true
+            "},
+        )
+        .unwrap();
+    });
+
+    TestRunner::default().build(config, |context| {
+        assert_empty!(context.pack_stderr);
+        let command_output = context.run_shell_command(indoc! {"
+            set -euo pipefail
+            printenv PYTHONFAULTHANDLER
+            env -i /layers/heroku_python/python-env-run/bin/python-env-run printenv PYTHONFAULTHANDLER
+        "});
+        assert_empty!(command_output.stderr);
+        assert_eq!(command_output.stdout, "1\n1\n");
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn project_toml_slim_stdlib_disabled() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            indoc! {"
+                [com.heroku.buildpacks.python]
+                slim-stdlib = false
+            "},
+        )
+        .unwrap();
+    });
+
+    TestRunner::default().build(config, |context| {
+        assert_empty!(context.pack_stderr);
+        let command_output =
+            context.run_shell_command("python -c 'import ensurepip, idlelib, test.support'");
+        assert_empty!(command_output.stderr);
+        assert_empty!(command_output.stdout);
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn project_toml_assets() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            indoc! {r#"
+                [com.heroku.buildpacks.python.assets]
+                prefetch = "if [[ -f $HF_HOME/model.bin ]]; then echo 'Model already downloaded'; else mkdir -p $HF_HOME && echo 'model' > $HF_HOME/model.bin; fi"
+            "#},
+        )
+        .unwrap();
+    });
+
+    TestRunner::default().build(&config, |context| {
+        assert_empty!(context.pack_stderr);
+        assert_contains!(
+            context.pack_stdout,
+            indoc! {"
+                [Preparing assets]
+                Running 'if [[ -f $HF_HOME/model.bin ]]; then echo 'Model already downloaded'; else mkdir -p $HF_HOME && echo 'model' > $HF_HOME/model.bin; fi'
+            "}
+        );
+        let command_output = context.run_shell_command(indoc! {"
+            set -euo pipefail
+            printenv HF_HOME TORCH_HOME TRANSFORMERS_CACHE
+            cat $HF_HOME/model.bin
+        "});
+        assert_empty!(command_output.stderr);
+        assert_eq!(
+            command_output.stdout,
+            indoc! {"
+                /layers/heroku_python/assets/huggingface
+                /layers/heroku_python/assets/torch
+                /layers/heroku_python/assets/huggingface/hub
+                model
+            "}
+        );
+
+        context.rebuild(&config, |rebuild_context| {
+            assert_contains!(
+                rebuild_context.pack_stdout,
+                indoc! {"
+                    [Preparing assets]
+                    Using cached assets
+                    Running 'if [[ -f $HF_HOME/model.bin ]]; then echo 'Model already downloaded'; else mkdir -p $HF_HOME && echo 'model' > $HF_HOME/model.bin; fi'
+                    Model already downloaded
+                "}
+            );
+        });
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn source_version_exposed_to_build_steps() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.env("GITHUB_SHA", "7c3b2a1f0e9d8c7b6a5f4e3d2c1b0a9f8e7d6c5b");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            indoc! {r#"
+                [com.heroku.buildpacks.python.assets]
+                prefetch = "echo Source version: ${SOURCE_VERSION}"
+            "#},
+        )
+        .unwrap();
+    });
+
+    TestRunner::default().build(config, |context| {
+        assert_empty!(context.pack_stderr);
+        assert_contains!(
+            context.pack_stdout,
+            "Source version: 7c3b2a1f0e9d8c7b6a5f4e3d2c1b0a9f8e7d6c5b"
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn project_toml_verify_command_failure() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            "[com.heroku.buildpacks.python]\nverify-command = \"python -c 'import nonexistent_module'\"\n",
+        )
+        .unwrap();
+    });
+    config.expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            indoc! {"
+                [Running the verification command]
+                Running 'python -c 'import nonexistent_module''
+            "}
+        );
+        assert_contains!(
+            context.pack_stderr,
+            "ModuleNotFoundError: No module named 'nonexistent_module'"
+        );
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Error: Verification command failed]
+                The verification command configured using the 'verify-command'
+                setting in your app's 'project.toml' file failed (exit status: 1).
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn build_env_file() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join(".env.build"),
+            "# Non-secret build settings\nBUILD_SETTING=from-file\nOVERRIDDEN_SETTING=from-file\n",
+        )
+        .unwrap();
+        fs::write(
+            app_dir.join("project.toml"),
+            "[com.heroku.buildpacks.python]\nverify-command = 'test \"$BUILD_SETTING,$OVERRIDDEN_SETTING\" = from-file,from-env'\n",
+        )
+        .unwrap();
+    });
+    config.env("OVERRIDDEN_SETTING", "from-env");
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            "Loaded environment variables from .env.build: BUILD_SETTING\n"
+        );
+        assert_contains!(context.pack_stdout, "[Running the verification command]");
+    });
+}
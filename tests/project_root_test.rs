@@ -0,0 +1,66 @@
+use crate::packaging_tool_versions::PIP_VERSION;
+use crate::python_version::latest_python_version;
+use crate::tests::default_build_config;
+use indoc::{formatdoc, indoc};
+use libcnb_test::{assert_contains, PackResult, TestRunner};
+use std::fs;
+
+#[test]
+#[ignore = "integration test"]
+fn project_root_subdirectory() {
+    let latest_python_3_12 = latest_python_version(3, 12).unwrap();
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.app_dir_preprocessor(|app_dir| {
+        let backend_dir = app_dir.join("backend");
+        fs::create_dir(&backend_dir).unwrap();
+        fs::rename(
+            app_dir.join("requirements.txt"),
+            backend_dir.join("requirements.txt"),
+        )
+        .unwrap();
+        fs::write(backend_dir.join(".python-version"), "3.12\n").unwrap();
+        fs::write(
+            app_dir.join("project.toml"),
+            "[com.heroku.buildpacks.python]\nproject-root = \"backend\"\n",
+        )
+        .unwrap();
+    });
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            &formatdoc! {"
+                [Determining Python version]
+                Using Python version 3.12 specified in .python-version
+                
+                [Build configuration]
+                Python version: {latest_python_3_12} (requested 3.12 in .python-version)
+                Project root: backend
+                Package manager: pip {PIP_VERSION} (using requirements.txt)
+            "}
+        );
+        assert_contains!(
+            context.pack_stdout,
+            "Successfully installed typing-extensions-4.12.2"
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn project_root_invalid() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config
+        .env("PYTHON_PROJECT_ROOT", "../backend")
+        .expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Error: Invalid project root]
+                The project root '../backend' isn't a directory inside your app.
+            "}
+        );
+    });
+}
@@ -1,15 +1,16 @@
+use crate::packaging_tool_versions::PIP_VERSION;
 use crate::python_version::{
-    PythonVersion, DEFAULT_PYTHON_FULL_VERSION, DEFAULT_PYTHON_VERSION, LATEST_PYTHON_3_10,
-    LATEST_PYTHON_3_11, LATEST_PYTHON_3_12, LATEST_PYTHON_3_13, LATEST_PYTHON_3_8,
-    LATEST_PYTHON_3_9,
+    default_python_full_version, latest_python_version, PythonVersion, DEFAULT_PYTHON_VERSION,
 };
 use crate::tests::{builder, default_build_config};
 use indoc::{formatdoc, indoc};
 use libcnb_test::{assert_contains, assert_empty, PackResult, TestRunner};
+use std::fs;
 
 #[test]
 #[ignore = "integration test"]
 fn python_version_unspecified() {
+    let default_python_full_version = default_python_full_version();
     let config = default_build_config("tests/fixtures/python_version_unspecified");
 
     TestRunner::default().build(config, |context| {
@@ -22,8 +23,41 @@ fn python_version_unspecified() {
                 We recommend setting an explicit version. In the root of your app create
                 a '.python-version' file, containing a Python version like '{DEFAULT_PYTHON_VERSION}'.
                 
+                [Build configuration]
+                Python version: {default_python_full_version} (buildpack default)
+                Package manager: pip {PIP_VERSION} (using requirements.txt)
+                Environment variable overrides: None
+                Build cache: Not available (first build, or the cache was cleared)
+                
+                [Installing Python]
+                Installing Python {default_python_full_version}
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn python_version_requires_python() {
+    let latest_python_3_12 = latest_python_version(3, 12).unwrap();
+    let config = default_build_config("tests/fixtures/python_version_requires_python");
+
+    TestRunner::default().build(config, |context| {
+        assert_empty!(context.pack_stderr);
+        assert_contains!(
+            context.pack_stdout,
+            &formatdoc! {"
+                [Determining Python version]
+                Using Python version 3.12, the newest version compatible with requires-python in pyproject.toml
+                
+                [Build configuration]
+                Python version: {latest_python_3_12} (resolved 3.12 from requires-python in pyproject.toml)
+                Package manager: pip {PIP_VERSION} (using requirements.txt)
+                Environment variable overrides: None
+                Build cache: Not available (first build, or the cache was cleared)
+                
                 [Installing Python]
-                Installing Python {DEFAULT_PYTHON_FULL_VERSION}
+                Installing Python {latest_python_3_12}
             "}
         );
     });
@@ -59,42 +93,58 @@ fn python_3_7() {
 #[test]
 #[ignore = "integration test"]
 fn python_3_8() {
+    let latest_python_3_8 = latest_python_version(3, 8).unwrap();
     // Python 3.8 is only available on Heroku-20 and older.
     let fixture = "tests/fixtures/python_3.8";
     match builder().as_str() {
-        "heroku/builder:20" => builds_with_python_version(fixture, &LATEST_PYTHON_3_8),
-        _ => rejects_non_existent_python_version(fixture, &LATEST_PYTHON_3_8),
-    };
+        "heroku/builder:20" => builds_with_python_version(fixture, &latest_python_3_8),
+        _ => rejects_non_existent_python_version(fixture, &latest_python_3_8),
+    }
 }
 
 #[test]
 #[ignore = "integration test"]
 fn python_3_9() {
-    builds_with_python_version("tests/fixtures/python_3.9", &LATEST_PYTHON_3_9);
+    builds_with_python_version(
+        "tests/fixtures/python_3.9",
+        &latest_python_version(3, 9).unwrap(),
+    );
 }
 
 #[test]
 #[ignore = "integration test"]
 fn python_3_10() {
-    builds_with_python_version("tests/fixtures/python_3.10", &LATEST_PYTHON_3_10);
+    builds_with_python_version(
+        "tests/fixtures/python_3.10",
+        &latest_python_version(3, 10).unwrap(),
+    );
 }
 
 #[test]
 #[ignore = "integration test"]
 fn python_3_11() {
-    builds_with_python_version("tests/fixtures/python_3.11", &LATEST_PYTHON_3_11);
+    builds_with_python_version(
+        "tests/fixtures/python_3.11",
+        &latest_python_version(3, 11).unwrap(),
+    );
 }
 
 #[test]
 #[ignore = "integration test"]
 fn python_3_12() {
-    builds_with_python_version("tests/fixtures/python_3.12", &LATEST_PYTHON_3_12);
+    builds_with_python_version(
+        "tests/fixtures/python_3.12",
+        &latest_python_version(3, 12).unwrap(),
+    );
 }
 
 #[test]
 #[ignore = "integration test"]
 fn python_3_13() {
-    builds_with_python_version("tests/fixtures/python_3.13", &LATEST_PYTHON_3_13);
+    builds_with_python_version(
+        "tests/fixtures/python_3.13",
+        &latest_python_version(3, 13).unwrap(),
+    );
 }
 
 fn builds_with_python_version(fixture_path: &str, python_version: &PythonVersion) {
@@ -105,13 +155,30 @@ fn builds_with_python_version(fixture_path: &str, python_version: &PythonVersion
     } = python_version;
 
     TestRunner::default().build(default_build_config(fixture_path), |context| {
-        assert_empty!(context.pack_stderr);
+        if python_version.is_eol() {
+            assert_contains!(
+                context.pack_stderr,
+                &formatdoc! {"
+                    [Warning: Python version has reached end-of-life]
+                    Python {major}.{minor} has reached its upstream end-of-life, and is
+                    therefore no longer receiving security updates:
+                "}
+            );
+        } else {
+            assert_empty!(context.pack_stderr);
+        }
         assert_contains!(
             context.pack_stdout,
             &formatdoc! {"
                 [Determining Python version]
                 Using Python version {major}.{minor} specified in .python-version
                 
+                [Build configuration]
+                Python version: {major}.{minor}.{patch} (requested {major}.{minor} in .python-version)
+                Package manager: pip {PIP_VERSION} (using requirements.txt)
+                Environment variable overrides: None
+                Build cache: Not available (first build, or the cache was cleared)
+                
                 [Installing Python]
                 Installing Python {major}.{minor}.{patch}
             "}
@@ -144,6 +211,18 @@ fn builds_with_python_version(fixture_path: &str, python_version: &PythonVersion
                   echo "The above dynamically linked libraries were not found!"
                   exit 1
                 fi
+                
+                # Check that the build-only files (such as the Python headers) aren't in the run image.
+                if [[ -e /layers/heroku_python/python/include/ || -d /layers/heroku_python/python-build-only/ ]]; then
+                  echo "The build-only files were included in the run image!"
+                  exit 1
+                fi
+                
+                # Check that the unused parts of the standard library aren't in the run image.
+                if python -c 'import idlelib' 2>/dev/null || python -c 'import test.support' 2>/dev/null; then
+                  echo "The standard library test suite or idlelib were included in the run image!"
+                  exit 1
+                fi
             "#}
         );
         assert_empty!(command_output.stderr);
@@ -211,7 +290,8 @@ fn python_version_file_invalid_version() {
                 an.invalid.version
                 
                 However, the version must be specified as either:
-                1. '<major>.<minor>' (recommended, for automatic security updates)
+                1. '<major>.<minor>' or '<major>.<minor>.*' (recommended, for automatic
+                   security updates)
                 2. '<major>.<minor>.<patch>' (to pin to an exact Python version)
                 
                 Do not include quotes or a 'python-' prefix. To include comments, add them
@@ -307,10 +387,16 @@ fn runtime_txt() {
         assert_empty!(context.pack_stderr);
         assert_contains!(
             context.pack_stdout,
-            indoc! {"
+            &formatdoc! {"
                 [Determining Python version]
                 Using Python version 3.9.0 specified in runtime.txt
                 
+                [Build configuration]
+                Python version: 3.9.0 (requested 3.9.0 in runtime.txt)
+                Package manager: pip {PIP_VERSION} (using requirements.txt)
+                Environment variable overrides: None
+                Build cache: Not available (first build, or the cache was cleared)
+                
                 [Installing Python]
                 Installing Python 3.9.0
             "}
@@ -340,6 +426,7 @@ fn runtime_txt_io_error() {
 #[test]
 #[ignore = "integration test"]
 fn runtime_txt_invalid_version() {
+    let default_python_full_version = default_python_full_version();
     let mut config = default_build_config("tests/fixtures/runtime_txt_invalid_version");
     config.expected_pack_result(PackResult::Failure);
 
@@ -356,9 +443,175 @@ fn runtime_txt_invalid_version() {
                 However, the file contents must begin with a 'python-' prefix, followed by the
                 version specified as '<major>.<minor>.<patch>'. Comments are not supported.
                 
-                For example, to request Python {DEFAULT_PYTHON_FULL_VERSION}, update the 'runtime.txt' file so it
+                For example, to request Python {default_python_full_version}, update the 'runtime.txt' file so it
                 contains exactly:
-                python-{DEFAULT_PYTHON_FULL_VERSION}
+                python-{default_python_full_version}
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn python_version_env_var() {
+    let latest_python_3_12 = latest_python_version(3, 12).unwrap();
+    let mut config = default_build_config("tests/fixtures/python_3.13");
+    config.env("PYTHON_VERSION", "3.12");
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            &formatdoc! {"
+                [Determining Python version]
+                Using Python version 3.12 specified by the PYTHON_VERSION env var
+                
+                [Build configuration]
+                Python version: {latest_python_3_12} (requested 3.12 using PYTHON_VERSION)
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn python_version_project_toml() {
+    let latest_python_3_12 = latest_python_version(3, 12).unwrap();
+    let mut config = default_build_config("tests/fixtures/python_3.13");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            "[com.heroku.buildpacks.python]\nversion = \"3.12\"\n",
+        )
+        .unwrap();
+    });
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stdout,
+            &formatdoc! {"
+                [Determining Python version]
+                Using Python version 3.12 specified in project.toml
+                
+                [Build configuration]
+                Python version: {latest_python_3_12} (requested 3.12 in project.toml)
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn python_version_env_var_invalid_version() {
+    let mut config = default_build_config("tests/fixtures/python_3.13");
+    config
+        .env("PYTHON_VERSION", "python-3.12")
+        .expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            &formatdoc! {"
+                [Error: Invalid Python version in PYTHON_VERSION]
+                The Python version specified by the 'PYTHON_VERSION' env var is not in the correct format.
+                
+                The following version was found:
+                python-3.12
+                
+                However, the version must be specified as either:
+                1. '<major>.<minor>' or '<major>.<minor>.*' (recommended, for automatic
+                   security updates)
+                2. '<major>.<minor>.<patch>' (to pin to an exact Python version)
+                
+                Do not include quotes or a 'python-' prefix.
+                
+                For example, to request the latest version of Python {DEFAULT_PYTHON_VERSION},
+                set 'PYTHON_VERSION' to:
+                {DEFAULT_PYTHON_VERSION}
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn additional_python_versions() {
+    let latest_python_3_12 = latest_python_version(3, 12).unwrap();
+    let latest_python_3_13 = latest_python_version(3, 13).unwrap();
+    let mut config = default_build_config("tests/fixtures/python_3.13");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            "[com.heroku.buildpacks.python]\nadditional-python-versions = [\"3.12\", \"3.13\"]\n",
+        )
+        .unwrap();
+    });
+
+    TestRunner::default().build(&config, |context| {
+        assert_empty!(context.pack_stderr);
+        assert_contains!(
+            context.pack_stdout,
+            &formatdoc! {"
+                [Installing Python]
+                Installing Python {latest_python_3_13}
+                Installing additional Python {latest_python_3_12}
+            "}
+        );
+
+        let command_output = context.run_shell_command(indoc! {"
+            set -euo pipefail
+            python3.12 --version
+            python3.12 -c 'import ssl, sqlite3, sys; print(sys.prefix)'
+            python --version
+        "});
+        assert_empty!(command_output.stderr);
+        assert_contains!(
+            command_output.stdout,
+            &format!("Python {latest_python_3_12}\n")
+        );
+        assert_contains!(
+            command_output.stdout,
+            "/layers/heroku_python/python-additional/versions/3.12\n"
+        );
+        assert_contains!(
+            command_output.stdout,
+            &format!("Python {latest_python_3_13}\n")
+        );
+
+        context.rebuild(&config, |rebuild_context| {
+            assert_contains!(
+                rebuild_context.pack_stdout,
+                &format!("Using cached additional Python {latest_python_3_12}\n")
+            );
+        });
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn additional_python_versions_invalid() {
+    let mut config = default_build_config("tests/fixtures/python_3.13");
+    config.app_dir_preprocessor(|app_dir| {
+        fs::write(
+            app_dir.join("project.toml"),
+            "[com.heroku.buildpacks.python]\nadditional-python-versions = [\"3.7\"]\n",
+        )
+        .unwrap();
+    });
+    config.expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Error: Requested Python version has reached end-of-life]
+                The requested Python version 3.7 has reached its upstream end-of-life,
+                and is therefore no longer receiving security updates:
+                https://devguide.python.org/versions/#supported-versions
+                
+                As such, it is no longer supported by this buildpack.
+                
+                Please upgrade to a newer Python version by updating the version
+                configured via the project.toml file.
             "}
         );
     });
@@ -0,0 +1,54 @@
+use crate::tests::default_build_config;
+use indoc::indoc;
+use libcnb_test::{assert_contains, PackResult, TestRunner};
+
+#[test]
+#[ignore = "integration test"]
+fn strict_mode_unpinned_dependencies() {
+    let mut config = default_build_config("tests/fixtures/django_staticfiles_latest_django");
+    config.env("PYTHON_STRICT_MODE", "unpinned-dependencies");
+    config.expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Error: Possible problems with your app's dependencies]
+                The following packages in your 'requirements.txt' file are not pinned
+                to an exact version:
+
+                Django
+
+                This means a different version may be installed each time your app is
+                built, which can unexpectedly break your app. Pin each package to an
+                exact version using '==' (for example: 'Django==5.1.4').
+
+                This warning has been treated as an error, since strict mode is enabled
+                for 'unpinned-dependencies' via the 'PYTHON_STRICT_MODE' environment variable.
+            "}
+        );
+    });
+}
+
+#[test]
+#[ignore = "integration test"]
+fn strict_mode_unknown_warning_class() {
+    let mut config = default_build_config("tests/fixtures/pip_basic");
+    config.env("PYTHON_STRICT_MODE", "eol-python,unknown");
+    config.expected_pack_result(PackResult::Failure);
+
+    TestRunner::default().build(config, |context| {
+        assert_contains!(
+            context.pack_stderr,
+            indoc! {"
+                [Error: Invalid strict mode configuration]
+                The 'PYTHON_STRICT_MODE' environment variable contains an unknown
+                warning class: 'unknown'
+
+                Set it to '1' to enable strict mode for all warning classes, or to a
+                comma separated list of one or more of the following warning classes:
+                django-deploy-check, eol-python, unpinned-dependencies
+            "}
+        );
+    });
+}